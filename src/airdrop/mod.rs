@@ -8,16 +8,27 @@ pub use sol::*;
 pub use spl::*;
 
 pub use std::{collections::HashMap, fs::File, path::PathBuf, str::FromStr};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
 
+use anyhow::anyhow;
 pub use anyhow::Result;
-use indicatif::ProgressBar;
-pub use jib::{Jib, JibFailedTransaction, Network};
+pub use jib::{JibFailedTransaction, JibResult};
 pub use log::debug;
 use metaboss_lib::data::Priority;
 pub use serde::{Deserialize, Serialize};
 pub use solana_client::rpc_client::RpcClient;
+use solana_program::{instruction::Instruction, program_pack::Pack};
+use solana_sdk::{message::Message, signature::Signature};
 pub use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
+use crate::constants::{AUTO_PRIORITY, PRIORITY_FEE_BUDGET};
+use crate::cost::{confirm_cost, CostEstimate};
+use crate::output::{emit_item, ItemOutput, Status};
+use crate::priority::estimate_micro_lamports;
+use crate::run_state::{ItemStatus, RunState, RunStateEntry, SharedRunState};
 pub use crate::update::{parse_keypair, parse_solana_config};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -37,3 +48,380 @@ struct Recipient {
 
 // Test transactions take 3_150, but we pad it a bit.
 pub const AIRDROP_SOL_CU: u32 = 5_000;
+
+/// The compute unit price of airdrop transactions. They use so few compute
+/// units that each fixed level pays more than it does for other commands.
+/// Every transaction is sent at this one price, so only the per-transaction
+/// cap applies.
+pub fn airdrop_priority_fee(client: &RpcClient, priority: &Priority) -> Result<u64> {
+    let auto = *AUTO_PRIORITY.read().unwrap();
+    let price = match auto {
+        Some(percentile) => estimate_micro_lamports(client, &[], percentile)?,
+        None => match priority {
+            Priority::None => 200,
+            Priority::Low => 200_000,
+            Priority::Medium => 1_000_000,
+            Priority::High => 5_000_000,
+            Priority::Max => 20_000_000,
+        },
+    };
+    Ok(PRIORITY_FEE_BUDGET.limit(price, AIRDROP_SOL_CU))
+}
+
+/// Show what an airdrop of `transactions` transactions costs, at most one per
+/// recipient, and ask to go ahead. `new_token_accounts` recipient token
+/// accounts are created on the way.
+pub fn confirm_airdrop_cost(
+    client: &RpcClient,
+    transactions: usize,
+    signatures: u64,
+    priority_fee: u64,
+    new_token_accounts: usize,
+) -> Result<()> {
+    let mut estimate = CostEstimate::new();
+    estimate.add_transactions(
+        transactions as u64,
+        signatures,
+        priority_fee,
+        AIRDROP_SOL_CU,
+    );
+    estimate.add_rent(
+        client,
+        "token accounts",
+        new_token_accounts as u64,
+        spl_token::state::Account::LEN,
+    )?;
+    confirm_cost(&estimate)
+}
+
+/// How many of `accounts` don't exist yet.
+pub fn count_missing_accounts(client: &RpcClient, accounts: &[Pubkey]) -> Result<usize> {
+    let mut missing = 0;
+    for chunk in accounts.chunks(100) {
+        missing += client
+            .get_multiple_accounts(chunk)?
+            .iter()
+            .filter(|account| account.is_none())
+            .count();
+    }
+    Ok(missing)
+}
+
+/// One recipient of an airdrop. Its instructions go out together in one
+/// transaction and its progress is journaled under its wallet address.
+#[derive(Clone, Debug)]
+pub struct AirdropRecipient {
+    pub address: String,
+    pub instructions: Vec<Instruction>,
+}
+
+impl AirdropRecipient {
+    /// A run state entry for the recipient. Every entry holds the recipient's
+    /// instructions as a legacy message, so an unfinished airdrop can be
+    /// resumed from the cache file whatever state it stopped in.
+    fn entry(&self, status: ItemStatus, payer: &Pubkey) -> Result<RunStateEntry> {
+        let message = Message::new(&self.instructions, Some(payer));
+        Ok(RunStateEntry::new(&self.address, status).data(serde_json::to_value(message)?))
+    }
+}
+
+/// Journal every recipient as pending, before anything is sent.
+pub fn record_pending(
+    run_state: &SharedRunState,
+    recipients: &[AirdropRecipient],
+    payer: &Pubkey,
+) -> Result<()> {
+    let mut run_state = run_state.lock().unwrap();
+    for recipient in recipients {
+        run_state.record(recipient.entry(ItemStatus::Pending, payer)?)?;
+    }
+    Ok(())
+}
+
+/// Journal the recipients of a transaction as sent, before it goes out.
+pub fn record_sent(
+    run_state: &SharedRunState,
+    recipients: &[AirdropRecipient],
+    payer: &Pubkey,
+    signature: &Signature,
+) -> Result<()> {
+    let mut run_state = run_state.lock().unwrap();
+    for recipient in recipients {
+        run_state.record(
+            recipient
+                .entry(ItemStatus::Sent, payer)?
+                .signature(signature.to_string()),
+        )?;
+    }
+    Ok(())
+}
+
+/// Journal the outcome of a transaction for each of its recipients.
+pub fn record_outcome(
+    run_state: &SharedRunState,
+    recipients: &[AirdropRecipient],
+    payer: &Pubkey,
+    result: &JibResult,
+) -> Result<()> {
+    let mut run_state = run_state.lock().unwrap();
+    for recipient in recipients {
+        let (entry, item) = match result {
+            JibResult::Success(signature) => (
+                recipient
+                    .entry(ItemStatus::Confirmed, payer)?
+                    .signature(signature),
+                ItemOutput::new(&recipient.address, Status::Ok),
+            ),
+            JibResult::Failure(failure) => (
+                recipient
+                    .entry(ItemStatus::Failed, payer)?
+                    .signature(failure.signature.to_string())
+                    .error(&failure.error),
+                ItemOutput::new(&recipient.address, Status::Failed).error(&failure.error),
+            ),
+        };
+        emit_item(item.signature(entry.signature.clone()));
+        run_state.record(entry)?;
+    }
+    Ok(())
+}
+
+/// Recipients of an earlier airdrop that weren't confirmed, with the
+/// signature of the transaction they were last sent in, if any.
+///
+/// Accepts the JSONL run state as well as the legacy bincode `.bin` cache,
+/// whose failed transactions stand in for their recipients.
+pub fn unfinished_recipients(path: &str) -> Result<Vec<(AirdropRecipient, Option<Signature>)>> {
+    if PathBuf::from(path)
+        .extension()
+        .is_some_and(|ext| ext == "bin")
+    {
+        let failed: Vec<JibFailedTransaction> = bincode::deserialize_from(File::open(path)?)?;
+        return Ok(failed
+            .into_iter()
+            .map(|tx| {
+                let recipient = AirdropRecipient {
+                    address: tx.signature.to_string(),
+                    instructions: instructions_from_message(&tx.message),
+                };
+                (recipient, Some(tx.signature))
+            })
+            .collect());
+    }
+
+    RunState::load(path)?
+        .into_values()
+        .filter(|entry| entry.status != ItemStatus::Confirmed)
+        .map(|entry| {
+            let data = entry
+                .data
+                .ok_or_else(|| anyhow!("Cache entry {} has no instructions", entry.item))?;
+            let message: Message = serde_json::from_value(data)?;
+            let signature = entry
+                .signature
+                .as_deref()
+                .map(Signature::from_str)
+                .transpose()?;
+            let recipient = AirdropRecipient {
+                address: entry.item,
+                instructions: instructions_from_message(&message),
+            };
+            Ok((recipient, signature))
+        })
+        .collect()
+}
+
+/// Resume an airdrop from its cache file. Recipients whose transaction landed
+/// after all are journaled as confirmed in `run_state`; the rest are returned
+/// to be sent again, so nobody is paid twice.
+pub fn resume_airdrop(
+    client: &RpcClient,
+    run_state: &SharedRunState,
+    payer: &Pubkey,
+    path: &str,
+) -> Result<(Vec<JibResult>, Vec<AirdropRecipient>)> {
+    let unfinished = unfinished_recipients(path)?;
+
+    let mut signatures: Vec<Signature> = unfinished.iter().filter_map(|(_, sig)| *sig).collect();
+    signatures.sort();
+    signatures.dedup();
+    let mut landed = HashSet::new();
+    for chunk in signatures.chunks(256) {
+        let statuses = client.get_signature_statuses(chunk)?.value;
+        for (signature, status) in chunk.iter().zip(statuses) {
+            if matches!(status, Some(status) if status.err.is_none()) {
+                landed.insert(*signature);
+            }
+        }
+    }
+
+    let mut results = vec![];
+    let mut resend = vec![];
+    for (recipient, signature) in unfinished {
+        match signature.filter(|signature| landed.contains(signature)) {
+            Some(signature) => {
+                let result = JibResult::Success(signature.to_string());
+                record_outcome(run_state, std::slice::from_ref(&recipient), payer, &result)?;
+                results.push(result);
+            }
+            None => resend.push(recipient),
+        }
+    }
+    Ok((results, resend))
+}
+
+/// Start the run state for an airdrop, carrying over recipients already
+/// confirmed in `previous` so the new file is a complete record of the airdrop.
+pub fn create_airdrop_run_state(path: &str, previous: Option<&str>) -> Result<SharedRunState> {
+    let mut run_state = RunState::create(path)?;
+
+    // Legacy caches only hold failures so there is nothing to carry over.
+    if let Some(previous) = previous.filter(|p| !p.ends_with(".bin")) {
+        for entry in RunState::load(previous)?.into_values() {
+            if entry.status == ItemStatus::Confirmed {
+                run_state.record(entry)?;
+            }
+        }
+    }
+
+    Ok(Arc::new(Mutex::new(run_state)))
+}
+
+/// The signatures of the transactions that landed.
+pub fn successful_signatures(results: &[JibResult]) -> Vec<String> {
+    let mut signatures: Vec<String> = results.iter().filter_map(|r| r.signature()).collect();
+    signatures.sort();
+    signatures.dedup();
+    signatures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("mb-airdrop-{}-{name}", std::process::id()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn failure(error: &str) -> JibFailedTransaction {
+        JibFailedTransaction {
+            signature: Signature::new_unique(),
+            message: Message::default(),
+            error: error.to_string(),
+        }
+    }
+
+    fn recipient(address: &str) -> AirdropRecipient {
+        AirdropRecipient {
+            address: address.to_string(),
+            instructions: vec![solana_sdk::system_instruction::transfer(
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                100,
+            )],
+        }
+    }
+
+    #[test]
+    fn recipients_are_journaled_before_and_as_they_are_sent() {
+        let path = temp_path("journal.jsonl");
+        let run_state = create_airdrop_run_state(&path, None).unwrap();
+        let payer = Pubkey::new_unique();
+        let recipients = vec![recipient("alice"), recipient("bob"), recipient("carol")];
+
+        record_pending(&run_state, &recipients, &payer).unwrap();
+        let sent = Signature::new_unique();
+        record_sent(&run_state, &recipients[..2], &payer, &sent).unwrap();
+        record_outcome(
+            &run_state,
+            &recipients[..1],
+            &payer,
+            &JibResult::Success(sent.to_string()),
+        )
+        .unwrap();
+        drop(run_state);
+
+        let entries = RunState::load(&path).unwrap();
+        assert_eq!(entries["alice"].status, ItemStatus::Confirmed);
+        assert_eq!(entries["bob"].status, ItemStatus::Sent);
+        assert_eq!(entries["bob"].signature, Some(sent.to_string()));
+        assert_eq!(entries["carol"].status, ItemStatus::Pending);
+
+        let unfinished = unfinished_recipients(&path).unwrap();
+        assert_eq!(unfinished.len(), 2);
+        assert_eq!(unfinished[0].0.address, "bob");
+        assert_eq!(unfinished[0].0.instructions, recipients[1].instructions);
+        assert_eq!(unfinished[0].1, Some(sent));
+        assert_eq!(unfinished[1].0.address, "carol");
+        assert_eq!(unfinished[1].1, None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn failed_recipients_keep_their_error() {
+        let path = temp_path("failed.jsonl");
+        let run_state = create_airdrop_run_state(&path, None).unwrap();
+        let payer = Pubkey::new_unique();
+        let recipients = vec![recipient("alice")];
+        let failed = failure("blockhash not found");
+        let failed_sig = failed.signature;
+
+        record_outcome(&run_state, &recipients, &payer, &JibResult::Failure(failed)).unwrap();
+        drop(run_state);
+
+        let entries = RunState::load(&path).unwrap();
+        assert_eq!(entries["alice"].status, ItemStatus::Failed);
+        assert_eq!(
+            entries["alice"].error.as_deref(),
+            Some("blockhash not found")
+        );
+        assert_eq!(unfinished_recipients(&path).unwrap()[0].1, Some(failed_sig));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn retry_run_state_keeps_confirmed_recipients() {
+        let previous = temp_path("previous.jsonl");
+        let run_state = create_airdrop_run_state(&previous, None).unwrap();
+        let payer = Pubkey::new_unique();
+        let recipients = vec![recipient("alice"), recipient("bob")];
+        record_pending(&run_state, &recipients, &payer).unwrap();
+        record_outcome(
+            &run_state,
+            &recipients[..1],
+            &payer,
+            &JibResult::Success("sigA".to_string()),
+        )
+        .unwrap();
+        drop(run_state);
+
+        let path = temp_path("next.jsonl");
+        let run_state = create_airdrop_run_state(&path, Some(&previous)).unwrap();
+        let run_state = run_state.lock().unwrap();
+
+        assert_eq!(run_state.len(), 1);
+        assert_eq!(
+            run_state.get("alice").unwrap().status,
+            ItemStatus::Confirmed
+        );
+        std::fs::remove_file(previous).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn legacy_bin_cache_is_still_readable() {
+        let path = temp_path("legacy.bin");
+        let failed = failure("old");
+        let failed_sig = failed.signature;
+        bincode::serialize_into(File::create(&path).unwrap(), &vec![failed]).unwrap();
+
+        let unfinished = unfinished_recipients(&path).unwrap();
+
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0].1, Some(failed_sig));
+        std::fs::remove_file(path).unwrap();
+    }
+}
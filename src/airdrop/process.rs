@@ -1,6 +1,8 @@
 use structopt::StructOpt;
 
 use super::*;
+use crate::priority::PriorityArg;
+use crate::spinner::new_spinner;
 
 #[derive(Debug, StructOpt)]
 pub enum AirdropSubcommands {
@@ -23,9 +25,9 @@ pub enum AirdropSubcommands {
         #[structopt(short = "R", long)]
         rate_limit: Option<u64>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Airdrop SPL tokens (experimental)
     #[structopt(name = "spl")]
@@ -53,11 +55,15 @@ pub enum AirdropSubcommands {
         #[structopt(short = "R", long)]
         rate_limit: Option<u64>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
+
+        /// Address lookup table to compile transactions against, so more recipients fit per transaction.
+        #[structopt(long)]
+        lookup_table: Option<Pubkey>,
     },
-    /// Convert the bin cache file to json for readability
+    /// Convert a legacy bin cache file to json for readability
     ReadCache {
         /// Path to the cache file
         cache_file: String,
@@ -82,7 +88,7 @@ pub async fn process_airdrop(client: RpcClient, commands: AirdropSubcommands) ->
                 keypair,
                 recipient_list,
                 cache_file,
-                priority,
+                priority: priority.apply(),
                 rate_limit,
             })
             .await
@@ -95,6 +101,7 @@ pub async fn process_airdrop(client: RpcClient, commands: AirdropSubcommands) ->
             mint_tokens,
             priority,
             rate_limit,
+            lookup_table,
         } => {
             airdrop_spl(AirdropSplArgs {
                 client,
@@ -103,8 +110,9 @@ pub async fn process_airdrop(client: RpcClient, commands: AirdropSubcommands) ->
                 cache_file,
                 mint,
                 mint_tokens,
-                priority,
+                priority: priority.apply(),
                 rate_limit,
+                lookup_table,
             })
             .await
         }
@@ -114,7 +122,7 @@ pub async fn process_airdrop(client: RpcClient, commands: AirdropSubcommands) ->
             let cache: Vec<JibFailedTransaction> = bincode::deserialize_from(file)?;
 
             let json_filename = path.with_extension("json");
-            let pb = ProgressBar::new_spinner();
+            let pb = new_spinner();
             pb.set_message("Writing cache file...");
             pb.enable_steady_tick(100);
 
@@ -124,7 +132,7 @@ pub async fn process_airdrop(client: RpcClient, commands: AirdropSubcommands) ->
 
             if errors {
                 for tx in cache {
-                    println!("{:?}", tx.error);
+                    say!("{:?}", tx.error);
                 }
             }
             Ok(())
@@ -1,4 +1,8 @@
+use std::sync::Arc;
+
 use super::*;
+use crate::parse::parse_payer;
+use crate::signer::{parse_keypair_or_signer, KeypairOrSigner, SharedSigner};
 
 pub struct AirdropSolArgs {
     pub client: RpcClient,
@@ -11,10 +15,22 @@ pub struct AirdropSolArgs {
 
 pub async fn airdrop_sol(args: AirdropSolArgs) -> Result<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
+    let authority: SharedSigner = match parse_keypair_or_signer(args.keypair, solana_opts)? {
+        KeypairOrSigner::Keypair(keypair) => Arc::new(*keypair),
+        KeypairOrSigner::Signer(signer) => signer,
+    };
+    let payer = parse_payer()?;
+    let fee_payer = payer
+        .as_ref()
+        .map_or_else(|| authority.pubkey(), |payer| payer.pubkey());
+    let signatures = if fee_payer == authority.pubkey() {
+        1
+    } else {
+        2
+    };
+    let payer: Option<SharedSigner> = payer.map(|payer| Arc::new(payer) as SharedSigner);
 
-    let mut jib = Jib::new(vec![keypair], args.client.url())?;
-    let mut instructions = vec![];
+    let client = Arc::new(args.client);
 
     if args.recipient_list.is_some() && args.cache_file.is_some() {
         eprintln!("Cannot provide both a recipient list and a cache file.");
@@ -24,27 +40,25 @@ pub async fn airdrop_sol(args: AirdropSolArgs) -> Result<()> {
     let now = chrono::Local::now();
     let timestamp = now.format("%Y-%m-%d-%H-%M-%S").to_string();
 
-    let mut cache_file_name = format!("mb-cache-airdrop-{timestamp}.bin");
+    let cache_file_name = format!("mb-cache-airdrop-{timestamp}.jsonl");
     let successful_tx_file_name = format!("mb-successful-airdrops-{timestamp}.json");
 
-    let priority_fee = match args.priority {
-        Priority::None => 200,
-        Priority::Low => 200_000,
-        Priority::Medium => 1_000_000,
-        Priority::High => 5_000_000,
-        Priority::Max => 20_000_000,
-    };
-
-    jib.set_priority_fee(priority_fee);
-    jib.set_compute_budget(AIRDROP_SOL_CU);
+    let priority_fee = airdrop_priority_fee(&client, &args.priority)?;
 
-    if let Some(rate) = args.rate_limit {
-        jib.set_rate_limit(rate);
-    }
+    let airdrop = PackedAirdrop {
+        client: client.clone(),
+        authority: authority.clone(),
+        payer,
+        lookup_tables: vec![],
+        priority_fee,
+        rate_limit: args.rate_limit,
+        run_state: create_airdrop_run_state(&cache_file_name, args.cache_file.as_deref())?,
+    };
 
     let results = if let Some(list_file) = args.recipient_list {
         let airdrop_list: HashMap<String, u64> = serde_json::from_reader(File::open(list_file)?)?;
 
+        let mut recipients = vec![];
         for (address, amount) in &airdrop_list {
             let pubkey = match Pubkey::from_str(address) {
                 Ok(pubkey) => pubkey,
@@ -54,62 +68,41 @@ pub async fn airdrop_sol(args: AirdropSolArgs) -> Result<()> {
                 }
             };
 
-            instructions.push(solana_sdk::system_instruction::transfer(
-                &jib.payer().pubkey(),
-                &pubkey,
-                *amount,
-            ));
+            recipients.push(AirdropRecipient {
+                address: pubkey.to_string(),
+                instructions: vec![solana_sdk::system_instruction::transfer(
+                    &authority.pubkey(),
+                    &pubkey,
+                    *amount,
+                )],
+            });
         }
-        println!("Airdropping to {} recipients...", airdrop_list.len());
-
-        jib.set_instructions(instructions);
-        jib.hoist().await?
-    } else if let Some(cache_file) = args.cache_file {
-        cache_file_name = PathBuf::from(cache_file.clone())
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
-
-        let failed_txes: Vec<JibFailedTransaction> =
-            bincode::deserialize_from(File::open(cache_file)?)?;
-        jib.retry_failed(failed_txes).await?
+        confirm_airdrop_cost(&client, recipients.len(), signatures, priority_fee, 0)?;
+        say!("Airdropping to {} recipients...", airdrop_list.len());
+
+        airdrop_packed(&airdrop, recipients).await?
+    } else if let Some(cache_file) = &args.cache_file {
+        let (mut landed, unlanded) =
+            resume_airdrop(&client, &airdrop.run_state, &fee_payer, cache_file)?;
+        confirm_airdrop_cost(&client, unlanded.len(), signatures, priority_fee, 0)?;
+        landed.extend(airdrop_packed(&airdrop, unlanded).await?);
+        landed
     } else {
         eprintln!("No recipient list or cache file provided.");
         std::process::exit(1);
     };
 
     if results.iter().any(|r| r.is_failure()) {
-        println!(
-            "Some transactions failed. Check the cache file for details by running `metaboss airdrop read-cache {cache_file_name}` to convert it to a JSON file.");
+        say!(
+            "Some transactions failed. Check {cache_file_name} for details and pass it to --cache-file to retry them."
+        );
     }
 
-    let mut successes = vec![];
-    let mut failures = vec![];
-
-    results.into_iter().for_each(|r| {
-        if r.is_failure() {
-            let failure = r.get_failure().unwrap();
-            failures.push(failure);
-        } else {
-            debug!("Transaction successful: {}", r.signature().unwrap());
-            successes.push(r.signature().unwrap());
-        }
-    });
-
+    let successes = successful_signatures(&results);
     if !successes.is_empty() {
         let successful_tx_file = std::fs::File::create(successful_tx_file_name)?;
         serde_json::to_writer_pretty(successful_tx_file, &successes)?;
     }
 
-    let pb = ProgressBar::new_spinner();
-    pb.set_message("Writing cache file...");
-    pb.enable_steady_tick(100);
-
-    let cache_file = std::fs::File::create(cache_file_name)?;
-    bincode::serialize_into(cache_file, &failures)?;
-    pb.finish_and_clear();
-
     Ok(())
 }
@@ -1,19 +1,35 @@
 #![allow(dead_code)]
 use anyhow::anyhow;
 use borsh::{BorshDeserialize, BorshSerialize};
-use indicatif::ProgressBar;
-use jib::JibFailedTransaction;
-use metaboss_lib::transaction::send_and_confirm_tx;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     program_pack::Pack,
     pubkey,
 };
-use solana_sdk_ids::system_program;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, compute_budget::ComputeBudgetInstruction,
+    message::Message, transaction::VersionedTransaction,
+};
+use solana_sdk_ids::{compute_budget, system_program};
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::instruction::transfer_checked;
 
+use std::sync::Arc;
+
 use super::*;
+use crate::{
+    cache::pack::pack,
+    constants::DEFAULT_RATE_LIMIT,
+    limiter::Pacer,
+    lookup_table::parse_lookup_table,
+    offline::{compile_message, compute_units_unsigned},
+    parse::parse_payer,
+    run_state::SharedRunState,
+    signer::{
+        parse_keypair_or_signer, payer_first, send_and_confirm_signed, KeypairOrSigner,
+        SharedSigner,
+    },
+};
 
 pub struct AirdropSplArgs {
     pub client: RpcClient,
@@ -24,6 +40,7 @@ pub struct AirdropSplArgs {
     pub mint_tokens: bool,
     pub priority: Priority,
     pub rate_limit: Option<u64>,
+    pub lookup_table: Option<Pubkey>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -34,22 +51,33 @@ struct SplFailedTransaction {
     error: String,
 }
 
-type Recipient = String;
-type Ata = String;
-
 pub async fn airdrop_spl(args: AirdropSplArgs) -> Result<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
-
-    let mut jib = Jib::new(vec![keypair], args.client.url())?;
-    let mut instructions = vec![];
+    let authority: SharedSigner = match parse_keypair_or_signer(args.keypair, solana_opts)? {
+        KeypairOrSigner::Keypair(keypair) => Arc::new(*keypair),
+        KeypairOrSigner::Signer(signer) => signer,
+    };
+    let payer = parse_payer()?;
+    let fee_payer = payer
+        .as_ref()
+        .map_or_else(|| authority.pubkey(), |payer| payer.pubkey());
+    let signatures = if fee_payer == authority.pubkey() {
+        1
+    } else {
+        2
+    };
+    let payer: Option<SharedSigner> = payer.map(|payer| Arc::new(payer) as SharedSigner);
 
-    let mut recipients_lookup: HashMap<Ata, Recipient> = HashMap::new();
+    let client = Arc::new(args.client);
+    let lookup_tables: Vec<AddressLookupTableAccount> =
+        parse_lookup_table(&client, args.lookup_table.as_ref())?
+            .into_iter()
+            .collect();
 
-    let source_ata = get_associated_token_address(&jib.payer().pubkey(), &args.mint);
+    let source_ata = get_associated_token_address(&authority.pubkey(), &args.mint);
 
     let mint_account =
-        spl_token::state::Mint::unpack(args.client.get_account(&args.mint)?.data.as_slice())?;
+        spl_token::state::Mint::unpack(client.get_account(&args.mint)?.data.as_slice())?;
     let decimals = mint_account.decimals;
 
     if args.recipient_list.is_some() && args.cache_file.is_some() {
@@ -61,27 +89,39 @@ pub async fn airdrop_spl(args: AirdropSplArgs) -> Result<()> {
     let now = chrono::Local::now();
     let timestamp = now.format("%Y-%m-%d-%H-%M-%S").to_string();
 
-    let mut cache_file_name = format!("mb-cache-airdrop-{timestamp}.bin");
+    let cache_file_name = format!("mb-cache-airdrop-{timestamp}.jsonl");
     let successful_tx_file_name = format!("mb-successful-airdrops-{timestamp}.json");
 
-    let priority_fee = match args.priority {
-        Priority::None => 200,
-        Priority::Low => 200_000,
-        Priority::Medium => 1_000_000,
-        Priority::High => 5_000_000,
-        Priority::Max => 20_000_000,
-    };
+    let priority_fee = airdrop_priority_fee(&client, &args.priority)?;
 
-    jib.set_priority_fee(priority_fee);
-    jib.set_compute_budget(AIRDROP_SOL_CU);
-
-    if let Some(rate) = args.rate_limit {
-        jib.set_rate_limit(rate);
-    }
+    let airdrop = PackedAirdrop {
+        client: client.clone(),
+        authority: authority.clone(),
+        payer,
+        lookup_tables,
+        priority_fee,
+        rate_limit: args.rate_limit,
+        run_state: create_airdrop_run_state(&cache_file_name, args.cache_file.as_deref())?,
+    };
 
     let results = if let Some(list_file) = args.recipient_list {
         let airdrop_list: HashMap<String, f64> = serde_json::from_reader(File::open(list_file)?)?;
 
+        // Recipients without a token account yet get one paid for by the fee payer.
+        let destination_atas: Vec<Pubkey> = airdrop_list
+            .keys()
+            .filter_map(|address| Pubkey::from_str(address).ok())
+            .map(|pubkey| get_associated_token_address(&pubkey, &args.mint))
+            .collect();
+        let new_token_accounts = count_missing_accounts(&client, &destination_atas)?;
+        confirm_airdrop_cost(
+            &client,
+            destination_atas.len() + usize::from(args.mint_tokens),
+            signatures,
+            priority_fee,
+            new_token_accounts,
+        )?;
+
         if args.mint_tokens {
             let total_tokens = airdrop_list.values().sum::<f64>();
 
@@ -92,13 +132,19 @@ pub async fn airdrop_spl(args: AirdropSplArgs) -> Result<()> {
                 &spl_token::ID,
                 &args.mint,
                 &source_ata,
-                &jib.payer().pubkey(),
+                &authority.pubkey(),
                 &[],
                 total_tokens_native_units,
             )?;
-            send_and_confirm_tx(&args.client, &[jib.payer()], &[mint_tokens_ix])?;
+            let signers = payer_first(
+                airdrop.payer.as_deref().unwrap_or(authority.as_ref()),
+                authority.as_ref(),
+            );
+            send_and_confirm_signed(&client, &signers[..], &[mint_tokens_ix], &[])?;
         }
 
+        // Each recipient's instructions stay together in one transaction.
+        let mut recipients = vec![];
         for (address, amount) in &airdrop_list {
             let amount_native_units = convert_to_base_units(*amount, decimals).ok_or(anyhow!(
                 format!("Invalid token amount for address {address}")
@@ -114,79 +160,210 @@ pub async fn airdrop_spl(args: AirdropSplArgs) -> Result<()> {
 
             let destination_ata = get_associated_token_address(&pubkey, &args.mint);
 
-            recipients_lookup.insert(destination_ata.to_string(), pubkey.to_string());
-
-            instructions.push(create_token_if_missing_instruction(
-                &jib.payer().pubkey(),
-                &destination_ata,
-                &args.mint,
-                &pubkey,
-                &destination_ata,
-            ));
-
-            instructions.push(transfer_checked(
-                &spl_token::ID,
-                &source_ata,
-                &args.mint,
-                &destination_ata,
-                &jib.payer().pubkey(),
-                &[],
-                amount_native_units,
-                decimals,
-            )?);
+            recipients.push(AirdropRecipient {
+                address: pubkey.to_string(),
+                instructions: vec![
+                    create_token_if_missing_instruction(
+                        &fee_payer,
+                        &destination_ata,
+                        &args.mint,
+                        &pubkey,
+                        &destination_ata,
+                    ),
+                    transfer_checked(
+                        &spl_token::ID,
+                        &source_ata,
+                        &args.mint,
+                        &destination_ata,
+                        &authority.pubkey(),
+                        &[],
+                        amount_native_units,
+                        decimals,
+                    )?,
+                ],
+            });
         }
 
-        jib.set_instructions(instructions);
-        jib.hoist().await?
-    } else if let Some(cache_file) = args.cache_file {
-        cache_file_name = PathBuf::from(cache_file.clone())
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
-
-        let failed_txes: Vec<JibFailedTransaction> =
-            bincode::deserialize_from(File::open(cache_file)?)?;
-        jib.retry_failed(failed_txes).await?
+        airdrop_packed(&airdrop, recipients).await?
+    } else if let Some(cache_file) = &args.cache_file {
+        let (mut landed, unlanded) =
+            resume_airdrop(&client, &airdrop.run_state, &fee_payer, cache_file)?;
+        confirm_airdrop_cost(&client, unlanded.len(), signatures, priority_fee, 0)?;
+        landed.extend(airdrop_packed(&airdrop, unlanded).await?);
+        landed
     } else {
         eprintln!("No recipient list or cache file provided.");
         std::process::exit(1);
     };
 
     if results.iter().any(|r| r.is_failure()) {
-        println!(
-            "Some transactions failed. Check the cache file for details by running `metaboss airdrop read-cache {cache_file_name}` to convert it to a JSON file."
+        say!(
+            "Some transactions failed. Check {cache_file_name} for details and pass it to --cache-file to retry them."
         );
     }
 
-    let mut successes = vec![];
-    let mut failures = vec![];
-
-    results.into_iter().for_each(|r| {
-        if r.is_failure() {
-            let failure = r.get_failure().unwrap();
-            failures.push(failure);
-        } else {
-            debug!("Transaction successful: {}", r.signature().unwrap());
-            successes.push(r.signature().unwrap());
-        }
-    });
-
+    let successes = successful_signatures(&results);
     if !successes.is_empty() {
         let successful_tx_file = std::fs::File::create(successful_tx_file_name)?;
         serde_json::to_writer_pretty(successful_tx_file, &successes)?;
     }
 
-    let pb = ProgressBar::new_spinner();
-    pb.set_message("Writing cache file...");
-    pb.enable_steady_tick(100);
+    Ok(())
+}
 
-    let cache_file = std::fs::File::create(cache_file_name)?;
-    bincode::serialize_into(cache_file, &failures)?;
-    pb.finish_and_clear();
+/// How an airdrop's transactions are signed, priced and journaled.
+pub(super) struct PackedAirdrop {
+    pub client: Arc<RpcClient>,
+    pub authority: SharedSigner,
+    /// Pays the fees when given, otherwise the authority does.
+    pub payer: Option<SharedSigner>,
+    /// Transactions are compiled as v0 against these when any are given.
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+    pub priority_fee: u64,
+    pub rate_limit: Option<u64>,
+    pub run_state: SharedRunState,
+}
 
-    Ok(())
+/// Send the airdrop, packing as many recipients into each transaction as fit.
+/// Every recipient is journaled as pending before anything is sent, then as
+/// sent with its transaction's signature just before that transaction goes out.
+pub(super) async fn airdrop_packed(
+    airdrop: &PackedAirdrop,
+    recipients: Vec<AirdropRecipient>,
+) -> Result<Vec<JibResult>> {
+    let payer = airdrop
+        .payer
+        .clone()
+        .unwrap_or_else(|| airdrop.authority.clone());
+    record_pending(&airdrop.run_state, &recipients, &payer.pubkey())?;
+
+    let lookup_tables = Arc::new(airdrop.lookup_tables.clone());
+    let groups = pack(
+        recipients,
+        |recipient: &AirdropRecipient| recipient.instructions.as_slice(),
+        &payer.pubkey(),
+        &lookup_tables,
+    );
+    say!("Sending the airdrop in {} transactions. . .", groups.len());
+
+    let rate_limit = airdrop.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT.parse()?) as usize;
+    let rate_limiter = Pacer::new(rate_limit);
+
+    let mut tasks = vec![];
+    for group in groups {
+        rate_limiter.wait();
+        let client = airdrop.client.clone();
+        let authority = airdrop.authority.clone();
+        let payer = payer.clone();
+        let lookup_tables = lookup_tables.clone();
+        let run_state = airdrop.run_state.clone();
+        let priority_fee = airdrop.priority_fee;
+
+        tasks.push(tokio::spawn(async move {
+            let result = send_packed_airdrop(
+                &client,
+                authority.as_ref(),
+                payer.as_ref(),
+                &group,
+                &lookup_tables,
+                priority_fee,
+                &run_state,
+            )?;
+            record_outcome(&run_state, &group, &payer.pubkey(), &result)?;
+            Ok::<_, anyhow::Error>(result)
+        }));
+    }
+
+    let mut results = vec![];
+    for task in tasks {
+        results.push(task.await??);
+    }
+    Ok(results)
+}
+
+fn send_packed_airdrop(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    payer: &dyn Signer,
+    recipients: &[AirdropRecipient],
+    lookup_tables: &[AddressLookupTableAccount],
+    priority_fee: u64,
+    run_state: &SharedRunState,
+) -> Result<JibResult> {
+    let instructions: Vec<Instruction> = recipients
+        .iter()
+        .flat_map(|recipient| recipient.instructions.clone())
+        .collect();
+    let units = compute_units_unsigned(client, &instructions, &payer.pubkey(), lookup_tables);
+
+    let mut ixs = vec![];
+    if let Ok(Some(units)) = units {
+        ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            units as u32,
+        ));
+    }
+    ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
+        priority_fee,
+    ));
+    ixs.extend(instructions);
+
+    let message = compile_message(
+        &payer.pubkey(),
+        &ixs,
+        lookup_tables,
+        client.get_latest_blockhash()?,
+    )?;
+    let tx = VersionedTransaction::try_new(message, &payer_first(payer, authority)[..])?;
+    let signature = tx.signatures[0];
+
+    let result = match units {
+        Ok(_) => {
+            record_sent(run_state, recipients, &payer.pubkey(), &signature)?;
+            client
+                .send_and_confirm_transaction(&tx)
+                .map_err(|e| e.to_string())
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    Ok(match result {
+        Ok(signature) => JibResult::Success(signature.to_string()),
+        Err(error) => JibResult::Failure(JibFailedTransaction {
+            signature,
+            message: Message::new(&ixs, Some(&payer.pubkey())),
+            error,
+        }),
+    })
+}
+
+/// The instructions of a cached transaction, without its compute budget instructions.
+pub(super) fn instructions_from_message(message: &Message) -> Vec<Instruction> {
+    message
+        .instructions
+        .iter()
+        .filter_map(|ix| {
+            let program_id = message.account_keys[ix.program_id_index as usize];
+            if program_id == compute_budget::id() {
+                return None;
+            }
+
+            let accounts = ix
+                .accounts
+                .iter()
+                .map(|&i| AccountMeta {
+                    pubkey: message.account_keys[i as usize],
+                    is_signer: message.is_signer(i as usize),
+                    is_writable: message.is_maybe_writable(i as usize, None),
+                })
+                .collect();
+
+            Some(Instruction {
+                program_id,
+                accounts,
+                data: ix.data.clone(),
+            })
+        })
+        .collect()
 }
 
 const MPL_TOOLBOX_ID: Pubkey = pubkey!("TokExjvjJmhKaRBShsBAsbSvEWMA1AgUNK7ps4SAc2p");
@@ -233,3 +410,31 @@ fn convert_to_base_units(amount: f64, decimals: u8) -> Option<u64> {
         Some(base_units as u64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_message_recompiles_without_compute_budget() {
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let ata = get_associated_token_address(&recipient, &mint);
+        let source = get_associated_token_address(&payer, &mint);
+        let instructions = vec![
+            create_token_if_missing_instruction(&payer, &ata, &mint, &recipient, &ata),
+            transfer_checked(&spl_token::ID, &source, &mint, &ata, &payer, &[], 10, 6).unwrap(),
+        ];
+
+        let mut ixs = vec![ComputeBudgetInstruction::set_compute_unit_price(200)];
+        ixs.extend(instructions.clone());
+        let message = Message::new(&ixs, Some(&payer));
+
+        // Account flags are merged per message, so compare the compiled messages.
+        assert_eq!(
+            Message::new(&instructions_from_message(&message), Some(&payer)),
+            Message::new(&instructions, Some(&payer))
+        );
+    }
+}
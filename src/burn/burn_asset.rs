@@ -1,10 +1,21 @@
-use crate::{cache::NewValue, update::parse_mint_list};
+use metaboss_lib::{data::Asset, derive::derive_token_record_pda};
+use mpl_token_metadata::{instructions::BurnV1Builder, types::TokenStandard};
+use solana_sdk::instruction::Instruction;
+
+use crate::{
+    cache::{NewValue, PackedAction},
+    parse::parse_payer,
+    proposal::{propose, MultisigSettings},
+    signer::{parse_batch_authority, send_with_signer},
+    update::parse_mint_list,
+};
 
 use super::*;
 
 pub struct BurnAssetArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub token_account: Option<String>,
     pub amount: u64,
@@ -33,15 +44,112 @@ pub async fn burn_asset(args: BurnAssetArgs) -> Result<Signature, ActionError> {
         get_associated_token_address(&args.keypair.pubkey(), &mint)
     };
 
-    let burn_args = metaboss_lib::burn::BurnAssetArgs::V1 {
-        authority: &args.keypair,
+    let ix = burn_asset_ix(
+        &args.client,
+        args.keypair.pubkey(),
         mint,
         token,
-        amount: args.amount,
+        args.amount,
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    send_with_signer(
+        &args.client,
+        args.keypair.as_ref(),
+        args.payer.as_ref().as_ref(),
+        &[ix],
+        &args.priority,
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+}
+
+/// Burn a single asset, signed by the token owner `authority`, which may be a
+/// hardware wallet. `payer` pays the fees when given.
+pub fn burn_asset_with_signer(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    payer: Option<&Keypair>,
+    mint_account: &str,
+    token_account: Option<String>,
+    amount: u64,
+    priority: &Priority,
+) -> AnyResult<Signature> {
+    let mint = Pubkey::from_str(mint_account)?;
+    let token = match token_account {
+        Some(token) => Pubkey::from_str(&token)?,
+        None => get_associated_token_address(&authority.pubkey(), &mint),
+    };
+
+    let ix = burn_asset_ix(client, authority.pubkey(), mint, token, amount)?;
+    send_with_signer(client, authority, payer, &[ix], priority)
+}
+
+/// The burn instruction alone, for export as a multisig proposal. `authority`
+/// owns the token account and signs the burn.
+pub fn burn_asset_ix(
+    client: &RpcClient,
+    authority: Pubkey,
+    mint: Pubkey,
+    token: Pubkey,
+    amount: u64,
+) -> AnyResult<Instruction> {
+    let mut asset = Asset::new(mint);
+    let md = asset.get_metadata(client)?;
+
+    let mut burn_builder = BurnV1Builder::new();
+    burn_builder
+        .authority(authority)
+        .mint(asset.mint)
+        .metadata(asset.metadata)
+        .token(token)
+        .amount(amount);
+
+    if matches!(
+        md.token_standard,
+        Some(
+            TokenStandard::NonFungible
+                | TokenStandard::NonFungibleEdition
+                | TokenStandard::ProgrammableNonFungible
+                | TokenStandard::ProgrammableNonFungibleEdition
+        ) | None
+    ) {
+        // NonFungible types need an edition.
+        asset.add_edition();
+        burn_builder.edition(asset.edition);
+
+        // pNFTs additionally need a token record.
+        if let Some(
+            TokenStandard::ProgrammableNonFungible | TokenStandard::ProgrammableNonFungibleEdition,
+        ) = md.token_standard
+        {
+            burn_builder.token_record(Some(derive_token_record_pda(&mint, &token)));
+        }
+    }
+
+    // A verified collection member needs its collection parent passed in.
+    if let Some(collection) = md.collection.filter(|c| c.verified) {
+        burn_builder.collection_metadata(Some(derive_metadata_pda(&collection.key)));
+    }
+
+    Ok(burn_builder.instruction())
+}
+
+/// Write a burn as a proposal for the multisig vault, which owns the token account.
+pub fn propose_burn_asset(
+    client: &RpcClient,
+    mint_account: &str,
+    token_account: Option<String>,
+    amount: u64,
+    multisig: &MultisigSettings,
+) -> AnyResult<()> {
+    let mint = Pubkey::from_str(mint_account)?;
+    let token = match token_account {
+        Some(token) => Pubkey::from_str(&token)?,
+        None => get_associated_token_address(&multisig.vault, &mint),
     };
 
-    metaboss_lib::burn::burn_asset(&args.client, burn_args)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+    let ix = burn_asset_ix(client, multisig.vault, mint, token, amount)?;
+    propose("burn-asset", mint_account, &[ix], multisig)
 }
 
 pub struct BurnAssetAll {}
@@ -56,6 +164,7 @@ impl Action for BurnAssetAll {
         burn_asset(BurnAssetArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account.clone(),
             token_account: None, // Must be ATA for this action, currently.
             amount: 1,
@@ -64,14 +173,32 @@ impl Action for BurnAssetAll {
         .await
         .map(|_| ())
     }
+
+    fn builds_instructions() -> bool {
+        true
+    }
+
+    async fn instructions(args: RunActionArgs) -> Result<PackedAction, ActionError> {
+        let mint = Pubkey::from_str(&args.mint_account)
+            .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+        // Must be the authority's ATA for this action, currently.
+        let token = get_associated_token_address(&args.authority, &mint);
+
+        let ix = burn_asset_ix(&args.client, args.authority, mint, token, 1)
+            .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+        Ok(PackedAction {
+            instructions: vec![ix],
+            diff: None,
+        })
+    }
 }
 
 pub async fn burn_asset_all(args: BurnAssetAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
+    let (keypair, signer) = parse_batch_authority(args.keypair, solana_opts)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
@@ -79,6 +206,7 @@ pub async fn burn_asset_all(args: BurnAssetAllArgs) -> AnyResult<()> {
         client: args.client,
         keypair,
         payer,
+        signer,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::None,
@@ -86,6 +214,7 @@ pub async fn burn_asset_all(args: BurnAssetAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     BurnAssetAll::run(args).await
 }
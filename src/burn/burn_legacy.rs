@@ -32,6 +32,7 @@ pub struct BurnPrintAllArgs {
 pub struct BurnArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_pubkey: Pubkey,
     pub priority: Priority,
 }
@@ -39,6 +40,7 @@ pub struct BurnArgs {
 pub struct BurnPrintArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_pubkey: Pubkey,
     pub master_mint_pubkey: Pubkey,
     pub priority: Priority,
@@ -56,17 +58,19 @@ pub async fn burn_one(
 
     let client = Arc::new(client);
     let keypair = Arc::new(keypair);
+    let payer = Arc::new(parse_payer()?);
 
     let args = BurnArgs {
         client,
         keypair,
+        payer,
         mint_pubkey,
         priority,
     };
 
     let sig = burn(args).await?;
 
-    println!("Tx sig: {sig}");
+    say!("Tx sig: {sig}");
 
     Ok(())
 }
@@ -85,10 +89,12 @@ pub async fn burn_print_one(
 
     let client = Arc::new(client);
     let keypair = Arc::new(keypair);
+    let payer = Arc::new(parse_payer()?);
 
     let args = BurnPrintArgs {
         client,
         keypair,
+        payer,
         mint_pubkey,
         master_mint_pubkey,
         priority,
@@ -96,7 +102,7 @@ pub async fn burn_print_one(
 
     let sig = burn_print(args).await?;
 
-    println!("Tx sig: {sig}");
+    say!("Tx sig: {sig}");
 
     Ok(())
 }
@@ -114,6 +120,7 @@ impl Action for BurnAll {
         let _sig = burn(BurnArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_pubkey,
             priority: args.priority,
         })
@@ -128,8 +135,7 @@ pub async fn burn_all(args: BurnAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
@@ -137,6 +143,7 @@ pub async fn burn_all(args: BurnAllArgs) -> AnyResult<()> {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::None,
@@ -144,6 +151,7 @@ pub async fn burn_all(args: BurnAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     BurnAll::run(args).await?;
 
@@ -184,22 +192,13 @@ pub async fn burn(args: BurnArgs) -> AnyResult<Signature> {
 
     let instructions = vec![burn_ix];
 
-    let recent_blockhash = args.client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
+    let payer = args.payer.as_ref().as_ref().unwrap_or(&args.keypair);
+    land(
+        &args.client,
+        &with_payer(payer, &[&args.keypair]),
         &instructions,
-        Some(&args.keypair.pubkey()),
-        &[&*args.keypair],
-        recent_blockhash,
-    );
-
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || args.client.send_and_confirm_transaction(&tx),
-    );
-    let sig = res?;
-
-    Ok(sig)
+        &[],
+    )
 }
 
 #[async_trait]
@@ -217,6 +216,7 @@ impl Action for BurnPrintAll {
         let _sig = burn_print(BurnPrintArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_pubkey,
             master_mint_pubkey,
             priority: args.priority,
@@ -232,8 +232,7 @@ pub async fn burn_print_all(args: BurnPrintAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
@@ -241,6 +240,7 @@ pub async fn burn_print_all(args: BurnPrintAllArgs) -> AnyResult<()> {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::Single(args.master_mint),
@@ -248,6 +248,7 @@ pub async fn burn_print_all(args: BurnPrintAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     BurnPrintAll::run(args).await?;
 
@@ -292,20 +293,11 @@ pub async fn burn_print(args: BurnPrintArgs) -> AnyResult<Signature> {
 
     let instructions = vec![burn_ix];
 
-    let recent_blockhash = args.client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
+    let payer = args.payer.as_ref().as_ref().unwrap_or(&args.keypair);
+    land(
+        &args.client,
+        &with_payer(payer, &[&args.keypair]),
         &instructions,
-        Some(&args.keypair.pubkey()),
-        &[&*args.keypair],
-        recent_blockhash,
-    );
-
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || args.client.send_and_confirm_transaction(&tx),
-    );
-    let sig = res?;
-
-    Ok(sig)
+        &[],
+    )
 }
@@ -1,31 +1,49 @@
 use anyhow::{anyhow, Result as AnyResult};
 use async_trait::async_trait;
 use indexmap::IndexMap;
-use log::info;
+use log::{error, info};
 use metaboss_lib::data::Priority;
-use once_cell::sync::Lazy;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::signature::Keypair;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
 
 use std::{
-    collections::HashMap,
-    fs::{File, OpenOptions},
+    collections::{HashMap, HashSet},
+    fs::File,
+    future::Future,
     io::Write,
     ops::{Deref, DerefMut},
     path::Path,
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
+pub mod pack;
+
+pub use pack::PackedAction;
+use pack::{run_packed_round, PackedRoundArgs};
+
 use crate::{
-    constants::NANO_SECONDS_IN_SECOND, errors::ActionError,
-    limiter::create_rate_limiter_with_capacity, spinner::create_progress_bar, utils::find_tm_error,
+    constants::{DRY_RUN, OFFLINE, RESUME, SPEND_GUARD},
+    cost::{confirm_cost, estimated_price, CostEstimate, ESTIMATED_COMPUTE_UNITS},
+    dry_run::DryRunReport,
+    errors::ActionError,
+    limiter::Pacer,
+    lookup_table::parse_lookup_table,
+    offline::{export_offline, ExportOfflineArgs},
+    output::{emit_item, error_name, json_output, ItemOutput, Status},
+    proposal::{export_proposals, multisig_settings, ExportProposalsArgs},
+    run_state::{reconcile_sent, ItemStatus, RunState, RunStateEntry, SharedRunState, TrackItem},
+    signer::SharedSigner,
+    spinner::create_progress_bar,
+    update::DiffJournal,
 };
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Cache(pub IndexMap<String, CacheItem>);
-pub type CacheResults = Vec<Result<(), ActionError>>;
 
 impl Default for Cache {
     fn default() -> Self {
@@ -64,23 +82,11 @@ impl Cache {
         // Clear out old errors.
         self.clear();
 
-        //Regex to find hex codes in error
-        static RE: Lazy<Regex> =
-            Lazy::new(|| Regex::new(r" 0x[0-9a-fA-F]+").expect("Failed to create regex"));
-
         for error in errors {
             match error {
                 ActionError::ActionFailed(mint_address, _) => {
-                    // Find hex codes in error message.
-                    let error_message = if let Some(mat) = RE.find(&error.to_string()) {
-                        find_tm_error(&mat.as_str().trim_start().replace("0x", ""))
-                            .unwrap_or_else(|| error.to_string())
-                    } else {
-                        error.to_string()
-                    };
-
                     let item = CacheItem {
-                        error: Some(error_message),
+                        error: Some(format_action_error(error)),
                     };
 
                     self.insert(mint_address.to_string(), item);
@@ -90,6 +96,12 @@ impl Cache {
     }
 }
 
+/// Error message for an item, with Token Metadata error codes resolved to their names.
+pub fn format_action_error(error: &ActionError) -> String {
+    let message = error.to_string();
+    error_name(&message).unwrap_or(message)
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CacheItem {
     pub error: Option<String>,
@@ -107,6 +119,9 @@ pub struct BatchActionArgs {
     pub client: RpcClient,
     pub keypair: Keypair,
     pub payer: Option<Keypair>,
+    /// An external signer, e.g. a remote signing service, that signs in place
+    /// of the keypair. Only actions that build their instructions can use one.
+    pub signer: Option<SharedSigner>,
     pub mint_list: Option<Vec<String>>,
     pub cache_file: Option<String>,
     pub new_value: NewValue,
@@ -114,19 +129,28 @@ pub struct BatchActionArgs {
     pub rate_limit: usize,
     pub retries: u8,
     pub priority: Priority,
+    /// Address lookup table to compile packed transactions against.
+    pub lookup_table: Option<Pubkey>,
 }
 
 pub struct RunActionArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
     pub payer: Arc<Option<Keypair>>,
+    /// Signs the action's instructions; the keypair unless running offline or
+    /// proposing to a multisig.
+    pub authority: Pubkey,
+    /// Pays for accounts the action's instructions create.
+    pub fee_payer: Pubkey,
     pub mint_account: String,
     pub new_value: String,
     pub should_append: bool,
     pub priority: Priority,
+    pub journal: Arc<DiffJournal>,
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
     use crate::errors::ActionError;
@@ -264,6 +288,104 @@ mod tests {
             .unwrap()
             .contains("Simple error without hex"));
     }
+
+    fn shared_run_state(name: &str) -> (std::path::PathBuf, SharedRunState) {
+        let path =
+            std::env::temp_dir().join(format!("mb-cache-test-{}-{name}.jsonl", std::process::id()));
+        let state = RunState::create(&path).unwrap();
+        (path, Arc::new(Mutex::new(state)))
+    }
+
+    #[test]
+    fn test_track_outcome_keeps_sent_items_that_fail() {
+        // Arrange
+        let (path, run_state) = shared_run_state("sent-fails");
+        run_state
+            .lock()
+            .unwrap()
+            .record(RunStateEntry::new("mintA", ItemStatus::Sent).signature("sigA"))
+            .unwrap();
+        let fut = async {
+            Err(ActionError::ActionFailed(
+                "mintA".to_string(),
+                "confirmation timed out".to_string(),
+            ))
+        };
+
+        // Act
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(track_outcome(
+            Some(run_state.clone()),
+            "mintA".to_string(),
+            fut,
+        ));
+
+        // Assert
+        assert!(result.is_err());
+        let state = run_state.lock().unwrap();
+        let entry = state.get("mintA").unwrap();
+        assert_eq!(entry.status, ItemStatus::Sent);
+        assert_eq!(entry.signature.as_deref(), Some("sigA"));
+        assert!(entry
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("confirmation timed out"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_track_outcome_records_confirmed_signature() {
+        // Arrange
+        let (path, run_state) = shared_run_state("confirmed");
+        run_state
+            .lock()
+            .unwrap()
+            .record(RunStateEntry::new("mintA", ItemStatus::Sent).signature("sigA"))
+            .unwrap();
+
+        // Act
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime
+            .block_on(track_outcome(
+                Some(run_state.clone()),
+                "mintA".to_string(),
+                async { Ok(()) },
+            ))
+            .unwrap();
+
+        // Assert
+        let state = run_state.lock().unwrap();
+        let entry = state.get("mintA").unwrap();
+        assert_eq!(entry.status, ItemStatus::Confirmed);
+        assert_eq!(entry.signature.as_deref(), Some("sigA"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_track_outcome_marks_unsent_failures_failed() {
+        // Arrange
+        let (path, run_state) = shared_run_state("unsent-fails");
+        let fut = async {
+            Err(ActionError::ActionFailed(
+                "mintA".to_string(),
+                "account not found".to_string(),
+            ))
+        };
+
+        // Act
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _ = runtime.block_on(track_outcome(
+            Some(run_state.clone()),
+            "mintA".to_string(),
+            fut,
+        ));
+
+        // Assert
+        let state = run_state.lock().unwrap();
+        assert_eq!(state.get("mintA").unwrap().status, ItemStatus::Failed);
+        std::fs::remove_file(path).unwrap();
+    }
 }
 
 #[async_trait]
@@ -272,6 +394,26 @@ pub trait Action {
 
     fn name() -> &'static str;
 
+    /// Whether items can share a transaction. Packable actions are run with
+    /// `instructions` instead of `action`.
+    fn packable() -> bool {
+        false
+    }
+
+    /// Whether `instructions` is implemented, so the action can be exported as
+    /// multisig proposals.
+    fn builds_instructions() -> bool {
+        Self::packable()
+    }
+
+    /// Build the instructions for one item without sending them.
+    async fn instructions(args: RunActionArgs) -> Result<PackedAction, ActionError> {
+        Err(ActionError::ActionFailed(
+            args.mint_account,
+            "action can't build its instructions without sending them".to_string(),
+        ))
+    }
+
     async fn run(args: BatchActionArgs) -> AnyResult<()> {
         if args.cache_file.is_some() && args.mint_list.is_some() {
             return Err(anyhow!(
@@ -279,42 +421,198 @@ pub trait Action {
             ));
         }
 
-        // Default name, if we don't get an output_file option or a cache file.
-        let mut cache_file_name = format!("mb-cache-{}.json", Self::name());
-        let mut cache = Cache::new();
+        // Default name, if we don't get a cache file.
+        let cache_file_name = args
+            .cache_file
+            .clone()
+            .unwrap_or_else(|| format!("mb-cache-{}.jsonl", Self::name()));
+
+        // Only packed transactions are compiled against a lookup table.
+        if args.lookup_table.is_some() && !Self::packable() {
+            return Err(anyhow!("{} doesn't support lookup tables.", Self::name()));
+        }
+        let lookup_tables: Vec<AddressLookupTableAccount> =
+            parse_lookup_table(&args.client, args.lookup_table.as_ref())?
+                .into_iter()
+                .collect();
+
+        // Offline runs export transactions for the authority to sign instead of sending them.
+        let offline = OFFLINE.read().unwrap().clone();
+        if let Some(offline) = offline {
+            if !Self::packable() {
+                return Err(anyhow!("{} can't be signed offline.", Self::name()));
+            }
+            let mints = args
+                .mint_list
+                .ok_or_else(|| anyhow!("Offline mode needs a mint list."))?;
+
+            let fee_payer = args.keypair.pubkey();
+            return export_offline::<Self>(ExportOfflineArgs {
+                action: Self::name(),
+                round: PackedRoundArgs {
+                    mints,
+                    client: Arc::new(args.client),
+                    keypair: Arc::new(args.keypair),
+                    payer: Arc::new(None),
+                    signer: None,
+                    authority: offline.authority,
+                    fee_payer,
+                    new_value: &args.new_value,
+                    should_append: args.should_append,
+                    priority: args.priority,
+                    journal: Arc::new(DiffJournal::new()),
+                    run_state: None,
+                    rate_limiter: Pacer::new(args.rate_limit),
+                    lookup_tables,
+                },
+                nonce_accounts: offline.nonce_accounts,
+            })
+            .await;
+        }
+
+        // Multisig runs export every instruction as proposals for the vault to execute.
+        if let Some(multisig) = multisig_settings() {
+            if !Self::builds_instructions() {
+                return Err(anyhow!(
+                    "{} can't be exported as a multisig proposal.",
+                    Self::name()
+                ));
+            }
+            if !lookup_tables.is_empty() {
+                return Err(anyhow!("Multisig proposals don't use lookup tables."));
+            }
+            let mints = args
+                .mint_list
+                .ok_or_else(|| anyhow!("Multisig proposals need a mint list."))?;
+
+            return export_proposals::<Self>(ExportProposalsArgs {
+                action: Self::name(),
+                round: PackedRoundArgs {
+                    mints,
+                    client: Arc::new(args.client),
+                    keypair: Arc::new(args.keypair),
+                    payer: Arc::new(None),
+                    signer: None,
+                    authority: multisig.vault,
+                    fee_payer: multisig.vault,
+                    new_value: &args.new_value,
+                    should_append: args.should_append,
+                    priority: args.priority,
+                    journal: Arc::new(DiffJournal::new()),
+                    run_state: None,
+                    rate_limiter: Pacer::new(args.rate_limit),
+                    lookup_tables: Vec::new(),
+                },
+                settings: multisig,
+            })
+            .await;
+        }
+
+        // External signers can't be handed to metaboss_lib, which signs with
+        // keypairs, so their instructions are built here and sent packed.
+        if args.signer.is_some() && !Self::builds_instructions() {
+            return Err(anyhow!(
+                "{} needs a keypair file; it can't use an external signer.",
+                Self::name()
+            ));
+        }
+        let packed = Self::packable() || args.signer.is_some();
+
+        let dry_run = *DRY_RUN.read().unwrap();
+        let resume = *RESUME.read().unwrap();
+        let continue_previous = args.cache_file.is_some() || resume;
+
+        // A dry run never writes run state so an existing cache file is left intact
+        // for the real run.
+        let mut run_state = if dry_run {
+            None
+        } else if continue_previous {
+            Some(RunState::open(&cache_file_name)?)
+        } else {
+            Some(RunState::create(&cache_file_name)?)
+        };
+
+        // Settle transactions an interrupted run sent but never saw confirmed, so
+        // nothing that may already have landed is sent again.
+        if let Some(run_state) = run_state.as_mut() {
+            let sent = run_state.with_status(ItemStatus::Sent).len();
+            if sent > 0 {
+                say!("Checking the status of {sent} previously sent transactions. . .");
+                let confirmed = reconcile_sent(&args.client, run_state)?;
+                say!("{confirmed}/{sent} had already been confirmed.");
+            }
+        }
+
+        let previous: Vec<RunStateEntry> = match &run_state {
+            Some(run_state) => run_state.entries().cloned().collect(),
+            None if continue_previous && Path::new(&cache_file_name).exists() => {
+                RunState::load(&cache_file_name)?.into_values().collect()
+            }
+            None => Vec::new(),
+        };
 
         let mut mint_list: Vec<String> = if let Some(mint_list) = args.mint_list {
+            // Only resumed runs have previous state, in which case confirmed items are skipped.
+            let confirmed: HashSet<&str> = previous
+                .iter()
+                .filter(|entry| entry.status == ItemStatus::Confirmed)
+                .map(|entry| entry.item.as_str())
+                .collect();
             mint_list
-        } else if let Some(cache_path) = args.cache_file {
-            println!("Retrying items from cache file. . .");
-            cache_file_name = cache_path;
-
-            let f = File::open(&cache_file_name)?;
-            let cache: Cache = serde_json::from_reader(f)?;
-            cache.0.keys().map(|k| k.to_string()).collect()
+                .into_iter()
+                .filter(|mint| !confirmed.contains(mint.as_str()))
+                .collect()
+        } else if args.cache_file.is_some() {
+            say!("Retrying items from cache file. . .");
+            previous
+                .into_iter()
+                .filter(|entry| entry.status != ItemStatus::Confirmed)
+                .map(|entry| entry.item)
+                .collect()
         } else {
             return Err(anyhow!(
                 "Please specify either a n mint_list file or a cache file."
             ));
         };
 
-        let f = if !Path::new(&cache_file_name).exists() {
-            File::create(&cache_file_name)?
-        } else {
-            OpenOptions::new()
-                .read(true)
-                .write(true)
-                .truncate(true)
-                .open(&cache_file_name)?
-        };
+        let run_state: Option<SharedRunState> = run_state.map(|s| Arc::new(Mutex::new(s)));
 
         let mut counter = 0u8;
         let client = Arc::new(args.client);
+        let authority = match &args.signer {
+            Some(signer) => signer.pubkey(),
+            None => args.keypair.pubkey(),
+        };
+        let fee_payer = args
+            .payer
+            .as_ref()
+            .map_or(authority, |payer| payer.pubkey());
         let keypair = Arc::new(args.keypair);
         let payer = Arc::new(args.payer);
+        // Record what changed as items land so collection owners can audit the run and it
+        // can be reverted, even if the run dies partway.
+        let journal = Arc::new(if dry_run {
+            DiffJournal::new()
+        } else {
+            DiffJournal::to_file(DiffJournal::file_name(Self::name()))
+        });
+
+        // At most one transaction per item, signed by the authority and any separate payer.
+        let signatures = if fee_payer == authority { 1 } else { 2 };
+        let price = estimated_price(&client, &args.priority, ESTIMATED_COMPUTE_UNITS)?;
+        let mut estimate = CostEstimate::new();
+        estimate.add_transactions(
+            mint_list.len() as u64,
+            signatures,
+            price,
+            ESTIMATED_COMPUTE_UNITS,
+        );
+        confirm_cost(&estimate)?;
 
-        let delay = NANO_SECONDS_IN_SECOND / args.rate_limit;
-        let rate_limiter = create_rate_limiter_with_capacity(args.rate_limit as u32, delay as u32);
+        let rate_limiter = Pacer::new(args.rate_limit);
+        let mut outcome = Ok(());
+        // The latest result of each item, for JSON output.
+        let mut item_errors: IndexMap<String, Option<String>> = IndexMap::new();
 
         loop {
             let remaining_mints = mint_list.clone();
@@ -322,97 +620,252 @@ pub trait Action {
             let mint_length = remaining_mints.len();
 
             info!("Sending network requests...");
-            let mut update_tasks = Vec::new();
-            let pb =
-                create_progress_bar("Sending network requests...", remaining_mints.len() as u64);
-
-            // Create a vector of futures to execute.
-            for mint_address in remaining_mints {
-                let mut rate_limiter = rate_limiter.clone();
-
-                let empty_string = String::new();
-
-                let new_value = match &args.new_value {
-                    NewValue::None => &empty_string,
-                    NewValue::Single(value) => value,
-                    NewValue::List(values) => match values.get(&mint_address) {
-                        Some(v) => v,
-                        None => {
-                            return Err(ActionError::ActionFailed(
-                                mint_address.clone(),
-                                "mint found in cache but missing from input list".to_string(),
-                            )
-                            .into());
-                        }
-                    },
-                };
-
-                // Create task to run the action in a separate thread.
-                let task = tokio::spawn({
-                    rate_limiter.wait();
-                    let fut = Self::action(RunActionArgs {
-                        client: client.clone(),
-                        keypair: keypair.clone(),
-                        payer: payer.clone(),
-                        mint_account: mint_address,
-                        new_value: new_value.to_string(),
-                        should_append: args.should_append,
-                        priority: args.priority.clone(),
+            let results = if packed {
+                run_packed_round::<Self>(PackedRoundArgs {
+                    mints: remaining_mints,
+                    client: client.clone(),
+                    keypair: keypair.clone(),
+                    payer: payer.clone(),
+                    signer: args.signer.clone(),
+                    authority,
+                    fee_payer,
+                    new_value: &args.new_value,
+                    should_append: args.should_append,
+                    priority: args.priority.clone(),
+                    journal: journal.clone(),
+                    run_state: run_state.clone(),
+                    rate_limiter: rate_limiter.clone(),
+                    lookup_tables: lookup_tables.clone(),
+                })
+                .await?
+            } else {
+                let mut update_tasks = Vec::new();
+                let pb = create_progress_bar(
+                    "Sending network requests...",
+                    remaining_mints.len() as u64,
+                );
+
+                // Create a vector of futures to execute.
+                for mint_address in remaining_mints {
+                    let rate_limiter = rate_limiter.clone();
+
+                    let new_value = new_value_for(&args.new_value, &mint_address)?;
+
+                    if let Some(run_state) = &run_state {
+                        run_state
+                            .lock()
+                            .unwrap()
+                            .record(RunStateEntry::new(&mint_address, ItemStatus::Pending))?;
+                    }
+
+                    // Create task to run the action in a separate thread.
+                    let task = tokio::spawn({
+                        rate_limiter.wait();
+                        let fut = Self::action(RunActionArgs {
+                            client: client.clone(),
+                            keypair: keypair.clone(),
+                            payer: payer.clone(),
+                            authority,
+                            fee_payer,
+                            mint_account: mint_address.clone(),
+                            new_value,
+                            should_append: args.should_append,
+                            priority: args.priority.clone(),
+                            journal: journal.clone(),
+                        });
+
+                        pb.inc(1);
+
+                        track_outcome(run_state.clone(), mint_address.clone(), fut)
                     });
 
-                    pb.inc(1);
+                    // Collect all the tasks in our futures vector.
+                    update_tasks.push((mint_address, task));
+                }
 
-                    fut
-                });
+                pb.finish_and_clear();
 
-                // Collect all the tasks in our futures vector.
-                update_tasks.push(task);
-            }
+                let pb =
+                    create_progress_bar("Waiting for requests to resolve...", mint_length as u64);
 
-            pb.finish_and_clear();
+                // Wait for all the tasks to resolve and push the results to our results vector
+                let mut results = Vec::new();
+                for (mint_address, task) in update_tasks {
+                    results.push((mint_address, task.await.unwrap()));
+                    // Increment the counter and update the progress bar.
+                    pb.inc(1);
+                }
+                pb.finish_and_clear();
 
-            let update_tasks_len = update_tasks.len();
+                results
+            };
 
-            let pb = create_progress_bar("Waiting for requests to resolve...", mint_length as u64);
+            let update_tasks_len = results.len();
 
-            // Wait for all the tasks to resolve and push the results to our results vector
-            let mut update_results = Vec::new();
-            for task in update_tasks {
-                update_results.push(task.await.unwrap());
-                // Increment the counter and update the progress bar.
-                pb.inc(1);
+            let mut update_failed = Vec::new();
+            let mut dry_run_report = DryRunReport::new();
+            for (mint_address, result) in results {
+                if dry_run {
+                    dry_run_report.add_result(mint_address.clone(), &result);
+                }
+                if json_output() {
+                    let error = result.as_ref().err().map(|e| e.to_string());
+                    item_errors.insert(mint_address.clone(), error);
+                }
+                if result.is_err() {
+                    update_failed.push(mint_address);
+                }
+            }
+            // Simulated runs are never retried: report every item and stop.
+            if dry_run {
+                let report_file_name = format!("mb-dry-run-{}.json", Self::name());
+                say!(
+                    "Dry run: {}/{} actions would fail. Writing report to {}",
+                    dry_run_report.failed_count(),
+                    update_tasks_len,
+                    report_file_name
+                );
+                dry_run_report.write(File::create(&report_file_name)?)?;
+                break;
             }
-            pb.finish_and_clear();
 
-            // Partition migration results.
-            let (_update_successful, update_failed): (CacheResults, CacheResults) =
-                update_results.into_iter().partition(Result::is_ok);
+            say!("Updates failed: {}", update_failed.len());
 
-            println!("Updates failed: {}", update_failed.len());
+            // Retrying can't help once --max-spend has been reached.
+            if let Err(e) = SPEND_GUARD.ensure_not_exceeded() {
+                outcome = Err(e);
+                break;
+            }
 
-            // If some of the migrations failed, check the retry count and re-run if appropriate,
-            // otherwise, break out of the loop and write the cache to disk.
+            // If some of the updates failed, check the retry count and re-run if appropriate,
+            // otherwise, break out of the loop. The run state already holds every outcome.
             if !update_failed.is_empty() && counter < args.retries {
                 counter += 1;
-                println!(
+
+                // Failed items whose transaction was sent may still land, so check them first.
+                if let Some(run_state) = &run_state {
+                    let mut run_state = run_state.lock().unwrap();
+                    reconcile_sent(&client, &mut run_state)?;
+                    update_failed.retain(|mint| {
+                        !matches!(
+                            run_state.get(mint),
+                            Some(entry) if entry.status == ItemStatus::Confirmed
+                        )
+                    });
+                }
+
+                say!(
                     "{}/{} updates failed. Retrying. . .",
                     &update_failed.len(),
                     update_tasks_len
                 );
-                cache.update_errors(update_failed);
-                mint_list = cache.keys().map(|m| m.to_string()).collect();
+                mint_list = update_failed;
             } else if update_failed.is_empty() {
                 // None failed so we exit the loop.
-                println!("All actions successfully run!");
+                say!("All actions successfully run!");
                 break;
             } else {
-                println!("Reached max retries. Writing remaining items to cache.");
-                cache.update_errors(update_failed);
-                cache.write(f)?;
+                say!(
+                    "Reached max retries. Remaining items are recorded in {}; run again with --resume to continue.",
+                    cache_file_name
+                );
                 break;
             }
         }
 
-        Ok(())
+        for (mint_address, error) in item_errors {
+            let entry = run_state
+                .as_ref()
+                .and_then(|run_state| run_state.lock().unwrap().get(&mint_address).cloned());
+            let status = match (&error, &entry) {
+                (None, _) => Status::Ok,
+                (Some(_), Some(entry)) if entry.status == ItemStatus::Confirmed => Status::Ok,
+                (Some(_), Some(entry)) if entry.status == ItemStatus::Sent => Status::Sent,
+                (Some(_), _) => Status::Failed,
+            };
+            let item = ItemOutput::new(&mint_address, status)
+                .signature(entry.and_then(|entry| entry.signature));
+            emit_item(match error {
+                Some(error) if status != Status::Ok => item.error(error),
+                _ => item,
+            });
+        }
+
+        if let (false, Some(path)) = (journal.is_empty(), journal.path()) {
+            say!(
+                "Metadata diffs for {} mints written to {}",
+                journal.len(),
+                path.display()
+            );
+        }
+
+        outcome
+    }
+}
+
+/// The new value for a mint, from the batch's single value or per-mint list.
+fn new_value_for(new_value: &NewValue, mint_address: &str) -> Result<String, ActionError> {
+    match new_value {
+        NewValue::None => Ok(String::new()),
+        NewValue::Single(value) => Ok(value.clone()),
+        NewValue::List(values) => values.get(mint_address).cloned().ok_or_else(|| {
+            ActionError::ActionFailed(
+                mint_address.to_string(),
+                "mint found in cache but missing from input list".to_string(),
+            )
+        }),
+    }
+}
+
+/// Run an action for one mint, journaling the signatures it sends and its
+/// outcome as soon as it resolves.
+async fn track_outcome<F>(
+    run_state: Option<SharedRunState>,
+    mint_address: String,
+    fut: F,
+) -> Result<(), ActionError>
+where
+    F: Future<Output = Result<(), ActionError>>,
+{
+    let Some(run_state) = run_state else {
+        return fut.await;
+    };
+
+    let result = TrackItem::new(&mint_address, run_state.clone(), fut).await;
+    record_outcome(&run_state, &mint_address, &result);
+
+    result
+}
+
+/// Record an item's outcome once its action has resolved.
+fn record_outcome(
+    run_state: &SharedRunState,
+    mint_address: &str,
+    result: &Result<(), ActionError>,
+) {
+    let mut state = run_state.lock().unwrap();
+    let sent = state
+        .get(mint_address)
+        .filter(|entry| entry.status == ItemStatus::Sent)
+        .cloned();
+    let entry = match (result, sent) {
+        (Ok(()), sent) => {
+            let entry = RunStateEntry::new(mint_address, ItemStatus::Confirmed);
+            match sent.and_then(|sent| sent.signature) {
+                Some(signature) => entry.signature(signature),
+                None => entry,
+            }
+        }
+        // The transaction went out and may still land, so it stays `Sent` until checked.
+        (Err(e), Some(sent)) => RunStateEntry {
+            error: Some(format_action_error(e)),
+            ..sent
+        },
+        (Err(e), None) => {
+            RunStateEntry::new(mint_address, ItemStatus::Failed).error(format_action_error(e))
+        }
+    };
+    if let Err(e) = state.record(entry) {
+        error!("Failed to write run state for {mint_address}: {e}");
     }
 }
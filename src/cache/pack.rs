@@ -0,0 +1,551 @@
+use metaboss_lib::data::Priority;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use anyhow::Result as AnyResult;
+
+use std::sync::Arc;
+
+use crate::{
+    errors::ActionError,
+    limiter::Pacer,
+    offline::compute_units_unsigned,
+    priority::with_priority,
+    run_state::{ItemStatus, RunStateEntry, SharedRunState, TrackItem},
+    signer::{send_and_confirm_signed, SharedSigner},
+    spinner::create_progress_bar,
+    update::{DiffJournal, MetadataDiff},
+};
+
+use super::{new_value_for, record_outcome, Action, NewValue, RunActionArgs};
+
+/// Instructions for one item of a batch action that can share a transaction with other items.
+pub struct PackedAction {
+    pub instructions: Vec<Instruction>,
+    /// Recorded in the run's diff journal once the transaction lands.
+    pub diff: Option<MetadataDiff>,
+}
+
+pub struct PackItem {
+    pub mint_address: String,
+    pub action: PackedAction,
+}
+
+/// Compute budget instructions with placeholder values, used to size a transaction.
+fn compute_budget_placeholders() -> [Instruction; 2] {
+    [
+        ComputeBudgetInstruction::set_compute_unit_limit(u32::MAX),
+        ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+    ]
+}
+
+/// Whether a transaction with these instructions, plus compute budget
+/// instructions, fits in a single packet.
+///
+/// With lookup tables the transaction is compiled as a v0 message, so accounts
+/// found in a table take one byte instead of 32.
+pub fn fits(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> bool {
+    let mut ixs = compute_budget_placeholders().to_vec();
+    ixs.extend_from_slice(instructions);
+
+    // An unsigned transaction has placeholder signatures, so its size is final.
+    let size = if lookup_tables.is_empty() {
+        let message = Message::new(&ixs, Some(payer));
+        bincode::serialized_size(&Transaction::new_unsigned(message))
+    } else {
+        let Ok(message) = v0::Message::try_compile(payer, &ixs, lookup_tables, Hash::default())
+        else {
+            return false;
+        };
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+            message: VersionedMessage::V0(message),
+        };
+        bincode::serialized_size(&tx)
+    };
+
+    size.map(|size| size as usize <= PACKET_DATA_SIZE)
+        .unwrap_or(false)
+}
+
+/// Greedily group items, in order, so each group fits in one transaction.
+///
+/// An item too large to share a transaction still gets a group of its own.
+pub fn pack<T, F>(
+    items: Vec<T>,
+    instructions: F,
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Vec<Vec<T>>
+where
+    F: Fn(&T) -> &[Instruction],
+{
+    pack_with_prefix(items, instructions, &[], payer, lookup_tables)
+}
+
+/// Like [`pack`], but leaves room in every transaction for `prefix`, the
+/// instructions each one starts with, e.g. a durable nonce advance.
+pub fn pack_with_prefix<T, F>(
+    items: Vec<T>,
+    instructions: F,
+    prefix: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Vec<Vec<T>>
+where
+    F: Fn(&T) -> &[Instruction],
+{
+    let mut groups: Vec<Vec<T>> = Vec::new();
+    let mut current: Vec<T> = Vec::new();
+    let mut current_ixs: Vec<Instruction> = prefix.to_vec();
+
+    for item in items {
+        let mut candidate = current_ixs.clone();
+        candidate.extend_from_slice(instructions(&item));
+
+        if current.is_empty() || fits(&candidate, payer, lookup_tables) {
+            current_ixs = candidate;
+            current.push(item);
+        } else {
+            current_ixs = prefix.to_vec();
+            current_ixs.extend_from_slice(instructions(&item));
+            groups.push(std::mem::replace(&mut current, vec![item]));
+        }
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    groups
+}
+
+/// Groups that simulated successfully, and the items that failed on their own.
+pub struct Simulated {
+    /// Each group with the compute units its simulation needed.
+    pub groups: Vec<(Vec<PackItem>, Option<u64>)>,
+    pub failed: Vec<(String, Result<(), ActionError>)>,
+}
+
+/// Simulate a group of packed items with `simulate`.
+///
+/// If the simulation fails, the group is split in half and each half is tried
+/// again, so one bad mint only fails itself.
+pub fn split_until_simulated<F>(group: Vec<PackItem>, mut simulate: F) -> Simulated
+where
+    F: FnMut(&[Instruction]) -> AnyResult<Option<u64>>,
+{
+    let mut simulated = Simulated {
+        groups: Vec::new(),
+        failed: Vec::new(),
+    };
+    let mut pending = vec![group];
+
+    while let Some(mut group) = pending.pop() {
+        let instructions: Vec<Instruction> = group
+            .iter()
+            .flat_map(|item| item.action.instructions.iter().cloned())
+            .collect();
+
+        match simulate(&instructions) {
+            Ok(units) => simulated.groups.push((group, units)),
+            Err(e) if group.len() == 1 => {
+                let mint_address = group.remove(0).mint_address;
+                let result = Err(ActionError::ActionFailed(
+                    mint_address.clone(),
+                    e.to_string(),
+                ));
+                simulated.failed.push((mint_address, result));
+            }
+            Err(_) => {
+                let second = group.split_off(group.len() / 2);
+                // Popped in order: the first half is tried first.
+                pending.push(second);
+                pending.push(group);
+            }
+        }
+    }
+
+    simulated
+}
+
+pub struct SendGroupArgs {
+    pub client: Arc<RpcClient>,
+    /// Signs the action's instructions.
+    pub authority: SharedSigner,
+    /// Pays the fees, if not the authority.
+    pub payer: Option<SharedSigner>,
+    pub priority: Priority,
+    pub run_state: Option<SharedRunState>,
+    pub journal: Arc<DiffJournal>,
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+/// Send a group of packed items, returning the outcome for each item.
+///
+/// The group is simulated first and split until each part simulates, see
+/// [`split_until_simulated`]. A send error then fails the whole part: the
+/// transaction may still land, so it is never split and resent here.
+pub async fn send_group(
+    args: &SendGroupArgs,
+    group: Vec<PackItem>,
+) -> Vec<(String, Result<(), ActionError>)> {
+    let payer = args.payer.as_ref().unwrap_or(&args.authority);
+    let mut signers: Vec<&(dyn Signer + Send + Sync)> = vec![payer.as_ref()];
+    if args.authority.pubkey() != payer.pubkey() {
+        signers.push(args.authority.as_ref());
+    }
+
+    // Simulated unsigned, so an external signer only signs what is sent.
+    let simulated = split_until_simulated(group, |instructions| {
+        compute_units_unsigned(
+            &args.client,
+            instructions,
+            &payer.pubkey(),
+            &args.lookup_tables,
+        )
+    });
+
+    let mut results = Vec::new();
+    for (mint_address, result) in simulated.failed {
+        if let Some(run_state) = &args.run_state {
+            record_outcome(run_state, &mint_address, &result);
+        }
+        results.push((mint_address, result));
+    }
+
+    for (group, units) in simulated.groups {
+        let mut ixs = Vec::new();
+        if let Some(units) = units {
+            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(
+                units as u32,
+            ));
+        }
+        ixs.extend(
+            group
+                .iter()
+                .flat_map(|item| item.action.instructions.iter().cloned()),
+        );
+
+        let mint_addresses: Vec<String> =
+            group.iter().map(|item| item.mint_address.clone()).collect();
+        let send = async {
+            let ixs = with_priority(&args.client, &args.priority, &ixs)?;
+            send_and_confirm_signed(&args.client, &signers, &ixs, &args.lookup_tables)
+        };
+        let sent = match &args.run_state {
+            Some(run_state) => TrackItem::batch(mint_addresses, run_state.clone(), send).await,
+            None => send.await,
+        };
+
+        for item in group {
+            let result = match &sent {
+                Ok(_) => {
+                    if let Some(diff) = item.action.diff {
+                        args.journal.insert(&item.mint_address, diff);
+                    }
+                    Ok(())
+                }
+                Err(e) => Err(ActionError::ActionFailed(
+                    item.mint_address.clone(),
+                    e.to_string(),
+                )),
+            };
+            if let Some(run_state) = &args.run_state {
+                record_outcome(run_state, &item.mint_address, &result);
+            }
+            results.push((item.mint_address, result));
+        }
+    }
+
+    results
+}
+
+pub struct PackedRoundArgs<'a> {
+    pub mints: Vec<String>,
+    pub client: Arc<RpcClient>,
+    pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
+    /// An external signer standing in for the keypair.
+    pub signer: Option<SharedSigner>,
+    /// Signs the action's instructions; the keypair unless running offline or
+    /// proposing to a multisig.
+    pub authority: Pubkey,
+    /// Pays for accounts the action's instructions create.
+    pub fee_payer: Pubkey,
+    pub new_value: &'a NewValue,
+    pub should_append: bool,
+    pub priority: Priority,
+    pub journal: Arc<DiffJournal>,
+    pub run_state: Option<SharedRunState>,
+    pub rate_limiter: Pacer,
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+/// Build every mint's instructions, returning the built items and the
+/// outcomes of the mints that failed to build.
+pub async fn build_items<A: Action + ?Sized>(
+    args: &PackedRoundArgs<'_>,
+) -> AnyResult<(Vec<PackItem>, Vec<(String, Result<(), ActionError>)>)> {
+    let rate_limiter = args.rate_limiter.clone();
+
+    let pb = create_progress_bar("Building instructions...", args.mints.len() as u64);
+    let mut build_tasks = Vec::new();
+    for mint_address in &args.mints {
+        let new_value = new_value_for(args.new_value, mint_address)?;
+
+        if let Some(run_state) = &args.run_state {
+            run_state
+                .lock()
+                .unwrap()
+                .record(RunStateEntry::new(mint_address, ItemStatus::Pending))?;
+        }
+
+        rate_limiter.wait();
+        let task = tokio::spawn(A::instructions(RunActionArgs {
+            client: args.client.clone(),
+            keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
+            authority: args.authority,
+            fee_payer: args.fee_payer,
+            mint_account: mint_address.clone(),
+            new_value,
+            should_append: args.should_append,
+            priority: args.priority.clone(),
+            journal: args.journal.clone(),
+        }));
+        build_tasks.push((mint_address.clone(), task));
+    }
+
+    let mut failed = Vec::new();
+    let mut items = Vec::new();
+    for (mint_address, task) in build_tasks {
+        match task.await.unwrap() {
+            Ok(action) => items.push(PackItem {
+                mint_address,
+                action,
+            }),
+            Err(e) => {
+                let result = Err(e);
+                if let Some(run_state) = &args.run_state {
+                    record_outcome(run_state, &mint_address, &result);
+                }
+                failed.push((mint_address, result));
+            }
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    Ok((items, failed))
+}
+
+/// Run one round of a packable action: build every mint's instructions, then
+/// send them packed into as few transactions as fit.
+pub async fn run_packed_round<A: Action + ?Sized>(
+    args: PackedRoundArgs<'_>,
+) -> AnyResult<Vec<(String, Result<(), ActionError>)>> {
+    let (items, mut results) = build_items::<A>(&args).await?;
+    let rate_limiter = args.rate_limiter;
+
+    let authority: SharedSigner = args.signer.clone().unwrap_or_else(|| args.keypair.clone());
+    let payer: Option<SharedSigner> = args
+        .payer
+        .as_ref()
+        .as_ref()
+        .map(|payer| Arc::new(payer.insecure_clone()) as SharedSigner);
+    let fee_payer = payer.as_ref().unwrap_or(&authority).pubkey();
+    let item_count = items.len();
+    let groups = pack(
+        items,
+        |item: &PackItem| item.action.instructions.as_slice(),
+        &fee_payer,
+        &args.lookup_tables,
+    );
+    say!(
+        "Packed {} mints into {} transactions.",
+        item_count,
+        groups.len()
+    );
+
+    let send_args = Arc::new(SendGroupArgs {
+        client: args.client,
+        authority,
+        payer,
+        priority: args.priority,
+        run_state: args.run_state,
+        journal: args.journal,
+        lookup_tables: args.lookup_tables,
+    });
+
+    let pb = create_progress_bar("Sending transactions...", groups.len() as u64);
+    let mut send_tasks = Vec::new();
+    for group in groups {
+        rate_limiter.wait();
+        let send_args = send_args.clone();
+        send_tasks.push(tokio::spawn(
+            async move { send_group(&send_args, group).await },
+        ));
+    }
+    for task in send_tasks {
+        results.extend(task.await.unwrap());
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    fn item(mint_address: &str, accounts: usize, data_len: usize) -> PackItem {
+        let accounts = (0..accounts)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect();
+        item_with_accounts(mint_address, Pubkey::new_unique(), accounts, data_len)
+    }
+
+    fn item_with_accounts(
+        mint_address: &str,
+        program_id: Pubkey,
+        accounts: Vec<AccountMeta>,
+        data_len: usize,
+    ) -> PackItem {
+        PackItem {
+            mint_address: mint_address.to_string(),
+            action: PackedAction {
+                instructions: vec![Instruction::new_with_bytes(
+                    program_id,
+                    &vec![0; data_len],
+                    accounts,
+                )],
+                diff: None,
+            },
+        }
+    }
+
+    fn instructions(item: &PackItem) -> &[Instruction] {
+        &item.action.instructions
+    }
+
+    #[test]
+    fn packed_groups_fit_in_one_packet() {
+        let payer = Pubkey::new_unique();
+        let items: Vec<PackItem> = (0..20).map(|i| item(&format!("mint-{i}"), 3, 40)).collect();
+
+        let groups = pack(items, instructions, &payer, &[]);
+
+        assert!(groups.len() > 1);
+        assert_eq!(groups.iter().map(Vec::len).sum::<usize>(), 20);
+        for group in &groups {
+            let ixs: Vec<Instruction> = group
+                .iter()
+                .flat_map(|item| item.action.instructions.clone())
+                .collect();
+            assert!(fits(&ixs, &payer, &[]));
+        }
+        // Items keep their order.
+        assert_eq!(groups[0][0].mint_address, "mint-0");
+        assert_eq!(
+            groups[1][0].mint_address,
+            format!("mint-{}", groups[0].len())
+        );
+    }
+
+    #[test]
+    fn oversized_item_gets_its_own_group() {
+        let payer = Pubkey::new_unique();
+        let items = vec![item("a", 1, 10), item("b", 1, 2000), item("c", 1, 10)];
+
+        let groups = pack(items, instructions, &payer, &[]);
+
+        let sizes: Vec<usize> = groups.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn lookup_table_fits_more_items_per_group() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let shared: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let items: Vec<PackItem> = (0..40)
+            .map(|i| {
+                let mut accounts: Vec<AccountMeta> = shared
+                    .iter()
+                    .map(|key| AccountMeta::new_readonly(*key, false))
+                    .collect();
+                accounts.extend((0..2).map(|_| AccountMeta::new(Pubkey::new_unique(), false)));
+                item_with_accounts(&format!("mint-{i}"), program_id, accounts, 10)
+            })
+            .collect();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: items
+                .iter()
+                .flat_map(|item| {
+                    item.action.instructions[0]
+                        .accounts
+                        .iter()
+                        .map(|a| a.pubkey)
+                })
+                .chain(shared.iter().copied())
+                .collect(),
+        };
+        let legacy = pack(
+            items.iter().collect(),
+            |item: &&PackItem| item.action.instructions.as_slice(),
+            &payer,
+            &[],
+        );
+        let v0 = pack(
+            items.iter().collect(),
+            |item: &&PackItem| item.action.instructions.as_slice(),
+            &payer,
+            &[table],
+        );
+
+        assert!(v0.len() < legacy.len());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failed_simulation_splits_down_to_single_mints() {
+        let args = SendGroupArgs {
+            // The "fails" mock returns an error for every request.
+            client: Arc::new(RpcClient::new_mock("fails".to_string())),
+            authority: Arc::new(Keypair::new()),
+            payer: None,
+            priority: Priority::None,
+            run_state: None,
+            journal: Arc::new(DiffJournal::new()),
+            lookup_tables: vec![],
+        };
+        let group = vec![item("a", 1, 10), item("b", 1, 10), item("c", 1, 10)];
+
+        let results = send_group(&args, group).await;
+
+        let failed: Vec<&str> = results
+            .iter()
+            .map(|(mint_address, result)| {
+                assert!(result.is_err());
+                mint_address.as_str()
+            })
+            .collect();
+        assert_eq!(failed, vec!["a", "b", "c"]);
+    }
+}
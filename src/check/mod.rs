@@ -59,9 +59,9 @@ fn check_value_all(metadata_file_dir: PathBuf, value: MetadataValue) -> Result<(
     }
 
     if !paths.is_empty() {
-        println!("Files with metadata that don't match the specified value:");
+        say!("Files with metadata that don't match the specified value:");
         for path in paths {
-            println!("{}", path.display());
+            say!("{}", path.display());
         }
         let file_name = format!(
             "mb_check_mints_{}.json",
@@ -69,9 +69,9 @@ fn check_value_all(metadata_file_dir: PathBuf, value: MetadataValue) -> Result<(
         );
         let f = std::fs::File::create(&file_name)?;
         serde_json::to_writer_pretty(f, &mints)?;
-        println!("Mints written to {:?}.", file_name);
+        say!("Mints written to {:?}.", file_name);
     } else {
-        println!("All metadata files have the specified value!");
+        say!("All metadata files have the specified value!");
     }
 
     Ok(())
@@ -1,6 +1,7 @@
 use super::*;
 use crate::collections::data::*;
 use crate::derive::derive_metadata_pda;
+use crate::snapshot::{write_mints, ExportFormat};
 use crate::spinner::create_alt_spinner;
 use crate::theindexio::THE_INDEX_MAINNET;
 use borsh::BorshDeserialize;
@@ -9,11 +10,12 @@ pub async fn get_collection_items(
     collection_mint: String,
     method: GetCollectionItemsMethods,
     api_key: Option<String>,
+    format: ExportFormat,
 ) -> AnyResult<()> {
     match method {
         GetCollectionItemsMethods::TheIndexIO => {
             if let Some(key) = api_key {
-                get_collection_items_by_the_index_io(collection_mint, key).await?
+                get_collection_items_by_the_index_io(collection_mint, key, format).await?
             } else {
                 return Err(anyhow!(
                     "This method requires an index key for TheIndex.io."
@@ -27,6 +29,7 @@ pub async fn get_collection_items(
 pub async fn get_collection_items_by_the_index_io(
     collection_mint: String,
     api_key: String,
+    format: ExportFormat,
 ) -> AnyResult<()> {
     let jrpc = JRPCRequest::new("getNFTsByCollection", vec![collection_mint.clone()]);
     let url = format!("{THE_INDEX_MAINNET}/{api_key}");
@@ -44,12 +47,11 @@ pub async fn get_collection_items_by_the_index_io(
         .map(|nft| nft.metadata.mint.clone())
         .collect();
 
-    let file_name = format!("{collection_mint}_collection_items.json");
-    let f = File::create(&file_name).unwrap();
+    let file_name = format.file_name(&format!("{collection_mint}_collection_items"));
 
     mints.sort_unstable();
-    serde_json::to_writer_pretty(f, &mints).unwrap();
-    println!("Data written to {file_name}");
+    write_mints(Path::new(&file_name), format, &mints)?;
+    say!("Data written to {file_name}");
 
     Ok(())
 }
@@ -133,7 +135,7 @@ pub async fn check_collection_items(
     // Check if there's the only one and correct collection parent associated with the mint list and that all items in the list belong to it.
     if !keys.contains(&&collection_mint) || keys.len() > 1 {
         if debug {
-            println!("Writing debug file...");
+            say!("Writing debug file...");
             let out = File::create(format!("{collection_mint}-debug-collections.json"))?;
             serde_json::to_writer_pretty(out, &collections)?;
         }
@@ -162,7 +164,7 @@ pub async fn check_collection_items(
         ));
     }
 
-    println!("All mints are in the collection!");
+    say!("All mints are in the collection!");
     Ok(())
 }
 
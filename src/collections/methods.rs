@@ -1,9 +1,10 @@
 use super::*;
 
-use crate::parse::parse_keypair;
+use crate::parse::{parse_keypair, parse_payer};
+use crate::proposal::{multisig_settings, propose};
 use crate::{parse::parse_solana_config, utils::send_and_confirm_transaction};
 use metaboss_lib::{
-    data::Priority,
+    data::{Asset, Priority},
     delegate::{delegate_asset, DelegateAssetArgs},
     derive::derive_metadata_pda,
     revoke::{revoke_asset, RevokeAssetArgs},
@@ -13,9 +14,14 @@ use metaboss_lib::{
 };
 use mpl_token_metadata::types::SetCollectionSizeArgs;
 use mpl_token_metadata::{
-    instructions::SetCollectionSizeBuilder,
-    types::{CollectionToggle, DelegateArgs, RevokeArgs},
+    accounts::MetadataDelegateRecord,
+    hooked::MetadataDelegateRoleSeed,
+    instructions::{
+        SetCollectionSizeBuilder, UnverifyCollectionV1Builder, VerifyCollectionV1Builder,
+    },
+    types::{CollectionToggle, DelegateArgs, MetadataDelegateRole, RevokeArgs},
 };
+use solana_sdk::instruction::Instruction;
 
 pub const OPEN_FILES_LIMIT: usize = 1024;
 
@@ -28,6 +34,7 @@ pub fn set_and_verify_nft_collection(
 ) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
 
     let collection_pubkey = Pubkey::from_str(&collection_mint)?;
 
@@ -44,7 +51,7 @@ pub fn set_and_verify_nft_collection(
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: payer.as_ref(),
         authority: &keypair,
         mint: nft_mint.clone(),
         token: None::<String>,
@@ -95,6 +102,78 @@ pub fn unverify_nft_collection(
     Ok(())
 }
 
+/// The verify instruction for an `authority` that signs elsewhere, such as a
+/// multisig vault.
+pub fn verify_collection_ix_for_authority(
+    client: &RpcClient,
+    authority: Pubkey,
+    nft_mint: &str,
+    collection_mint: &str,
+    is_delegate_present: bool,
+) -> AnyResult<Instruction> {
+    let asset = Asset::new(Pubkey::from_str(nft_mint)?);
+    let collection_mint = Pubkey::from_str(collection_mint)?;
+    let mut collection_asset = Asset::new(collection_mint);
+    collection_asset.add_edition();
+
+    let md = asset.get_metadata(client)?;
+
+    let mut verify_builder = VerifyCollectionV1Builder::new();
+    verify_builder
+        .authority(authority)
+        .metadata(asset.metadata)
+        .collection_mint(collection_mint)
+        .collection_metadata(Some(collection_asset.metadata))
+        .collection_master_edition(collection_asset.edition);
+
+    if is_delegate_present {
+        let (delegate_record, _) = MetadataDelegateRecord::find_pda(
+            &collection_mint,
+            MetadataDelegateRoleSeed::from(MetadataDelegateRole::Collection),
+            &md.update_authority,
+            &authority,
+        );
+        verify_builder.delegate_record(Some(delegate_record));
+    }
+
+    Ok(verify_builder.instruction())
+}
+
+/// The unverify instruction for an `authority` that signs elsewhere, such as a
+/// hardware wallet.
+pub fn unverify_collection_ix_for_authority(
+    client: &RpcClient,
+    authority: Pubkey,
+    nft_mint: &str,
+    collection_mint: &Pubkey,
+    is_delegate_present: bool,
+) -> AnyResult<Instruction> {
+    let asset = Asset::new(Pubkey::from_str(nft_mint)?);
+    let mut collection_asset = Asset::new(*collection_mint);
+    collection_asset.add_edition();
+
+    let md = asset.get_metadata(client)?;
+
+    let mut unverify_builder = UnverifyCollectionV1Builder::new();
+    unverify_builder
+        .authority(authority)
+        .metadata(asset.metadata)
+        .collection_mint(*collection_mint)
+        .collection_metadata(Some(collection_asset.metadata));
+
+    if is_delegate_present {
+        let (delegate_record, _) = MetadataDelegateRecord::find_pda(
+            collection_mint,
+            MetadataDelegateRoleSeed::from(MetadataDelegateRole::Collection),
+            &md.update_authority,
+            &authority,
+        );
+        unverify_builder.delegate_record(Some(delegate_record));
+    }
+
+    Ok(unverify_builder.instruction())
+}
+
 pub fn verify_nft_collection(
     client: RpcClient,
     keypair_path: Option<String>,
@@ -102,6 +181,17 @@ pub fn verify_nft_collection(
     collection_mint: String,
     is_delegate_present: bool,
 ) -> AnyResult<()> {
+    if let Some(multisig) = multisig_settings() {
+        let ix = verify_collection_ix_for_authority(
+            &client,
+            multisig.vault,
+            &nft_mint,
+            &collection_mint,
+            is_delegate_present,
+        )?;
+        return propose("verify-collection", &nft_mint, &[ix], &multisig);
+    }
+
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(keypair_path, solana_opts);
 
@@ -127,11 +217,12 @@ pub fn approve_delegate(
 ) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
 
     let delegate_pubkey = Pubkey::from_str(&delegate_authority)?;
 
     let delegate_args = DelegateAssetArgs::V1 {
-        payer: None,
+        payer: payer.as_ref(),
         authority: &keypair,
         mint: collection_mint,
         delegate: delegate_pubkey,
@@ -143,7 +234,7 @@ pub fn approve_delegate(
 
     let sig = delegate_asset(&client, delegate_args)?;
 
-    println!("Signature: {}", sig);
+    say!("Signature: {}", sig);
 
     Ok(())
 }
@@ -156,11 +247,12 @@ pub fn revoke_delegate(
 ) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
 
     let delegate_pubkey = Pubkey::from_str(&delegate_authority)?;
 
     let revoke_args = RevokeAssetArgs::V1 {
-        payer: None,
+        payer: payer.as_ref(),
         authority: &keypair,
         mint: collection_mint,
         delegate: delegate_pubkey,
@@ -170,7 +262,7 @@ pub fn revoke_delegate(
 
     let sig = revoke_asset(&client, revoke_args)?;
 
-    println!("Signature: {}", sig);
+    say!("Signature: {}", sig);
 
     Ok(())
 }
@@ -1,23 +1,20 @@
 use super::*;
 
-use crate::constants::NANO_SECONDS_IN_SECOND;
-use crate::limiter::create_rate_limiter_with_capacity;
+use crate::landing::land;
+use crate::limiter::Pacer;
+use crate::run_state::{ItemStatus, RunState, RunStateEntry};
+use crate::signer::{parse_signer, payer_first, SharedSigner};
+use crate::snapshot::get_mint_accounts;
 use crate::spinner::create_progress_bar;
-use crate::{derive::derive_metadata_pda, errors::MigrateError, parse::parse_solana_config};
-use crate::{parse::parse_keypair, snapshot::get_mint_accounts};
-use metaboss_lib::{
-    data::Priority,
-    unverify::{unverify_collection_ix, UnverifyCollectionArgs},
-    update::{update_asset_ix, UpdateAssetArgs, V1UpdateArgs},
-    verify::{verify_collection_ix, VerifyCollectionArgs},
+use crate::update::update_asset_ix_for_authority;
+use crate::{
+    derive::derive_metadata_pda,
+    errors::MigrateError,
+    parse::{parse_payer, parse_solana_config},
 };
+use metaboss_lib::update::V1UpdateArgs;
 use mpl_token_metadata::types::CollectionToggle;
-use solana_sdk::{
-    signature::{Keypair, Signature},
-    signer::Signer,
-    transaction::Transaction,
-};
-use std::ops::{Deref, DerefMut};
+use solana_sdk::signature::Signature;
 
 pub struct MigrateArgs {
     pub client: RpcClient,
@@ -32,66 +29,10 @@ pub struct MigrateArgs {
     pub output_file: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct MigrateCache(IndexMap<String, CacheItem>);
-pub type MigrateResults = Vec<Result<(), MigrateError>>;
-
-impl Default for MigrateCache {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl Deref for MigrateCache {
-    type Target = IndexMap<String, CacheItem>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl DerefMut for MigrateCache {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
-}
-
-impl MigrateCache {
-    pub fn new() -> Self {
-        MigrateCache(IndexMap::new())
-    }
-
-    pub fn write<W: Write>(&mut self, writer: W) -> AnyResult<()> {
-        self.sort_unstable_keys();
-        serde_json::to_writer_pretty(writer, &self)?;
-        Ok(())
-    }
-
-    pub fn update_errors(&mut self, errors: Vec<MigrateError>) {
-        self.clear();
-
-        for error in errors {
-            match error {
-                MigrateError::MigrationFailed(ref mint_address, _) => {
-                    let item = CacheItem {
-                        error: Some(error.to_string()),
-                    };
-
-                    self.insert(mint_address.to_string(), item);
-                }
-            }
-        }
-    }
-}
-
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct CacheItem {
-    pub error: Option<String>,
-}
-
 async fn set_and_verify(
     client: Arc<RpcClient>,
-    authority_keypair: Arc<Keypair>,
+    authority: SharedSigner,
+    payer: Option<SharedSigner>,
     nft_mint: String,
     collection_mint: String,
     is_delegate: bool,
@@ -113,18 +54,15 @@ async fn set_and_verify(
 
     if let Some(current_collection) = nft_metadata.collection {
         if current_collection.verified {
-            let current_collection_mint = current_collection.key;
-
-            let unverify_args = UnverifyCollectionArgs::V1 {
-                authority: &authority_keypair,
-                mint: nft_mint.clone(),
-                collection_mint: current_collection_mint,
-                is_delegate,
-            };
-
             // This instruction handles both the case where the collection NFT exists and the case where it doesn't.
-            let ix = unverify_collection_ix(&client, unverify_args)
-                .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))?;
+            let ix = unverify_collection_ix_for_authority(
+                &client,
+                authority.pubkey(),
+                &nft_mint,
+                &current_collection.key,
+                is_delegate,
+            )
+            .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))?;
             instructions.push(ix);
         }
     }
@@ -139,52 +77,33 @@ async fn set_and_verify(
         ..Default::default()
     };
 
-    let update_ix = update_asset_ix(
+    let payer = payer.unwrap_or_else(|| authority.clone());
+    let update_ix = update_asset_ix_for_authority(
         &client,
-        UpdateAssetArgs::V1 {
-            authority: &authority_keypair,
-            mint: nft_mint.clone(),
-            payer: None,
-            token: None::<String>,
-            delegate_record: None::<String>, // Not supported yet in update.
-            update_args,
-            priority: Priority::None,
-        },
+        payer.pubkey(),
+        authority.pubkey(),
+        &nft_mint,
+        update_args,
     )
     .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))?;
 
     instructions.push(update_ix);
 
-    // Add verify instruction to verify the collection.
-    let verify_args = VerifyCollectionArgs::V1 {
-        authority: &authority_keypair,
-        mint: nft_mint.clone(),
-        collection_mint,
-        is_delegate,
-    };
-
     // This instruction handles both the case where the collection NFT exists and the case where it doesn't.
-    let verify_ix = verify_collection_ix(&client, verify_args)
-        .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))?;
+    let verify_ix = verify_collection_ix_for_authority(
+        &client,
+        authority.pubkey(),
+        &nft_mint,
+        &collection_mint,
+        is_delegate,
+    )
+    .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))?;
 
     instructions.push(verify_ix);
 
-    let recent_blockhash = client
-        .get_latest_blockhash()
-        .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))?;
-
-    let tx = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&authority_keypair.pubkey()),
-        &[&*authority_keypair],
-        recent_blockhash,
-    );
-
-    let sig = client
-        .send_and_confirm_transaction(&tx)
-        .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))?;
-
-    Ok(sig)
+    let signers = payer_first(payer.as_ref(), authority.as_ref());
+    land(&client, &signers[..], &instructions, &[])
+        .map_err(|e| MigrateError::MigrationFailed(nft_mint.clone(), e.to_string()))
 }
 
 pub async fn migrate_collection(args: MigrateArgs) -> AnyResult<()> {
@@ -201,14 +120,16 @@ pub async fn migrate_collection(args: MigrateArgs) -> AnyResult<()> {
     }
 
     // Default name, if we don't get an output_file option or a cache file.
-    let mut cache_file_name = String::from("mb-cache-migrate.json");
-    let mut cache = MigrateCache::new();
+    let mut cache_file_name = String::from("mb-cache-migrate.jsonl");
 
+    // The authority may be a hardware wallet, in which case every mint needs
+    // approving on the device.
     let solana_opts = parse_solana_config();
-    let keypair = Arc::new(parse_keypair(args.keypair, solana_opts));
+    let keypair = parse_signer(args.keypair, solana_opts)?;
+    let payer: Option<SharedSigner> = parse_payer()?.map(|payer| Arc::new(payer) as SharedSigner);
 
     let mut mint_accounts = if let Some(candy_machine_id) = args.candy_machine_id {
-        println!("Using candy machine id to fetch mint list. . .");
+        say!("Using candy machine id to fetch mint list. . .");
         get_mint_accounts(
             &args.client,
             &Some(candy_machine_id),
@@ -221,13 +142,15 @@ pub async fn migrate_collection(args: MigrateArgs) -> AnyResult<()> {
     } else if let Some(mint_list) = args.mint_list {
         let f = File::open(mint_list)?;
         serde_json::from_reader(f)?
-    } else if let Some(cache_path) = args.cache_file {
-        println!("Retrying items from cache file. . .");
-        cache_file_name = cache_path;
-
-        let f = File::open(&cache_file_name)?;
-        let cache: MigrateCache = serde_json::from_reader(f)?;
-        cache.0.keys().map(|k| k.to_string()).collect()
+    } else if let Some(cache_path) = &args.cache_file {
+        say!("Retrying items from cache file. . .");
+        cache_file_name = cache_path.clone();
+
+        RunState::load(cache_path)?
+            .into_values()
+            .filter(|entry| entry.status != ItemStatus::Confirmed)
+            .map(|entry| entry.item)
+            .collect()
     } else {
         return Err(anyhow!(
             "Please specify either a candy machine id or an mint_list file."
@@ -239,21 +162,17 @@ pub async fn migrate_collection(args: MigrateArgs) -> AnyResult<()> {
         cache_file_name = path;
     }
 
-    let f = if !Path::new(&cache_file_name).exists() {
-        File::create(&cache_file_name)?
+    // Continue the existing run state when retrying in place, otherwise start a new one.
+    let mut run_state = if args.cache_file.as_ref() == Some(&cache_file_name) {
+        RunState::open(&cache_file_name)?
     } else {
-        OpenOptions::new()
-            .read(true)
-            .write(true)
-            .truncate(true)
-            .open(&cache_file_name)?
+        RunState::create(&cache_file_name)?
     };
 
     let client = Arc::new(args.client);
 
     let mut counter = 0u8;
-    let delay = NANO_SECONDS_IN_SECOND / args.rate_limit;
-    let rate_limiter = create_rate_limiter_with_capacity(args.rate_limit as u32, delay as u32);
+    let rate_limiter = Pacer::new(args.rate_limit);
 
     // Loop over migrate process so we can retry repeatedly until the user exits.
     loop {
@@ -266,18 +185,24 @@ pub async fn migrate_collection(args: MigrateArgs) -> AnyResult<()> {
         let mut migrate_tasks = Vec::new();
 
         for mint in remaining_mints {
+            run_state.record(RunStateEntry::new(&mint, ItemStatus::Pending))?;
+
             let client = client.clone();
             let keypair = keypair.clone();
+            let payer = payer.clone();
             let mint_address = args.mint_address.clone();
-            let mut rate_limiter = rate_limiter.clone();
-
-            migrate_tasks.push(tokio::spawn({
-                rate_limiter.wait();
-
-                let fut = set_and_verify(client, keypair, mint, mint_address, false);
-                pb.inc(1);
-                fut
-            }));
+            let rate_limiter = rate_limiter.clone();
+
+            migrate_tasks.push((
+                mint.clone(),
+                tokio::spawn({
+                    rate_limiter.wait();
+
+                    let fut = set_and_verify(client, keypair, payer, mint, mint_address, false);
+                    pb.inc(1);
+                    fut
+                }),
+            ));
         }
         pb.finish_and_clear();
 
@@ -290,34 +215,41 @@ pub async fn migrate_collection(args: MigrateArgs) -> AnyResult<()> {
             migrate_tasks.len() as u64,
         );
 
-        for task in migrate_tasks {
+        for (mint, task) in migrate_tasks {
             match task.await.unwrap() {
-                Ok(_) => (),
-                Err(e) => migrate_failed.push(e),
+                Ok(sig) => run_state.record(
+                    RunStateEntry::new(&mint, ItemStatus::Confirmed).signature(sig.to_string()),
+                )?,
+                Err(e) => {
+                    run_state.record(
+                        RunStateEntry::new(&mint, ItemStatus::Failed).error(e.to_string()),
+                    )?;
+                    migrate_failed.push(mint);
+                }
             }
             pb.inc(1);
         }
         pb.finish_and_clear();
 
         // If some of the migrations failed, ask user if they wish to retry and the loop starts again.
-        // Otherwise, break out of the loop and write the cache to disk.
+        // Otherwise, break out of the loop; the run state already holds every outcome.
         if !migrate_failed.is_empty() && counter < args.retries {
             counter += 1;
-            println!(
+            say!(
                 "{}/{} migrations failed. Retrying. . .",
                 &migrate_failed.len(),
                 migrate_tasks_len
             );
-            cache.update_errors(migrate_failed);
-            mint_accounts = cache.0.keys().map(|m| m.to_string()).collect();
+            mint_accounts = migrate_failed;
         } else if migrate_failed.is_empty() {
             // None failed so we exit the loop.
-            println!("All items successfully migrated!");
+            say!("All items successfully migrated!");
             break;
         } else {
-            println!("Reached max retries. Writing remaining items to cache.");
-            cache.update_errors(migrate_failed);
-            cache.write(f)?;
+            say!(
+                "Reached max retries. Remaining items are recorded in {}",
+                cache_file_name
+            );
             break;
         }
     }
@@ -0,0 +1,185 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::job::{ArgValue, RpcEndpoints};
+
+/// Metaboss settings shared by a team, kept in `~/.config/metaboss/config.toml`.
+///
+/// Each named profile holds the options that would otherwise be typed on
+/// every command. One is chosen with `--profile`, or `default_profile` when
+/// that isn't given.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetabossConfig {
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// The settings of one profile. Anything given on the command line or in a
+/// job file takes precedence.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub rpc: Option<RpcEndpoints>,
+    pub das_url: Option<String>,
+    pub keypair: Option<String>,
+    pub priority: Option<String>,
+    pub rate_limit: Option<usize>,
+    pub commitment: Option<String>,
+    pub timeout: Option<u64>,
+}
+
+impl MetabossConfig {
+    /// `~/.config/metaboss/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".config").join("metaboss").join("config.toml"))
+    }
+
+    /// Read the config file at `path`, or `None` if there isn't one.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)?;
+        let config = toml::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid config file {}: {e}", path.display()))?;
+        Ok(Some(config))
+    }
+
+    /// The profile called `name`, or the default profile if no name is given.
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<Profile>> {
+        let Some(name) = name.or(self.default_profile.as_deref()) else {
+            return Ok(None);
+        };
+        self.profiles.get(name).cloned().map(Some).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            anyhow!(
+                "No profile named {name} in the config file; profiles: {}",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )
+        })
+    }
+}
+
+/// Resolve the profile to use from the config file at its default location.
+/// Naming a profile without a config file is an error; having neither is not.
+pub fn load_profile(name: Option<&str>) -> Result<Option<Profile>> {
+    let path = MetabossConfig::default_path();
+    match path
+        .as_ref()
+        .map(MetabossConfig::load)
+        .transpose()?
+        .flatten()
+    {
+        Some(config) => config.profile(name),
+        None => match name {
+            Some(name) => Err(anyhow!(
+                "Profile {name} was requested but there is no config file at {}",
+                path.map_or_else(
+                    || "~/.config/metaboss/config.toml".into(),
+                    |p| p.display().to_string()
+                )
+            )),
+            None => Ok(None),
+        },
+    }
+}
+
+impl Profile {
+    /// Command options the profile sets, as `(option name, value)` pairs to
+    /// pass to every command that takes them.
+    pub fn defaults(&self) -> Vec<(&'static str, ArgValue)> {
+        let mut defaults = Vec::new();
+        if let Some(keypair) = &self.keypair {
+            let keypair = shellexpand::tilde(keypair).to_string();
+            defaults.push(("keypair", ArgValue::String(keypair)));
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            defaults.push(("rate-limit", ArgValue::Int(rate_limit as i64)));
+        }
+        if let Some(priority) = &self.priority {
+            defaults.push(("priority", ArgValue::String(priority.clone())));
+        }
+        defaults
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+default_profile = "devnet-test"
+
+[profiles.mainnet-prod]
+rpc = ["https://primary.example.com", "https://backup.example.com"]
+das_url = "https://das.example.com"
+keypair = "~/keys/prod.json"
+priority = "auto:90"
+rate_limit = 20
+commitment = "finalized"
+timeout = 60
+
+[profiles.devnet-test]
+rpc = "https://api.devnet.solana.com"
+"#;
+
+    #[test]
+    fn profiles_parse_from_toml() {
+        let config: MetabossConfig = toml::from_str(CONFIG).unwrap();
+
+        let prod = config.profile(Some("mainnet-prod")).unwrap().unwrap();
+        assert_eq!(
+            prod.rpc.unwrap().to_rpc_arg(),
+            "https://primary.example.com,https://backup.example.com"
+        );
+        assert_eq!(prod.das_url.as_deref(), Some("https://das.example.com"));
+        assert_eq!(prod.commitment.as_deref(), Some("finalized"));
+        assert_eq!(prod.timeout, Some(60));
+    }
+
+    #[test]
+    fn default_profile_is_used_without_a_name() {
+        let config: MetabossConfig = toml::from_str(CONFIG).unwrap();
+
+        let profile = config.profile(None).unwrap().unwrap();
+
+        assert_eq!(
+            profile.rpc,
+            Some(RpcEndpoints::One("https://api.devnet.solana.com".into()))
+        );
+        assert!(MetabossConfig::default().profile(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_profile_lists_the_known_ones() {
+        let config: MetabossConfig = toml::from_str(CONFIG).unwrap();
+
+        let err = config.profile(Some("staging")).unwrap_err().to_string();
+
+        assert!(err.contains("No profile named staging"));
+        assert!(err.contains("devnet-test, mainnet-prod"));
+    }
+
+    #[test]
+    fn profile_defaults_cover_command_options() {
+        let config: MetabossConfig = toml::from_str(CONFIG).unwrap();
+        let prod = config.profile(Some("mainnet-prod")).unwrap().unwrap();
+
+        let names: Vec<&str> = prod.defaults().into_iter().map(|(name, _)| name).collect();
+
+        assert_eq!(names, vec!["keypair", "rate-limit", "priority"]);
+    }
+}
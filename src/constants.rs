@@ -1,5 +1,14 @@
 use lazy_static::lazy_static;
-use std::{collections::HashMap, sync::RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::{
+    cost::SpendGuard,
+    limiter::AdaptiveLimiter,
+    offline::OfflineSettings,
+    output::{CommandOutput, OutputFormat},
+    priority::FeeBudget,
+    proposal::MultisigSettings,
+};
 
 pub const MAX_NAME_LENGTH: usize = 32;
 pub const MAX_URI_LENGTH: usize = 200;
@@ -21,7 +30,12 @@ pub const PUBLIC_RPC_URLS: &[&str] = &[
     "https://solana-api.projectserum.com",
 ];
 
-pub const DEFAULT_RPC_DELAY_MS: u32 = 200;
+/// Starting rate and ceiling, in requests per second, for known public RPCs.
+pub const PUBLIC_RPC_RATE: f64 = 5.0;
+pub const PUBLIC_MAX_RPC_RATE: f64 = 10.0;
+/// Starting rate and ceiling, in requests per second, for every other RPC.
+pub const DEFAULT_RPC_RATE: f64 = 50.0;
+pub const DEFAULT_MAX_RPC_RATE: f64 = 1000.0;
 
 // These are strs so they can be used in Structopt arguments
 pub const DEFAULT_RATE_LIMIT: &str = "10";
@@ -33,13 +47,22 @@ use phf::phf_map;
 "#;
 
 lazy_static! {
-    pub static ref USE_RATE_LIMIT: RwLock<bool> = RwLock::new(false);
-    pub static ref RPC_DELAY_NS: RwLock<u32> = RwLock::new(DEFAULT_RPC_DELAY_MS * 1_000_000);
-    pub static ref RATE_LIMIT_DELAYS: HashMap<&'static str, u32> =
-        [("https://ssc-dao.genesysgo.net", 25),]
-            .iter()
-            .copied()
-            .collect();
+    pub static ref RPC_LIMITER: Arc<AdaptiveLimiter> =
+        Arc::new(AdaptiveLimiter::new(DEFAULT_RPC_RATE, DEFAULT_MAX_RPC_RATE));
+    pub static ref DRY_RUN: RwLock<bool> = RwLock::new(false);
+    pub static ref RESUME: RwLock<bool> = RwLock::new(false);
+    pub static ref OFFLINE: RwLock<Option<OfflineSettings>> = RwLock::new(None);
+    pub static ref MULTISIG: RwLock<Option<MultisigSettings>> = RwLock::new(None);
+    pub static ref PAYER: RwLock<Option<String>> = RwLock::new(None);
+    /// The percentile of recent fees to pay under `--priority auto`.
+    pub static ref AUTO_PRIORITY: RwLock<Option<u8>> = RwLock::new(None);
+    pub static ref PRIORITY_FEE_BUDGET: FeeBudget = FeeBudget::default();
+    pub static ref SPEND_GUARD: SpendGuard = SpendGuard::default();
+    /// Skip the cost confirmation prompt before batch runs.
+    pub static ref ASSUME_YES: RwLock<bool> = RwLock::new(false);
+    pub static ref OUTPUT_FORMAT: RwLock<OutputFormat> = RwLock::new(OutputFormat::Text);
+    /// What the running command has done, for its JSON result object.
+    pub static ref COMMAND_OUTPUT: Mutex<CommandOutput> = Mutex::new(CommandOutput::default());
 }
 
 pub const MINT_LAYOUT: u64 = 82;
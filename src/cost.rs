@@ -0,0 +1,389 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use metaboss_lib::data::Priority;
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    rpc_client::RpcClient,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{
+    instruction::Instruction, native_token::LAMPORTS_PER_SOL, transaction::VersionedTransaction,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, BufRead, IsTerminal, Write},
+    sync::Mutex,
+};
+
+use crate::{
+    constants::{ASSUME_YES, AUTO_PRIORITY, DRY_RUN, PRIORITY_FEE_BUDGET, SPEND_GUARD},
+    dry_run::PREFLIGHT_FAILURE_CODE,
+    output::json_output,
+    priority::{
+        compute_unit_limit, estimate_micro_lamports, instruction_compute_unit_price,
+        level_micro_lamports, priority_fee_lamports,
+    },
+    utils::decode_wire_transaction,
+};
+
+/// Base fee the cluster charges for each signature.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Compute units assumed for a transaction whose usage isn't known before the
+/// run, which is the most an instruction gets without a limit.
+pub const ESTIMATED_COMPUTE_UNITS: u32 = 200_000;
+
+/// Most bytes Token Metadata allocates for the accounts minting an NFT creates.
+pub const METADATA_ACCOUNT_SIZE: usize = 679;
+pub const MASTER_EDITION_ACCOUNT_SIZE: usize = 282;
+
+/// Rent paid for one kind of account a run creates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RentItem {
+    pub account: String,
+    pub count: u64,
+    pub lamports: u64,
+}
+
+/// What a batch run is expected to cost, in lamports.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CostEstimate {
+    pub transactions: u64,
+    pub base_fees: u64,
+    pub priority_fees: u64,
+    pub rent: Vec<RentItem>,
+}
+
+impl CostEstimate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `count` transactions, each with `signatures` signatures and paying
+    /// `price` micro-lamports for `units` compute units.
+    pub fn add_transactions(&mut self, count: u64, signatures: u64, price: u64, units: u32) {
+        self.transactions += count;
+        self.base_fees += count * signatures * LAMPORTS_PER_SIGNATURE;
+        self.priority_fees += count * priority_fee_lamports(price, units);
+    }
+
+    /// Add rent for `count` new `account`s of `size` bytes each.
+    pub fn add_rent(
+        &mut self,
+        client: &RpcClient,
+        account: &str,
+        count: u64,
+        size: usize,
+    ) -> Result<()> {
+        if count > 0 {
+            let lamports = client.get_minimum_balance_for_rent_exemption(size)? * count;
+            self.rent.push(RentItem {
+                account: account.to_string(),
+                count,
+                lamports,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn rent_total(&self) -> u64 {
+        self.rent.iter().map(|item| item.lamports).sum()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.base_fees + self.priority_fees + self.rent_total()
+    }
+}
+
+fn sol(lamports: u64) -> String {
+    format!("{:.9} SOL", lamports as f64 / LAMPORTS_PER_SOL as f64)
+}
+
+impl Display for CostEstimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Estimated cost of up to {} transactions:",
+            self.transactions
+        )?;
+        writeln!(f, "  Base fees:     {}", sol(self.base_fees))?;
+        writeln!(f, "  Priority fees: {}", sol(self.priority_fees))?;
+        writeln!(f, "  Rent:          {}", sol(self.rent_total()))?;
+        for item in &self.rent {
+            writeln!(
+                f,
+                "    {} {}: {}",
+                item.count,
+                item.account,
+                sol(item.lamports)
+            )?;
+        }
+        write!(
+            f,
+            "  Total:         {} ({} lamports)",
+            sol(self.total()),
+            self.total()
+        )
+    }
+}
+
+/// The compute unit price a run at `priority` is expected to pay, within the
+/// per-transaction priority fee cap.
+pub fn estimated_price(client: &RpcClient, priority: &Priority, units: u32) -> Result<u64> {
+    let auto = *AUTO_PRIORITY.read().unwrap();
+    let price = match auto {
+        Some(percentile) => estimate_micro_lamports(client, &[], percentile)?,
+        None => level_micro_lamports(priority),
+    };
+    Ok(PRIORITY_FEE_BUDGET.limit(price, units))
+}
+
+/// Show `estimate` and check it against `--max-spend`, then ask to go ahead.
+///
+/// The prompt is skipped for dry runs, with `--yes`, and when stdin isn't a
+/// terminal so scripts and jobs keep running unattended. Rent is reserved
+/// against `--max-spend` up front, since it isn't visible in the transactions
+/// the spend limit checks as they are sent.
+pub fn confirm_cost(estimate: &CostEstimate) -> Result<()> {
+    say!("{estimate}");
+
+    if *DRY_RUN.read().unwrap() {
+        return Ok(());
+    }
+    if let Some(remaining) = SPEND_GUARD.remaining() {
+        if estimate.total() > remaining {
+            return Err(anyhow!(
+                "Estimated cost of {} lamports exceeds the {} lamports left under --max-spend",
+                estimate.total(),
+                remaining
+            ));
+        }
+    }
+
+    let stdin = io::stdin();
+    if !*ASSUME_YES.read().unwrap() && stdin.is_terminal() {
+        // The question goes wherever `say!` sends text, keeping JSON output clean.
+        if json_output() {
+            eprint!("Proceed? [y/N] ");
+            io::stderr().flush()?;
+        } else {
+            print!("Proceed? [y/N] ");
+            io::stdout().flush()?;
+        }
+        let mut answer = String::new();
+        stdin.lock().read_line(&mut answer)?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            return Err(anyhow!("Run cancelled"));
+        }
+    }
+
+    SPEND_GUARD.reserve(estimate.rent_total())
+}
+
+/// Lamports the cluster charges to land `tx`: its base fee plus priority fee.
+pub fn transaction_fee(tx: &VersionedTransaction) -> u64 {
+    let keys = tx.message.static_account_keys();
+    let instructions: Vec<Instruction> = tx
+        .message
+        .instructions()
+        .iter()
+        .map(|ix| Instruction {
+            program_id: keys[ix.program_id_index as usize],
+            accounts: vec![],
+            data: ix.data.clone(),
+        })
+        .collect();
+
+    let signatures = tx.message.header().num_required_signatures as u64;
+    let price = instruction_compute_unit_price(&instructions).unwrap_or(0);
+    signatures * LAMPORTS_PER_SIGNATURE
+        + priority_fee_lamports(price, compute_unit_limit(&instructions))
+}
+
+#[derive(Default)]
+struct SpendState {
+    max: Option<u64>,
+    spent: u64,
+    exceeded: bool,
+}
+
+/// The `--max-spend` budget of a run, in lamports.
+#[derive(Default)]
+pub struct SpendGuard {
+    state: Mutex<SpendState>,
+}
+
+impl SpendGuard {
+    /// Set the budget. What was already spent still counts against it.
+    pub fn configure(&self, max: Option<u64>) {
+        self.state.lock().unwrap().max = max;
+    }
+
+    pub fn spent(&self) -> u64 {
+        self.state.lock().unwrap().spent
+    }
+
+    /// Lamports left to spend, if there is a budget.
+    pub fn remaining(&self) -> Option<u64> {
+        let state = self.state.lock().unwrap();
+        state.max.map(|max| max.saturating_sub(state.spent))
+    }
+
+    /// Count `lamports` as spent, failing instead when they don't fit.
+    pub fn reserve(&self, lamports: u64) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(max) = state.max {
+            if state.spent.saturating_add(lamports) > max {
+                state.exceeded = true;
+                return Err(anyhow!(
+                    "Spending {} more lamports would exceed --max-spend of {} lamports; {} already spent",
+                    lamports,
+                    max,
+                    state.spent
+                ));
+            }
+        }
+        state.spent = state.spent.saturating_add(lamports);
+        Ok(())
+    }
+
+    /// Give back `lamports` for a transaction that was never forwarded.
+    pub fn refund(&self, lamports: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.spent = state.spent.saturating_sub(lamports);
+    }
+
+    /// Fail once a transaction has been refused for going over the budget, so
+    /// a run stops instead of retrying.
+    pub fn ensure_not_exceeded(&self) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        if state.exceeded {
+            return Err(anyhow!(
+                "Stopped: --max-spend of {} lamports reached after spending {} lamports",
+                state.max.unwrap_or_default(),
+                state.spent
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// An `RpcSender` that counts the fees of every transaction sent against the
+/// `--max-spend` budget, refusing to send those that don't fit. A transaction
+/// re-signed by the landing engine counts again, so the tally can run ahead
+/// of what was actually paid but never behind it.
+pub struct SpendLimitSender<T> {
+    inner: T,
+}
+
+impl<T> SpendLimitSender<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<T: RpcSender + Send + Sync> RpcSender for SpendLimitSender<T> {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        if request != RpcRequest::SendTransaction {
+            return self.inner.send(request, params).await;
+        }
+
+        let encoded = params[0]
+            .as_str()
+            .ok_or_else(|| RpcError::ParseError("encoded transaction".to_string()))?;
+        let encoding = params[1]["encoding"].as_str().unwrap_or("base64");
+        let fee = transaction_fee(&decode_wire_transaction(encoded, encoding)?);
+        SPEND_GUARD
+            .reserve(fee)
+            .map_err(|e| RpcError::ForUser(e.to_string()))?;
+
+        let result = self.inner.send(request, params).await;
+
+        // A failed preflight check means the transaction never reached the leader.
+        if let Err(err) = &result {
+            if let ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) = err.kind() {
+                if *code == PREFLIGHT_FAILURE_CODE {
+                    SPEND_GUARD.refund(fee);
+                }
+            }
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction, hash::Hash, message::Message, pubkey::Pubkey,
+        signature::Keypair, signer::Signer, transaction::Transaction,
+    };
+
+    #[test]
+    fn adds_up_fees_and_rent() {
+        let mut estimate = CostEstimate::new();
+        estimate.add_transactions(10, 2, 1_000_000, 200_000);
+        estimate.rent.push(RentItem {
+            account: "token records".to_string(),
+            count: 10,
+            lamports: 10 * 1_447_680,
+        });
+
+        assert_eq!(estimate.base_fees, 100_000);
+        assert_eq!(estimate.priority_fees, 2_000_000);
+        assert_eq!(estimate.total(), 100_000 + 2_000_000 + 14_476_800);
+        assert!(estimate.to_string().contains("10 token records"));
+    }
+
+    #[test]
+    fn transaction_fee_counts_signatures_and_priority() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![solana_sdk::instruction::AccountMeta::new_readonly(
+                authority.pubkey(),
+                true,
+            )],
+        );
+        let message = Message::new(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+                ComputeBudgetInstruction::set_compute_unit_price(50_000),
+                ix,
+            ],
+            Some(&payer.pubkey()),
+        );
+        let tx = Transaction::new(&[&payer, &authority], message, Hash::default());
+
+        // Two signatures, and 50_000 micro-lamports for 100_000 units.
+        assert_eq!(transaction_fee(&tx.into()), 10_000 + 5_000);
+    }
+
+    #[test]
+    fn spend_guard_refuses_what_does_not_fit() {
+        let guard = SpendGuard::default();
+        guard.configure(Some(20_000));
+
+        guard.reserve(15_000).unwrap();
+        assert!(guard.ensure_not_exceeded().is_ok());
+        assert!(guard.reserve(10_000).is_err());
+        assert_eq!(guard.spent(), 15_000);
+        assert!(guard.ensure_not_exceeded().is_err());
+
+        guard.refund(15_000);
+        assert_eq!(guard.remaining(), Some(20_000));
+    }
+}
@@ -1,11 +1,7 @@
 use std::path::PathBuf;
 
 use anyhow::anyhow;
-use metaboss_lib::{
-    data::Priority,
-    derive::derive_edition_pda,
-    transaction::{get_compute_units, send_and_confirm_tx},
-};
+use metaboss_lib::{data::Priority, derive::derive_edition_pda, transaction::get_compute_units};
 use mpl_token_metadata::{
     instructions::{CreateBuilder, CreateMasterEditionV3Builder},
     types::{CreateArgs, DataV2, TokenStandard},
@@ -43,6 +39,9 @@ use spl_token_metadata_interface::{
     state::{Field, TokenMetadata},
 };
 
+use crate::landing::land;
+use crate::output::record_address;
+use crate::priority::with_priority;
 use crate::utils::create_token_if_missing_instruction;
 
 use super::*;
@@ -65,6 +64,8 @@ pub fn create_metadata(args: CreateMetadataArgs) -> Result<()> {
 
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
 
     let f = File::open(args.metadata)?;
     let data: FungibleFields = serde_json::from_reader(f)?;
@@ -100,41 +101,33 @@ pub fn create_metadata(args: CreateMetadataArgs) -> Result<()> {
         .metadata(metadata_pubkey)
         .mint(mint_pubkey, false)
         .authority(keypair.pubkey())
-        .payer(keypair.pubkey())
+        .payer(payer.pubkey())
         .update_authority(keypair.pubkey(), true)
         .create_args(create_args)
         .instruction();
 
-    let micro_lamports = match args.priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
     let mut instructions = vec![];
 
     if !args.full_compute {
         // Only set the compute unit limit if we're not doing a full compute
-        let compute_units =
-            get_compute_units(&args.client, std::slice::from_ref(&create_ix), &[&keypair])?
-                .unwrap_or(DEFAULT_COMPUTE_UNITS);
+        let compute_units = get_compute_units(
+            &args.client,
+            std::slice::from_ref(&create_ix),
+            &with_payer(payer, &[&keypair]),
+        )?
+        .unwrap_or(DEFAULT_COMPUTE_UNITS);
 
         instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
             compute_units as u32,
         ));
     }
 
-    // Always set the compute unit price
-    instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
-        micro_lamports,
-    ));
     instructions.push(create_ix);
+    let instructions = with_priority(&args.client, &args.priority, &instructions)?;
 
     let sig = send_and_confirm_transaction(&args.client, keypair, &instructions)?;
 
-    println!("Signature: {sig}");
+    say!("Signature: {sig}");
 
     Ok(())
 }
@@ -254,6 +247,8 @@ pub fn parse_pubkey(pubkey_str: &str) -> Result<Pubkey> {
 pub fn create_fungible(args: CreateFungibleArgs) -> Result<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
 
     let mint = if let Some(path) = args.mint_path {
         read_keypair_file(&path)
@@ -288,7 +283,7 @@ pub fn create_fungible(args: CreateFungibleArgs) -> Result<()> {
         .metadata(metadata_pubkey)
         .mint(mint.pubkey(), true)
         .authority(keypair.pubkey())
-        .payer(keypair.pubkey())
+        .payer(payer.pubkey())
         .update_authority(keypair.pubkey(), true)
         .create_args(create_args)
         .spl_token_program(Some(spl_token::ID))
@@ -305,7 +300,7 @@ pub fn create_fungible(args: CreateFungibleArgs) -> Result<()> {
 
         // Create associated token account if needed
         instructions.push(create_token_if_missing_instruction(
-            &keypair.pubkey(),
+            &payer.pubkey(),
             &assoc,
             &mint.pubkey(),
             &keypair.pubkey(),
@@ -324,15 +319,7 @@ pub fn create_fungible(args: CreateFungibleArgs) -> Result<()> {
         instructions.push(mint_to_ix);
     }
 
-    let signers = vec![&keypair, &mint];
-
-    let micro_lamports = match args.priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
+    let signers = with_payer(payer, &[&keypair, &mint]);
 
     let mut extra_instructions = vec![];
 
@@ -346,18 +333,17 @@ pub fn create_fungible(args: CreateFungibleArgs) -> Result<()> {
         ));
     }
 
-    // Always set the compute unit price
-    extra_instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
-        micro_lamports,
-    ));
-
     instructions.splice(0..0, extra_instructions);
+    let instructions = with_priority(&args.client, &args.priority, &instructions)?;
 
-    let sig = send_and_confirm_tx(&args.client, &signers, &instructions)?;
+    let sig = land(&args.client, &signers, &instructions, &[])?;
 
-    println!("Signature: {sig}");
-    println!("Mint: {}", mint.pubkey());
-    println!("Metadata: {metadata_pubkey}");
+    say!("Signature: {sig}");
+    say!("Mint: {}", mint.pubkey());
+    record_address("mint", mint.pubkey());
+    say!("Metadata: {metadata_pubkey}");
+    record_address("mint", mint.pubkey());
+    record_address("metadata", metadata_pubkey);
 
     Ok(())
 }
@@ -365,6 +351,8 @@ pub fn create_fungible(args: CreateFungibleArgs) -> Result<()> {
 pub fn create_fungible_22(args: CreateFungible22Args) -> Result<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
 
     let mint = if let Some(path) = args.mint_path {
         read_keypair_file(&path)
@@ -469,7 +457,7 @@ pub fn create_fungible_22(args: CreateFungible22Args) -> Result<()> {
     let mut instructions = vec![];
 
     let create_mint_account_ix = create_account(
-        &keypair_pubkey,
+        &payer.pubkey(),
         &mint_pubkey,
         mint_rent,
         u64::try_from(mint_size).unwrap(),
@@ -645,7 +633,7 @@ pub fn create_fungible_22(args: CreateFungible22Args) -> Result<()> {
 
             // Create associated token account if needed
             let create_token_ix = create_associated_token_account(
-                &keypair_pubkey,
+                &payer.pubkey(),
                 &keypair_pubkey,
                 &mint_pubkey,
                 &TOKEN_22_PROGRAM_ID,
@@ -666,32 +654,21 @@ pub fn create_fungible_22(args: CreateFungible22Args) -> Result<()> {
         }
     }
 
-    let signers = vec![&keypair, &mint];
-
-    // Priority fees
-    let micro_lamports = match args.priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
+    let signers = with_payer(payer, &[&keypair, &mint]);
 
-    // Always set the compute unit price
     let compute_units =
         get_compute_units(&args.client, &instructions, &signers)?.unwrap_or(DEFAULT_COMPUTE_UNITS);
 
-    let extra_instructions = vec![
+    instructions.insert(
+        0,
         ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-    ];
-
-    instructions.splice(0..0, extra_instructions);
+    );
+    let instructions = with_priority(&args.client, &args.priority, &instructions)?;
 
-    let sig = send_and_confirm_tx(&args.client, &signers, &instructions)?;
+    let sig = land(&args.client, &signers, &instructions, &[])?;
 
-    println!("Signature: {sig}");
-    println!("Mint: {}", mint.pubkey());
+    say!("Signature: {sig}");
+    say!("Mint: {}", mint.pubkey());
 
     Ok(())
 }
@@ -699,6 +676,8 @@ pub fn create_fungible_22(args: CreateFungible22Args) -> Result<()> {
 pub fn create_fungible_22_token(args: CreateFungible22TokenArgs) -> Result<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
 
     let mint_pubkey = Pubkey::from_str(&args.mint_address)?;
     let keypair_pubkey = keypair.pubkey();
@@ -758,7 +737,7 @@ pub fn create_fungible_22_token(args: CreateFungible22TokenArgs) -> Result<()> {
             .get_minimum_balance_for_rent_exemption(account_size)?;
 
         instructions.push(create_account(
-            &keypair_pubkey,
+            &payer.pubkey(),
             &destination_token_pubkey,
             account_rent,
             u64::try_from(account_size).unwrap(),
@@ -796,46 +775,26 @@ pub fn create_fungible_22_token(args: CreateFungible22TokenArgs) -> Result<()> {
         }
     }
 
-    let signers = vec![&keypair, &token];
+    let signers = with_payer(payer, &[&keypair, &token]);
 
     let compute_units =
         get_compute_units(&args.client, &instructions, &signers)?.unwrap_or(200_000);
 
-    let micro_lamports = match args.priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
-    let mut final_instructions = vec![
-        ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-    ];
+    let mut final_instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_units as u32,
+    )];
     final_instructions.extend(instructions);
+    let final_instructions = with_priority(&args.client, &args.priority, &final_instructions)?;
 
-    let recent_blockhash = args.client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &final_instructions,
-        Some(&keypair_pubkey),
-        &signers,
-        recent_blockhash,
-    );
-
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || args.client.send_and_confirm_transaction(&tx),
-    );
-    let sig = res?;
+    let sig = land(&args.client, &signers, &final_instructions, &[])?;
 
-    println!(
+    say!(
         "Token: {:?} created successfully!",
         destination_token_pubkey.to_string()
     );
+    record_address("token_account", destination_token_pubkey);
 
-    println!("Created in tx: {:?}", &sig);
+    say!("Created in tx: {:?}", &sig);
 
     Ok(())
 }
@@ -843,6 +802,8 @@ pub fn create_fungible_22_token(args: CreateFungible22TokenArgs) -> Result<()> {
 pub fn create_master_edition(args: CreateMasterEditionArgs) -> Result<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
 
     let mint_authority = if let Some(mint_authority) = args.mint_authority {
         read_keypair_file(&mint_authority)
@@ -869,49 +830,31 @@ pub fn create_master_edition(args: CreateMasterEditionArgs) -> Result<()> {
         .update_authority(keypair.pubkey())
         .mint_authority(mint_authority.pubkey())
         .metadata(metadata_pubkey)
-        .payer(keypair.pubkey());
+        .payer(payer.pubkey());
 
     if let Some(max_supply) = max_supply {
         builder.max_supply(max_supply);
     }
     let ix = builder.instruction();
 
-    let signers = vec![&keypair, &mint_authority];
+    let signers = with_payer(payer, &[&keypair, &mint_authority]);
 
     let compute_units = get_compute_units(&args.client, std::slice::from_ref(&ix), &signers)?
         .unwrap_or(DEFAULT_COMPUTE_UNITS);
 
-    let micro_lamports = match args.priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
-    let instructions = vec![
-        ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-        ix,
-    ];
-
-    let recent_blockhash = args.client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&keypair.pubkey()),
-        &signers,
-        recent_blockhash,
-    );
-
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || args.client.send_and_confirm_transaction(&tx),
-    );
+    let instructions = with_priority(
+        &args.client,
+        &args.priority,
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
+            ix,
+        ],
+    )?;
 
-    let sig = res?;
-    println!("Signature: {sig}");
-    println!("Edition: {edition_pubkey}");
+    let sig = land(&args.client, &signers, &instructions, &[])?;
+    say!("Signature: {sig}");
+    say!("Edition: {edition_pubkey}");
+    record_address("edition", edition_pubkey);
 
     Ok(())
 }
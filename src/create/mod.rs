@@ -3,13 +3,12 @@ pub use methods::*;
 
 use anyhow::Result;
 use metaboss_lib::derive::derive_metadata_pda;
-use retry::{delay::Exponential, retry};
 use serde::Deserialize;
 use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+use solana_sdk::{signature::Keypair, signer::Signer};
 use std::fs::File;
 use std::str::FromStr;
 
-use crate::parse::{parse_keypair, parse_solana_config};
-use crate::utils::send_and_confirm_transaction;
+use crate::parse::{parse_keypair, parse_payer, parse_solana_config};
+use crate::utils::{send_and_confirm_transaction, with_payer};
@@ -16,13 +16,15 @@ use retry::{delay::Exponential, retry};
 use serde::Serialize;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
+use spl_token::state::{Account as TokenAccount, Mint};
 use std::fs::File;
 use std::str::FromStr;
 
 use crate::constants::*;
 use crate::errors::*;
-use crate::limiter::create_default_rate_limiter;
+use crate::output::record_data;
 use crate::parse::is_only_one_option;
+use crate::spinner::new_progress_bar;
 
 mod rule_set;
 pub use rule_set::*;
@@ -52,6 +54,54 @@ pub struct JSONUses {
     pub total: u64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct JSONMint {
+    pub mint_authority: Option<String>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<String>,
+}
+
+impl From<Mint> for JSONMint {
+    fn from(mint: Mint) -> Self {
+        Self {
+            mint_authority: Option::from(mint.mint_authority).map(|a: Pubkey| a.to_string()),
+            supply: mint.supply,
+            decimals: mint.decimals,
+            is_initialized: mint.is_initialized,
+            freeze_authority: Option::from(mint.freeze_authority).map(|a: Pubkey| a.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JSONTokenAccount {
+    pub mint: String,
+    pub owner: String,
+    pub amount: u64,
+    pub delegate: Option<String>,
+    pub state: String,
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<String>,
+}
+
+impl From<TokenAccount> for JSONTokenAccount {
+    fn from(account: TokenAccount) -> Self {
+        Self {
+            mint: account.mint.to_string(),
+            owner: account.owner.to_string(),
+            amount: account.amount,
+            delegate: Option::from(account.delegate).map(|d: Pubkey| d.to_string()),
+            state: format!("{:?}", account.state),
+            is_native: account.is_native.into(),
+            delegated_amount: account.delegated_amount,
+            close_authority: Option::from(account.close_authority).map(|a: Pubkey| a.to_string()),
+        }
+    }
+}
+
 pub fn decode_metadata_all(
     client: &RpcClient,
     json_file: &str,
@@ -60,20 +110,12 @@ pub fn decode_metadata_all(
 ) -> AnyResult<()> {
     let file = File::open(json_file)?;
     let mint_accounts: Vec<String> = serde_json::from_reader(file)?;
-    let use_rate_limit = *USE_RATE_LIMIT.read().unwrap();
-    let handle = create_default_rate_limiter();
-
     info!("Decoding accounts...");
-    println!("Decoding accounts...");
+    say!("Decoding accounts...");
     mint_accounts
         .par_iter()
-        .progress()
+        .progress_with(new_progress_bar(mint_accounts.len() as u64))
         .for_each(|mint_account| {
-            let mut handle = handle.clone();
-            if use_rate_limit {
-                handle.wait();
-            }
-
             debug!("Decoding metadata for mint account: {}", mint_account);
             let mut metadata = match decode(client, mint_account) {
                 Ok(m) => m,
@@ -142,14 +184,16 @@ pub fn decode_metadata_all(
 
 pub fn decode_master_edition(client: &RpcClient, mint_account: &str) -> AnyResult<()> {
     let master_edition = decode_master_edition_from_mint(client, mint_account)?;
-    println!("{master_edition:?}");
+    say!("{master_edition:?}");
+    record_data(&master_edition);
 
     Ok(())
 }
 
 pub fn decode_print_edition(client: &RpcClient, mint_account: &str) -> AnyResult<()> {
     let print_edition = decode_edition_from_mint(client, mint_account)?;
-    println!("{print_edition:?}");
+    say!("{print_edition:?}");
+    record_data(&print_edition);
 
     Ok(())
 }
@@ -169,7 +213,8 @@ pub fn decode_edition_marker(
     };
 
     let edition_marker = decode_edition_marker_from_mint(client, mint_account, edition_num)?;
-    println!("{edition_marker:?}");
+    say!("{edition_marker:?}");
+    record_data(&edition_marker);
 
     Ok(())
 }
@@ -192,7 +237,8 @@ pub fn decode_metadata_from_mint(
     if let Some(mint_account) = account {
         if raw {
             let data = decode_raw(client, mint_account)?;
-            println!("{data:?}");
+            say!("{data:?}");
+            record_data(&data);
             return Ok(());
         }
         let mut metadata = decode(client, mint_account)?;
@@ -204,9 +250,11 @@ pub fn decode_metadata_from_mint(
 
         if full {
             serde_json::to_writer_pretty(&mut file, &metadata)?;
+            record_data(&metadata);
         } else {
             let data = NftData::from(metadata);
             serde_json::to_writer_pretty(&mut file, &data)?;
+            record_data(&data);
         }
     } else if let Some(list_path) = list_path {
         decode_metadata_all(client, list_path, full, output)?;
@@ -224,20 +272,23 @@ pub fn decode_metadata(client: &RpcClient, account: String, output: &str) -> Any
     let metadata = metaboss_lib::decode::decode_metadata(client, &pubkey)?;
     let mut file = File::create(format!("{output}/{account}.json"))?;
     serde_json::to_writer_pretty(&mut file, &metadata)?;
+    record_data(&metadata);
 
     Ok(())
 }
 
 pub fn decode_mint_account(client: &RpcClient, mint_account: &str) -> AnyResult<()> {
     let mint = decode_mint(client, mint_account)?;
-    println!("{mint:?}");
+    say!("{mint:?}");
+    record_data(&JSONMint::from(mint));
 
     Ok(())
 }
 
 pub fn decode_token_account(client: &RpcClient, token_account: &str) -> AnyResult<()> {
     let account = decode_token(client, token_account)?;
-    println!("{account:?}");
+    say!("{account:?}");
+    record_data(&JSONTokenAccount::from(account));
 
     Ok(())
 }
@@ -245,7 +296,8 @@ pub fn decode_token_account(client: &RpcClient, token_account: &str) -> AnyResul
 pub fn decode_token_record_from_mint(client: &RpcClient, mint: &str) -> AnyResult<()> {
     let pubkey = Pubkey::from_str(mint)?;
     let token_record = metaboss_lib::decode::decode_token_record_from_mint(client, pubkey)?;
-    println!("{token_record:?}");
+    say!("{token_record:?}");
+    record_data(&token_record);
 
     Ok(())
 }
@@ -296,7 +348,8 @@ pub fn process_decode_bpf_loader_upgradable_state(
 ) -> AnyResult<()> {
     let state = decode_bpf_loader_upgradeable_state(client, address)?;
 
-    println!("{state:?}");
+    say!("{state:?}");
+    record_data(&state);
 
     Ok(())
 }
@@ -320,6 +373,7 @@ pub fn decode_core_asset(client: &RpcClient, asset: &str) -> AnyResult<()> {
     let pubkey = Pubkey::from_str(asset)?;
     let account = client.get_account(&pubkey)?;
     let asset = BaseAssetV1::deserialize(&mut account.data.as_slice())?;
-    println!("{asset:?}");
+    say!("{asset:?}");
+    record_data(&asset);
     Ok(())
 }
@@ -1,4 +1,5 @@
 use crate::constants::{MASTER_EDITION_PREFIX, METADATA_PREFIX, USER_PREFIX};
+use crate::output::record_address;
 use crate::update::{parse_keypair, parse_solana_config};
 use metaboss_lib::derive::{derive_collection_delegate_pda, derive_token_record_pda};
 use mpl_token_metadata::ID;
@@ -26,7 +27,9 @@ pub fn get_token_account_pda(mint: String, owner: Option<String>, token_22: bool
         token_program_id
     };
 
-    println!("{}", derive_token_account_pda(&mint, &owner, &program_id))
+    let pda = derive_token_account_pda(&mint, &owner, &program_id);
+    say!("{}", pda);
+    record_address("token_account", pda);
 }
 
 pub fn get_generic_pda(str_seeds: String, program_id: String) {
@@ -40,7 +43,9 @@ pub fn get_generic_pda(str_seeds: String, program_id: String) {
 
     let program_id =
         Pubkey::from_str(&program_id).expect("Failed to parse pubkey from program_id!");
-    println!("{}", derive_generic_pda(seeds, program_id));
+    let pda = derive_generic_pda(seeds, program_id);
+    say!("{}", pda);
+    record_address("pda", pda);
 }
 
 fn parse_seed(s: &str) -> Vec<Vec<u8>> {
@@ -72,31 +77,41 @@ fn pubkey_or_bytes(seed: String) -> Vec<u8> {
 pub fn get_metadata_pda(mint_account: String) {
     let pubkey =
         Pubkey::from_str(&mint_account).expect("Failed to parse pubkey from mint account!");
-    println!("{}", derive_metadata_pda(&pubkey));
+    let pda = derive_metadata_pda(&pubkey);
+    say!("{}", pda);
+    record_address("metadata", pda);
 }
 
 pub fn get_edition_pda(mint_account: String) {
     let pubkey =
         Pubkey::from_str(&mint_account).expect("Failed to parse pubkey from mint account!");
-    println!("{}", derive_edition_pda(&pubkey));
+    let pda = derive_edition_pda(&pubkey);
+    say!("{}", pda);
+    record_address("edition", pda);
 }
 
 pub fn get_edition_marker_pda(mint_account: String, edition_num: u64) {
     let pubkey =
         Pubkey::from_str(&mint_account).expect("Failed to parse pubkey from mint account!");
-    println!("{}", derive_edition_marker_pda(&pubkey, edition_num));
+    let pda = derive_edition_marker_pda(&pubkey, edition_num);
+    say!("{}", pda);
+    record_address("edition_marker", pda);
 }
 
 pub fn get_cmv2_pda(candy_machine_id: String) {
     let pubkey =
         Pubkey::from_str(&candy_machine_id).expect("Failed to parse pubkey from candy_machine_id!");
-    println!("{}", derive_cmv2_pda(&pubkey));
+    let pda = derive_cmv2_pda(&pubkey);
+    say!("{}", pda);
+    record_address("candy_machine_creator", pda);
 }
 
 pub fn get_cmv3_pda(candy_machine_id: String) {
     let pubkey =
         Pubkey::from_str(&candy_machine_id).expect("Failed to parse pubkey from candy_machine_id!");
-    println!("{}", derive_cmv3_pda(&pubkey));
+    let pda = derive_cmv3_pda(&pubkey);
+    say!("{}", pda);
+    record_address("candy_machine_creator", pda);
 }
 
 pub fn get_token_record_pda(mint_account: String, token_account: String) {
@@ -105,14 +120,15 @@ pub fn get_token_record_pda(mint_account: String, token_account: String) {
     let token_pubkey =
         Pubkey::from_str(&token_account).expect("Failed to parse pubkey from token account!");
 
-    println!("{}", derive_token_record_pda(&mint_pubkey, &token_pubkey));
+    let pda = derive_token_record_pda(&mint_pubkey, &token_pubkey);
+    say!("{}", pda);
+    record_address("token_record", pda);
 }
 
 pub fn get_collection_delegate(mint: Pubkey, authority: Pubkey, delegate: Pubkey) {
-    println!(
-        "{:?}",
-        derive_collection_delegate_pda(&mint, &delegate, &authority)
-    );
+    let pda = derive_collection_delegate_pda(&mint, &delegate, &authority);
+    say!("{:?}", pda);
+    record_address("collection_delegate", pda);
 }
 
 fn derive_generic_pda(seeds: Vec<&[u8]>, program_id: Pubkey) -> Pubkey {
@@ -0,0 +1,330 @@
+use anyhow::Result as AnyResult;
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::{
+    client_error::Result as ClientResult,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_response::{Response, RpcSimulateTransactionResult},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use std::{
+    collections::HashSet,
+    io::Write,
+    ops::{Deref, DerefMut},
+    sync::RwLock,
+};
+
+use crate::{errors::ActionError, utils::decode_wire_transaction};
+
+/// JSON-RPC error code returned by nodes when preflight simulation fails.
+pub const PREFLIGHT_FAILURE_CODE: i64 = -32002;
+
+/// An `RpcSender` that never broadcasts transactions.
+///
+/// Every `sendTransaction` request is turned into a `simulateTransaction`
+/// request against the wrapped sender. Simulation failures are returned the
+/// same way a node reports a failed preflight check, so callers see the exact
+/// error they would get from a real send. Successful simulations are
+/// remembered so that the confirmation polling done by
+/// `send_and_confirm_transaction` resolves immediately instead of waiting for
+/// a transaction that will never land.
+pub struct DryRunSender {
+    inner: Box<dyn RpcSender + Send + Sync>,
+    simulated: RwLock<HashSet<String>>,
+}
+
+impl DryRunSender {
+    pub fn new<T: RpcSender + Send + Sync + 'static>(inner: T) -> Self {
+        Self {
+            inner: Box::new(inner),
+            simulated: RwLock::new(HashSet::new()),
+        }
+    }
+
+    async fn simulate(&self, params: Value) -> ClientResult<Value> {
+        let tx_str = params[0]
+            .as_str()
+            .ok_or_else(|| RpcError::ParseError("encoded transaction".to_string()))?
+            .to_string();
+        let encoding = params[1]["encoding"]
+            .as_str()
+            .unwrap_or("base64")
+            .to_string();
+        let tx = decode_wire_transaction(&tx_str, &encoding)?;
+        let signature = tx.signatures.first().copied().unwrap_or_default();
+
+        let config = json!({
+            "encoding": encoding,
+            "sigVerify": false,
+            "replaceRecentBlockhash": true,
+            "commitment": params[1]["preflightCommitment"],
+        });
+
+        let response = self
+            .inner
+            .send(RpcRequest::SimulateTransaction, json!([tx_str, config]))
+            .await?;
+        let result: Response<RpcSimulateTransactionResult> = serde_json::from_value(response)?;
+
+        if let Some(err) = &result.value.err {
+            return Err(RpcError::RpcResponseError {
+                code: PREFLIGHT_FAILURE_CODE,
+                message: format!("Transaction simulation failed: {err}"),
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(result.value),
+            }
+            .into());
+        }
+
+        self.simulated
+            .write()
+            .unwrap()
+            .insert(signature.to_string());
+
+        Ok(Value::String(signature.to_string()))
+    }
+
+    async fn signature_statuses(&self, params: Value) -> ClientResult<Value> {
+        let signatures: Vec<String> = params[0]
+            .as_array()
+            .map(|sigs| {
+                sigs.iter()
+                    .filter_map(|s| s.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let simulated = self.simulated.read().unwrap().clone();
+        let mut response = self
+            .inner
+            .send(RpcRequest::GetSignatureStatuses, params)
+            .await?;
+
+        // Statuses for simulated transactions are reported as finalized; any others
+        // are left as the node returned them.
+        if let Some(statuses) = response["value"].as_array_mut() {
+            for (status, signature) in statuses.iter_mut().zip(signatures.iter()) {
+                if simulated.contains(signature) {
+                    *status = json!({
+                        "slot": 0,
+                        "confirmations": null,
+                        "err": null,
+                        "status": { "Ok": null },
+                        "confirmationStatus": "finalized",
+                    });
+                }
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl RpcSender for DryRunSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        match request {
+            RpcRequest::SendTransaction => self.simulate(params).await,
+            RpcRequest::GetSignatureStatuses => self.signature_statuses(params).await,
+            _ => self.inner.send(request, params).await,
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DryRunItem {
+    pub would_succeed: bool,
+    pub error: Option<String>,
+}
+
+/// Per-mint outcome of a simulated batch run, keyed by mint address.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct DryRunReport(pub IndexMap<String, DryRunItem>);
+
+impl Deref for DryRunReport {
+    type Target = IndexMap<String, DryRunItem>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for DryRunReport {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl DryRunReport {
+    pub fn new() -> Self {
+        DryRunReport(IndexMap::new())
+    }
+
+    pub fn add_result(&mut self, mint_address: String, result: &Result<(), ActionError>) {
+        let item = match result {
+            Ok(()) => DryRunItem {
+                would_succeed: true,
+                error: None,
+            },
+            Err(err) => DryRunItem {
+                would_succeed: false,
+                error: Some(err.to_string()),
+            },
+        };
+        self.insert(mint_address, item);
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.values().filter(|item| !item.would_succeed).count()
+    }
+
+    pub fn write<W: Write>(&mut self, writer: W) -> AnyResult<()> {
+        self.sort_unstable_keys();
+        serde_json::to_writer_pretty(writer, &self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+    use solana_rpc_client::mock_sender::{MockSender, Mocks};
+    use solana_sdk::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        transaction::Transaction,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// Records every request before handing it to a `MockSender`.
+    struct RecordingSender {
+        inner: MockSender,
+        requests: Arc<Mutex<Vec<RpcRequest>>>,
+    }
+
+    #[async_trait]
+    impl RpcSender for RecordingSender {
+        async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+            self.requests.lock().unwrap().push(request);
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            self.inner.get_transport_stats()
+        }
+
+        fn url(&self) -> String {
+            self.inner.url()
+        }
+    }
+
+    fn dry_run_client(mocks: Mocks) -> (RpcClient, Arc<Mutex<Vec<RpcRequest>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let sender = RecordingSender {
+            inner: MockSender::new_with_mocks("succeeds", mocks),
+            requests: requests.clone(),
+        };
+        let client = RpcClient::new_sender(DryRunSender::new(sender), RpcClientConfig::default());
+        (client, requests)
+    }
+
+    fn test_tx() -> Transaction {
+        let payer = Keypair::new();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new(payer.pubkey(), true)],
+        );
+        Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], Hash::default())
+    }
+
+    #[test]
+    fn dry_run_sender_simulates_instead_of_sending() {
+        let (client, requests) = dry_run_client(Mocks::default());
+        let tx = test_tx();
+
+        let signature = client.send_and_confirm_transaction(&tx).unwrap();
+
+        assert_eq!(signature, tx.signatures[0]);
+        let requests = requests.lock().unwrap();
+        assert!(requests.contains(&RpcRequest::SimulateTransaction));
+        assert!(!requests.contains(&RpcRequest::SendTransaction));
+    }
+
+    #[test]
+    fn dry_run_sender_reports_simulation_failure_as_preflight_error() {
+        let mut mocks = Mocks::default();
+        mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "err": { "InstructionError": [0, { "Custom": 1 }] },
+                    "logs": ["Program log: failed"],
+                    "accounts": null,
+                    "unitsConsumed": null,
+                    "returnData": null,
+                }
+            }),
+        );
+        let (client, requests) = dry_run_client(mocks);
+
+        let err = client.send_and_confirm_transaction(&test_tx()).unwrap_err();
+
+        assert!(err.to_string().contains("Transaction simulation failed"));
+        assert!(err.to_string().contains("custom program error: 0x1"));
+        assert!(!requests
+            .lock()
+            .unwrap()
+            .contains(&RpcRequest::SendTransaction));
+    }
+
+    #[test]
+    fn dry_run_report_tracks_successes_and_failures() {
+        let mut report = DryRunReport::new();
+        report.add_result("mintB".to_string(), &Ok(()));
+        report.add_result(
+            "mintA".to_string(),
+            &Err(ActionError::ActionFailed(
+                "mintA".to_string(),
+                "simulation failed".to_string(),
+            )),
+        );
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.failed_count(), 1);
+        assert!(report["mintB"].would_succeed);
+        assert!(report["mintA"]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("simulation failed"));
+    }
+
+    #[test]
+    fn dry_run_report_write_sorts_by_mint() {
+        let mut report = DryRunReport::new();
+        report.add_result("mintB".to_string(), &Ok(()));
+        report.add_result("mintA".to_string(), &Ok(()));
+
+        let mut buf = Vec::new();
+        report.write(&mut buf).unwrap();
+
+        let parsed: DryRunReport = serde_json::from_slice(&buf).unwrap();
+        let keys: Vec<_> = parsed.keys().cloned().collect();
+        assert_eq!(keys, vec!["mintA".to_string(), "mintB".to_string()]);
+    }
+}
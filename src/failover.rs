@@ -0,0 +1,401 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_rpc_client::http_sender::HttpSender;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// JSON-RPC error code returned by a node that is unhealthy or far behind.
+pub const NODE_UNHEALTHY_CODE: i64 = -32005;
+
+/// How long a failed endpoint is left out of the rotation before it is
+/// health-checked again.
+pub const FAILOVER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Split an `--rpc` value into its endpoints. Several URLs are separated by commas.
+pub fn parse_rpc_urls(rpc: &str) -> Vec<String> {
+    rpc.split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Whether `request` feeds a decision to send or resend a transaction, so it
+/// has to see what the primary sees: a blockhash from a node that is behind
+/// fails preflight, and a stale signature status or block height can resend a
+/// transaction that already landed.
+pub fn reads_from_primary(request: RpcRequest) -> bool {
+    matches!(
+        request,
+        RpcRequest::GetLatestBlockhash
+            | RpcRequest::IsBlockhashValid
+            | RpcRequest::GetSignatureStatuses
+            | RpcRequest::GetBlockHeight
+            | RpcRequest::SimulateTransaction
+            | RpcRequest::GetFeeForMessage
+            | RpcRequest::GetRecentPrioritizationFees
+            | RpcRequest::GetAccountInfo
+    )
+}
+
+/// Whether `err` means the endpoint itself is in trouble, rather than the request.
+pub fn is_endpoint_failure(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == NODE_UNHEALTHY_CODE
+        }
+        _ => false,
+    }
+}
+
+struct Endpoint {
+    sender: Box<dyn RpcSender + Send + Sync>,
+    /// When set, the endpoint failed and stays out of the rotation until then.
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn mark_down(&self, cooldown: Duration) {
+        *self.down_until.lock().unwrap() = Some(Instant::now() + cooldown);
+    }
+
+    fn mark_up(&self) {
+        *self.down_until.lock().unwrap() = None;
+    }
+
+    /// Whether the endpoint is in the rotation, health-checking it first if
+    /// its cooldown has run out.
+    async fn is_healthy(&self, cooldown: Duration) -> bool {
+        let down_until = *self.down_until.lock().unwrap();
+        match down_until {
+            None => true,
+            Some(until) if Instant::now() < until => false,
+            Some(_) => match self.sender.send(RpcRequest::GetHealth, json!(null)).await {
+                Ok(_) => {
+                    self.mark_up();
+                    true
+                }
+                Err(_) => {
+                    self.mark_down(cooldown);
+                    false
+                }
+            },
+        }
+    }
+}
+
+/// An `RpcSender` over several endpoints.
+///
+/// Reads are spread round-robin across the healthy endpoints. An endpoint that
+/// fails with a transport error or reports itself unhealthy is taken out of
+/// the rotation and the request moves on to the next one. After the cooldown
+/// it is health-checked with `getHealth` before it is used again. Transactions
+/// are only ever sent to the first, primary endpoint, so a retry can't land
+/// them twice through different nodes. The reads behind sending them, see
+/// [`reads_from_primary`], go to the primary too and only move on to the
+/// other endpoints when it fails.
+pub struct FailoverSender {
+    endpoints: Vec<Endpoint>,
+    next: AtomicUsize,
+    cooldown: Duration,
+}
+
+impl FailoverSender {
+    pub fn new(senders: Vec<Box<dyn RpcSender + Send + Sync>>, cooldown: Duration) -> Self {
+        assert!(!senders.is_empty(), "FailoverSender needs an endpoint");
+        Self {
+            endpoints: senders
+                .into_iter()
+                .map(|sender| Endpoint {
+                    sender,
+                    down_until: Mutex::new(None),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            cooldown,
+        }
+    }
+
+    /// An HTTP endpoint for each of `urls`; the first is the primary.
+    pub fn http(urls: &[String], timeout: Duration) -> Self {
+        let senders = urls
+            .iter()
+            .map(|url| {
+                Box::new(HttpSender::new_with_timeout(url.clone(), timeout))
+                    as Box<dyn RpcSender + Send + Sync>
+            })
+            .collect();
+        Self::new(senders, FAILOVER_COOLDOWN)
+    }
+
+    fn primary(&self) -> &Endpoint {
+        &self.endpoints[0]
+    }
+
+    async fn read(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let rotation: Vec<&Endpoint> = if reads_from_primary(request) {
+            let backups = &self.endpoints[1..];
+            std::iter::once(self.primary())
+                .chain((0..backups.len()).map(|i| &backups[(start + i) % backups.len()]))
+                .collect()
+        } else {
+            (0..self.endpoints.len())
+                .map(|i| &self.endpoints[(start + i) % self.endpoints.len()])
+                .collect()
+        };
+
+        // Healthy endpoints first; the rest are only tried when every healthy one fails.
+        let mut healthy = Vec::new();
+        let mut down = Vec::new();
+        for endpoint in rotation {
+            if endpoint.is_healthy(self.cooldown).await {
+                healthy.push(endpoint);
+            } else {
+                down.push(endpoint);
+            }
+        }
+
+        let mut last_err = None;
+        for endpoint in healthy.into_iter().chain(down) {
+            match endpoint.sender.send(request, params.clone()).await {
+                Err(err) if is_endpoint_failure(&err) => {
+                    endpoint.mark_down(self.cooldown);
+                    last_err = Some(err);
+                }
+                result => {
+                    endpoint.mark_up();
+                    return result;
+                }
+            }
+        }
+        Err(last_err.expect("at least one endpoint was tried"))
+    }
+}
+
+#[async_trait]
+impl RpcSender for FailoverSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        if self.endpoints.len() == 1
+            || matches!(
+                request,
+                RpcRequest::SendTransaction | RpcRequest::RequestAirdrop
+            )
+        {
+            return self.primary().sender.send(request, params).await;
+        }
+        self.read(request, params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.sender.get_transport_stats())
+            .fold(RpcTransportStats::default(), |total, stats| {
+                RpcTransportStats {
+                    request_count: total.request_count + stats.request_count,
+                    elapsed_time: total.elapsed_time + stats.elapsed_time,
+                    rate_limited_time: total.rate_limited_time + stats.rate_limited_time,
+                }
+            })
+    }
+
+    fn url(&self) -> String {
+        self.primary().sender.url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+    use solana_rpc_client::mock_sender::MockSender;
+    use std::{
+        io,
+        sync::{atomic::AtomicBool, Arc},
+    };
+
+    /// Forwards to a `MockSender`, counting requests and failing them while `down` is set.
+    struct TestEndpoint {
+        name: &'static str,
+        inner: MockSender,
+        down: Arc<AtomicBool>,
+        requests: Arc<Mutex<Vec<(&'static str, RpcRequest)>>>,
+    }
+
+    #[async_trait]
+    impl RpcSender for TestEndpoint {
+        async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+            self.requests.lock().unwrap().push((self.name, request));
+            if self.down.load(Ordering::SeqCst) {
+                return Err(io::Error::new(io::ErrorKind::ConnectionRefused, "down").into());
+            }
+            self.inner.send(request, params).await
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            self.name.to_string()
+        }
+    }
+
+    type Requests = Arc<Mutex<Vec<(&'static str, RpcRequest)>>>;
+
+    fn failover_client(
+        names: &[&'static str],
+        cooldown: Duration,
+    ) -> (RpcClient, Vec<Arc<AtomicBool>>, Requests) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let downs: Vec<_> = names
+            .iter()
+            .map(|_| Arc::new(AtomicBool::new(false)))
+            .collect();
+        let senders = names
+            .iter()
+            .zip(&downs)
+            .map(|(name, down)| {
+                Box::new(TestEndpoint {
+                    name,
+                    inner: MockSender::new("succeeds"),
+                    down: down.clone(),
+                    requests: requests.clone(),
+                }) as Box<dyn RpcSender + Send + Sync>
+            })
+            .collect();
+        let client = RpcClient::new_sender(
+            FailoverSender::new(senders, cooldown),
+            RpcClientConfig::default(),
+        );
+        (client, downs, requests)
+    }
+
+    fn names(requests: &Requests) -> Vec<&'static str> {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    #[test]
+    fn parses_comma_separated_urls() {
+        assert_eq!(
+            parse_rpc_urls("https://a.example.com, https://b.example.com,"),
+            vec!["https://a.example.com", "https://b.example.com"]
+        );
+        assert_eq!(
+            parse_rpc_urls("https://a.example.com"),
+            vec!["https://a.example.com"]
+        );
+    }
+
+    #[test]
+    fn reads_rotate_across_endpoints() {
+        let (client, _, requests) = failover_client(&["a", "b", "c"], FAILOVER_COOLDOWN);
+        for _ in 0..3 {
+            client.get_slot().unwrap();
+        }
+        assert_eq!(names(&requests), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn failed_endpoint_is_skipped_until_its_cooldown_ends() {
+        let (client, downs, requests) = failover_client(&["a", "b"], FAILOVER_COOLDOWN);
+        downs[0].store(true, Ordering::SeqCst);
+
+        client.get_slot().unwrap();
+        assert_eq!(names(&requests), vec!["a", "b"]);
+
+        requests.lock().unwrap().clear();
+        for _ in 0..2 {
+            client.get_slot().unwrap();
+        }
+        assert_eq!(names(&requests), vec!["b", "b"]);
+    }
+
+    #[test]
+    fn recovered_endpoint_rejoins_after_a_health_check() {
+        let (client, downs, requests) = failover_client(&["a", "b"], Duration::ZERO);
+        downs[0].store(true, Ordering::SeqCst);
+        client.get_slot().unwrap();
+
+        downs[0].store(false, Ordering::SeqCst);
+        requests.lock().unwrap().clear();
+        client.get_slot().unwrap();
+        client.get_slot().unwrap();
+
+        let requests = requests.lock().unwrap().clone();
+        assert!(requests.contains(&("a", RpcRequest::GetHealth)));
+        assert!(requests.contains(&("a", RpcRequest::GetSlot)));
+    }
+
+    #[test]
+    fn transactions_only_go_to_the_primary() {
+        let (client, downs, requests) = failover_client(&["a", "b"], FAILOVER_COOLDOWN);
+        let tx = solana_sdk::transaction::Transaction::default();
+
+        downs[0].store(true, Ordering::SeqCst);
+        assert!(client.send_transaction(&tx).is_err());
+
+        let sends: Vec<_> = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, request)| *request == RpcRequest::SendTransaction)
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(sends, vec!["a"]);
+    }
+
+    #[test]
+    fn send_decisions_read_from_the_primary() {
+        let (client, downs, requests) = failover_client(&["a", "b", "c"], FAILOVER_COOLDOWN);
+        let signature = solana_sdk::signature::Signature::default();
+
+        for _ in 0..2 {
+            client.get_latest_blockhash().unwrap();
+            client.get_signature_statuses(&[signature]).unwrap();
+            client.get_block_height().unwrap();
+        }
+        assert_eq!(names(&requests), vec!["a"; 6]);
+
+        // A backup only answers them while the primary is failing.
+        downs[0].store(true, Ordering::SeqCst);
+        requests.lock().unwrap().clear();
+        client.get_latest_blockhash().unwrap();
+        assert_eq!(names(&requests), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn request_errors_do_not_fail_over() {
+        let err = ClientError::from(RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed".to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        });
+        assert!(!is_endpoint_failure(&err));
+
+        let err = ClientError::from(RpcError::RpcResponseError {
+            code: NODE_UNHEALTHY_CODE,
+            message: "Node is behind".to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        });
+        assert!(is_endpoint_failure(&err));
+    }
+}
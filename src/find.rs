@@ -2,18 +2,29 @@ use anyhow::Result;
 use borsh::BorshDeserialize;
 use metaboss_lib::{derive::derive_edition_pda, snapshot::get_edition_accounts_by_master};
 use mpl_token_metadata::accounts::Edition;
+use serde_json::json;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
-use crate::{errors::DecodeError, spinner::create_spinner};
+use crate::{errors::DecodeError, output::record_data, spinner::create_spinner};
 
 pub fn find_missing_editions_process(client: &RpcClient, mint: &str) -> Result<()> {
-    find_missing_editions(client, mint)?;
+    let (edition_nums, missing_nums) = edition_numbers(client, mint)?;
+    record_data(&json!({
+        "edition_numbers": edition_nums,
+        "missing_numbers": missing_nums,
+    }));
     Ok(())
 }
 
 pub fn find_missing_editions(client: &RpcClient, mint: &str) -> Result<Vec<u64>> {
+    let (_, missing_nums) = edition_numbers(client, mint)?;
+    Ok(missing_nums)
+}
+
+/// The printed edition numbers of `mint`, and those missing below the largest.
+fn edition_numbers(client: &RpcClient, mint: &str) -> Result<(Vec<u64>, Vec<u64>)> {
     let master_edition_pubkey = derive_edition_pda(&Pubkey::from_str(mint)?);
 
     let mut edition_nums = Vec::new();
@@ -40,8 +51,8 @@ pub fn find_missing_editions(client: &RpcClient, mint: &str) -> Result<Vec<u64>>
 
     spinner.finish();
 
-    println!("Edition numbers: {edition_nums:?}");
-    println!("Missing numbers: {missing_nums:?}");
+    say!("Edition numbers: {edition_nums:?}");
+    say!("Missing numbers: {missing_nums:?}");
 
-    Ok(missing_nums)
+    Ok((edition_nums, missing_nums))
 }
@@ -0,0 +1,553 @@
+use anyhow::{anyhow, bail, Result};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use structopt::{clap::ErrorKind, StructOpt};
+
+use std::{fs, path::Path};
+
+use crate::{
+    config::Profile,
+    opt::{Command, Opt},
+    process_subcommands::{check_global_options, process_command},
+    setup::AppConfigBuilder,
+};
+
+/// A declarative batch job: settings shared by every step, then the steps to
+/// run in order.
+///
+/// Each step is an ordinary metaboss command, e.g. `update uri-all`, including
+/// any positional arguments, with its options given as a map of long option
+/// names to values. The shared settings are passed to every step whose
+/// command accepts them, unless the step sets them itself.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    pub rpc: Option<RpcEndpoints>,
+    pub keypair: Option<String>,
+    pub rate_limit: Option<usize>,
+    pub retries: Option<u8>,
+    pub priority: Option<String>,
+    pub steps: Vec<JobStep>,
+}
+
+/// One RPC url, or a list to fail over between with the first as primary.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum RpcEndpoints {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl RpcEndpoints {
+    /// The endpoints in the comma-separated form `--rpc` takes.
+    pub fn to_rpc_arg(&self) -> String {
+        match self {
+            RpcEndpoints::One(url) => url.clone(),
+            RpcEndpoints::Many(urls) => urls.join(","),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobStep {
+    pub name: Option<String>,
+    pub command: String,
+    #[serde(default)]
+    pub args: IndexMap<String, ArgValue>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ArgValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<ArgValue>),
+}
+
+impl ArgValue {
+    fn push_args(&self, flag: &str, argv: &mut Vec<String>) {
+        match self {
+            // Flags are present or absent.
+            ArgValue::Bool(true) => argv.push(flag.to_string()),
+            ArgValue::Bool(false) => (),
+            ArgValue::Int(value) => argv.extend([flag.to_string(), value.to_string()]),
+            ArgValue::Float(value) => argv.extend([flag.to_string(), value.to_string()]),
+            ArgValue::String(value) => argv.extend([flag.to_string(), value.clone()]),
+            ArgValue::List(values) => values.iter().for_each(|v| v.push_args(flag, argv)),
+        }
+    }
+}
+
+impl Job {
+    /// Read a job file, choosing YAML or TOML by its extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(toml::from_str(&contents)?),
+            Some("yaml") | Some("yml") => Ok(serde_yaml::from_str(&contents)?),
+            _ => Err(anyhow!(
+                "Job file must have a .yaml, .yml or .toml extension: {}",
+                path.display()
+            )),
+        }
+    }
+
+    /// Fill in the shared settings the job leaves out from a config file
+    /// profile.
+    pub fn with_profile(mut self, profile: &Profile) -> Self {
+        self.keypair = self.keypair.or_else(|| profile.keypair.clone());
+        self.rate_limit = self.rate_limit.or(profile.rate_limit);
+        self.priority = self.priority.or_else(|| profile.priority.clone());
+        self
+    }
+
+    /// Shared settings as `(option name, value)` pairs.
+    fn defaults(&self) -> Vec<(&'static str, ArgValue)> {
+        let mut defaults = Vec::new();
+        if let Some(keypair) = &self.keypair {
+            defaults.push(("keypair", ArgValue::String(keypair.clone())));
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            defaults.push(("rate-limit", ArgValue::Int(rate_limit as i64)));
+        }
+        if let Some(retries) = self.retries {
+            defaults.push(("retries", ArgValue::Int(retries as i64)));
+        }
+        if let Some(priority) = &self.priority {
+            defaults.push(("priority", ArgValue::String(priority.clone())));
+        }
+        defaults
+    }
+
+    /// Parse every step up front so a mistake in a later step is caught before
+    /// anything is sent.
+    pub fn commands(&self) -> Result<Vec<Command>> {
+        let defaults = self.defaults();
+
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                step.parse(&defaults)
+                    .map_err(|e| anyhow!("Invalid step {} ({}): {e}", i + 1, step.label()))
+            })
+            .collect()
+    }
+}
+
+impl JobStep {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{name}: {}", self.command),
+            None => self.command.clone(),
+        }
+    }
+
+    fn args(&self) -> Vec<String> {
+        let mut argv = vec!["metaboss".to_string()];
+        argv.extend(self.command.split_whitespace().map(String::from));
+
+        for (name, value) in &self.args {
+            let name = name.trim_start_matches("--").replace('_', "-");
+            value.push_args(&format!("--{name}"), &mut argv);
+        }
+        argv
+    }
+
+    fn parse(&self, defaults: &[(&str, ArgValue)]) -> Result<Command> {
+        let argv = self.args();
+        let opt = parse_with_defaults(&argv, defaults)?;
+        if matches!(opt.cmd, Command::Run { .. }) {
+            bail!("A job can't run another job.");
+        }
+        if let Some(option) = global_option_in(&argv) {
+            bail!(
+                "--{option} is a global option and applies to the whole job; \
+                pass it to `metaboss run` instead."
+            );
+        }
+        Ok(opt.cmd)
+    }
+}
+
+/// Global options of `Opt`. They configure the whole run, so a step can't set them.
+const GLOBAL_OPTIONS: &[&str] = &[
+    "rpc",
+    "timeout",
+    "profile",
+    "das-url",
+    "log-level",
+    "dry-run",
+    "resume",
+    "offline",
+    "nonce-accounts",
+    "multisig",
+    "proposal-encoding",
+    "payer",
+    "max-priority-fee",
+    "max-priority-fee-total",
+    "max-spend",
+    "yes",
+    "output-format",
+];
+
+/// The first global option `argv` gives, if any.
+fn global_option_in(argv: &[String]) -> Option<&'static str> {
+    let matches = Opt::clap().get_matches_from_safe(argv).ok()?;
+    GLOBAL_OPTIONS
+        .iter()
+        .copied()
+        .find(|option| matches.occurrences_of(option) > 0)
+}
+
+/// Parse `argv` with `defaults` appended as long options. Defaults the command
+/// doesn't take, or that `argv` already sets, are left out.
+pub fn parse_with_defaults(
+    argv: &[String],
+    defaults: &[(&str, ArgValue)],
+) -> Result<Opt, structopt::clap::Error> {
+    let mut defaults = defaults.to_vec();
+
+    loop {
+        let mut args = argv.to_vec();
+        for (name, value) in &defaults {
+            value.push_args(&format!("--{name}"), &mut args);
+        }
+
+        match Opt::from_iter_safe(args) {
+            Ok(opt) => return Ok(opt),
+            Err(e)
+                if matches!(
+                    e.kind,
+                    ErrorKind::UnknownArgument | ErrorKind::UnexpectedMultipleUsage
+                ) =>
+            {
+                // Unknown arguments are reported as given, repeated ones by field name.
+                let rejected = e
+                    .info
+                    .as_ref()
+                    .and_then(|info| info.first())
+                    .map(|arg| arg.trim_start_matches('-').replace('_', "-"));
+                let before = defaults.len();
+                defaults.retain(|(name, _)| Some(*name) != rejected.as_deref());
+                if defaults.len() == before {
+                    return Err(e);
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Run every step of the job at `job_file` in order, stopping at the first failure.
+pub async fn run_job(
+    job_file: &str,
+    builder: AppConfigBuilder,
+    profile: Option<&Profile>,
+) -> Result<()> {
+    let mut job = Job::from_file(job_file)?;
+    if let Some(profile) = profile {
+        job = job.with_profile(profile);
+    }
+    let commands = job.commands()?;
+
+    let builder = match &job.rpc {
+        Some(rpc) => builder.default_rpc_url(rpc.to_rpc_arg()),
+        None => builder,
+    };
+
+    // Global options apply to every step, so check them all before the first runs.
+    builder.clone().build()?;
+    for (i, (step, command)) in job.steps.iter().zip(&commands).enumerate() {
+        check_global_options(command)
+            .map_err(|e| anyhow!("Invalid step {} ({}): {e}", i + 1, step.label()))?;
+    }
+
+    let total = commands.len();
+    for (i, (step, command)) in job.steps.iter().zip(commands).enumerate() {
+        say!("Step {}/{}: {}", i + 1, total, step.label());
+
+        process_command(builder.clone().build()?, command)
+            .await
+            .map_err(|e| anyhow!("Step {} ({}) failed: {e}", i + 1, step.label()))?;
+    }
+
+    say!("Job complete: {total} steps run.");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opt::{DeriveSubcommands, UpdateSubcommands};
+
+    const YAML_JOB: &str = r#"
+keypair: authority.json
+rate_limit: 5
+priority: medium
+steps:
+  - name: Point to new metadata
+    command: update uri-all
+    args:
+      new_uris_file: new_uris.json
+      retries: 3
+  - command: derive metadata 11111111111111111111111111111111
+"#;
+
+    #[test]
+    fn yaml_job_steps_parse_into_commands() {
+        let job: Job = serde_yaml::from_str(YAML_JOB).unwrap();
+
+        let commands = job.commands().unwrap();
+
+        assert_eq!(commands.len(), 2);
+        match &commands[0] {
+            Command::Update {
+                update_subcommands:
+                    UpdateSubcommands::UriAll {
+                        keypair,
+                        new_uris_file,
+                        rate_limit,
+                        retries,
+                        ..
+                    },
+            } => {
+                assert_eq!(keypair.as_deref(), Some("authority.json"));
+                assert_eq!(new_uris_file, "new_uris.json");
+                assert_eq!(*rate_limit, 5);
+                // Step args take precedence over shared settings.
+                assert_eq!(*retries, 3);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+        // Shared settings the command doesn't take are left out.
+        assert!(matches!(
+            commands[1],
+            Command::Derive {
+                derive_subcommands: DeriveSubcommands::Metadata { .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn toml_job_parses() {
+        let job: Job = toml::from_str(
+            r#"
+keypair = "authority.json"
+
+[[steps]]
+command = "update uri-all"
+args = { new-uris-file = "new_uris.json" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(job.steps.len(), 1);
+        assert_eq!(job.commands().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rpc_can_be_one_url_or_a_list() {
+        let job: Job = serde_yaml::from_str(
+            r#"
+rpc:
+  - https://primary.example.com
+  - https://backup.example.com
+steps: []
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            job.rpc.unwrap().to_rpc_arg(),
+            "https://primary.example.com,https://backup.example.com"
+        );
+
+        let job: Job = toml::from_str(
+            r#"
+rpc = "https://primary.example.com"
+steps = []
+"#,
+        )
+        .unwrap();
+        assert_eq!(job.rpc.unwrap().to_rpc_arg(), "https://primary.example.com");
+    }
+
+    #[test]
+    fn step_args_expand_flags_and_lists() {
+        let step = JobStep {
+            name: None,
+            command: "update creators-all".to_string(),
+            args: IndexMap::from([
+                ("append".to_string(), ArgValue::Bool(true)),
+                ("force".to_string(), ArgValue::Bool(false)),
+                (
+                    "creators".to_string(),
+                    ArgValue::List(vec![
+                        ArgValue::String("a".to_string()),
+                        ArgValue::String("b".to_string()),
+                    ]),
+                ),
+            ]),
+        };
+
+        let argv = step.args();
+
+        assert_eq!(
+            argv,
+            vec![
+                "metaboss",
+                "update",
+                "creators-all",
+                "--append",
+                "--creators",
+                "a",
+                "--creators",
+                "b"
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults_never_override_given_options() {
+        let argv: Vec<String> = [
+            "metaboss",
+            "update",
+            "uri-all",
+            "-u",
+            "new_uris.json",
+            "-k",
+            "mine.json",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let defaults = [
+            ("keypair", ArgValue::String("profile.json".to_string())),
+            ("rate-limit", ArgValue::Int(20)),
+            ("not-an-option", ArgValue::Bool(true)),
+        ];
+
+        let opt = parse_with_defaults(&argv, &defaults).unwrap();
+
+        match opt.cmd {
+            Command::Update {
+                update_subcommands:
+                    UpdateSubcommands::UriAll {
+                        keypair,
+                        rate_limit,
+                        ..
+                    },
+            } => {
+                assert_eq!(keypair.as_deref(), Some("mine.json"));
+                assert_eq!(rate_limit, 20);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn profile_fills_in_missing_shared_settings() {
+        let job: Job = serde_yaml::from_str(YAML_JOB).unwrap();
+        let profile = Profile {
+            keypair: Some("profile.json".to_string()),
+            priority: Some("high".to_string()),
+            timeout: Some(30),
+            ..Profile::default()
+        };
+
+        let job = job.with_profile(&profile);
+
+        assert_eq!(job.keypair.as_deref(), Some("authority.json"));
+        assert_eq!(job.priority.as_deref(), Some("medium"));
+        assert_eq!(job.rate_limit, Some(5));
+
+        let job: Job = serde_yaml::from_str("steps: []").unwrap();
+        let job = job.with_profile(&profile);
+        assert_eq!(job.keypair.as_deref(), Some("profile.json"));
+        assert_eq!(job.priority.as_deref(), Some("high"));
+    }
+
+    #[test]
+    fn invalid_step_is_reported_with_its_number() {
+        let job: Job = serde_yaml::from_str(
+            r#"
+steps:
+  - command: update uri-all
+    args:
+      new-uris-file: new_uris.json
+  - command: update not-a-command
+"#,
+        )
+        .unwrap();
+
+        let err = job.commands().unwrap_err().to_string();
+
+        assert!(err.starts_with("Invalid step 2 (update not-a-command)"));
+    }
+
+    #[test]
+    fn nested_jobs_are_rejected() {
+        let job: Job = serde_yaml::from_str(
+            r#"
+steps:
+  - command: run other.yaml
+"#,
+        )
+        .unwrap();
+
+        assert!(job
+            .commands()
+            .unwrap_err()
+            .to_string()
+            .contains("can't run another job"));
+    }
+
+    #[test]
+    fn global_options_in_steps_are_rejected() {
+        let job: Job = serde_yaml::from_str(
+            r#"
+steps:
+  - command: update uri-all
+    args:
+      new-uris-file: new_uris.json
+      dry-run: true
+"#,
+        )
+        .unwrap();
+
+        let err = job.commands().unwrap_err().to_string();
+
+        assert!(err.contains("--dry-run is a global option"));
+
+        // Every listed option is one `Opt` knows as global.
+        for option in GLOBAL_OPTIONS {
+            let mut argv: Vec<String> = ["metaboss", "derive", "metadata", SYSTEM_PROGRAM]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+            argv.push(format!("--{option}"));
+            argv.extend(sample_value(option).map(String::from));
+
+            assert_eq!(global_option_in(&argv), Some(*option));
+        }
+    }
+
+    const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+
+    fn sample_value(option: &str) -> Option<&'static str> {
+        match option {
+            "dry-run" | "resume" | "yes" => None,
+            "offline" | "multisig" => Some(SYSTEM_PROGRAM),
+            "proposal-encoding" => Some("base64"),
+            "output-format" => Some("json"),
+            "timeout" | "max-priority-fee" | "max-priority-fee-total" | "max-spend" => Some("1"),
+            _ => Some("value"),
+        }
+    }
+}
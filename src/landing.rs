@@ -0,0 +1,456 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::rpc_client::RpcClient as AsyncRpcClient,
+    rpc_client::RpcClient,
+    rpc_request::RpcRequest,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, instruction::Instruction,
+    signature::Signature, signers::Signers, transaction::VersionedTransaction,
+};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    constants::PRIORITY_FEE_BUDGET,
+    offline::compile_message,
+    output::record_signature,
+    priority::{
+        compute_unit_limit, instruction_compute_unit_price, level_micro_lamports,
+        with_compute_unit_price,
+    },
+};
+use metaboss_lib::data::Priority;
+
+/// How often a pending transaction is sent again while its blockhash is valid.
+pub const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+/// Blockhashes expire after about 150 blocks, so anything older is given up on.
+const MAX_PENDING_AGE: Duration = Duration::from_secs(120);
+
+/// How hard the landing engine tries before giving up.
+#[derive(Clone, Debug)]
+pub struct LandingConfig {
+    /// Times the transaction is signed with a fresh blockhash.
+    pub max_attempts: u32,
+    /// The priority fee is multiplied by this on every new attempt.
+    pub fee_multiplier: u64,
+    /// Delay between signature status checks.
+    pub poll_interval: Duration,
+}
+
+impl Default for LandingConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            fee_multiplier: 2,
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl LandingConfig {
+    /// The priority fee for `attempt`, counting from zero.
+    pub fn fee_for_attempt(&self, base_fee: u64, attempt: u32) -> u64 {
+        base_fee.saturating_mul(self.fee_multiplier.saturating_pow(attempt))
+    }
+}
+
+/// Sign `instructions` with `signers`, the first of which pays, and land them.
+///
+/// Each attempt is signed against a fresh blockhash and sent once, then its
+/// status is polled; the `RebroadcastSender` under the app's clients resends
+/// it meanwhile. Only once the blockhash's `lastValidBlockHeight` has passed is
+/// the transaction signed again, with the priority fee raised within the caps. The starting fee
+/// is the compute unit price in `instructions`, or the `Priority::None` price.
+pub async fn land_async<T: Signers + ?Sized>(
+    client: &AsyncRpcClient,
+    signers: &T,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    config: &LandingConfig,
+) -> Result<Signature> {
+    let payer = signers
+        .pubkeys()
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow!("No signers."))?;
+    let base_fee = instruction_compute_unit_price(instructions)
+        .unwrap_or_else(|| level_micro_lamports(&Priority::None));
+    let units = compute_unit_limit(instructions);
+
+    let mut fee = base_fee;
+    for attempt in 0..config.max_attempts {
+        if attempt > 0 {
+            // Escalation is charged to the run and held to the priority fee caps.
+            fee = PRIORITY_FEE_BUDGET.raise(fee, config.fee_for_attempt(base_fee, attempt), units);
+        }
+        let ixs = with_compute_unit_price(instructions, fee);
+
+        let (blockhash, last_valid_block_height) = client
+            .get_latest_blockhash_with_commitment(client.commitment())
+            .await?;
+        let message = compile_message(&payer, &ixs, lookup_tables, blockhash)?;
+        let tx = VersionedTransaction::try_new(message, signers)?;
+        let signature = client.send_transaction(&tx).await?;
+
+        loop {
+            tokio::time::sleep(config.poll_interval).await;
+            match client.get_signature_status(&signature).await? {
+                Some(Ok(())) => {
+                    record_signature(signature);
+                    return Ok(signature);
+                }
+                Some(Err(err)) => return Err(err.into()),
+                None => {
+                    if client.get_block_height().await? > last_valid_block_height {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "Transaction did not land after {} attempts",
+        config.max_attempts
+    ))
+}
+
+/// `land_async` for the blocking client, run on the client's own runtime.
+pub fn land<T: Signers + ?Sized>(
+    client: &RpcClient,
+    signers: &T,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Signature> {
+    let config = LandingConfig::default();
+    tokio::task::block_in_place(|| {
+        client.runtime().block_on(land_async(
+            client.get_inner_client(),
+            signers,
+            instructions,
+            lookup_tables,
+            &config,
+        ))
+    })
+}
+
+struct Pending {
+    params: Value,
+    first_sent: Instant,
+    last_sent: Instant,
+}
+
+/// An `RpcSender` that rebroadcasts unconfirmed transactions.
+///
+/// Every transaction sent is remembered. Whenever its status is polled and it
+/// hasn't landed, the same signed transaction is sent again, at most every
+/// `REBROADCAST_INTERVAL`. This covers every command, including those that
+/// confirm through `RpcClient::send_and_confirm_transaction`, which otherwise
+/// sends once and waits for the blockhash to expire.
+pub struct RebroadcastSender<T> {
+    inner: T,
+    pending: Mutex<HashMap<String, Pending>>,
+    interval: Duration,
+}
+
+impl<T> RebroadcastSender<T> {
+    pub fn new(inner: T) -> Self {
+        Self::with_interval(inner, REBROADCAST_INTERVAL)
+    }
+
+    pub fn with_interval(inner: T, interval: Duration) -> Self {
+        Self {
+            inner,
+            pending: Mutex::new(HashMap::new()),
+            interval,
+        }
+    }
+
+    /// Signatures in `params` whose transactions are due to be sent again,
+    /// given their `statuses`, forgetting those that landed or are too old.
+    fn due(&self, params: &Value, statuses: &Value) -> Vec<(String, Value)> {
+        let signatures = params[0].as_array().cloned().unwrap_or_default();
+        let statuses = statuses["value"].as_array().cloned().unwrap_or_default();
+        let now = Instant::now();
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| now.duration_since(p.first_sent) < MAX_PENDING_AGE);
+
+        let mut due = Vec::new();
+        for (signature, status) in signatures.iter().zip(statuses.iter()) {
+            let Some(signature) = signature.as_str() else {
+                continue;
+            };
+            if !status.is_null() {
+                pending.remove(signature);
+                continue;
+            }
+            if let Some(p) = pending.get_mut(signature) {
+                if now.duration_since(p.last_sent) >= self.interval {
+                    p.last_sent = now;
+                    due.push((signature.to_string(), p.params.clone()));
+                }
+            }
+        }
+        due
+    }
+}
+
+#[async_trait]
+impl<T: RpcSender + Send + Sync> RpcSender for RebroadcastSender<T> {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        match request {
+            RpcRequest::SendTransaction => {
+                let result = self.inner.send(request, params.clone()).await?;
+                if let Some(signature) = result.as_str() {
+                    // Rebroadcasts skip preflight; the first send already ran it.
+                    let mut params = params;
+                    params[1]["skipPreflight"] = json!(true);
+                    let now = Instant::now();
+                    self.pending.lock().unwrap().insert(
+                        signature.to_string(),
+                        Pending {
+                            params,
+                            first_sent: now,
+                            last_sent: now,
+                        },
+                    );
+                }
+                Ok(result)
+            }
+            RpcRequest::GetSignatureStatuses => {
+                let statuses = self.inner.send(request, params.clone()).await?;
+                for (_, params) in self.due(&params, &statuses) {
+                    // A failed rebroadcast changes nothing; the status poll decides.
+                    let _ = self.inner.send(RpcRequest::SendTransaction, params).await;
+                }
+                Ok(statuses)
+            }
+            _ => self.inner.send(request, params).await,
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::decode_wire_transaction;
+    use solana_client::rpc_client::RpcClientConfig;
+    use solana_sdk::{
+        instruction::AccountMeta, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    };
+    use std::sync::Arc;
+
+    /// A cluster where every transaction stays pending, except those signed
+    /// after `lands_after` sends, and the block height climbs on every check.
+    struct ScriptedCluster {
+        lands_after: usize,
+        state: Arc<Mutex<ClusterState>>,
+    }
+
+    #[derive(Default)]
+    struct ClusterState {
+        /// Every transaction sent, in order.
+        sent: Vec<VersionedTransaction>,
+        landed: Vec<String>,
+        block_height: u64,
+    }
+
+    #[async_trait]
+    impl RpcSender for ScriptedCluster {
+        async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+            let mut state = self.state.lock().unwrap();
+            Ok(match request {
+                RpcRequest::GetLatestBlockhash => json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "blockhash": solana_sdk::hash::Hash::new_unique().to_string(),
+                        "lastValidBlockHeight": state.block_height + 2,
+                    },
+                }),
+                RpcRequest::SendTransaction => {
+                    let tx = decode_wire_transaction(
+                        params[0].as_str().unwrap(),
+                        params[1]["encoding"].as_str().unwrap_or("base64"),
+                    )?;
+                    let signature = tx.signatures[0].to_string();
+                    let distinct: std::collections::HashSet<_> =
+                        state.sent.iter().map(|tx| tx.signatures[0]).collect();
+                    if distinct.len() >= self.lands_after && !distinct.contains(&tx.signatures[0]) {
+                        state.landed.push(signature.clone());
+                    }
+                    state.sent.push(tx);
+                    json!(signature)
+                }
+                RpcRequest::GetSignatureStatuses => {
+                    let statuses: Vec<Value> = params[0]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|sig| {
+                            if state.landed.iter().any(|s| s == sig.as_str().unwrap()) {
+                                json!({
+                                    "slot": 1,
+                                    "confirmations": null,
+                                    "err": null,
+                                    "status": { "Ok": null },
+                                    "confirmationStatus": "finalized",
+                                })
+                            } else {
+                                Value::Null
+                            }
+                        })
+                        .collect();
+                    json!({ "context": { "slot": 1 }, "value": statuses })
+                }
+                RpcRequest::GetBlockHeight => {
+                    state.block_height += 1;
+                    json!(state.block_height)
+                }
+                other => panic!("unexpected request {other}"),
+            })
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "scripted".to_string()
+        }
+    }
+
+    fn scripted_client<S: RpcSender + Send + Sync + 'static>(sender: S) -> AsyncRpcClient {
+        AsyncRpcClient::new_sender(sender, RpcClientConfig::default())
+    }
+
+    fn config() -> LandingConfig {
+        LandingConfig {
+            poll_interval: Duration::ZERO,
+            ..LandingConfig::default()
+        }
+    }
+
+    fn instruction(payer: &Keypair) -> Vec<Instruction> {
+        vec![Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new(payer.pubkey(), true)],
+        )]
+    }
+
+    fn sent_prices(state: &Mutex<ClusterState>) -> Vec<u64> {
+        state
+            .lock()
+            .unwrap()
+            .sent
+            .iter()
+            .map(|tx| {
+                let message = &tx.message;
+                let ixs: Vec<Instruction> = message
+                    .instructions()
+                    .iter()
+                    .map(|ix| Instruction {
+                        program_id: message.static_account_keys()[ix.program_id_index as usize],
+                        accounts: vec![],
+                        data: ix.data.clone(),
+                    })
+                    .collect();
+                instruction_compute_unit_price(&ixs).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fees_escalate_on_every_attempt() {
+        let config = LandingConfig::default();
+        let fees: Vec<_> = (0..4).map(|a| config.fee_for_attempt(1_000, a)).collect();
+        assert_eq!(fees, vec![1_000, 2_000, 4_000, 8_000]);
+    }
+
+    #[tokio::test]
+    async fn resigns_with_a_higher_fee_after_the_blockhash_expires() {
+        let payer = Keypair::new();
+        let state = Arc::new(Mutex::new(ClusterState::default()));
+        let client = scripted_client(ScriptedCluster {
+            lands_after: 1,
+            state: state.clone(),
+        });
+        let ixs = with_compute_unit_price(&instruction(&payer), 1_000);
+
+        let signature = land_async(&client, &[&payer], &ixs, &[], &config())
+            .await
+            .unwrap();
+
+        let state_ref = state.lock().unwrap();
+        assert_eq!(state_ref.sent.len(), 2);
+        assert_ne!(state_ref.sent[0].signatures[0], signature);
+        assert_eq!(state_ref.sent[1].signatures[0], signature);
+        drop(state_ref);
+        assert_eq!(sent_prices(&state), vec![1_000, 2_000]);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_attempt() {
+        let payer = Keypair::new();
+        let state = Arc::new(Mutex::new(ClusterState::default()));
+        let client = scripted_client(ScriptedCluster {
+            lands_after: usize::MAX,
+            state: state.clone(),
+        });
+
+        let err = land_async(&client, &[&payer], &instruction(&payer), &[], &config())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("did not land after 4 attempts"));
+        assert_eq!(state.lock().unwrap().sent.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn pending_transactions_are_rebroadcast() {
+        let payer = Keypair::new();
+        let state = Arc::new(Mutex::new(ClusterState::default()));
+        let client = scripted_client(RebroadcastSender::with_interval(
+            ScriptedCluster {
+                lands_after: usize::MAX,
+                state: state.clone(),
+            },
+            Duration::ZERO,
+        ));
+        let config = LandingConfig {
+            max_attempts: 1,
+            ..config()
+        };
+
+        assert!(
+            land_async(&client, &[&payer], &instruction(&payer), &[], &config)
+                .await
+                .is_err()
+        );
+
+        // One send, then the same transaction again on each of the status polls.
+        let state = state.lock().unwrap();
+        assert!(state.sent.len() > 1);
+        assert!(state
+            .sent
+            .iter()
+            .all(|tx| tx.signatures[0] == state.sent[0].signatures[0]));
+    }
+}
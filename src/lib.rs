@@ -1,25 +1,41 @@
 #![allow(clippy::result_large_err)]
 
+// Declared first so `say!` can be used by every other module.
+#[macro_use]
+pub mod output;
+
 pub mod airdrop;
 pub mod burn;
 pub mod cache;
 pub mod check;
 pub mod collections;
+pub mod config;
 pub mod constants;
+pub mod cost;
 pub mod create;
 pub mod data;
 pub mod decode;
 pub mod derive;
+pub mod dry_run;
 pub mod errors;
 pub mod extend_program;
+pub mod failover;
 pub mod find;
+pub mod job;
+pub mod landing;
 pub mod limiter;
+pub mod lookup_table;
 pub mod mint;
+pub mod offline;
 pub mod opt;
 pub mod parse;
+pub mod priority;
 pub mod process_subcommands;
+pub mod proposal;
+pub mod run_state;
 pub mod setup;
 pub mod sign;
+pub mod signer;
 pub mod snapshot;
 pub mod spinner;
 pub mod theindexio;
@@ -29,4 +45,5 @@ pub mod update;
 pub mod uses;
 pub mod utils;
 pub mod verify;
+pub mod watch;
 pub mod wtf_errors;
@@ -1,48 +1,400 @@
-use ratelimit::Handle;
-use std::{thread, time::Duration};
+use async_trait::async_trait;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
 use crate::constants::*;
 
-pub fn create_default_rate_limiter() -> Handle {
-    let num_cpus = num_cpus::get();
-
-    let mut limiter = ratelimit::Builder::new()
-        .capacity(num_cpus as u32)
-        .quantum(1)
-        .interval(Duration::new(0, *RPC_DELAY_NS.read().unwrap()))
-        .build();
-
-    let handle = limiter.make_handle();
-    thread::spawn(move || {
-        limiter.run();
-    });
-    handle
-}
-
-pub fn create_rate_limiter(delay: u32) -> Handle {
-    let mut limiter = ratelimit::Builder::new()
-        .capacity(1000)
-        .quantum(1)
-        .interval(Duration::new(0, delay))
-        .build();
-
-    let handle = limiter.make_handle();
-    thread::spawn(move || {
-        limiter.run();
-    });
-    handle
-}
-
-pub fn create_rate_limiter_with_capacity(capacity: u32, delay: u32) -> Handle {
-    let mut limiter = ratelimit::Builder::new()
-        .capacity(capacity)
-        .quantum(1)
-        .interval(Duration::new(0, delay))
-        .build();
-
-    let handle = limiter.make_handle();
-    thread::spawn(move || {
-        limiter.run();
-    });
-    handle
+/// JSON-RPC error code some providers return when a client is over its rate limit.
+pub const RATE_LIMITED_CODE: i64 = -32005;
+
+/// The lowest rate, in requests per second, the limiter backs off to.
+pub const MIN_RPC_RATE: f64 = 1.0;
+
+/// Requests per second added after each healthy response.
+const RATE_INCREASE: f64 = 1.0;
+/// Factor the rate is cut by when the RPC throttles us.
+const THROTTLED_DECREASE: f64 = 0.5;
+/// Factor the rate is cut by when a response is slow.
+const SLOW_DECREASE: f64 = 0.9;
+/// Responses slower than this count as a sign the RPC is struggling.
+const SLOW_RESPONSE: Duration = Duration::from_secs(2);
+
+/// Times DAS requests are retried after a 429.
+const DAS_THROTTLED_RETRIES: u8 = 5;
+
+/// An AIMD rate limiter shared by every RPC client.
+///
+/// Requests are spaced evenly at the current rate. Each healthy response adds
+/// `RATE_INCREASE` requests per second up to the ceiling, a slow one trims the
+/// rate, and an HTTP 429 or `-32005` halves it and holds back the next request.
+pub struct AdaptiveLimiter {
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    rate: f64,
+    max_rate: f64,
+    next: Instant,
+}
+
+impl AdaptiveLimiter {
+    pub fn new(rate: f64, max_rate: f64) -> Self {
+        let max_rate = max_rate.max(MIN_RPC_RATE);
+        Self {
+            state: Mutex::new(LimiterState {
+                rate: rate.clamp(MIN_RPC_RATE, max_rate),
+                max_rate,
+                next: Instant::now(),
+            }),
+        }
+    }
+
+    /// Start again from `rate`, never going above `max_rate`.
+    pub fn configure(&self, rate: f64, max_rate: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.max_rate = max_rate.max(MIN_RPC_RATE);
+        state.rate = rate.clamp(MIN_RPC_RATE, state.max_rate);
+    }
+
+    /// The current rate in requests per second.
+    pub fn rate(&self) -> f64 {
+        self.state.lock().unwrap().rate
+    }
+
+    /// Claim the next request slot, returning how long to wait for it.
+    fn reserve(&self) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let slot = state.next.max(now);
+        state.next = slot + Duration::from_secs_f64(1.0 / state.rate);
+        slot - now
+    }
+
+    /// Block the current thread until a request may be sent.
+    pub fn wait(&self) {
+        let delay = self.reserve();
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+
+    /// Wait until a request may be sent.
+    pub async fn acquire(&self) {
+        let delay = self.reserve();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Speed up after a healthy response, or ease off after a slow one.
+    pub fn record_success(&self, latency: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.rate = if latency > SLOW_RESPONSE {
+            (state.rate * SLOW_DECREASE).max(MIN_RPC_RATE)
+        } else {
+            (state.rate + RATE_INCREASE).min(state.max_rate)
+        };
+    }
+
+    /// Back off after the RPC refused a request for being over its limit.
+    pub fn record_throttled(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.rate = (state.rate * THROTTLED_DECREASE).max(MIN_RPC_RATE);
+        let pause = Instant::now() + Duration::from_secs_f64(1.0 / state.rate);
+        state.next = state.next.max(pause);
+    }
+}
+
+/// The starting rate and ceiling for `rpc_url`. Known public endpoints start
+/// slow with a low ceiling, since they rate-limit aggressively.
+pub fn rpc_limits(rpc_url: &str) -> (f64, f64) {
+    if PUBLIC_RPC_URLS.contains(&rpc_url) {
+        (PUBLIC_RPC_RATE, PUBLIC_MAX_RPC_RATE)
+    } else {
+        (DEFAULT_RPC_RATE, DEFAULT_MAX_RPC_RATE)
+    }
+}
+
+/// Configure the shared limiter for `rpc_url`.
+pub fn configure_rpc_limiter(rpc_url: &str) {
+    let (rate, max_rate) = rpc_limits(rpc_url);
+    RPC_LIMITER.configure(rate, max_rate);
+}
+
+/// Whether `err` is the RPC telling us to slow down.
+pub fn is_throttled(err: &ClientError) -> bool {
+    match err.kind() {
+        // Solana's reqwest is a different version, so compare the raw code.
+        ClientErrorKind::Reqwest(err) => err
+            .status()
+            .is_some_and(|status| status.as_u16() == StatusCode::TOO_MANY_REQUESTS.as_u16()),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == RATE_LIMITED_CODE
+        }
+        _ => false,
+    }
+}
+
+/// An `RpcSender` that paces every request through an `AdaptiveLimiter`.
+///
+/// `HttpSender` retries 429s itself, so a rise in the wrapped sender's
+/// rate-limited time counts as throttling too, even when the request succeeds.
+pub struct LimitedSender<T> {
+    inner: T,
+    limiter: Arc<AdaptiveLimiter>,
+}
+
+impl<T> LimitedSender<T> {
+    pub fn new(inner: T, limiter: Arc<AdaptiveLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl<T: RpcSender + Send + Sync> RpcSender for LimitedSender<T> {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        self.limiter.acquire().await;
+
+        let rate_limited_before = self.inner.get_transport_stats().rate_limited_time;
+        let start = Instant::now();
+        let result = self.inner.send(request, params).await;
+
+        let throttled = self.inner.get_transport_stats().rate_limited_time > rate_limited_before
+            || result.as_ref().err().is_some_and(is_throttled);
+        if throttled {
+            self.limiter.record_throttled();
+        } else if result.is_ok() {
+            self.limiter.record_success(start.elapsed());
+        }
+
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Send a DAS request through the shared limiter, backing off and retrying
+/// when the endpoint answers 429.
+pub async fn send_das_request(request: RequestBuilder) -> reqwest::Result<Response> {
+    let mut retries = DAS_THROTTLED_RETRIES;
+    loop {
+        RPC_LIMITER.acquire().await;
+
+        // Bodies built with `json` can always be cloned.
+        let attempt = request
+            .try_clone()
+            .expect("DAS requests have cloneable bodies");
+        let start = Instant::now();
+        let response = attempt.send().await?;
+
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            RPC_LIMITER.record_success(start.elapsed());
+            return Ok(response);
+        }
+        RPC_LIMITER.record_throttled();
+        if retries == 0 {
+            return Ok(response);
+        }
+        retries -= 1;
+    }
+}
+
+/// Paces the tasks a batch command spawns at `--rate-limit` per second, or at
+/// the shared RPC rate when the RPC has pushed that lower. Clones share one
+/// schedule.
+#[derive(Clone)]
+pub struct Pacer {
+    max_rate: f64,
+    next: Arc<Mutex<Instant>>,
+}
+
+impl Pacer {
+    pub fn new(max_rate: usize) -> Self {
+        Self {
+            max_rate: (max_rate as f64).max(MIN_RPC_RATE),
+            next: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Block until the next task may start.
+    pub fn wait(&self) {
+        let rate = self.max_rate.min(RPC_LIMITER.rate());
+        let delay = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + Duration::from_secs_f64(1.0 / rate);
+            slot - now
+        };
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+    use solana_rpc_client::mock_sender::MockSender;
+
+    /// Fails every request with the given JSON-RPC error code.
+    struct FailingSender {
+        code: i64,
+    }
+
+    #[async_trait]
+    impl RpcSender for FailingSender {
+        async fn send(&self, _request: RpcRequest, _params: Value) -> ClientResult<Value> {
+            Err(RpcError::RpcResponseError {
+                code: self.code,
+                message: "error".to_string(),
+                data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+            }
+            .into())
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "failing".to_string()
+        }
+    }
+
+    fn limited_client<T: RpcSender + Send + Sync + 'static>(
+        sender: T,
+        limiter: Arc<AdaptiveLimiter>,
+    ) -> RpcClient {
+        RpcClient::new_sender(
+            LimitedSender::new(sender, limiter),
+            RpcClientConfig::default(),
+        )
+    }
+
+    // ── AdaptiveLimiter ──
+
+    #[test]
+    fn healthy_responses_raise_the_rate_up_to_the_ceiling() {
+        let limiter = AdaptiveLimiter::new(5.0, 7.0);
+        limiter.record_success(Duration::from_millis(50));
+        assert_eq!(limiter.rate(), 6.0);
+        limiter.record_success(Duration::from_millis(50));
+        limiter.record_success(Duration::from_millis(50));
+        assert_eq!(limiter.rate(), 7.0);
+    }
+
+    #[test]
+    fn throttling_halves_the_rate_down_to_the_floor() {
+        let limiter = AdaptiveLimiter::new(8.0, 100.0);
+        limiter.record_throttled();
+        assert_eq!(limiter.rate(), 4.0);
+        for _ in 0..10 {
+            limiter.record_throttled();
+        }
+        assert_eq!(limiter.rate(), MIN_RPC_RATE);
+    }
+
+    #[test]
+    fn slow_responses_trim_the_rate() {
+        let limiter = AdaptiveLimiter::new(10.0, 100.0);
+        limiter.record_success(SLOW_RESPONSE + Duration::from_millis(1));
+        assert_eq!(limiter.rate(), 9.0);
+    }
+
+    #[test]
+    fn requests_are_spaced_at_the_current_rate() {
+        let limiter = AdaptiveLimiter::new(10.0, 10.0);
+        assert!(limiter.reserve().is_zero());
+        let delay = limiter.reserve();
+        assert!(delay > Duration::from_millis(90) && delay <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn configure_resets_rate_and_ceiling() {
+        let limiter = AdaptiveLimiter::new(50.0, 1000.0);
+        limiter.configure(5.0, 10.0);
+        assert_eq!(limiter.rate(), 5.0);
+        for _ in 0..20 {
+            limiter.record_success(Duration::ZERO);
+        }
+        assert_eq!(limiter.rate(), 10.0);
+    }
+
+    #[test]
+    fn public_rpcs_start_slower_than_private_ones() {
+        assert_eq!(
+            rpc_limits("https://api.mainnet-beta.solana.com"),
+            (PUBLIC_RPC_RATE, PUBLIC_MAX_RPC_RATE)
+        );
+        assert_eq!(
+            rpc_limits("https://my-private-rpc.example.com"),
+            (DEFAULT_RPC_RATE, DEFAULT_MAX_RPC_RATE)
+        );
+    }
+
+    // ── LimitedSender ──
+
+    #[test]
+    fn rate_limited_errors_slow_the_limiter() {
+        let limiter = Arc::new(AdaptiveLimiter::new(20.0, 100.0));
+        let client = limited_client(
+            FailingSender {
+                code: RATE_LIMITED_CODE,
+            },
+            limiter.clone(),
+        );
+
+        assert!(client.get_slot().is_err());
+        assert_eq!(limiter.rate(), 10.0);
+    }
+
+    #[test]
+    fn other_errors_leave_the_rate_alone() {
+        let limiter = Arc::new(AdaptiveLimiter::new(20.0, 100.0));
+        let client = limited_client(FailingSender { code: -32002 }, limiter.clone());
+
+        assert!(client.get_slot().is_err());
+        assert_eq!(limiter.rate(), 20.0);
+    }
+
+    #[test]
+    fn successful_requests_speed_the_limiter_up() {
+        let limiter = Arc::new(AdaptiveLimiter::new(20.0, 100.0));
+        let client = limited_client(MockSender::new("succeeds"), limiter.clone());
+
+        client.get_slot().unwrap();
+        client.get_slot().unwrap();
+        assert_eq!(limiter.rate(), 22.0);
+    }
+
+    #[test]
+    fn only_rate_limit_errors_count_as_throttling() {
+        let err = ClientError::from(RpcError::RpcResponseError {
+            code: RATE_LIMITED_CODE,
+            message: "Too many requests".to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        });
+        assert!(is_throttled(&err));
+        assert!(!is_throttled(&ClientError::from(RpcError::ForUser(
+            "other".to_string()
+        ))));
+    }
 }
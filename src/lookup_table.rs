@@ -0,0 +1,251 @@
+use anyhow::{anyhow, bail, Result};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{
+            close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table,
+        },
+        state::AddressLookupTable,
+        AddressLookupTableAccount,
+    },
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::VersionedTransaction,
+};
+
+use std::{fs::File, str::FromStr};
+
+use crate::{
+    landing::land,
+    output::record_address,
+    parse::{parse_keypair, parse_payer, parse_solana_config},
+    utils::with_payer,
+};
+
+/// Addresses added per extend transaction, which keeps it well under the size limit.
+pub const EXTEND_CHUNK_SIZE: usize = 20;
+
+/// Fetch a lookup table's addresses so messages can be compiled against it.
+pub fn fetch_lookup_table(
+    client: &RpcClient,
+    address: &Pubkey,
+) -> Result<AddressLookupTableAccount> {
+    let account = client.get_account(address)?;
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| anyhow!("{address} is not an address lookup table: {e}"))?;
+
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Parse and fetch an optional `--lookup-table` argument.
+pub fn parse_lookup_table(
+    client: &RpcClient,
+    address: Option<&Pubkey>,
+) -> Result<Option<AddressLookupTableAccount>> {
+    address
+        .map(|address| fetch_lookup_table(client, address))
+        .transpose()
+}
+
+/// Compile and sign a v0 transaction. The first signer pays the fees.
+pub fn build_v0_transaction(
+    signers: &[&Keypair],
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let message = v0::Message::try_compile(
+        &signers[0].pubkey(),
+        instructions,
+        lookup_tables,
+        recent_blockhash,
+    )?;
+
+    Ok(VersionedTransaction::try_new(
+        VersionedMessage::V0(message),
+        signers,
+    )?)
+}
+
+pub fn send_and_confirm_v0_tx(
+    client: &RpcClient,
+    signers: &[&Keypair],
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Signature> {
+    land(client, signers, instructions, lookup_tables)
+}
+
+/// Simulate a v0 transaction to find the compute units it needs, padded by 20%.
+pub fn get_compute_units_v0(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Option<u64>> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    };
+
+    let tx = build_v0_transaction(signers, instructions, lookup_tables, Hash::default())?;
+
+    let sim_result = client.simulate_transaction_with_config(&tx, config)?;
+    if let Some(err) = sim_result.value.err {
+        bail!("Transaction simulation failed: {err}");
+    }
+
+    Ok(sim_result
+        .value
+        .units_consumed
+        .map(|units| (units as f64 * 1.20) as u64))
+}
+
+/// Addresses from the command line and from a JSON list file.
+fn parse_addresses(addresses: Vec<Pubkey>, addresses_file: Option<String>) -> Result<Vec<Pubkey>> {
+    let mut all = addresses;
+    if let Some(path) = addresses_file {
+        let list: Vec<String> = serde_json::from_reader(File::open(path)?)?;
+        for address in list {
+            all.push(Pubkey::from_str(&address)?);
+        }
+    }
+    Ok(all)
+}
+
+fn extend(
+    client: &RpcClient,
+    authority: &Keypair,
+    payer: &Keypair,
+    lookup_table: Pubkey,
+    addresses: Vec<Pubkey>,
+) -> Result<()> {
+    let chunks: Vec<&[Pubkey]> = addresses.chunks(EXTEND_CHUNK_SIZE).collect();
+    let total = chunks.len();
+
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let ix = extend_lookup_table(
+            lookup_table,
+            authority.pubkey(),
+            Some(payer.pubkey()),
+            chunk.to_vec(),
+        );
+        let sig = land(client, &with_payer(payer, &[authority]), &[ix], &[])?;
+        say!("Added {} addresses ({}/{total}): {sig}", chunk.len(), i + 1);
+    }
+
+    Ok(())
+}
+
+pub fn process_create_lookup_table(
+    client: &RpcClient,
+    keypair_path: Option<String>,
+    addresses: Vec<Pubkey>,
+    addresses_file: Option<String>,
+) -> Result<()> {
+    let solana_opts = parse_solana_config();
+    let keypair = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
+    let addresses = parse_addresses(addresses, addresses_file)?;
+
+    // The table address is derived from a recent slot, which must be finalized.
+    let recent_slot = client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+    let (ix, lookup_table) = create_lookup_table(keypair.pubkey(), payer.pubkey(), recent_slot);
+
+    let sig = land(client, &with_payer(payer, &[&keypair]), &[ix], &[])?;
+    say!("Created lookup table {lookup_table}: {sig}");
+    record_address("lookup_table", lookup_table);
+
+    extend(client, &keypair, payer, lookup_table, addresses)
+}
+
+pub fn process_extend_lookup_table(
+    client: &RpcClient,
+    keypair_path: Option<String>,
+    lookup_table: Pubkey,
+    addresses: Vec<Pubkey>,
+    addresses_file: Option<String>,
+) -> Result<()> {
+    let solana_opts = parse_solana_config();
+    let keypair = parse_keypair(keypair_path, solana_opts);
+    let addresses = parse_addresses(addresses, addresses_file)?;
+
+    if addresses.is_empty() {
+        bail!("No addresses to add.");
+    }
+
+    // Skip addresses already in the table so extending twice is harmless.
+    let existing = fetch_lookup_table(client, &lookup_table)?.addresses;
+    let mut new_addresses: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        if !existing.contains(&address) && !new_addresses.contains(&address) {
+            new_addresses.push(address);
+        }
+    }
+
+    if new_addresses.is_empty() {
+        say!("All addresses are already in the lookup table.");
+        return Ok(());
+    }
+
+    let payer = parse_payer()?;
+    extend(
+        client,
+        &keypair,
+        payer.as_ref().unwrap_or(&keypair),
+        lookup_table,
+        new_addresses,
+    )
+}
+
+pub fn process_deactivate_lookup_table(
+    client: &RpcClient,
+    keypair_path: Option<String>,
+    lookup_table: Pubkey,
+) -> Result<()> {
+    let solana_opts = parse_solana_config();
+    let keypair = parse_keypair(keypair_path, solana_opts);
+
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
+
+    let ix = deactivate_lookup_table(lookup_table, keypair.pubkey());
+    let sig = land(client, &with_payer(payer, &[&keypair]), &[ix], &[])?;
+
+    say!("Deactivated lookup table {lookup_table}: {sig}");
+    say!("It can be closed once the deactivation cooldown of about 513 slots has passed.");
+
+    Ok(())
+}
+
+pub fn process_close_lookup_table(
+    client: &RpcClient,
+    keypair_path: Option<String>,
+    lookup_table: Pubkey,
+    recipient: Option<Pubkey>,
+) -> Result<()> {
+    let solana_opts = parse_solana_config();
+    let keypair = parse_keypair(keypair_path, solana_opts);
+    let recipient = recipient.unwrap_or_else(|| keypair.pubkey());
+
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
+
+    let ix = close_lookup_table(lookup_table, keypair.pubkey(), recipient);
+    let sig = land(client, &with_payer(payer, &[&keypair]), &[ix], &[])?;
+
+    say!("Closed lookup table {lookup_table}, rent returned to {recipient}: {sig}");
+
+    Ok(())
+}
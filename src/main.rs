@@ -3,81 +3,65 @@
 extern crate log;
 
 use anyhow::Result;
-use metaboss::airdrop::process_airdrop;
-use metaboss::check::process_check;
-use metaboss::extend_program::process_extend_program;
+use metaboss::config::load_profile;
+use metaboss::job::{parse_with_defaults, run_job};
+use metaboss::output::emit_command;
 use metaboss::setup::AppConfigBuilder;
 use structopt::StructOpt;
 
 use metaboss::opt::*;
 use metaboss::process_subcommands::*;
-use metaboss::snapshot::process_snapshot;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let options = Opt::from_args();
+    let args: Vec<String> = std::env::args().collect();
+    let options = Opt::from_iter(&args);
+
+    // A profile supplies the options a command takes but wasn't given.
+    let profile = load_profile(options.profile.as_deref())?;
+    let options = match &profile {
+        Some(profile) if !profile.defaults().is_empty() => {
+            parse_with_defaults(&args, &profile.defaults()).unwrap_or_else(|e| e.exit())
+        }
+        _ => options,
+    };
 
     let log_level = format!("solana={}", options.log_level);
     solana_logger::setup_with_default(&log_level);
 
-    let mut builder = AppConfigBuilder::new().timeout(options.timeout);
+    let mut builder = AppConfigBuilder::new();
+    if let Some(profile) = &profile {
+        builder = builder.profile(profile);
+    }
+    builder = builder
+        .dry_run(options.dry_run)
+        .resume(options.resume)
+        .offline(options.offline, options.nonce_accounts)
+        .multisig(options.multisig, options.proposal_encoding)
+        .payer(options.payer)
+        .priority_fee_caps(options.max_priority_fee, options.max_priority_fee_total)
+        .max_spend(options.max_spend)
+        .assume_yes(options.yes)
+        .output(options.output_format);
 
+    if let Some(timeout) = options.timeout {
+        builder = builder.timeout(timeout);
+    }
     if let Some(rpc) = options.rpc {
         builder = builder.rpc_url(rpc);
     }
-
-    let config = builder.build()?;
-    let rpc = config.rpc_url;
-    let client = config.client;
-    let async_client = config.async_client;
-
-    match options.cmd {
-        Command::Collections {
-            collections_subcommands,
-        } => process_collections(client, async_client, collections_subcommands).await?,
-        Command::Airdrop {
-            airdrop_subcommands,
-        } => process_airdrop(client, airdrop_subcommands).await?,
-        Command::Burn { burn_subcommands } => process_burn_asset(client, burn_subcommands).await?,
-        Command::BurnNft {
-            burn_nft_subcommands,
-        } => process_burn_nft(client, burn_nft_subcommands).await?,
-        Command::BurnPrint {
-            burn_print_subcommands,
-        } => process_burn_print(client, burn_print_subcommands).await?,
-        Command::Check { check_subcommands } => process_check(check_subcommands).await?,
-        Command::Create { create_subcommands } => process_create(client, create_subcommands)?,
-        Command::Decode { decode_subcommands } => process_decode(&client, decode_subcommands)?,
-        Command::Derive { derive_subcommands } => process_derive(derive_subcommands),
-        Command::ExtendProgram {
-            keypair_path,
-            program_address,
-            additional_bytes,
-        } => process_extend_program(client, keypair_path, program_address, additional_bytes)?,
-        Command::Find { find_subcommands } => process_find(&client, find_subcommands)?,
-        Command::Mint { mint_subcommands } => process_mint(client, mint_subcommands)?,
-        Command::ParseErrors {
-            parse_errors_file_subcommands,
-        } => process_parse_errors_file(parse_errors_file_subcommands)?,
-        Command::Set { set_subcommands } => process_set(client, set_subcommands).await?,
-        Command::Sign { sign_subcommands } => process_sign(&client, sign_subcommands)?,
-        Command::Snapshot {
-            snapshot_subcommands,
-        } => process_snapshot(client, rpc, snapshot_subcommands).await?,
-        Command::Transfer {
-            transfer_subcommands,
-        } => process_transfer(client, transfer_subcommands)?,
-        Command::Update { update_subcommands } => {
-            process_update(client, update_subcommands).await?
-        }
-        Command::Uses { uses_subcommands } => process_uses(&client, uses_subcommands)?,
-        Command::Verify { verify_subcommands } => {
-            process_verify(client, verify_subcommands).await?
-        }
-        Command::Unverify {
-            unverify_subcommands,
-        } => process_unverify(client, unverify_subcommands).await?,
+    if let Some(das_url) = options.das_url {
+        builder = builder.das_url(das_url);
     }
 
-    Ok(())
+    let result = match options.cmd {
+        Command::Run { job_file } => run_job(&job_file, builder, profile.as_ref()).await,
+        cmd => match builder.build() {
+            Ok(config) => process_command(config, cmd).await,
+            Err(e) => Err(e),
+        },
+    };
+    emit_command(&args, &result);
+
+    result
 }
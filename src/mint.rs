@@ -34,7 +34,6 @@ use solana_sdk::{
     signature::Signature,
     signer::{keypair::Keypair, Signer},
     system_instruction::create_account,
-    transaction::Transaction,
 };
 use spl_associated_token_account::{
     get_associated_token_address, get_associated_token_address_with_program_id,
@@ -57,11 +56,19 @@ use std::{
 };
 
 use crate::constants::*;
+use crate::cost::{
+    confirm_cost, estimated_price, CostEstimate, ESTIMATED_COMPUTE_UNITS,
+    MASTER_EDITION_ACCOUNT_SIZE, METADATA_ACCOUNT_SIZE,
+};
 use crate::derive::derive_edition_pda;
 use crate::derive::derive_metadata_pda;
+use crate::landing::land;
+use crate::output::{emit_item, record_address, record_signature, ItemOutput, Status};
+use crate::priority::with_priority;
 use crate::sign::sign_one;
+use crate::spinner::create_spinner;
+use crate::utils::with_payer;
 use crate::{find::find_missing_editions, parse::*};
-use crate::{limiter::create_default_rate_limiter, spinner::create_spinner};
 
 const MINT_LAYOUT: u64 = 82;
 
@@ -133,9 +140,6 @@ pub fn mint_from_files(
     sized: bool,
     priority: Priority,
 ) -> Result<()> {
-    let use_rate_limit = *USE_RATE_LIMIT.read().unwrap();
-    let handle = create_default_rate_limiter();
-
     let path = Path::new(&list_dir).join("*.json");
     let pattern = path
         .to_str()
@@ -146,12 +150,9 @@ pub fn mint_from_files(
     let paths: Vec<_> = paths.into_iter().map(Result::unwrap).collect();
     let errors: Vec<_> = errors.into_iter().map(Result::unwrap_err).collect();
 
-    paths.par_iter().for_each(|path| {
-        let mut handle = handle.clone();
-        if use_rate_limit {
-            handle.wait();
-        }
+    confirm_mint_cost(client, paths.len(), sign, &priority)?;
 
+    paths.par_iter().for_each(|path| {
         match mint_one(
             client,
             keypair_path.clone(),
@@ -166,8 +167,13 @@ pub fn mint_from_files(
             sized,
             &priority,
         ) {
-            Ok(_) => (),
-            Err(e) => error!("Failed to mint {:?}: {}", &path, e),
+            Ok(mint) => {
+                emit_item(ItemOutput::new(path.display().to_string(), Status::Ok).address(mint))
+            }
+            Err(e) => {
+                error!("Failed to mint {:?}: {}", &path, e);
+                emit_item(ItemOutput::new(path.display().to_string(), Status::Failed).error(e));
+            }
         }
     });
 
@@ -201,6 +207,8 @@ pub fn mint_from_uris(
     let f = File::open(&external_metadata_uris_path)?;
     let external_metadata_uris: Vec<String> = serde_json::from_reader(f)?;
 
+    confirm_mint_cost(client, external_metadata_uris.len(), sign, &priority)?;
+
     if !track {
         external_metadata_uris
             .par_iter()
@@ -220,8 +228,11 @@ pub fn mint_from_uris(
                     false,
                     &priority,
                 ) {
-                    Ok(_) => (),
-                    Err(e) => println!("Failed to mint {:?}: {}", &uri, e),
+                    Ok(mint) => emit_item(ItemOutput::new(uri, Status::Ok).address(mint)),
+                    Err(e) => {
+                        say!("Failed to mint {:?}: {}", &uri, e);
+                        emit_item(ItemOutput::new(uri, Status::Failed).error(e));
+                    }
                 }
             });
     } else {
@@ -255,12 +266,16 @@ pub fn mint_from_uris(
                     false,
                     &priority,
                 ) {
-                    Ok(m) => MintResult {
-                        uri: uri.clone(),
-                        mint_account: Some(m),
-                    },
+                    Ok(m) => {
+                        emit_item(ItemOutput::new(uri, Status::Ok).address(&m));
+                        MintResult {
+                            uri: uri.clone(),
+                            mint_account: Some(m),
+                        }
+                    }
                     Err(e) => {
                         error!("Failed to mint {:?}: {}", &uri, e);
+                        emit_item(ItemOutput::new(uri, Status::Failed).error(e));
                         MintResult {
                             uri: uri.clone(),
                             mint_account: None,
@@ -300,6 +315,8 @@ pub fn mint_fungible(
 ) -> Result<()> {
     let solana_opts = parse_solana_config();
     let mint_authority_keypair = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&mint_authority_keypair);
 
     let token_program_id = if token_22 {
         TOKEN_22_PROGRAM_ID
@@ -338,7 +355,7 @@ pub fn mint_fungible(
 
     if destination_ata_pubkey_info.is_none() {
         instructions.push(create_associated_token_account(
-            &mint_authority_pubkey,
+            &payer.pubkey(),
             &destination_pubkey,
             &mint_pubkey,
             &token_program_id,
@@ -381,49 +398,64 @@ pub fn mint_fungible(
 
     instructions.push(mint_ix);
 
-    let signers = vec![&mint_authority_keypair];
+    let signers = with_payer(payer, &[&mint_authority_keypair]);
 
     let compute_units = get_compute_units(client, &instructions, &signers)?.unwrap_or(200_000);
 
-    let micro_lamports = match priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
-    let mut final_instructions = vec![
-        ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-    ];
+    let mut final_instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_units as u32,
+    )];
     final_instructions.extend(instructions);
+    let final_instructions = with_priority(client, &priority, &final_instructions)?;
 
-    let recent_blockhash = client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &final_instructions,
-        Some(&mint_authority_pubkey),
-        &[&mint_authority_keypair],
-        recent_blockhash,
-    );
-
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || client.send_and_confirm_transaction(&tx),
-    );
-    let sig = res?;
+    let sig = land(client, &signers, &final_instructions, &[])?;
 
-    println!(
+    say!(
         "Mint: {:?} minted {:?} tokens successfully!",
-        mint_address, amount
+        mint_address,
+        amount
     );
 
-    println!("Created in tx: {:?}", &sig);
+    say!("Created in tx: {:?}", &sig);
 
     Ok(())
 }
 
+/// Show what minting `count` NFTs costs and ask to go ahead. Each mint is one
+/// transaction signed by the funder, the new mint and any separate payer, and
+/// creates a mint, token account, metadata and master edition.
+fn confirm_mint_cost(
+    client: &RpcClient,
+    count: usize,
+    sign: bool,
+    priority: &Priority,
+) -> Result<()> {
+    let count = count as u64;
+    let signatures = if parse_payer()?.is_some() { 3 } else { 2 };
+    let price = estimated_price(client, priority, ESTIMATED_COMPUTE_UNITS)?;
+
+    let mut estimate = CostEstimate::new();
+    estimate.add_transactions(count, signatures, price, ESTIMATED_COMPUTE_UNITS);
+    if sign {
+        estimate.add_transactions(count, 1, price, ESTIMATED_COMPUTE_UNITS);
+    }
+    estimate.add_rent(client, "mints", count, Mint::LEN)?;
+    estimate.add_rent(
+        client,
+        "token accounts",
+        count,
+        spl_token::state::Account::LEN,
+    )?;
+    estimate.add_rent(client, "metadata accounts", count, METADATA_ACCOUNT_SIZE)?;
+    estimate.add_rent(
+        client,
+        "master editions",
+        count,
+        MASTER_EDITION_ACCOUNT_SIZE,
+    )?;
+    confirm_cost(&estimate)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn mint_one<P: AsRef<Path>>(
     client: &RpcClient,
@@ -495,7 +527,7 @@ pub fn mint_one<P: AsRef<Path>>(
     )?;
     info!("Tx sig: {:?}\nMint account: {:?}", &tx_id, &mint_account);
     let message = format!("Tx sig: {:?}\nMint account: {:?}", &tx_id, &mint_account,);
-    println!("{message}");
+    say!("{message}");
     if sign {
         sign_one(
             client,
@@ -639,6 +671,8 @@ fn mint_edition(
 ) -> Result<(Signature, Pubkey)> {
     let solana_opts = parse_solana_config();
     let funder = parse_keypair(keypair_path.clone(), solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&funder);
     let metadata_mint = Pubkey::from_str(account)?;
     let new_mint_keypair = Keypair::new();
     let new_mint = new_mint_keypair.pubkey();
@@ -659,7 +693,7 @@ fn mint_edition(
 
     // Create mint account
     let create_mint_account_ix = create_account(
-        &funder.pubkey(),
+        &payer.pubkey(),
         &new_mint,
         min_rent,
         MINT_LAYOUT,
@@ -680,7 +714,7 @@ fn mint_edition(
     let new_assoc = get_associated_token_address(&receiver, &new_mint);
 
     let create_assoc_account_ix =
-        create_associated_token_account(&funder.pubkey(), &receiver, &new_mint, &spl_token::ID);
+        create_associated_token_account(&payer.pubkey(), &receiver, &new_mint, &spl_token::ID);
 
     // Mint to instruction
     let mint_to_ix = mint_to(
@@ -700,7 +734,7 @@ fn mint_edition(
         .master_edition(master_edition)
         .new_mint(new_mint)
         .new_mint_authority(funder.pubkey())
-        .payer(funder.pubkey())
+        .payer(payer.pubkey())
         .new_metadata_update_authority(funder.pubkey())
         .token_account(assoc)
         .token_account_owner(funder.pubkey())
@@ -721,40 +755,20 @@ fn mint_edition(
         mint_editions_ix,
     ];
 
-    let signers = vec![&funder, &new_mint_keypair];
+    let signers = with_payer(payer, &[&funder, &new_mint_keypair]);
 
     let compute_units = get_compute_units(client, &instructions, &signers)?.unwrap_or(200_000);
 
-    let micro_lamports = match priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
-    let mut final_instructions = vec![
-        ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-    ];
+    let mut final_instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_units as u32,
+    )];
     final_instructions.extend(instructions);
+    let final_instructions = with_priority(client, priority, &final_instructions)?;
 
-    let recent_blockhash = client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &final_instructions,
-        Some(&funder.pubkey()),
-        &signers,
-        recent_blockhash,
-    );
-
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || client.send_and_confirm_transaction(&tx),
-    );
-    let sig = res?;
-    println!("Edition with mint: {:?}", &new_mint);
-    println!("Created in tx: {:?}", &sig);
+    let sig = land(client, &signers, &final_instructions, &[])?;
+    say!("Edition with mint: {:?}", &new_mint);
+    record_address("mint", new_mint);
+    say!("Created in tx: {:?}", &sig);
 
     Ok((sig, new_mint))
 }
@@ -794,6 +808,8 @@ pub fn mint_core(
 ) -> Result<Signature> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
 
     let asset_keypair = if let Some(path) = asset_keypair_path {
         read_keypair(&path)?
@@ -812,40 +828,25 @@ pub fn mint_core(
         .asset(asset_pubkey)
         .name(name)
         .uri(uri)
-        .payer(keypair.pubkey())
+        .payer(payer.pubkey())
         .owner(Some(receiver_pubkey))
         .instruction();
 
     let instructions = vec![create_ix];
-    let signers = vec![&keypair, &asset_keypair];
+    let signers = with_payer(payer, &[&keypair, &asset_keypair]);
 
     let compute_units = get_compute_units(client, &instructions, &signers)?.unwrap_or(200_000);
 
-    let micro_lamports = match priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
-    let mut final_instructions = vec![
-        ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-    ];
+    let mut final_instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_units as u32,
+    )];
     final_instructions.extend(instructions);
+    let final_instructions = with_priority(client, &priority, &final_instructions)?;
 
-    let recent_blockhash = client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &final_instructions,
-        Some(&keypair.pubkey()),
-        &signers,
-        recent_blockhash,
-    );
-
-    let sig = client.send_and_confirm_transaction(&tx)?;
-    println!("Minted Core Asset: {}", asset_pubkey);
-    println!("Signature: {}", sig);
+    let sig = land(client, &signers, &final_instructions, &[])?;
+    say!("Minted Core Asset: {}", asset_pubkey);
+    record_address("asset", asset_pubkey);
+    say!("Signature: {}", sig);
 
     Ok(sig)
 }
@@ -864,6 +865,8 @@ pub fn mint(
     priority: Priority,
 ) -> Result<(Signature, Pubkey)> {
     let metaplex_program_id = Pubkey::from_str(METAPLEX_PROGRAM_ID)?;
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&funder);
     let mint = if let Some(mint_path) = mint_path {
         read_keypair(&mint_path).expect("Invalid mint keypair path")
     } else {
@@ -883,7 +886,7 @@ pub fn mint(
 
     // Create mint account
     let create_mint_account_ix = create_account(
-        &funder.pubkey(),
+        &payer.pubkey(),
         &mint.pubkey(),
         min_rent,
         MINT_LAYOUT,
@@ -903,12 +906,8 @@ pub fn mint(
     let assoc = get_associated_token_address(&receiver, &mint.pubkey());
 
     // Create associated account instruction
-    let create_assoc_account_ix = create_associated_token_account(
-        &funder.pubkey(),
-        &receiver,
-        &mint.pubkey(),
-        &spl_token::ID,
-    );
+    let create_assoc_account_ix =
+        create_associated_token_account(&payer.pubkey(), &receiver, &mint.pubkey(), &spl_token::ID);
 
     // Mint to instruction
     let mint_to_ix = mint_to(
@@ -954,7 +953,7 @@ pub fn mint(
         .metadata(metadata_account)
         .mint(mint.pubkey())
         .mint_authority(funder.pubkey())
-        .payer(funder.pubkey())
+        .payer(payer.pubkey())
         .update_authority(funder.pubkey(), true)
         .is_mutable(!immutable)
         .data(data_v2);
@@ -972,7 +971,7 @@ pub fn mint(
         .mint(mint.pubkey())
         .update_authority(funder.pubkey())
         .mint_authority(funder.pubkey())
-        .payer(funder.pubkey());
+        .payer(payer.pubkey());
 
     if let Some(max_supply) = max_supply {
         builder.max_supply(max_supply);
@@ -998,38 +997,17 @@ pub fn mint(
         instructions.push(ix);
     }
 
-    let signers = vec![&funder, &mint];
+    let signers = with_payer(payer, &[&funder, &mint]);
 
     let compute_units = get_compute_units(client, &instructions, &signers)?.unwrap_or(200_000);
 
-    let micro_lamports = match priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
-    let mut final_instructions = vec![
-        ComputeBudgetInstruction::set_compute_unit_limit(compute_units as u32),
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-    ];
+    let mut final_instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_units as u32,
+    )];
     final_instructions.extend(instructions);
+    let final_instructions = with_priority(client, &priority, &final_instructions)?;
 
-    let recent_blockhash = client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &final_instructions,
-        Some(&funder.pubkey()),
-        &signers,
-        recent_blockhash,
-    );
-
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || client.send_and_confirm_transaction(&tx),
-    );
-    let sig = res?;
+    let sig = land(client, &signers, &final_instructions, &[])?;
 
     Ok((sig, mint.pubkey()))
 }
@@ -1060,8 +1038,9 @@ pub fn process_mint_asset(args: MintAssetParams) -> Result<()> {
     } = args;
 
     let solana_opts = parse_solana_config();
-    // Authority is the payer as well.
+    // Authority is the payer as well, unless a payer is given.
     let authority = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
 
     let receiver = if let Some(receiver) = receiver {
         Pubkey::from_str(&receiver)?
@@ -1077,7 +1056,7 @@ pub fn process_mint_asset(args: MintAssetParams) -> Result<()> {
     let mint = mint_path.map(|path| read_keypair(&path).expect("Invalid mint keypair path"));
 
     let args = MintAssetArgs::V1 {
-        payer: None,
+        payer: payer.as_ref(),
         authority: &authority,
         receiver,
         mint,
@@ -1091,8 +1070,10 @@ pub fn process_mint_asset(args: MintAssetParams) -> Result<()> {
 
     let mint_result = mint_asset(&client, args)?;
 
-    println!("Minted asset: {:?}", mint_result.mint);
-    println!("Transaction signature: {:?}", mint_result.signature);
+    say!("Minted asset: {:?}", mint_result.mint);
+    say!("Transaction signature: {:?}", mint_result.signature);
+    record_address("mint", mint_result.mint);
+    record_signature(mint_result.signature);
 
     Ok(())
 }
@@ -0,0 +1,682 @@
+use anyhow::{anyhow, bail, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use solana_client::{nonce_utils, rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction::{advance_nonce_account, create_nonce_account},
+    transaction::VersionedTransaction,
+};
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    cache::{
+        format_action_error,
+        pack::{build_items, pack_with_prefix, split_until_simulated, PackItem, PackedRoundArgs},
+        Action,
+    },
+    errors::ActionError,
+    landing::land,
+    parse::{parse_keypair, parse_solana_config},
+    priority::compute_unit_price,
+    run_state::{reconcile_sent, ItemStatus, RunState, RunStateEntry},
+    update::{DiffJournal, MetadataDiff},
+    utils::send_and_confirm_versioned_transaction,
+};
+
+pub const DEFAULT_NONCE_ACCOUNTS_FILE: &str = "mb-nonce-accounts.json";
+
+/// Compute units for the nonce advance, which isn't part of the simulation.
+const ADVANCE_NONCE_UNITS: u64 = 500;
+
+/// Size of a durable nonce account's state.
+const NONCE_ACCOUNT_SIZE: usize = 80;
+
+/// Offline mode settings from `--offline` and `--nonce-accounts`.
+#[derive(Clone, Debug)]
+pub struct OfflineSettings {
+    /// The cold-wallet authority that signs the exported transactions.
+    pub authority: Pubkey,
+    /// JSON list of the nonce accounts the exported transactions use.
+    pub nonce_accounts: PathBuf,
+}
+
+/// Transactions exported by an offline batch run.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OfflineBundle {
+    pub action: String,
+    pub transactions: Vec<OfflineTransaction>,
+}
+
+impl OfflineBundle {
+    pub fn file_name(action_name: &str) -> String {
+        format!("mb-offline-{action_name}.json")
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, self)?;
+        Ok(())
+    }
+}
+
+/// A transaction using a durable nonce as its blockhash, so it stays valid
+/// until it is broadcast, however long collecting its signatures takes.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OfflineTransaction {
+    pub mints: Vec<String>,
+    pub nonce_account: String,
+    /// Base64-encoded message to sign.
+    pub message: String,
+    /// Signatures collected so far, by signer.
+    #[serde(default)]
+    pub signatures: BTreeMap<String, String>,
+    /// Metadata diffs to record once the transaction lands.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub diffs: IndexMap<String, MetadataDiff>,
+}
+
+impl OfflineTransaction {
+    pub fn new(mints: Vec<String>, nonce_account: &Pubkey, message: &VersionedMessage) -> Self {
+        Self {
+            mints,
+            nonce_account: nonce_account.to_string(),
+            message: base64::encode(message.serialize()),
+            signatures: BTreeMap::new(),
+            diffs: IndexMap::new(),
+        }
+    }
+
+    pub fn message(&self) -> Result<VersionedMessage> {
+        Ok(bincode::deserialize(&base64::decode(&self.message)?)?)
+    }
+
+    /// Every account that must sign, in the order the signatures are sent.
+    pub fn signers(&self) -> Result<Vec<Pubkey>> {
+        let message = self.message()?;
+        let count = message.header().num_required_signatures as usize;
+        Ok(message.static_account_keys()[..count].to_vec())
+    }
+
+    pub fn missing_signers(&self) -> Result<Vec<Pubkey>> {
+        Ok(self
+            .signers()?
+            .into_iter()
+            .filter(|signer| !self.signatures.contains_key(&signer.to_string()))
+            .collect())
+    }
+
+    /// Sign the message if `keypair` is one of its signers, returning whether it was.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<bool> {
+        if !self.signers()?.contains(&keypair.pubkey()) {
+            return Ok(false);
+        }
+
+        let signature = keypair.sign_message(&self.message()?.serialize());
+        self.signatures
+            .insert(keypair.pubkey().to_string(), signature.to_string());
+        Ok(true)
+    }
+
+    /// The signed transaction, once every signature is present and valid.
+    pub fn assemble(&self) -> Result<VersionedTransaction> {
+        let signatures = self
+            .signers()?
+            .iter()
+            .map(|signer| {
+                let signature = self
+                    .signatures
+                    .get(&signer.to_string())
+                    .ok_or_else(|| anyhow!("Missing signature from {signer}"))?;
+                Ok(Signature::from_str(signature)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let tx = VersionedTransaction {
+            signatures,
+            message: self.message()?,
+        };
+        if tx.verify_with_results().iter().any(|valid| !valid) {
+            bail!(
+                "Invalid signature for the transaction using nonce {}",
+                self.nonce_account
+            );
+        }
+
+        Ok(tx)
+    }
+}
+
+pub(crate) fn compile_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedMessage> {
+    Ok(if lookup_tables.is_empty() {
+        VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(payer),
+            &blockhash,
+        ))
+    } else {
+        VersionedMessage::V0(v0::Message::try_compile(
+            payer,
+            instructions,
+            lookup_tables,
+            blockhash,
+        )?)
+    })
+}
+
+/// Simulate without signatures, since the authority isn't available, to find
+/// the compute units needed, padded by 20%.
+pub(crate) fn compute_units_unsigned(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Option<u64>> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    };
+
+    let message = compile_message(payer, instructions, lookup_tables, Hash::default())?;
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message,
+    };
+
+    let sim_result = client.simulate_transaction_with_config(&tx, config)?;
+    if let Some(err) = sim_result.value.err {
+        bail!("Transaction simulation failed: {err}");
+    }
+
+    Ok(sim_result
+        .value
+        .units_consumed
+        .map(|units| (units as f64 * 1.20) as u64))
+}
+
+/// Most nonce accounts the `--nonce-accounts` pool holds, which bounds the
+/// rent locked up in them and the transactions one export can hold.
+pub const MAX_NONCE_ACCOUNTS: usize = 100;
+
+/// A nonce account in the pool and the export whose transaction uses it, if any.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PooledNonce {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<String>,
+}
+
+/// Pool entries are addresses in files written by older versions.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PoolEntry {
+    Address(String),
+    Nonce(PooledNonce),
+}
+
+fn read_nonce_accounts(path: &Path) -> Result<Vec<PooledNonce>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let entries: Vec<PoolEntry> = serde_json::from_reader(File::open(path)?)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| match entry {
+            PoolEntry::Address(address) => PooledNonce {
+                address,
+                bundle: None,
+            },
+            PoolEntry::Nonce(nonce) => nonce,
+        })
+        .collect())
+}
+
+fn write_nonce_accounts(path: &Path, accounts: &[PooledNonce]) -> Result<()> {
+    serde_json::to_writer_pretty(File::create(path)?, accounts)?;
+    Ok(())
+}
+
+/// Whether `tx` is still waiting to be broadcast: its nonce account hasn't
+/// advanced past the nonce it was signed with.
+fn awaits_broadcast(client: &RpcClient, tx: &OfflineTransaction) -> Result<bool> {
+    let nonce_account = Pubkey::from_str(&tx.nonce_account)?;
+    let account = match nonce_utils::get_account(client, &nonce_account) {
+        Ok(account) => account,
+        // A closed nonce account can't be advanced any more.
+        Err(_) => return Ok(false),
+    };
+    let data = nonce_utils::data_from_account(&account)?;
+    Ok(data.blockhash() == *tx.message()?.recent_blockhash())
+}
+
+/// Whether the export using `nonce` still waits to be broadcast, which would
+/// invalidate its transaction if the nonce were used again.
+fn nonce_in_use(client: &RpcClient, nonce: &PooledNonce) -> Result<bool> {
+    let Some(bundle_file) = &nonce.bundle else {
+        return Ok(false);
+    };
+    // Export files are overwritten by the next export of the same action.
+    let Ok(bundle) = OfflineBundle::read(bundle_file) else {
+        return Ok(false);
+    };
+    match bundle
+        .transactions
+        .iter()
+        .find(|tx| tx.nonce_account == nonce.address)
+    {
+        Some(tx) => awaits_broadcast(client, tx),
+        None => Ok(false),
+    }
+}
+
+/// `count` nonce accounts with `authority` as their authority for the export
+/// to `bundle_file`. Accounts in the pool at `path` are reused unless an
+/// unbroadcast export still uses them, and the rest are created, up to
+/// `MAX_NONCE_ACCOUNTS` in all.
+fn nonce_accounts_for(
+    client: &RpcClient,
+    authority: &Keypair,
+    path: &Path,
+    bundle_file: &str,
+    count: usize,
+) -> Result<Vec<Pubkey>> {
+    let mut pool = read_nonce_accounts(path)?;
+
+    let mut free = Vec::new();
+    for (i, nonce) in pool.iter().enumerate() {
+        if free.len() < count && !nonce_in_use(client, nonce)? {
+            free.push(i);
+        }
+    }
+
+    let missing = count - free.len();
+    if pool.len() + missing > MAX_NONCE_ACCOUNTS {
+        bail!(
+            "The export needs {count} nonce accounts but only {} of the {} in {} are free \
+            and the pool holds at most {MAX_NONCE_ACCOUNTS}. Broadcast earlier exports or \
+            split the mint list.",
+            free.len(),
+            pool.len(),
+            path.display()
+        );
+    }
+
+    if missing > 0 {
+        say!(
+            "Creating {missing} nonce accounts, listed in {}. . .",
+            path.display()
+        );
+        let rent = client.get_minimum_balance_for_rent_exemption(NONCE_ACCOUNT_SIZE)?;
+
+        for _ in 0..missing {
+            let nonce = Keypair::new();
+            let ixs = create_nonce_account(
+                &authority.pubkey(),
+                &nonce.pubkey(),
+                &authority.pubkey(),
+                rent,
+            );
+            land(client, &[authority, &nonce], &ixs, &[])?;
+            free.push(pool.len());
+            pool.push(PooledNonce {
+                address: nonce.pubkey().to_string(),
+                bundle: None,
+            });
+
+            // Saved after each one so the rent paid is never lost track of.
+            write_nonce_accounts(path, &pool)?;
+        }
+    }
+
+    for &i in &free {
+        pool[i].bundle = Some(bundle_file.to_string());
+    }
+    write_nonce_accounts(path, &pool)?;
+
+    free.iter()
+        .map(|&i| Ok(Pubkey::from_str(&pool[i].address)?))
+        .collect()
+}
+
+/// The nonce a transaction using `nonce_account` must use as its blockhash.
+fn nonce_blockhash(client: &RpcClient, nonce_account: &Pubkey, authority: &Pubkey) -> Result<Hash> {
+    let account = nonce_utils::get_account(client, nonce_account)?;
+    let data = nonce_utils::data_from_account(&account)?;
+    if data.authority != *authority {
+        bail!(
+            "Nonce account {nonce_account} has authority {}, not the keypair {authority}",
+            data.authority
+        );
+    }
+    Ok(data.blockhash())
+}
+
+pub struct ExportOfflineArgs<'a> {
+    pub action: &'static str,
+    pub round: PackedRoundArgs<'a>,
+    pub nonce_accounts: PathBuf,
+}
+
+/// Build a batch action's transactions for an offline authority and export
+/// them, signed by the keypair as fee payer, for `sign-offline` and `broadcast`.
+pub async fn export_offline<A: Action + ?Sized>(args: ExportOfflineArgs<'_>) -> Result<()> {
+    let round = args.round;
+    let payer = round.keypair.pubkey();
+
+    // Overwriting an earlier export that wasn't broadcast would lose its transactions.
+    let file_name = OfflineBundle::file_name(args.action);
+    if let Ok(previous) = OfflineBundle::read(&file_name) {
+        for tx in &previous.transactions {
+            if awaits_broadcast(&round.client, tx)? {
+                bail!("{file_name} holds transactions that haven't been broadcast. Broadcast it or move it away before exporting again.");
+            }
+        }
+    }
+
+    let (items, mut failed) = build_items::<A>(&round).await?;
+
+    // Every transaction starts by advancing its nonce; sized with a placeholder account.
+    let prefix = [advance_nonce_account(&Pubkey::new_unique(), &payer)];
+    let groups = pack_with_prefix(
+        items,
+        |item: &PackItem| item.action.instructions.as_slice(),
+        &prefix,
+        &payer,
+        &round.lookup_tables,
+    );
+
+    let mut simulated = Vec::new();
+    for group in groups {
+        let result = split_until_simulated(group, |instructions| {
+            compute_units_unsigned(&round.client, instructions, &payer, &round.lookup_tables)
+        });
+        failed.extend(result.failed);
+        simulated.extend(result.groups);
+    }
+
+    let nonce_accounts = nonce_accounts_for(
+        &round.client,
+        &round.keypair,
+        &args.nonce_accounts,
+        &file_name,
+        simulated.len(),
+    )?;
+
+    let mut transactions = Vec::new();
+    for ((group, units), nonce_account) in simulated.into_iter().zip(nonce_accounts) {
+        let blockhash = nonce_blockhash(&round.client, &nonce_account, &payer)?;
+
+        // The nonce advance must be the first instruction.
+        let mut ixs = vec![advance_nonce_account(&nonce_account, &payer)];
+        if let Some(units) = units {
+            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(
+                (units + ADVANCE_NONCE_UNITS) as u32,
+            ));
+        }
+        ixs.extend(
+            group
+                .iter()
+                .flat_map(|item| item.action.instructions.iter().cloned()),
+        );
+        let price = compute_unit_price(&round.client, &round.priority, &ixs)?;
+        ixs.insert(1, ComputeBudgetInstruction::set_compute_unit_price(price));
+
+        let message = compile_message(&payer, &ixs, &round.lookup_tables, blockhash)?;
+        let mut tx = OfflineTransaction::new(
+            group.iter().map(|item| item.mint_address.clone()).collect(),
+            &nonce_account,
+            &message,
+        );
+        tx.diffs = group
+            .into_iter()
+            .filter_map(|item| item.action.diff.map(|diff| (item.mint_address, diff)))
+            .collect();
+
+        // The keypair pays the fees, so it signs now.
+        tx.sign(&round.keypair)?;
+        transactions.push(tx);
+    }
+
+    for (mint_address, result) in &failed {
+        if let Err(e) = result {
+            say!("{mint_address}: {}", format_action_error(e));
+        }
+    }
+
+    let bundle = OfflineBundle {
+        action: args.action.to_string(),
+        transactions,
+    };
+    bundle.write(&file_name)?;
+
+    let mint_count: usize = bundle.transactions.iter().map(|tx| tx.mints.len()).sum();
+    say!(
+        "Exported {} transactions for {mint_count} mints to {file_name} ({} mints failed).",
+        bundle.transactions.len(),
+        failed.len()
+    );
+    say!("Sign them with `metaboss sign-offline {file_name} -k <AUTHORITY_KEYPAIR>`, then send them with `metaboss broadcast {file_name}`.");
+
+    Ok(())
+}
+
+pub fn process_sign_offline(keypair_path: Option<String>, bundle_file: String) -> Result<()> {
+    let solana_opts = parse_solana_config();
+    let keypair = parse_keypair(keypair_path, solana_opts);
+
+    let mut bundle = OfflineBundle::read(&bundle_file)?;
+
+    let mut signed = 0;
+    for tx in bundle.transactions.iter_mut() {
+        if tx.sign(&keypair)? {
+            signed += 1;
+        }
+    }
+    if signed == 0 {
+        bail!(
+            "{} doesn't sign any transaction in {bundle_file}",
+            keypair.pubkey()
+        );
+    }
+    bundle.write(&bundle_file)?;
+
+    let mint_count: usize = bundle.transactions.iter().map(|tx| tx.mints.len()).sum();
+    say!(
+        "Signed {signed}/{} {} transactions for {mint_count} mints as {}.",
+        bundle.transactions.len(),
+        bundle.action,
+        keypair.pubkey()
+    );
+
+    Ok(())
+}
+
+pub fn process_broadcast(client: &RpcClient, bundle_file: String) -> Result<()> {
+    let bundle = OfflineBundle::read(&bundle_file)?;
+
+    // Check every transaction before sending any, so a partial signing is caught up front.
+    let transactions = bundle
+        .transactions
+        .iter()
+        .map(|tx| tx.assemble().map(|signed| (tx, signed)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let cache_file_name = format!("mb-cache-{}.jsonl", bundle.action);
+    let mut run_state = RunState::open(&cache_file_name)?;
+    reconcile_sent(client, &mut run_state)?;
+
+    let journal = DiffJournal::to_file(DiffJournal::file_name(&bundle.action));
+    let mut sent = 0;
+    let mut failed = 0;
+    for (tx, signed) in transactions {
+        let done = tx.mints.iter().all(|mint| {
+            matches!(run_state.get(mint), Some(entry) if entry.status == ItemStatus::Confirmed)
+        });
+        if done {
+            continue;
+        }
+
+        sent += 1;
+        let signature = signed.signatures[0].to_string();
+        for mint in &tx.mints {
+            run_state.record(RunStateEntry::new(mint, ItemStatus::Sent).signature(&signature))?;
+        }
+
+        match send_and_confirm_versioned_transaction(client, &signed) {
+            Ok(_) => {
+                for mint in &tx.mints {
+                    run_state.record(
+                        RunStateEntry::new(mint, ItemStatus::Confirmed).signature(&signature),
+                    )?;
+                }
+                for (mint, diff) in &tx.diffs {
+                    journal.insert(mint, diff.clone());
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                let error = format_action_error(&ActionError::ActionFailed(
+                    tx.nonce_account.clone(),
+                    e.to_string(),
+                ));
+                for mint in &tx.mints {
+                    run_state.record(
+                        RunStateEntry::new(mint, ItemStatus::Failed)
+                            .signature(&signature)
+                            .error(&error),
+                    )?;
+                }
+            }
+        }
+    }
+
+    say!(
+        "Broadcast {sent} transactions, {failed} failed. Outcomes are recorded in {cache_file_name}."
+    );
+
+    if let (false, Some(path)) = (journal.is_empty(), journal.path()) {
+        say!(
+            "Metadata diffs for {} mints written to {}",
+            journal.len(),
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    fn offline_tx(payer: &Keypair, authority: &Keypair) -> OfflineTransaction {
+        let nonce_account = Pubkey::new_unique();
+        let ixs = [
+            advance_nonce_account(&nonce_account, &payer.pubkey()),
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1, 2, 3],
+                vec![
+                    AccountMeta::new_readonly(authority.pubkey(), true),
+                    AccountMeta::new(Pubkey::new_unique(), false),
+                ],
+            ),
+        ];
+        let message = compile_message(&payer.pubkey(), &ixs, &[], Hash::new_unique()).unwrap();
+        OfflineTransaction::new(vec!["mint".to_string()], &nonce_account, &message)
+    }
+
+    #[test]
+    fn signatures_from_each_signer_assemble_a_valid_transaction() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut tx = offline_tx(&payer, &authority);
+
+        assert!(tx.sign(&payer).unwrap());
+        assert_eq!(tx.missing_signers().unwrap(), vec![authority.pubkey()]);
+        assert!(tx.assemble().is_err());
+
+        // Round trip through the exported file, as the cold wallet would.
+        let mut tx: OfflineTransaction =
+            serde_json::from_str(&serde_json::to_string(&tx).unwrap()).unwrap();
+        assert!(tx.sign(&authority).unwrap());
+        assert!(tx.missing_signers().unwrap().is_empty());
+
+        let signed = tx.assemble().unwrap();
+        assert_eq!(signed.signatures.len(), 2);
+        assert!(signed.verify_with_results().iter().all(|valid| *valid));
+    }
+
+    #[test]
+    fn keypair_that_is_not_a_signer_does_not_sign() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut tx = offline_tx(&payer, &authority);
+
+        assert!(!tx.sign(&Keypair::new()).unwrap());
+        assert!(tx.signatures.is_empty());
+    }
+
+    #[test]
+    fn nonce_pool_reads_plain_address_lists() {
+        let path = std::env::temp_dir().join(format!("mb-nonce-pool-{}.json", std::process::id()));
+        std::fs::write(&path, r#"["11111111111111111111111111111111"]"#).unwrap();
+
+        let pool = read_nonce_accounts(&path).unwrap();
+        assert_eq!(
+            pool,
+            vec![PooledNonce {
+                address: "11111111111111111111111111111111".to_string(),
+                bundle: None,
+            }]
+        );
+
+        // Reserved entries keep the export that uses them.
+        let reserved = vec![PooledNonce {
+            address: "11111111111111111111111111111111".to_string(),
+            bundle: Some("mb-offline-update-symbol-all.json".to_string()),
+        }];
+        write_nonce_accounts(&path, &reserved).unwrap();
+        assert_eq!(read_nonce_accounts(&path).unwrap(), reserved);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_signature_is_rejected() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut tx = offline_tx(&payer, &authority);
+
+        tx.sign(&payer).unwrap();
+        tx.signatures.insert(
+            authority.pubkey().to_string(),
+            Keypair::new().sign_message(b"something else").to_string(),
+        );
+
+        assert!(tx.assemble().is_err());
+    }
+}
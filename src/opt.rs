@@ -1,6 +1,5 @@
 use std::path::PathBuf;
 
-use metaboss_lib::data::Priority;
 use solana_program::pubkey::Pubkey;
 use structopt::StructOpt;
 
@@ -10,24 +9,85 @@ use crate::{
     collections::GetCollectionItemsMethods,
     constants::DEFAULT_RATE_LIMIT,
     mint::Supply,
-    snapshot::{HolderGroupKey, MintsGroupKey},
+    offline::DEFAULT_NONCE_ACCOUNTS_FILE,
+    output::OutputFormat,
+    priority::PriorityArg,
+    proposal::ProposalEncoding,
+    snapshot::{ExportFormat, HolderGroupKey, MintsGroupKey, Timestamp, TraitFilter, TraitSource},
 };
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "Metaboss", about = "Metaplex NFT 'Swiss Army Knife' tool.")]
 pub struct Opt {
-    /// RPC endpoint url to override using the Solana config or the hard-coded default
+    /// RPC endpoint url to override using the Solana config or the hard-coded default.
+    /// Separate several urls with commas to fail over between them; the first is the primary
     #[structopt(short, long, global = true)]
     pub rpc: Option<String>,
 
     /// Timeout to override default value of 90 seconds
-    #[structopt(short = "T", long, global = true, default_value = "90")]
-    pub timeout: u64,
+    #[structopt(short = "T", long, global = true)]
+    pub timeout: Option<u64>,
+
+    /// Profile from ~/.config/metaboss/config.toml whose settings to use where no option is given
+    #[structopt(long, global = true)]
+    pub profile: Option<String>,
+
+    /// DAS API endpoint for snapshot commands that use it; defaults to the RPC url
+    #[structopt(long, global = true)]
+    pub das_url: Option<String>,
 
     /// Log level
     #[structopt(short, long, global = true, default_value = "off")]
     pub log_level: String,
 
+    /// Simulate every transaction instead of sending it and write a report of the results
+    #[structopt(long, global = true)]
+    pub dry_run: bool,
+
+    /// Continue an interrupted batch run from its cache file, checking sent transactions before resending
+    #[structopt(long, global = true)]
+    pub resume: bool,
+
+    /// Export batch transactions for this offline authority to sign instead of sending them
+    #[structopt(long, global = true)]
+    pub offline: Option<Pubkey>,
+
+    /// JSON list of durable nonce accounts for offline transactions, created as needed
+    #[structopt(long, global = true, default_value = DEFAULT_NONCE_ACCOUNTS_FILE)]
+    pub nonce_accounts: PathBuf,
+
+    /// Export authority-gated instructions as proposals for this multisig vault instead of sending them
+    #[structopt(long, global = true)]
+    pub multisig: Option<Pubkey>,
+
+    /// Encoding of exported proposal messages: base58 or base64
+    #[structopt(long, global = true, default_value = "base58")]
+    pub proposal_encoding: ProposalEncoding,
+
+    /// Path to a keypair file that pays transaction fees and rent, so the authority only signs
+    #[structopt(long, global = true)]
+    pub payer: Option<String>,
+
+    /// Most lamports to pay in priority fees for any one transaction; prices are lowered to fit
+    #[structopt(long, global = true)]
+    pub max_priority_fee: Option<u64>,
+
+    /// Most lamports to pay in priority fees over the whole run; prices are lowered to fit
+    #[structopt(long, global = true)]
+    pub max_priority_fee_total: Option<u64>,
+
+    /// Most lamports a run may spend on fees and rent; sending stops once the next transaction would exceed it
+    #[structopt(long, global = true)]
+    pub max_spend: Option<u64>,
+
+    /// Start batch runs without asking to confirm their estimated cost
+    #[structopt(long, global = true)]
+    pub yes: bool,
+
+    /// Output format: text, or json for one result object per line on stdout with progress hidden
+    #[structopt(long, global = true, default_value = "text")]
+    pub output_format: OutputFormat,
+
     #[structopt(subcommand)]
     pub cmd: Command,
 }
@@ -113,6 +173,12 @@ pub enum Command {
         #[structopt(subcommand)]
         find_subcommands: FindSubcommands,
     },
+    /// Manage address lookup tables
+    #[structopt(name = "lookup-table")]
+    LookupTable {
+        #[structopt(subcommand)]
+        lookup_table_subcommands: LookupTableSubcommands,
+    },
     /// Mint new NFTs from JSON files
     #[structopt(name = "mint")]
     Mint {
@@ -125,6 +191,45 @@ pub enum Command {
         #[structopt(subcommand)]
         update_subcommands: UpdateSubcommands,
     },
+    /// Revert a batch update using the diff file it wrote
+    #[structopt(
+        name = "rollback",
+        after_help = "EXAMPLES:\nmetaboss rollback mb-diff-update-uri-all-20240101T000000.jsonl"
+    )]
+    Rollback {
+        /// Path to the diff file written by the batch update
+        journal_file: String,
+
+        /// Path to the current update authority's keypair file
+        #[structopt(short, long)]
+        keypair: Option<String>,
+
+        /// Cache file
+        #[structopt(short, long)]
+        cache_file: Option<String>,
+
+        /// Maximum number of requests per second
+        #[structopt(short = "R", long, default_value = DEFAULT_RATE_LIMIT)]
+        rate_limit: usize,
+
+        /// Maximum retries: retry failed items up to this many times.
+        #[structopt(long, default_value = "0")]
+        retries: u8,
+
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
+        /// See https://metaboss.rs/priority-fees.html for more details.
+        #[structopt(short = "P", long, default_value = "none")]
+        priority: PriorityArg,
+    },
+    /// Run the steps of a YAML or TOML job file in order
+    #[structopt(
+        name = "run",
+        after_help = "EXAMPLES:\nmetaboss run job.yaml\nmetaboss run job.yaml --dry-run"
+    )]
+    Run {
+        /// Path to the job file
+        job_file: String,
+    },
     /// Set non-Data struct values for a NFT
     #[structopt(name = "set")]
     Set {
@@ -137,6 +242,25 @@ pub enum Command {
         #[structopt(subcommand)]
         sign_subcommands: SignSubcommands,
     },
+    /// Add signatures to transactions exported with --offline
+    #[structopt(
+        name = "sign-offline",
+        after_help = "EXAMPLES:\nmetaboss sign-offline mb-offline-update-symbol-all.json -k cold_wallet.json"
+    )]
+    SignOffline {
+        /// File of exported transactions
+        file: String,
+
+        /// Path to the signing keypair file
+        #[structopt(short, long)]
+        keypair: Option<String>,
+    },
+    /// Send fully signed transactions exported with --offline
+    #[structopt(name = "broadcast")]
+    Broadcast {
+        /// File of exported transactions
+        file: String,
+    },
     /// Get snapshots of various blockchain states
     #[structopt(name = "snapshot")]
     Snapshot {
@@ -161,6 +285,12 @@ pub enum Command {
         #[structopt(subcommand)]
         unverify_subcommands: UnverifySubcommands,
     },
+    /// Stream changes to a collection as they happen
+    #[structopt(name = "watch")]
+    Watch {
+        #[structopt(subcommand)]
+        watch_subcommands: WatchSubcommands,
+    },
 }
 
 #[derive(Debug, StructOpt)]
@@ -171,7 +301,7 @@ pub enum BurnSubcommands {
         after_help = "EXAMPLES:\nmetaboss burn asset --mint-account <MINT_ADDRESS>"
     )]
     Asset {
-        /// Path to the owner keypair file
+        /// Path to the owner keypair file, or a usb://ledger path
         #[structopt(short, long)]
         keypair: Option<String>,
 
@@ -187,10 +317,10 @@ pub enum BurnSubcommands {
         #[structopt(long, default_value = "1")]
         amount: u64,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Burn a batch of assets.
     #[structopt(name = "asset-all")]
@@ -215,10 +345,10 @@ pub enum BurnSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -235,10 +365,10 @@ pub enum BurnNftSubcommands {
         #[structopt(short = "a", long)]
         mint_account: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Burn a batch of NFTs.
     #[structopt(name = "all")]
@@ -263,10 +393,10 @@ pub enum BurnNftSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -287,10 +417,10 @@ pub enum BurnPrintSubcommands {
         #[structopt(short, long)]
         master_edition: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Burn a batch of NFTs.
     #[structopt(name = "all")]
@@ -319,10 +449,10 @@ pub enum BurnPrintSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -346,10 +476,10 @@ pub enum CreateSubcommands {
         #[structopt(long)]
         immutable: bool,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
 
         /// Use full compute units, 200k CU, instead of simulating the expected amount.
         #[structopt(long)]
@@ -382,10 +512,10 @@ pub enum CreateSubcommands {
         #[structopt(long)]
         immutable: bool,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
 
         /// Use full compute units, 200k CU, instead of simulating the expected amount.
         #[structopt(long)]
@@ -414,10 +544,10 @@ pub enum CreateSubcommands {
         #[structopt(short, long)]
         initial_supply: Option<u64>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 
     /// Create a SPL22 Token account from the Token Extensions Program
@@ -435,10 +565,10 @@ pub enum CreateSubcommands {
         #[structopt(short, long)]
         extensions: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 
     // Decorate an existing mint + metadata account with a master edition account.
@@ -460,10 +590,10 @@ pub enum CreateSubcommands {
         #[structopt(short = "s", long, default_value = "0")]
         max_supply: i64,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -656,7 +786,7 @@ pub enum CollectionsSubcommands {
     /// Migrate a collection to the on-chain standard.
     #[structopt(name = "migrate")]
     Migrate {
-        /// Path to the update authority keypair file
+        /// Path to the update authority keypair file, or a usb://ledger path
         #[structopt(short, long)]
         keypair: Option<String>,
 
@@ -684,7 +814,7 @@ pub enum CollectionsSubcommands {
         #[structopt(short = "R", long, default_value = DEFAULT_RATE_LIMIT)]
         rate_limit: usize,
 
-        /// Output file path for the cache file. Defaults to mb-cache-migrate.json.
+        /// Output file path for the cache file. Defaults to mb-cache-migrate.jsonl.
         #[structopt(short, long)]
         output_file: Option<String>,
     },
@@ -702,6 +832,10 @@ pub enum CollectionsSubcommands {
         /// API Key for an indexer, if used.
         #[structopt(short = "k", long)]
         api_key: Option<String>,
+
+        /// File format to write: json, csv, jsonl or parquet
+        #[structopt(long, default_value = "json")]
+        format: ExportFormat,
     },
     /// Check a list of items belong to a collection parent.
     #[structopt(name = "check-items")]
@@ -922,6 +1056,73 @@ pub enum FindSubcommands {
     },
 }
 
+#[derive(Debug, StructOpt)]
+pub enum LookupTableSubcommands {
+    /// Create an address lookup table, optionally adding addresses to it.
+    #[structopt(
+        name = "create",
+        after_help = "EXAMPLES:\nmetaboss lookup-table create --addresses-file shared_accounts.json"
+    )]
+    Create {
+        /// Path to the authority keypair file, which also pays for the table
+        #[structopt(short, long)]
+        keypair: Option<String>,
+
+        /// Addresses to add to the table
+        #[structopt(short, long)]
+        addresses: Vec<Pubkey>,
+
+        /// JSON list of addresses to add to the table
+        #[structopt(short = "f", long)]
+        addresses_file: Option<String>,
+    },
+    /// Add addresses to an address lookup table.
+    #[structopt(name = "extend")]
+    Extend {
+        /// Path to the authority keypair file
+        #[structopt(short, long)]
+        keypair: Option<String>,
+
+        /// Address of the lookup table
+        #[structopt(short = "t", long)]
+        lookup_table: Pubkey,
+
+        /// Addresses to add to the table
+        #[structopt(short, long)]
+        addresses: Vec<Pubkey>,
+
+        /// JSON list of addresses to add to the table
+        #[structopt(short = "f", long)]
+        addresses_file: Option<String>,
+    },
+    /// Deactivate an address lookup table so it can be closed.
+    #[structopt(name = "deactivate")]
+    Deactivate {
+        /// Path to the authority keypair file
+        #[structopt(short, long)]
+        keypair: Option<String>,
+
+        /// Address of the lookup table
+        #[structopt(short = "t", long)]
+        lookup_table: Pubkey,
+    },
+    /// Close a deactivated address lookup table and reclaim its rent.
+    #[structopt(name = "close")]
+    Close {
+        /// Path to the authority keypair file
+        #[structopt(short, long)]
+        keypair: Option<String>,
+
+        /// Address of the lookup table
+        #[structopt(short = "t", long)]
+        lookup_table: Pubkey,
+
+        /// Account to receive the rent, if different from the authority
+        #[structopt(short = "R", long)]
+        recipient: Option<Pubkey>,
+    },
+}
+
 #[derive(Debug, StructOpt)]
 pub enum MintSubcommands {
     /// Mint a normal SPL/SPL22 Token from the Token/Token Extensions Program
@@ -946,10 +1147,10 @@ pub enum MintSubcommands {
         #[structopt(short, long)]
         token_22: bool,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Mint an asset from the new Token Metadata Program unified handlers.
     Asset {
@@ -982,10 +1183,10 @@ pub enum MintSubcommands {
         #[structopt(short = "s", long)]
         max_print_edition_supply: Option<Supply>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Mint a single NFT from a JSON file
     #[structopt(name = "one")]
@@ -1030,10 +1231,10 @@ pub enum MintSubcommands {
         #[structopt(long)]
         sized: bool,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Mint one or more editions from a Master NFT.
     #[structopt(name = "editions")]
@@ -1058,10 +1259,10 @@ pub enum MintSubcommands {
         #[structopt(short = "s", long)]
         specific_editions: Option<Vec<u64>>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Find any missing editions for a Master NFT.
     #[structopt(name = "missing-editions")]
@@ -1073,10 +1274,10 @@ pub enum MintSubcommands {
         #[structopt(short, long)]
         account: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     #[structopt(
         name = "list",
@@ -1118,10 +1319,10 @@ pub enum MintSubcommands {
         #[structopt(long)]
         track: bool,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -1138,10 +1339,10 @@ pub enum SetSubcommands {
         #[structopt(short, long)]
         account: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Set primary sale happened to true for a list of mint addresses, enabling secondary sale royalties.
     #[structopt(name = "secondary-sale-all")]
@@ -1166,15 +1367,19 @@ pub enum SetSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
+
+        /// Address lookup table to compile transactions against, so more mints fit per transaction.
+        #[structopt(long)]
+        lookup_table: Option<Pubkey>,
     },
     /// Set update authority to a new account
     #[structopt(name = "update-authority")]
     UpdateAuthority {
-        /// Path to the update authority's keypair file
+        /// Path to the update authority's keypair file, or a usb://ledger path
         #[structopt(short, long)]
         keypair: Option<String>,
 
@@ -1186,14 +1391,15 @@ pub enum SetSubcommands {
         #[structopt(short, long)]
         new_update_authority: String,
 
-        //Path to the payers's keypair file
-        #[structopt(short = "p", long)]
+        /// Path to the payer's keypair file: deprecated--use --payer
+        #[deprecated]
+        #[structopt(short = "p", long, hidden = true)]
         keypair_payer: Option<String>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Set update authority on multiple accounts to a new account
     #[structopt(name = "update-authority-all")]
@@ -1202,9 +1408,10 @@ pub enum SetSubcommands {
         #[structopt(short, long)]
         keypair: Option<String>,
 
-        /// Path to the optional payers's keypair file
-        #[structopt(short, long)]
-        payer: Option<String>,
+        /// Path to the payer's keypair file: deprecated--use --payer
+        #[deprecated]
+        #[structopt(short = "p", hidden = true)]
+        payer_short: Option<String>,
 
         /// Path to mint list file
         #[structopt(short = "L", long)]
@@ -1226,10 +1433,10 @@ pub enum SetSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Set is-mutable to false, preventing any future updates to the NFT
     #[structopt(name = "immutable")]
@@ -1242,10 +1449,10 @@ pub enum SetSubcommands {
         #[structopt(short, long)]
         account: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Set is-mutable to false for a list of NFTs.
     ImmutableAll {
@@ -1269,10 +1476,10 @@ pub enum SetSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Set an asset to the correct Token Standard.
     TokenStandard {
@@ -1284,10 +1491,10 @@ pub enum SetSubcommands {
         #[structopt(short, long)]
         account: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Set all assets in a list to be the correct Token Standard.
     TokenStandardAll {
@@ -1311,10 +1518,10 @@ pub enum SetSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -1331,10 +1538,10 @@ pub enum SignSubcommands {
         #[structopt(short, long)]
         account: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Sign all metadata from a JSON list or for a given candy machine id / creator
     #[structopt(name = "all")]
@@ -1363,10 +1570,10 @@ pub enum SignSubcommands {
         #[structopt(short, long)]
         mint_accounts_file: Option<String>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -1388,6 +1595,47 @@ pub enum SnapshotSubcommands {
         /// Delay between DAS API requests in milliseconds
         #[structopt(short = "D", long, default_value = "500")]
         delay: u64,
+
+        /// Take the snapshot as it was after this slot, from transaction history
+        #[structopt(long)]
+        at_slot: Option<u64>,
+
+        /// Take the snapshot as it was at this time, as unix seconds or RFC 3339
+        #[structopt(long)]
+        at_time: Option<Timestamp>,
+
+        /// Directory to cache fetched transactions in for --at-slot and --at-time;
+        /// defaults to the user cache directory
+        #[structopt(long)]
+        tx_cache: Option<PathBuf>,
+
+        /// Also write the holders grouped by wallet, with counts and weights
+        #[structopt(long)]
+        aggregate: bool,
+
+        /// Wallets to leave out of the aggregate output, e.g. marketplace escrows
+        #[structopt(long)]
+        exclude: Vec<String>,
+
+        /// Path to a JSON list of wallets to leave out of the aggregate output
+        #[structopt(long)]
+        exclude_file: Option<PathBuf>,
+
+        /// Path to a JSON file of weights by trait type and value, e.g. {"Background": {"Gold": 2}}
+        #[structopt(long)]
+        weights: Option<PathBuf>,
+
+        /// Only count NFTs with this trait in the aggregate output, as TYPE=VALUE; repeatable
+        #[structopt(long = "trait")]
+        traits: Vec<TraitFilter>,
+
+        /// Where traits are read from: offchain (the JSON attributes) or onchain (the Core Attributes plugin)
+        #[structopt(long, default_value = "offchain")]
+        trait_source: TraitSource,
+
+        /// File format to write: json, csv, jsonl or parquet
+        #[structopt(long, default_value = "json")]
+        format: ExportFormat,
     },
     /// Get all mint accounts by various group types
     Mints {
@@ -1410,6 +1658,10 @@ pub enum SnapshotSubcommands {
         /// Delay between DAS API requests in milliseconds; defaults to 500
         #[structopt(short = "D", long, default_value = "500")]
         delay: u64,
+
+        /// File format to write: json, csv, jsonl or parquet
+        #[structopt(long, default_value = "json")]
+        format: ExportFormat,
     },
     /// Get all mint accounts by First Verified Creator Address
     Fvca {
@@ -1423,6 +1675,10 @@ pub enum SnapshotSubcommands {
         /// Delay between DAS API requests in milliseconds; defaults to 500
         #[structopt(short = "D", long, default_value = "500")]
         delay: u64,
+
+        /// File format to write: json, csv, jsonl or parquet
+        #[structopt(long, default_value = "json")]
+        format: ExportFormat,
     },
     /// Get all mint accounts by Metaplex Certified Collection key
     Mcc {
@@ -1436,6 +1692,10 @@ pub enum SnapshotSubcommands {
         /// Delay between DAS API requests in milliseconds; defaults to 500
         #[structopt(short = "D", long, default_value = "500")]
         delay: u64,
+
+        /// File format to write: json, csv, jsonl or parquet
+        #[structopt(long, default_value = "json")]
+        format: ExportFormat,
     },
     /// Get all current holders of NFTs by legacy gPA calls
     #[structopt(name = "holders-gpa")]
@@ -1471,6 +1731,19 @@ pub enum SnapshotSubcommands {
         /// Path to directory to save output files.
         #[structopt(short, long, default_value = ".")]
         output: String,
+
+        /// Take the snapshot as it was after this slot, from transaction history
+        #[structopt(long)]
+        at_slot: Option<u64>,
+
+        /// Take the snapshot as it was at this time, as unix seconds or RFC 3339
+        #[structopt(long)]
+        at_time: Option<Timestamp>,
+
+        /// Directory to cache fetched transactions in for --at-slot and --at-time;
+        /// defaults to the user cache directory
+        #[structopt(long)]
+        tx_cache: Option<PathBuf>,
     },
     /// Get all mint accounts using legacy getProgramAccounts call
     #[structopt(name = "mints-gpa")]
@@ -1503,6 +1776,18 @@ pub enum SnapshotSubcommands {
         #[structopt(short, long, default_value = ".")]
         output: String,
     },
+    /// Compare two holder snapshots or two mint lists
+    Diff {
+        /// The earlier snapshot file
+        old: PathBuf,
+
+        /// The later snapshot file
+        new: PathBuf,
+
+        /// Path of the JSON file to write the differences to
+        #[structopt(short, long, default_value = "snapshot_diff.json")]
+        output: PathBuf,
+    },
     /// Get all print edition mint accounts for a given master edition mint
     Prints {
         /// Master edition mint address.
@@ -1516,6 +1801,31 @@ pub enum SnapshotSubcommands {
         /// Path to directory to save output file
         #[structopt(short, long, default_value = ".")]
         output: String,
+
+        /// File format to write: json, csv, jsonl or parquet
+        #[structopt(long, default_value = "json")]
+        format: ExportFormat,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum WatchSubcommands {
+    /// Write a JSON line whenever an NFT in a collection changes owner, is burned or has its metadata updated
+    #[structopt(
+        name = "holders",
+        after_help = "EXAMPLES:\nmetaboss watch holders <COLLECTION_MINT> >> events.ndjson"
+    )]
+    Holders {
+        /// Collection parent mint address.
+        collection: Pubkey,
+
+        /// Websocket endpoint to subscribe through; defaults to the RPC url with a ws or wss scheme
+        #[structopt(long)]
+        ws_url: Option<String>,
+
+        /// Delay between DAS API requests in milliseconds
+        #[structopt(short = "D", long, default_value = "500")]
+        delay: u64,
     },
 }
 
@@ -1535,10 +1845,10 @@ pub enum UpdateSubcommands {
         #[structopt(short, long)]
         new_rule_set: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the rule set of a batch of pNFTs.
     RuleSetAll {
@@ -1566,10 +1876,10 @@ pub enum UpdateSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Remove the rule set of a pNFT.
     ClearRuleSet {
@@ -1581,10 +1891,10 @@ pub enum UpdateSubcommands {
         #[structopt(short = "a", long)]
         mint: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Remove the rule set from a batch of pNFTs.
     ClearRuleSetAll {
@@ -1608,10 +1918,10 @@ pub enum UpdateSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the seller fee basis points field inside the data struct on an NFT
     #[structopt(name = "sfbp")]
@@ -1628,10 +1938,10 @@ pub enum UpdateSubcommands {
         #[structopt(short, long)]
         new_sfbp: u16,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the seller fee basis points field inside the data struct on an NFT
     #[structopt(name = "sfbp-all")]
@@ -1660,10 +1970,10 @@ pub enum UpdateSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the name field inside the data struct on an NFT
     #[structopt(name = "name")]
@@ -1680,10 +1990,10 @@ pub enum UpdateSubcommands {
         #[structopt(short, long)]
         new_name: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the symbol field inside the data struct on an NFT
     #[structopt(name = "symbol")]
@@ -1700,10 +2010,10 @@ pub enum UpdateSubcommands {
         #[structopt(short, long)]
         new_symbol: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update all symbols for a list of mint addresses.
     SymbolAll {
@@ -1734,7 +2044,11 @@ pub enum UpdateSubcommands {
         /// Priority of the transaction: higher priority costs more
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
+
+        /// Address lookup table to compile transactions against, so more mints fit per transaction.
+        #[structopt(long)]
+        lookup_table: Option<Pubkey>,
     },
     /// Update the creators field by position inside the data struct on an NFT
     #[structopt(name = "creators")]
@@ -1755,10 +2069,10 @@ pub enum UpdateSubcommands {
         #[structopt(short = "A", long = "append")]
         append: bool,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update all the creators fields for a list of mint addresses.
     #[structopt(name = "creators-all")]
@@ -1791,10 +2105,10 @@ pub enum UpdateSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the data struct on a NFT
     #[structopt(name = "data")]
@@ -1811,10 +2125,10 @@ pub enum UpdateSubcommands {
         #[structopt(short, long)]
         new_data_file: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the data struct on a list of NFTs
     #[structopt(name = "data-all")]
@@ -1839,10 +2153,10 @@ pub enum UpdateSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the metadata URI, keeping the rest of the data the same
     #[structopt(name = "uri")]
@@ -1859,10 +2173,10 @@ pub enum UpdateSubcommands {
         #[structopt(short = "u", long)]
         new_uri: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the metadata URI on a list of mint accounts
     #[structopt(name = "uri-all")]
@@ -1887,10 +2201,10 @@ pub enum UpdateSubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Update the Uses data on a NFT
     #[structopt(name = "uses")]
@@ -1919,10 +2233,10 @@ pub enum UpdateSubcommands {
         #[structopt(long)]
         overwrite: bool,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
 }
 
@@ -1950,10 +2264,14 @@ pub enum TransferSubcommands {
         #[structopt(long)]
         receiver_account: Option<String>,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
+
+        /// Address lookup table to compile the transaction against.
+        #[structopt(long)]
+        lookup_table: Option<Pubkey>,
     },
 }
 
@@ -1969,10 +2287,10 @@ pub enum VerifySubcommands {
         #[structopt(short = "a", long)]
         mint: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Verify a creator for a list of NFTs.
     CreatorAll {
@@ -1996,10 +2314,14 @@ pub enum VerifySubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
+
+        /// Address lookup table to compile transactions against, so more mints fit per transaction.
+        #[structopt(long)]
+        lookup_table: Option<Pubkey>,
     },
 }
 
@@ -2015,10 +2337,10 @@ pub enum UnverifySubcommands {
         #[structopt(short = "a", long)]
         mint: String,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
     },
     /// Unverify a creator for a list of NFTs.
     CreatorAll {
@@ -2042,10 +2364,14 @@ pub enum UnverifySubcommands {
         #[structopt(long, default_value = "0")]
         retries: u8,
 
-        /// Priority of the transaction: higher priority costs more.
+        /// Priority of the transaction: higher priority costs more, or auto[:percentile] to match recent fees.
         /// See https://metaboss.rs/priority-fees.html for more details.
         #[structopt(short = "P", long, default_value = "none")]
-        priority: Priority,
+        priority: PriorityArg,
+
+        /// Address lookup table to compile transactions against, so more mints fit per transaction.
+        #[structopt(long)]
+        lookup_table: Option<Pubkey>,
     },
 }
 
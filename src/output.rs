@@ -0,0 +1,294 @@
+use indexmap::IndexSet;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    str::FromStr,
+};
+use structopt::StructOpt;
+
+use crate::{
+    constants::{COMMAND_OUTPUT, OUTPUT_FORMAT},
+    opt::Opt,
+    utils::find_tm_error,
+};
+
+/// Print a line for people to read: to stdout, or to stderr when the output
+/// is JSON so stdout carries nothing but result objects.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::json_output() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// How results are written, set with `--output-format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("Invalid output format: {s}")),
+        }
+    }
+}
+
+/// Whether results are written as JSON objects, one per line.
+pub fn json_output() -> bool {
+    *OUTPUT_FORMAT.read().unwrap() == OutputFormat::Json
+}
+
+/// One line of JSON output.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    /// The outcome of one item of a batch run.
+    Item(ItemOutput),
+    /// The outcome of the whole command, written last.
+    Command(CommandOutput),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    /// The transaction was sent but not seen to land; it may still.
+    Sent,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemOutput {
+    pub item: String,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_name: Option<String>,
+}
+
+impl ItemOutput {
+    pub fn new(item: impl Into<String>, status: Status) -> Self {
+        Self {
+            item: item.into(),
+            status,
+            signature: None,
+            address: None,
+            error: None,
+            error_name: None,
+        }
+    }
+
+    pub fn signature(mut self, signature: Option<String>) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    pub fn address(mut self, address: impl ToString) -> Self {
+        self.address = Some(address.to_string());
+        self
+    }
+
+    /// Set the error, with the name of any Token Metadata error code in it.
+    pub fn error(mut self, error: impl ToString) -> Self {
+        let error = error.to_string();
+        self.error_name = error_name(&error);
+        self.error = Some(error);
+        self
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CommandOutput {
+    pub command: String,
+    pub status: Option<Status>,
+    /// Every transaction the command landed.
+    pub signatures: IndexSet<String>,
+    /// Accounts the command created or found, by what they are.
+    pub addresses: BTreeMap<String, String>,
+    /// What the command decoded or found, for commands that read accounts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_name: Option<String>,
+}
+
+/// The name of the Token Metadata error whose code appears in `message`.
+pub fn error_name(message: &str) -> Option<String> {
+    static RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r" 0x([0-9a-fA-F]+)").expect("Failed to create regex"));
+
+    RE.captures(message)
+        .and_then(|captures| find_tm_error(&captures[1]))
+}
+
+/// Write `record` as one line of JSON to stdout.
+pub fn emit(record: &Record) {
+    let line = serde_json::to_string(record).expect("output records serialize");
+    let mut stdout = io::stdout().lock();
+    // A closed pipe leaves nobody to report to.
+    let _ = writeln!(stdout, "{line}");
+    let _ = stdout.flush();
+}
+
+/// Write the outcome of one batch item, when the output is JSON.
+pub fn emit_item(item: ItemOutput) {
+    if json_output() {
+        emit(&Record::Item(item));
+    }
+}
+
+/// Note a transaction the command landed, for its result object.
+pub fn record_signature(signature: impl ToString) {
+    if json_output() {
+        COMMAND_OUTPUT
+            .lock()
+            .unwrap()
+            .signatures
+            .insert(signature.to_string());
+    }
+}
+
+/// Note an account the command created or found, e.g. `("mint", address)`.
+pub fn record_address(label: &str, address: impl ToString) {
+    if json_output() {
+        COMMAND_OUTPUT
+            .lock()
+            .unwrap()
+            .addresses
+            .insert(label.to_string(), address.to_string());
+    }
+}
+
+/// Note what the command decoded or found, for its result object.
+pub fn record_data(data: &impl Serialize) {
+    if json_output() {
+        COMMAND_OUTPUT.lock().unwrap().data =
+            Some(serde_json::to_value(data).expect("decoded data serializes"));
+    }
+}
+
+/// Write the result object of the command run with `args`, when the output
+/// is JSON.
+pub fn emit_command(args: &[String], result: &anyhow::Result<()>) {
+    if !json_output() {
+        return;
+    }
+
+    let mut output = std::mem::take(&mut *COMMAND_OUTPUT.lock().unwrap());
+    output.command = command_name(args);
+    match result {
+        Ok(()) => output.status = Some(Status::Ok),
+        Err(e) => {
+            let error = format!("{e:#}");
+            output.status = Some(Status::Failed);
+            output.error_name = error_name(&error);
+            output.error = Some(error);
+        }
+    }
+    emit(&Record::Command(output));
+}
+
+/// The subcommands named in `args`, e.g. `update uri-all`.
+pub fn command_name(args: &[String]) -> String {
+    let Ok(matches) = Opt::clap().get_matches_from_safe(args) else {
+        return String::new();
+    };
+
+    let mut names = vec![];
+    let mut current = matches.subcommand();
+    while let (name, Some(sub)) = current {
+        names.push(name.to_string());
+        current = sub.subcommand();
+    }
+    names.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_records_are_tagged_and_skip_empty_fields() {
+        let record =
+            Record::Item(ItemOutput::new("mint1", Status::Ok).signature(Some("sig1".to_string())));
+
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"type":"item","item":"mint1","status":"ok","signature":"sig1"}"#
+        );
+    }
+
+    #[test]
+    fn errors_carry_decoded_token_metadata_names() {
+        let item = ItemOutput::new("mint1", Status::Failed)
+            .error("custom program error: 0x26 while updating");
+
+        assert_eq!(item.error_name.as_deref(), find_tm_error("26").as_deref());
+        assert!(item.error_name.is_some());
+        assert!(ItemOutput::new("mint1", Status::Failed)
+            .error("blockhash not found")
+            .error_name
+            .is_none());
+    }
+
+    #[test]
+    fn command_records_carry_decoded_data() {
+        let record = Record::Command(CommandOutput {
+            command: "decode mint-account".to_string(),
+            status: Some(Status::Ok),
+            data: Some(serde_json::json!({ "supply": 1 })),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"type":"command","command":"decode mint-account","status":"ok","signatures":[],"addresses":{},"data":{"supply":1}}"#
+        );
+    }
+
+    #[test]
+    fn command_name_follows_nested_subcommands() {
+        let args: Vec<String> = [
+            "metaboss",
+            "-r",
+            "https://example.com",
+            "update",
+            "uri-all",
+            "-u",
+            "uris.json",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+
+        assert_eq!(command_name(&args), "update uri-all");
+    }
+
+    #[test]
+    fn output_format_parses() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("text".parse::<OutputFormat>().unwrap(), OutputFormat::Text);
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+}
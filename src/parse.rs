@@ -1,4 +1,4 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use mpl_token_metadata::types::Creator;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -8,7 +8,8 @@ use std::fs::read_to_string;
 use std::path::PathBuf;
 use std::{env, fs, path::Path, str::FromStr};
 
-use crate::constants::ERROR_FILE_BEGIN;
+use crate::constants::{ERROR_FILE_BEGIN, MULTISIG, PAYER};
+use crate::signer::SignerSource;
 use crate::utils::{convert_to_wtf_error, find_errors};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -71,7 +72,55 @@ pub fn parse_keypair(
     keypair
 }
 
+/// The authority keypair for commands that can export multisig proposals.
+///
+/// Proposals are signed by the multisig, never locally, so no keypair file is
+/// needed and a throwaway keypair stands in for it.
+pub fn parse_authority_keypair(
+    keypair_opt: Option<String>,
+    sol_config_option: Option<SolanaConfig>,
+) -> Keypair {
+    if MULTISIG.read().unwrap().is_some() {
+        return Keypair::new();
+    }
+    parse_keypair(keypair_opt, sol_config_option)
+}
+
+/// The `--payer` keypair, which pays fees and rent in place of the authority.
+pub fn parse_payer() -> Result<Option<Keypair>> {
+    PAYER
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|path| read_keypair(path).context("Failed to read the --payer keypair file"))
+        .transpose()
+}
+
+/// Use a command's deprecated payer option as `--payer`.
+pub fn use_deprecated_payer(option: &str, path: Option<String>) -> Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    if MULTISIG.read().unwrap().is_some() {
+        bail!("{option} can't be used with --multisig; the vault pays for its proposals");
+    }
+
+    let mut payer = PAYER.write().unwrap();
+    if payer.as_ref().is_some_and(|payer| *payer != path) {
+        bail!("{option} and --payer name different keypairs");
+    }
+    say!("{option} is deprecated; use --payer instead.");
+    *payer = Some(path);
+    Ok(())
+}
+
 pub fn read_keypair(path: &String) -> Result<Keypair> {
+    if SignerSource::is_external(path) {
+        return Err(anyhow!(
+            "{path} is an external signer, which this command doesn't support yet. Pass a keypair file."
+        ));
+    }
+
     let secret_string: String = fs::read_to_string(path).context("Can't find key file")?;
 
     // Try to decode the secret string as a JSON array of ints first and then as a base58 encoded string to support Phantom private keys.
@@ -220,7 +269,7 @@ pub fn parse_errors_code(error_code: &str) -> Result<()> {
     }
 
     for error in errors {
-        println!("\t{:<10} |\t{}", error.domain, error.message);
+        say!("\t{:<10} |\t{}", error.domain, error.message);
     }
     Ok(())
 }
@@ -0,0 +1,429 @@
+use anyhow::{anyhow, bail, Result};
+use metaboss_lib::data::Priority;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, pubkey::Pubkey,
+};
+use solana_sdk_ids::compute_budget;
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use crate::constants::{AUTO_PRIORITY, PRIORITY_FEE_BUDGET};
+
+/// Percentile of recent fees used by `--priority auto`.
+pub const DEFAULT_AUTO_PERCENTILE: u8 = 75;
+
+/// `getRecentPrioritizationFees` accepts at most this many accounts.
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Compute units the runtime allows each instruction when no limit is set.
+const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u32 = 200_000;
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// Tags of the compute budget instructions.
+const SET_COMPUTE_UNIT_LIMIT_TAG: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE_TAG: u8 = 3;
+
+/// The `--priority` of a command: one of the fixed levels, or `auto`, which
+/// prices each transaction from the fees recently paid to write its accounts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PriorityArg {
+    Level(Priority),
+    Auto { percentile: u8 },
+}
+
+impl Default for PriorityArg {
+    fn default() -> Self {
+        Self::Level(Priority::None)
+    }
+}
+
+impl FromStr for PriorityArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        let Some(rest) = s.strip_prefix("auto") else {
+            return Priority::from_str(&s).map(Self::Level).map_err(|_| {
+                anyhow!(
+                    "Invalid priority: expected none, low, medium, high, max or auto[:percentile]"
+                )
+            });
+        };
+        let percentile = match rest.strip_prefix(':') {
+            Some(percentile) => percentile
+                .parse::<u8>()
+                .ok()
+                .filter(|percentile| *percentile <= 100)
+                .ok_or_else(|| anyhow!("Invalid auto priority percentile: {percentile}"))?,
+            None if rest.is_empty() => DEFAULT_AUTO_PERCENTILE,
+            None => return Err(anyhow!("Invalid priority: {s}")),
+        };
+        Ok(Self::Auto { percentile })
+    }
+}
+
+impl Display for PriorityArg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Level(priority) => write!(f, "{priority}"),
+            Self::Auto { percentile } => write!(f, "auto:{percentile}"),
+        }
+    }
+}
+
+impl PriorityArg {
+    /// Select this priority for the run and return the fixed level to pass on.
+    ///
+    /// `auto` is resolved per transaction by `compute_unit_price`, so it is
+    /// recorded globally and the level is `none`.
+    pub fn apply(self) -> Priority {
+        match self {
+            Self::Level(priority) => {
+                *AUTO_PRIORITY.write().unwrap() = None;
+                priority
+            }
+            Self::Auto { percentile } => {
+                *AUTO_PRIORITY.write().unwrap() = Some(percentile);
+                Priority::None
+            }
+        }
+    }
+
+    /// Like `apply`, for commands that send through metaboss_lib's own
+    /// transaction builders, which only know the fixed levels.
+    pub fn fixed_level(self, command: &str) -> Result<Priority> {
+        if let Self::Auto { .. } = self {
+            bail!(
+                "`{command}` does not support --priority auto; use none, low, medium, high or max"
+            );
+        }
+        Ok(self.apply())
+    }
+}
+
+/// The compute unit price, in micro-lamports, of a fixed priority level.
+pub fn level_micro_lamports(priority: &Priority) -> u64 {
+    match priority {
+        Priority::None => 20,
+        Priority::Low => 20_000,
+        Priority::Medium => 200_000,
+        Priority::High => 1_000_000,
+        Priority::Max => 2_000_000,
+    }
+}
+
+/// The compute unit price for a transaction of `instructions`, within the
+/// per-transaction and per-run caps. The price is charged to the run.
+pub fn compute_unit_price(
+    client: &RpcClient,
+    priority: &Priority,
+    instructions: &[Instruction],
+) -> Result<u64> {
+    let auto = *AUTO_PRIORITY.read().unwrap();
+    let price = match auto {
+        Some(percentile) => {
+            estimate_micro_lamports(client, &writable_accounts(instructions), percentile)?
+        }
+        None => level_micro_lamports(priority),
+    };
+    Ok(PRIORITY_FEE_BUDGET.charge(price, compute_unit_limit(instructions)))
+}
+
+/// `instructions` with their compute unit price set by `compute_unit_price`.
+pub fn with_priority(
+    client: &RpcClient,
+    priority: &Priority,
+    instructions: &[Instruction],
+) -> Result<Vec<Instruction>> {
+    let price = compute_unit_price(client, priority, instructions)?;
+    Ok(with_compute_unit_price(instructions, price))
+}
+
+/// The `percentile` of the fees recently paid by transactions writing
+/// `accounts`, never less than the `none` level.
+pub fn estimate_micro_lamports(
+    client: &RpcClient,
+    accounts: &[Pubkey],
+    percentile: u8,
+) -> Result<u64> {
+    let accounts = &accounts[..accounts.len().min(MAX_FEE_ACCOUNTS)];
+    let fees = client
+        .get_recent_prioritization_fees(accounts)?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    Ok(percentile_fee(fees, percentile).max(level_micro_lamports(&Priority::None)))
+}
+
+/// Nearest-rank percentile of `fees`, or zero when there are none.
+pub fn percentile_fee(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let rank = (fees.len() * percentile as usize).div_ceil(100);
+    fees[rank.saturating_sub(1)]
+}
+
+/// Accounts written by `instructions`, in order of first use.
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts
+}
+
+fn compute_budget_value(instructions: &[Instruction], tag: u8) -> Option<&[u8]> {
+    instructions
+        .iter()
+        .filter(|ix| ix.program_id == compute_budget::id())
+        .find(|ix| ix.data.first() == Some(&tag))
+        .map(|ix| &ix.data[1..])
+}
+
+/// The compute unit price set by `instructions`, if any.
+pub fn instruction_compute_unit_price(instructions: &[Instruction]) -> Option<u64> {
+    compute_budget_value(instructions, SET_COMPUTE_UNIT_PRICE_TAG)
+        .and_then(|data| data.try_into().ok())
+        .map(u64::from_le_bytes)
+}
+
+/// The compute units a transaction of `instructions` pays for: its limit, or
+/// the runtime default when it doesn't set one.
+pub fn compute_unit_limit(instructions: &[Instruction]) -> u32 {
+    compute_budget_value(instructions, SET_COMPUTE_UNIT_LIMIT_TAG)
+        .and_then(|data| data.try_into().ok())
+        .map(u32::from_le_bytes)
+        .unwrap_or_else(|| {
+            let count = instructions
+                .iter()
+                .filter(|ix| ix.program_id != compute_budget::id())
+                .count() as u32;
+            count
+                .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNITS)
+                .min(MAX_COMPUTE_UNITS)
+        })
+}
+
+/// `instructions` with their compute unit price set to `price`, replacing the
+/// existing price instruction or adding one at the front.
+pub fn with_compute_unit_price(instructions: &[Instruction], price: u64) -> Vec<Instruction> {
+    let price_ix = ComputeBudgetInstruction::set_compute_unit_price(price);
+    let mut ixs = instructions.to_vec();
+    match ixs.iter().position(|ix| {
+        ix.program_id == compute_budget::id()
+            && ix.data.first() == Some(&SET_COMPUTE_UNIT_PRICE_TAG)
+    }) {
+        Some(i) => ixs[i] = price_ix,
+        None => ixs.insert(0, price_ix),
+    }
+    ixs
+}
+
+/// Lamports paid in priority fees for `units` compute units at `price`.
+pub fn priority_fee_lamports(price: u64, units: u32) -> u64 {
+    (price as u128 * units as u128).div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64
+}
+
+/// The highest compute unit price at which `units` cost at most `lamports`.
+fn max_price(lamports: u64, units: u32) -> u64 {
+    (lamports as u128 * MICRO_LAMPORTS_PER_LAMPORT / units.max(1) as u128).min(u64::MAX as u128)
+        as u64
+}
+
+#[derive(Default)]
+struct BudgetState {
+    max_per_tx: Option<u64>,
+    max_per_run: Option<u64>,
+    spent: u64,
+}
+
+/// Caps, in lamports, on the priority fees of a transaction and of the run.
+#[derive(Default)]
+pub struct FeeBudget {
+    state: Mutex<BudgetState>,
+}
+
+impl FeeBudget {
+    /// Set the caps. What was already spent still counts against the run.
+    pub fn configure(&self, max_per_tx: Option<u64>, max_per_run: Option<u64>) {
+        let mut state = self.state.lock().unwrap();
+        state.max_per_tx = max_per_tx;
+        state.max_per_run = max_per_run;
+    }
+
+    /// Lamports charged to the run so far.
+    pub fn spent(&self) -> u64 {
+        self.state.lock().unwrap().spent
+    }
+
+    /// `price` lowered as needed to keep a transaction of `units` within the
+    /// per-transaction cap, without charging the run.
+    pub fn limit(&self, price: u64, units: u32) -> u64 {
+        match self.state.lock().unwrap().max_per_tx {
+            Some(max_per_tx) => price.min(max_price(max_per_tx, units)),
+            None => price,
+        }
+    }
+
+    /// Charge a new transaction paying `price` for `units`, returning the
+    /// price lowered as needed to stay within the caps.
+    pub fn charge(&self, price: u64, units: u32) -> u64 {
+        self.raise(0, price, units)
+    }
+
+    /// Charge raising the price of a transaction already charged at `from`
+    /// to `to`, returning the price it can be raised to within the caps.
+    pub fn raise(&self, from: u64, to: u64, units: u32) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let mut to = to;
+        if let Some(max_per_tx) = state.max_per_tx {
+            to = to.min(max_price(max_per_tx, units));
+        }
+        if let Some(max_per_run) = state.max_per_run {
+            let left = max_per_run.saturating_sub(state.spent);
+            to = to.min(from.saturating_add(max_price(left, units)));
+        }
+        let to = to.max(from);
+
+        let extra =
+            priority_fee_lamports(to, units).saturating_sub(priority_fee_lamports(from, units));
+        state.spent = state.spent.saturating_add(extra);
+        to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[test]
+    fn parses_levels_and_auto() {
+        assert_eq!(
+            PriorityArg::from_str("medium").unwrap(),
+            PriorityArg::Level(Priority::Medium)
+        );
+        assert_eq!(
+            PriorityArg::from_str("auto").unwrap(),
+            PriorityArg::Auto {
+                percentile: DEFAULT_AUTO_PERCENTILE
+            }
+        );
+        assert_eq!(
+            PriorityArg::from_str("Auto:90").unwrap(),
+            PriorityArg::Auto { percentile: 90 }
+        );
+        assert!(PriorityArg::from_str("auto:101").is_err());
+        assert!(PriorityArg::from_str("automatic").is_err());
+        assert!(PriorityArg::from_str("urgent").is_err());
+    }
+
+    #[test]
+    fn fixed_level_commands_reject_auto() {
+        let auto = PriorityArg::Auto { percentile: 75 };
+        assert!(auto.fixed_level("mint asset").is_err());
+    }
+
+    #[test]
+    fn picks_the_nearest_rank_percentile() {
+        let fees = vec![50, 10, 40, 20, 30];
+        assert_eq!(percentile_fee(fees.clone(), 0), 10);
+        assert_eq!(percentile_fee(fees.clone(), 50), 30);
+        assert_eq!(percentile_fee(fees.clone(), 75), 40);
+        assert_eq!(percentile_fee(fees, 100), 50);
+        assert_eq!(percentile_fee(Vec::new(), 75), 0);
+    }
+
+    #[test]
+    fn collects_writable_accounts_once() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let program = Pubkey::new_unique();
+        let ixs = vec![
+            Instruction::new_with_bytes(
+                program,
+                &[],
+                vec![
+                    AccountMeta::new(a, true),
+                    AccountMeta::new_readonly(b, false),
+                ],
+            ),
+            Instruction::new_with_bytes(
+                program,
+                &[],
+                vec![AccountMeta::new(c, false), AccountMeta::new(a, false)],
+            ),
+        ];
+        assert_eq!(writable_accounts(&ixs), vec![a, c]);
+    }
+
+    #[test]
+    fn reads_the_compute_unit_limit_or_the_default() {
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        assert_eq!(compute_unit_limit(&[ix.clone(), ix.clone()]), 400_000);
+
+        let ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            ix,
+        ];
+        assert_eq!(compute_unit_limit(&ixs), 50_000);
+        assert_eq!(instruction_compute_unit_price(&ixs), Some(1_000));
+    }
+
+    #[test]
+    fn compute_unit_price_is_replaced_or_added() {
+        let ixs = with_compute_unit_price(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![],
+            )],
+            50,
+        );
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(instruction_compute_unit_price(&ixs), Some(50));
+
+        let ixs = with_compute_unit_price(&ixs, 100);
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(instruction_compute_unit_price(&ixs), Some(100));
+    }
+
+    #[test]
+    fn caps_the_price_of_a_transaction() {
+        let budget = FeeBudget::default();
+        budget.configure(Some(1_000), None);
+
+        // 1_000 lamports over 100_000 units is 10_000 micro-lamports a unit.
+        assert_eq!(budget.charge(50_000, 100_000), 10_000);
+        assert_eq!(budget.charge(5_000, 100_000), 5_000);
+        assert_eq!(budget.spent(), 1_500);
+    }
+
+    #[test]
+    fn caps_the_fees_of_the_run() {
+        let budget = FeeBudget::default();
+        budget.configure(None, Some(1_000));
+
+        assert_eq!(budget.charge(6_000, 100_000), 6_000);
+        assert_eq!(budget.charge(6_000, 100_000), 4_000);
+        assert_eq!(budget.charge(6_000, 100_000), 0);
+        assert_eq!(budget.spent(), 1_000);
+
+        // A raise is only charged for the difference, and never lowers the price.
+        assert_eq!(budget.raise(4_000, 8_000, 100_000), 4_000);
+    }
+}
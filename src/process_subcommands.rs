@@ -10,11 +10,13 @@ use solana_client::{nonblocking::rpc_client::RpcClient as AsyncRpcClient, rpc_cl
 
 use crate::airdrop::*;
 use crate::burn::*;
+use crate::check::process_check;
 use crate::collections::{
     approve_delegate, check_collection_items, get_collection_items, migrate_collection,
     revoke_delegate, set_and_verify_nft_collection, set_size, unverify_nft_collection,
     verify_nft_collection, MigrateArgs,
 };
+use crate::constants::OFFLINE;
 use crate::create::{
     create_fungible, create_fungible_22, create_fungible_22_token, create_master_edition,
     create_metadata, CreateFungible22Args, CreateFungible22TokenArgs, CreateFungibleArgs,
@@ -29,14 +31,28 @@ use crate::derive::{
     get_cmv2_pda, get_collection_delegate, get_edition_marker_pda, get_edition_pda,
     get_generic_pda, get_metadata_pda, get_token_account_pda, get_token_record_pda,
 };
+use crate::extend_program::process_extend_program;
 use crate::find::find_missing_editions_process;
+use crate::lookup_table::{
+    process_close_lookup_table, process_create_lookup_table, process_deactivate_lookup_table,
+    process_extend_lookup_table,
+};
 use crate::mint::{
     mint_editions, mint_fungible, mint_list, mint_missing_editions, mint_one, process_mint_asset,
     MintAssetParams,
 };
+use crate::offline::{process_broadcast, process_sign_offline};
 use crate::opt::*;
-use crate::parse::{is_only_one_option, parse_errors_code, parse_errors_file};
+use crate::output::{record_address, record_signature};
+use crate::parse::{
+    is_only_one_option, parse_authority_keypair, parse_errors_code, parse_errors_file,
+    use_deprecated_payer,
+};
+use crate::proposal::{multisig_settings, propose};
+use crate::setup::AppConfig;
 use crate::sign::{sign_all, sign_one};
+use crate::signer::parse_signer;
+use crate::snapshot::process_snapshot;
 use crate::transfer::process_transfer_asset;
 use crate::unverify::{
     unverify_creator, unverify_creator_all, UnverifyCreatorAllArgs, UnverifyCreatorArgs,
@@ -44,6 +60,146 @@ use crate::unverify::{
 use crate::update::*;
 use crate::uses::{approve_use_delegate, revoke_use_delegate, utilize_nft};
 use crate::verify::{verify_creator, verify_creator_all, VerifyCreatorAllArgs, VerifyCreatorArgs};
+use crate::watch::{watch_holders, WatchHoldersArgs};
+
+/// Whether `cmd` can write its instructions as multisig proposals.
+pub fn exports_proposals(cmd: &Command) -> bool {
+    matches!(
+        cmd,
+        Command::Update {
+            update_subcommands: UpdateSubcommands::UriAll { .. }
+                | UpdateSubcommands::SymbolAll { .. },
+        } | Command::Set {
+            set_subcommands: SetSubcommands::PrimarySaleHappenedAll { .. }
+                | SetSubcommands::UpdateAuthority { .. }
+                | SetSubcommands::UpdateAuthorityAll { .. },
+        } | Command::Verify {
+            verify_subcommands: VerifySubcommands::CreatorAll { .. },
+        } | Command::Unverify {
+            unverify_subcommands: UnverifySubcommands::CreatorAll { .. },
+        } | Command::Collections {
+            collections_subcommands: CollectionsSubcommands::VerifyCollection { .. },
+        } | Command::Burn {
+            burn_subcommands: BurnSubcommands::Asset { .. } | BurnSubcommands::AssetAll { .. },
+        }
+    )
+}
+
+/// Whether `cmd` can export its transactions for an offline authority to sign.
+pub fn exports_offline(cmd: &Command) -> bool {
+    matches!(
+        cmd,
+        Command::Update {
+            update_subcommands: UpdateSubcommands::SymbolAll { .. },
+        } | Command::Set {
+            set_subcommands: SetSubcommands::PrimarySaleHappenedAll { .. },
+        } | Command::Verify {
+            verify_subcommands: VerifySubcommands::CreatorAll { .. },
+        } | Command::Unverify {
+            unverify_subcommands: UnverifySubcommands::CreatorAll { .. },
+        }
+    )
+}
+
+/// Fail before doing anything if a global option is set that `cmd` would
+/// otherwise ignore.
+pub fn check_global_options(cmd: &Command) -> Result<()> {
+    if multisig_settings().is_some() && !exports_proposals(cmd) {
+        bail!("This command can't export multisig proposals, so --multisig can't be used with it.");
+    }
+    if OFFLINE.read().unwrap().is_some() && !exports_offline(cmd) {
+        bail!("This command can't be signed offline, so --offline can't be used with it.");
+    }
+    Ok(())
+}
+
+/// Run a single top-level command with the clients from `config`.
+pub async fn process_command(config: AppConfig, cmd: Command) -> Result<()> {
+    check_global_options(&cmd)?;
+
+    let das_url = config.das_url;
+    let rpc_url = config.rpc_url;
+    let client = config.client;
+    let async_client = config.async_client;
+
+    match cmd {
+        Command::Collections {
+            collections_subcommands,
+        } => process_collections(client, async_client, collections_subcommands).await?,
+        Command::Airdrop {
+            airdrop_subcommands,
+        } => process_airdrop(client, airdrop_subcommands).await?,
+        Command::Burn { burn_subcommands } => process_burn_asset(client, burn_subcommands).await?,
+        Command::BurnNft {
+            burn_nft_subcommands,
+        } => process_burn_nft(client, burn_nft_subcommands).await?,
+        Command::BurnPrint {
+            burn_print_subcommands,
+        } => process_burn_print(client, burn_print_subcommands).await?,
+        Command::Check { check_subcommands } => process_check(check_subcommands).await?,
+        Command::Create { create_subcommands } => process_create(client, create_subcommands)?,
+        Command::Decode { decode_subcommands } => process_decode(&client, decode_subcommands)?,
+        Command::Derive { derive_subcommands } => process_derive(derive_subcommands),
+        Command::ExtendProgram {
+            keypair_path,
+            program_address,
+            additional_bytes,
+        } => process_extend_program(client, keypair_path, program_address, additional_bytes)?,
+        Command::Find { find_subcommands } => process_find(&client, find_subcommands)?,
+        Command::LookupTable {
+            lookup_table_subcommands,
+        } => process_lookup_table(&client, lookup_table_subcommands)?,
+        Command::Mint { mint_subcommands } => process_mint(client, mint_subcommands)?,
+        Command::ParseErrors {
+            parse_errors_file_subcommands,
+        } => process_parse_errors_file(parse_errors_file_subcommands)?,
+        Command::Rollback {
+            journal_file,
+            keypair,
+            cache_file,
+            rate_limit,
+            retries,
+            priority,
+        } => {
+            rollback(RollbackArgs {
+                client,
+                keypair,
+                journal_file,
+                cache_file,
+                rate_limit,
+                retries,
+                priority: priority.apply(),
+            })
+            .await?
+        }
+        Command::Set { set_subcommands } => process_set(client, set_subcommands).await?,
+        Command::Sign { sign_subcommands } => process_sign(&client, sign_subcommands)?,
+        Command::SignOffline { file, keypair } => process_sign_offline(keypair, file)?,
+        Command::Broadcast { file } => process_broadcast(&client, file)?,
+        Command::Snapshot {
+            snapshot_subcommands,
+        } => process_snapshot(client, das_url, snapshot_subcommands).await?,
+        Command::Transfer {
+            transfer_subcommands,
+        } => process_transfer(client, transfer_subcommands)?,
+        Command::Update { update_subcommands } => {
+            process_update(client, update_subcommands).await?
+        }
+        Command::Uses { uses_subcommands } => process_uses(&client, uses_subcommands)?,
+        Command::Verify { verify_subcommands } => {
+            process_verify(client, verify_subcommands).await?
+        }
+        Command::Unverify {
+            unverify_subcommands,
+        } => process_unverify(client, unverify_subcommands).await?,
+        Command::Watch { watch_subcommands } => {
+            process_watch(client, rpc_url, das_url, watch_subcommands).await?
+        }
+        Command::Run { .. } => bail!("A job can't run another job."),
+    }
+
+    Ok(())
+}
 
 pub fn process_uses(client: &RpcClient, commands: UsesSubcommands) -> Result<()> {
     match commands {
@@ -171,7 +327,8 @@ pub async fn process_collections(
             collection_mint,
             method,
             api_key,
-        } => get_collection_items(collection_mint, method, api_key).await,
+            format,
+        } => get_collection_items(collection_mint, method, api_key, format).await,
 
         CollectionsSubcommands::CheckItems {
             collection_mint,
@@ -190,22 +347,33 @@ pub async fn process_burn_asset(client: RpcClient, commands: BurnSubcommands) ->
             amount,
             priority,
         } => {
+            if let Some(multisig) = multisig_settings() {
+                return propose_burn_asset(
+                    &client,
+                    &mint_account,
+                    token_account,
+                    amount,
+                    &multisig,
+                );
+            }
+
+            // The token owner may be a hardware wallet.
             let solana_opts = parse_solana_config();
-            let keypair = parse_keypair(keypair, solana_opts);
+            let authority = parse_signer(keypair, solana_opts)?;
 
-            let args = BurnAssetArgs {
-                client: Arc::new(client),
-                keypair: Arc::new(keypair),
-                mint_account,
+            let sig = burn_asset_with_signer(
+                &client,
+                authority.as_ref(),
+                parse_payer()?.as_ref(),
+                &mint_account,
                 token_account,
                 amount,
-                priority,
-            };
-
-            let sig = burn_asset(args).await?;
+                &priority.apply(),
+            )?;
 
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -224,7 +392,7 @@ pub async fn process_burn_asset(client: RpcClient, commands: BurnSubcommands) ->
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -237,7 +405,7 @@ pub async fn process_burn_nft(client: RpcClient, commands: BurnNftSubcommands) -
             keypair,
             mint_account,
             priority,
-        } => burn_one(client, keypair, mint_account, priority).await,
+        } => burn_one(client, keypair, mint_account, priority.apply()).await,
 
         BurnNftSubcommands::All {
             keypair,
@@ -254,7 +422,7 @@ pub async fn process_burn_nft(client: RpcClient, commands: BurnNftSubcommands) -
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -268,7 +436,7 @@ pub async fn process_burn_print(client: RpcClient, commands: BurnPrintSubcommand
             account,
             master_edition,
             priority,
-        } => burn_print_one(client, keypair, account, master_edition, priority).await,
+        } => burn_print_one(client, keypair, account, master_edition, priority.apply()).await,
         BurnPrintSubcommands::All {
             keypair,
             mint_list,
@@ -286,7 +454,7 @@ pub async fn process_burn_print(client: RpcClient, commands: BurnPrintSubcommand
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -308,7 +476,7 @@ pub fn process_create(client: RpcClient, commands: CreateSubcommands) -> Result<
             mint,
             metadata,
             immutable,
-            priority,
+            priority: priority.apply(),
             full_compute,
         }),
         CreateSubcommands::Fungible {
@@ -328,7 +496,7 @@ pub fn process_create(client: RpcClient, commands: CreateSubcommands) -> Result<
             decimals,
             initial_supply,
             immutable,
-            priority,
+            priority: priority.apply(),
             full_compute,
         }),
         CreateSubcommands::Fungible22 {
@@ -345,7 +513,7 @@ pub fn process_create(client: RpcClient, commands: CreateSubcommands) -> Result<
             mint_path,
             decimals,
             initial_supply,
-            priority,
+            priority: priority.apply(),
         }),
         CreateSubcommands::Fungible22Token {
             keypair,
@@ -357,7 +525,7 @@ pub fn process_create(client: RpcClient, commands: CreateSubcommands) -> Result<
             keypair,
             extensions,
             mint_address,
-            priority,
+            priority: priority.apply(),
         }),
         CreateSubcommands::MasterEdition {
             keypair,
@@ -371,7 +539,7 @@ pub fn process_create(client: RpcClient, commands: CreateSubcommands) -> Result<
             mint_authority,
             mint,
             max_supply,
-            priority,
+            priority: priority.apply(),
         }),
     }
 }
@@ -396,19 +564,19 @@ pub fn process_decode(client: &RpcClient, commands: DecodeSubcommands) -> Result
         DecodeSubcommands::CollectionDelegate { authority_record } => {
             let record = decode_collection_authority_record(client, authority_record)?;
 
-            println!("{record:?}");
+            say!("{record:?}");
         }
         DecodeSubcommands::UseDelegate { use_record } => {
             let record = decode_use_authority_record(client, use_record)?;
 
-            println!("{record:?}");
+            say!("{record:?}");
         }
         DecodeSubcommands::MetadataDelegate {
             metadata_delegate_record,
         } => {
             let record = decode_metadata_delegate(client, metadata_delegate_record)?;
 
-            println!("{record:?}");
+            say!("{record:?}");
         }
         DecodeSubcommands::TokenRecord { token_record, mint } => {
             if !is_only_one_option(&token_record, &mint) {
@@ -418,11 +586,11 @@ pub fn process_decode(client: &RpcClient, commands: DecodeSubcommands) -> Result
             if let Some(token_record) = token_record {
                 let record = decode_token_record(client, token_record)?;
 
-                println!("{record:?}");
+                say!("{record:?}");
             } else if let Some(mint) = mint {
                 let records = decode_token_record_from_mint(client, mint)?;
 
-                println!("{records:?}");
+                say!("{records:?}");
             }
         }
         DecodeSubcommands::Mint {
@@ -462,7 +630,7 @@ pub fn process_decode(client: &RpcClient, commands: DecodeSubcommands) -> Result
                 .collect();
 
             let array: [u8; 32] = key.try_into().map_err(|_| anyhow!("Invalid pubkey"))?;
-            println!("{:?}", Pubkey::new_from_array(array));
+            say!("{:?}", Pubkey::new_from_array(array));
         }
         DecodeSubcommands::Account {
             account,
@@ -473,7 +641,7 @@ pub fn process_decode(client: &RpcClient, commands: DecodeSubcommands) -> Result
             let data = account.data;
             let start = start.unwrap_or(0);
             let end = end.unwrap_or(data.len());
-            println!("{:?}", &data[start..end]);
+            say!("{:?}", &data[start..end]);
         }
     }
     Ok(())
@@ -531,7 +699,7 @@ pub fn process_mint(client: RpcClient, commands: MintSubcommands) -> Result<()>
             amount,
             &receiver,
             token_22,
-            priority,
+            priority.apply(),
         ),
         MintSubcommands::Asset {
             keypair,
@@ -551,7 +719,7 @@ pub fn process_mint(client: RpcClient, commands: MintSubcommands) -> Result<()>
             decimals,
             amount,
             max_print_edition_supply,
-            priority,
+            priority: priority.fixed_level("mint asset")?,
         }),
         MintSubcommands::One {
             keypair,
@@ -577,9 +745,9 @@ pub fn process_mint(client: RpcClient, commands: MintSubcommands) -> Result<()>
             mint_path,
             sign,
             sized,
-            &priority,
+            &priority.apply(),
         )
-        .map(|_| ()),
+        .map(|mint| record_address("mint", mint)),
         MintSubcommands::Editions {
             keypair,
             account,
@@ -594,13 +762,13 @@ pub fn process_mint(client: RpcClient, commands: MintSubcommands) -> Result<()>
             &receiver,
             next_editions,
             specific_editions,
-            priority,
+            priority.apply(),
         ),
         MintSubcommands::MissingEditions {
             keypair,
             account,
             priority,
-        } => mint_missing_editions(&client, &keypair, &account, priority),
+        } => mint_missing_editions(&client, &keypair, &account, priority.apply()),
         MintSubcommands::List {
             keypair,
             receiver,
@@ -621,7 +789,7 @@ pub fn process_mint(client: RpcClient, commands: MintSubcommands) -> Result<()>
             primary_sale_happened,
             sign,
             track,
-            priority,
+            priority.apply(),
         ),
     }
 }
@@ -639,13 +807,15 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
             let args = SetPrimarySaleHappenedArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
-                priority,
+                priority: priority.apply(),
             };
 
             let sig = set_primary_sale_happened(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -656,6 +826,7 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
             rate_limit,
             retries,
             priority,
+            lookup_table,
         } => {
             set_primary_sale_happened_all(SetPrimarySaleHappenedAllArgs {
                 client,
@@ -664,10 +835,12 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
+                lookup_table,
             })
             .await
         }
+        #[allow(deprecated)]
         SetSubcommands::UpdateAuthority {
             keypair,
             account,
@@ -675,29 +848,50 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
             keypair_payer,
             priority,
         } => {
-            let solana_opts = parse_solana_config();
-            let keypair = parse_keypair(keypair, solana_opts);
-            let solana_opts = parse_solana_config();
-            let payer = keypair_payer.map(|path| parse_keypair(Some(path), solana_opts));
-
-            let args = SetUpdateAuthorityArgs {
-                client: Arc::new(client),
-                keypair: Arc::new(keypair),
-                payer: Arc::new(payer),
-                mint_account: account,
-                new_authority: new_update_authority,
-                priority,
-            };
+            use_deprecated_payer("--keypair-payer", keypair_payer)?;
+            let payer = parse_payer()?;
+
+            if let Some(multisig) = multisig_settings() {
+                let args = SetUpdateAuthorityArgs {
+                    client: Arc::new(client),
+                    keypair: Arc::new(parse_authority_keypair(keypair, None)),
+                    payer: Arc::new(payer),
+                    mint_account: account,
+                    new_authority: new_update_authority,
+                    priority: priority.apply(),
+                    journal: None,
+                };
+                let action = set_update_authority_ix(&args, multisig.vault, multisig.vault).await?;
+                return propose(
+                    "set-update-authority",
+                    &args.mint_account,
+                    &action.instructions,
+                    &multisig,
+                );
+            }
 
-            let sig = set_update_authority(args).await?;
+            // The authority may be a hardware wallet.
+            let solana_opts = parse_solana_config();
+            let authority = parse_signer(keypair, solana_opts)?;
+
+            let sig = set_update_authority_with_signer(
+                &client,
+                authority.as_ref(),
+                payer.as_ref(),
+                &account,
+                &new_update_authority,
+                &priority.apply(),
+            )?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
+        #[allow(deprecated)]
         SetSubcommands::UpdateAuthorityAll {
             keypair,
-            payer,
+            payer_short,
             mint_list,
             new_authority,
             cache_file,
@@ -705,16 +899,16 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
             retries,
             priority,
         } => {
+            use_deprecated_payer("-p", payer_short)?;
             set_update_authority_all(SetUpdateAuthorityAllArgs {
                 client,
                 keypair,
-                payer,
                 mint_list,
                 new_authority,
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -729,13 +923,15 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
             let args = SetImmutableArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
-                priority,
+                priority: priority.apply(),
             };
 
             let sig = set_immutable(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -754,7 +950,7 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -769,13 +965,15 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
             let args = SetTokenStandardArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
-                priority,
+                priority: priority.apply(),
             };
 
             let sig = set_token_standard_one(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -794,7 +992,7 @@ pub async fn process_set(client: RpcClient, commands: SetSubcommands) -> Result<
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -807,7 +1005,7 @@ pub fn process_sign(client: &RpcClient, commands: SignSubcommands) -> Result<()>
             keypair,
             account,
             priority,
-        } => sign_one(client, keypair, account, priority),
+        } => sign_one(client, keypair, account, priority.apply()),
         SignSubcommands::All {
             keypair,
             creator,
@@ -824,11 +1022,36 @@ pub fn process_sign(client: &RpcClient, commands: SignSubcommands) -> Result<()>
             v2,
             v3,
             mint_accounts_file,
-            priority,
+            priority.apply(),
         ),
     }
 }
 
+pub fn process_lookup_table(client: &RpcClient, commands: LookupTableSubcommands) -> Result<()> {
+    match commands {
+        LookupTableSubcommands::Create {
+            keypair,
+            addresses,
+            addresses_file,
+        } => process_create_lookup_table(client, keypair, addresses, addresses_file),
+        LookupTableSubcommands::Extend {
+            keypair,
+            lookup_table,
+            addresses,
+            addresses_file,
+        } => process_extend_lookup_table(client, keypair, lookup_table, addresses, addresses_file),
+        LookupTableSubcommands::Deactivate {
+            keypair,
+            lookup_table,
+        } => process_deactivate_lookup_table(client, keypair, lookup_table),
+        LookupTableSubcommands::Close {
+            keypair,
+            lookup_table,
+            recipient,
+        } => process_close_lookup_table(client, keypair, lookup_table, recipient),
+    }
+}
+
 pub fn process_transfer(client: RpcClient, commands: TransferSubcommands) -> Result<()> {
     match commands {
         TransferSubcommands::Asset {
@@ -838,6 +1061,7 @@ pub fn process_transfer(client: RpcClient, commands: TransferSubcommands) -> Res
             amount,
             receiver_account,
             priority,
+            lookup_table,
         } => process_transfer_asset(
             &client,
             keypair,
@@ -845,7 +1069,8 @@ pub fn process_transfer(client: RpcClient, commands: TransferSubcommands) -> Res
             receiver_account,
             mint,
             amount,
-            priority,
+            priority.apply(),
+            lookup_table,
         ),
     }
 }
@@ -864,14 +1089,16 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = UpdateRuleSetArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: mint,
                 new_rule_set,
-                priority,
+                priority: priority.apply(),
             };
 
             let sig = update_rule_set(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -892,7 +1119,7 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 new_rule_set,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -907,14 +1134,16 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = ClearRuleSetArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: mint,
-                priority,
+                priority: priority.apply(),
             };
 
             let sig = clear_rule_set(args).await?;
 
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -933,7 +1162,7 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -949,14 +1178,17 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = UpdateSellerFeeBasisPointsArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
                 new_sfbp,
-                priority,
+                priority: priority.apply(),
+                journal: None,
             };
 
             let sig = update_sfbp(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -977,7 +1209,7 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 new_sfbp,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -993,14 +1225,16 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = UpdateNameArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
                 new_name,
-                priority,
+                priority: priority.apply(),
             };
 
             let sig = update_name(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1016,14 +1250,17 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = UpdateSymbolArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
                 new_symbol,
-                priority,
+                priority: priority.apply(),
+                journal: None,
             };
 
             let sig = update_symbol(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1035,6 +1272,7 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             rate_limit,
             retries,
             priority,
+            lookup_table,
         } => {
             update_symbol_all(UpdateSymbolAllArgs {
                 client,
@@ -1044,7 +1282,8 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 new_symbol,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
+                lookup_table,
             })
             .await
         }
@@ -1061,15 +1300,18 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = UpdateCreatorArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
                 new_creators,
                 should_append: append,
-                priority,
+                priority: priority.apply(),
+                journal: None,
             };
 
             let sig = update_creator(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1092,7 +1334,7 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 should_append: append,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -1110,14 +1352,17 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = UpdateDataArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
                 new_data,
-                priority,
+                priority: priority.apply(),
+                journal: None,
             };
 
             let sig = update_data(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1136,7 +1381,7 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 new_data_dir: data_dir,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -1152,14 +1397,17 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
             let args = UpdateUriArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint_account: account,
                 new_uri,
-                priority,
+                priority: priority.apply(),
+                journal: None,
             };
 
             let sig = update_uri(args).await?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1178,7 +1426,7 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
             })
             .await
         }
@@ -1199,11 +1447,12 @@ pub async fn process_update(client: RpcClient, commands: UpdateSubcommands) -> R
                 remaining,
                 total,
                 overwrite,
-                priority,
+                priority: priority.apply(),
             };
             let sig = update_uses_one(args)?;
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1223,13 +1472,15 @@ pub async fn process_verify(client: RpcClient, commands: VerifySubcommands) -> R
             let args = VerifyCreatorArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint,
-                priority,
+                priority: priority.apply(),
             };
             let sig = verify_creator(args).await?;
 
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1240,6 +1491,7 @@ pub async fn process_verify(client: RpcClient, commands: VerifySubcommands) -> R
             rate_limit,
             retries,
             priority,
+            lookup_table,
         } => {
             verify_creator_all(VerifyCreatorAllArgs {
                 client,
@@ -1248,7 +1500,8 @@ pub async fn process_verify(client: RpcClient, commands: VerifySubcommands) -> R
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
+                lookup_table,
             })
             .await
         }
@@ -1268,13 +1521,15 @@ pub async fn process_unverify(client: RpcClient, commands: UnverifySubcommands)
             let args = UnverifyCreatorArgs {
                 client: Arc::new(client),
                 keypair: Arc::new(keypair),
+                payer: Arc::new(parse_payer()?),
                 mint,
-                priority,
+                priority: priority.apply(),
             };
             let sig = unverify_creator(args).await?;
 
             info!("Tx sig: {:?}", sig);
-            println!("Tx sig: {sig:?}");
+            say!("Tx sig: {sig:?}");
+            record_signature(sig);
 
             Ok(())
         }
@@ -1285,6 +1540,7 @@ pub async fn process_unverify(client: RpcClient, commands: UnverifySubcommands)
             rate_limit,
             retries,
             priority,
+            lookup_table,
         } => {
             unverify_creator_all(UnverifyCreatorAllArgs {
                 client,
@@ -1293,7 +1549,33 @@ pub async fn process_unverify(client: RpcClient, commands: UnverifySubcommands)
                 cache_file,
                 rate_limit,
                 retries,
-                priority,
+                priority: priority.apply(),
+                lookup_table,
+            })
+            .await
+        }
+    }
+}
+
+pub async fn process_watch(
+    client: RpcClient,
+    rpc_url: String,
+    das_url: String,
+    commands: WatchSubcommands,
+) -> Result<()> {
+    match commands {
+        WatchSubcommands::Holders {
+            collection,
+            ws_url,
+            delay,
+        } => {
+            watch_holders(WatchHoldersArgs {
+                client,
+                rpc_url,
+                das_url,
+                collection,
+                ws_url,
+                delay,
             })
             .await
         }
@@ -1305,3 +1587,103 @@ pub fn process_parse_errors_file(commands: ParseErrorsSubCommands) -> Result<()>
         ParseErrorsSubCommands::File => parse_errors_file(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn command(args: &[&str]) -> Command {
+        Opt::from_iter(std::iter::once("metaboss").chain(args.iter().copied())).cmd
+    }
+
+    #[test]
+    fn only_proposal_commands_export_proposals() {
+        assert!(exports_proposals(&command(&[
+            "update",
+            "uri-all",
+            "-u",
+            "uris.json"
+        ])));
+        assert!(exports_proposals(&command(&[
+            "set",
+            "update-authority",
+            "-a",
+            "11111111111111111111111111111111",
+            "-n",
+            "11111111111111111111111111111111",
+        ])));
+        assert!(!exports_proposals(&command(&[
+            "update",
+            "uri",
+            "-a",
+            "11111111111111111111111111111111",
+            "-u",
+            "https://example.com",
+        ])));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_payer_options_still_parse() {
+        match command(&[
+            "set",
+            "update-authority",
+            "-a",
+            "11111111111111111111111111111111",
+            "-n",
+            "11111111111111111111111111111111",
+            "-p",
+            "payer.json",
+        ]) {
+            Command::Set {
+                set_subcommands: SetSubcommands::UpdateAuthority { keypair_payer, .. },
+            } => assert_eq!(keypair_payer.as_deref(), Some("payer.json")),
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        match command(&[
+            "set",
+            "update-authority-all",
+            "-n",
+            "11111111111111111111111111111111",
+            "-p",
+            "payer.json",
+        ]) {
+            Command::Set {
+                set_subcommands: SetSubcommands::UpdateAuthorityAll { payer_short, .. },
+            } => assert_eq!(payer_short.as_deref(), Some("payer.json")),
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        // The long form was always the global option.
+        let opt = Opt::from_iter([
+            "metaboss",
+            "set",
+            "update-authority-all",
+            "-n",
+            "11111111111111111111111111111111",
+            "--payer",
+            "payer.json",
+        ]);
+        assert_eq!(opt.payer.as_deref(), Some("payer.json"));
+    }
+
+    #[test]
+    fn only_packed_batch_commands_export_offline() {
+        assert!(exports_offline(&command(&[
+            "update",
+            "symbol-all",
+            "-L",
+            "mints.json",
+            "-n",
+            "NEW"
+        ])));
+        assert!(!exports_offline(&command(&[
+            "update",
+            "uri-all",
+            "-u",
+            "uris.json"
+        ])));
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::Instruction, message::Message, pubkey::Pubkey};
+
+use std::{fs::File, path::Path, str::FromStr};
+
+use crate::{
+    cache::{
+        format_action_error,
+        pack::{build_items, pack, PackItem, PackedRoundArgs},
+        Action,
+    },
+    constants::MULTISIG,
+};
+
+/// How proposal messages are encoded in the exported file.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProposalEncoding {
+    Base58,
+    Base64,
+}
+
+impl FromStr for ProposalEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base58" => Ok(ProposalEncoding::Base58),
+            "base64" => Ok(ProposalEncoding::Base64),
+            _ => Err(format!("Invalid proposal encoding: {s}")),
+        }
+    }
+}
+
+impl ProposalEncoding {
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            ProposalEncoding::Base58 => bs58::encode(bytes).into_string(),
+            ProposalEncoding::Base64 => base64::encode(bytes),
+        }
+    }
+
+    pub fn decode(&self, encoded: &str) -> Result<Vec<u8>> {
+        match self {
+            ProposalEncoding::Base58 => Ok(bs58::decode(encoded).into_vec()?),
+            ProposalEncoding::Base64 => Ok(base64::decode(encoded)?),
+        }
+    }
+}
+
+/// Multisig settings from `--multisig` and `--proposal-encoding`.
+#[derive(Clone, Debug)]
+pub struct MultisigSettings {
+    /// The multisig vault that holds the authority and executes the proposals.
+    pub vault: Pubkey,
+    pub encoding: ProposalEncoding,
+}
+
+/// The multisig settings, if commands are exporting proposals.
+pub fn multisig_settings() -> Option<MultisigSettings> {
+    MULTISIG.read().unwrap().clone()
+}
+
+/// Instruction batches exported for a multisig to propose and execute.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProposalBundle {
+    pub action: String,
+    pub multisig_vault: String,
+    pub encoding: ProposalEncoding,
+    pub proposals: Vec<Proposal>,
+}
+
+impl ProposalBundle {
+    pub fn new(action: &str, settings: &MultisigSettings) -> Self {
+        Self {
+            action: action.to_string(),
+            multisig_vault: settings.vault.to_string(),
+            encoding: settings.encoding,
+            proposals: Vec::new(),
+        }
+    }
+
+    pub fn file_name(action_name: &str) -> String {
+        format!("mb-proposal-{action_name}.json")
+    }
+
+    pub fn push(&mut self, mints: Vec<String>, instructions: &[Instruction]) -> Result<()> {
+        let vault = Pubkey::from_str(&self.multisig_vault)?;
+        // The vault is the only signer and pays for everything; the multisig
+        // program supplies the blockhash when it executes the proposal.
+        let message = Message::new(instructions, Some(&vault));
+        self.proposals.push(Proposal {
+            mints,
+            message: self.encoding.encode(&message.serialize()),
+        });
+        Ok(())
+    }
+
+    pub fn message(&self, index: usize) -> Result<Message> {
+        let proposal = self
+            .proposals
+            .get(index)
+            .ok_or_else(|| anyhow!("No proposal {index}"))?;
+        Ok(bincode::deserialize(
+            &self.encoding.decode(&proposal.message)?,
+        )?)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, self)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Proposal {
+    pub mints: Vec<String>,
+    /// Serialized legacy message with the vault as fee payer and only signer.
+    pub message: String,
+}
+
+/// Write a single-mint command's instructions as a proposal.
+pub fn propose(
+    action: &str,
+    mint: &str,
+    instructions: &[Instruction],
+    settings: &MultisigSettings,
+) -> Result<()> {
+    let mut bundle = ProposalBundle::new(action, settings);
+    bundle.push(vec![mint.to_string()], instructions)?;
+
+    let file_name = ProposalBundle::file_name(action);
+    bundle.write(&file_name)?;
+    say!("Wrote a proposal for {} to {file_name}.", settings.vault);
+
+    Ok(())
+}
+
+pub struct ExportProposalsArgs<'a> {
+    pub action: &'static str,
+    pub round: PackedRoundArgs<'a>,
+    pub settings: MultisigSettings,
+}
+
+/// Build a batch action's instructions with the multisig vault as authority and
+/// export them in batches that each fit in one transaction.
+pub async fn export_proposals<A: Action + ?Sized>(args: ExportProposalsArgs<'_>) -> Result<()> {
+    let round = args.round;
+    let (items, failed) = build_items::<A>(&round).await?;
+
+    for (mint_address, result) in &failed {
+        if let Err(e) = result {
+            say!("{mint_address}: {}", format_action_error(e));
+        }
+    }
+
+    // Mints that are already up to date have nothing to propose.
+    let items: Vec<PackItem> = items
+        .into_iter()
+        .filter(|item| !item.action.instructions.is_empty())
+        .collect();
+    if items.is_empty() {
+        bail!("Nothing to propose.");
+    }
+
+    let groups = pack(
+        items,
+        |item: &PackItem| item.action.instructions.as_slice(),
+        &args.settings.vault,
+        &[],
+    );
+
+    let mut bundle = ProposalBundle::new(args.action, &args.settings);
+    for group in groups {
+        let instructions: Vec<Instruction> = group
+            .iter()
+            .flat_map(|item| item.action.instructions.iter().cloned())
+            .collect();
+        let mints = group.into_iter().map(|item| item.mint_address).collect();
+        bundle.push(mints, &instructions)?;
+    }
+
+    let file_name = ProposalBundle::file_name(args.action);
+    bundle.write(&file_name)?;
+
+    let mint_count: usize = bundle.proposals.iter().map(|p| p.mints.len()).sum();
+    say!(
+        "Wrote {} proposals for {mint_count} mints to {file_name} ({} mints failed).",
+        bundle.proposals.len(),
+        failed.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    fn settings(encoding: ProposalEncoding) -> MultisigSettings {
+        MultisigSettings {
+            vault: Pubkey::new_unique(),
+            encoding,
+        }
+    }
+
+    fn instruction(authority: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[7, 8, 9],
+            vec![
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new(Pubkey::new_unique(), false),
+            ],
+        )
+    }
+
+    #[test]
+    fn proposal_messages_decode_in_either_encoding() {
+        for encoding in [ProposalEncoding::Base58, ProposalEncoding::Base64] {
+            let settings = settings(encoding);
+            let ixs = vec![instruction(&settings.vault), instruction(&settings.vault)];
+
+            let mut bundle = ProposalBundle::new("update-uri-all", &settings);
+            bundle.push(vec!["mint".to_string()], &ixs).unwrap();
+
+            let message = bundle.message(0).unwrap();
+            assert_eq!(message, Message::new(&ixs, Some(&settings.vault)));
+        }
+    }
+
+    #[test]
+    fn vault_is_the_only_signer() {
+        let settings = settings(ProposalEncoding::Base58);
+        let mut bundle = ProposalBundle::new("burn-asset-all", &settings);
+        bundle
+            .push(vec!["mint".to_string()], &[instruction(&settings.vault)])
+            .unwrap();
+
+        let message = bundle.message(0).unwrap();
+        assert_eq!(message.header.num_required_signatures, 1);
+        assert_eq!(message.account_keys[0], settings.vault);
+    }
+
+    #[test]
+    fn encoding_parses_from_the_command_line() {
+        assert_eq!(
+            "base64".parse::<ProposalEncoding>().unwrap(),
+            ProposalEncoding::Base64
+        );
+        assert!("hex".parse::<ProposalEncoding>().is_err());
+    }
+}
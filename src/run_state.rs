@@ -0,0 +1,698 @@
+use anyhow::{anyhow, Result as AnyResult};
+use async_trait::async_trait;
+use chrono::Utc;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    rpc_client::RpcClient,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash, signature::Signature};
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    future::Future,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+use crate::{cache::Cache, dry_run::PREFLIGHT_FAILURE_CODE, utils::decode_wire_transaction};
+
+/// Maximum number of signatures accepted by a single `getSignatureStatuses` request.
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// How long to wait between status checks for transactions that may still land.
+const RECONCILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub type SharedRunState = Arc<Mutex<RunState>>;
+
+/// Where an item is in its lifecycle.
+///
+/// `Sent` means a transaction was submitted but its outcome was never observed,
+/// so the item must be checked on-chain before it is sent again.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Pending,
+    Sent,
+    Confirmed,
+    Failed,
+}
+
+/// One line of the run-state journal.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RunStateEntry {
+    pub item: String,
+    pub status: ItemStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Command-specific payload needed to retry the item, e.g. an airdrop transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    pub timestamp: i64,
+}
+
+impl RunStateEntry {
+    pub fn new(item: impl Into<String>, status: ItemStatus) -> Self {
+        Self {
+            item: item.into(),
+            status,
+            signature: None,
+            error: None,
+            data: None,
+            timestamp: Utc::now().timestamp(),
+        }
+    }
+
+    pub fn signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+
+    pub fn error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    pub fn data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+/// Persistent state of a batch run, shared by every batch command.
+///
+/// State is kept as an append-only JSONL journal: every status change is
+/// written and flushed as its own line, and the latest line for an item wins
+/// when the file is read back. A crash can therefore lose at most the line
+/// being written, which is ignored on the next load.
+#[derive(Debug)]
+pub struct RunState {
+    path: PathBuf,
+    file: File,
+    items: IndexMap<String, RunStateEntry>,
+}
+
+impl RunState {
+    /// Start a new run, discarding any state already at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> AnyResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path)?;
+
+        Ok(Self {
+            path,
+            file,
+            items: IndexMap::new(),
+        })
+    }
+
+    /// Continue a previous run, creating the file if it doesn't exist.
+    ///
+    /// The journal is compacted to one line per item on open, which also
+    /// converts legacy JSON cache files to the JSONL format.
+    pub fn open<P: AsRef<Path>>(path: P) -> AnyResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if !path.exists() {
+            return Self::create(path);
+        }
+
+        let items = Self::load(&path)?;
+
+        // Write the compacted journal next to the original and swap it in, so a
+        // crash here never loses the existing state.
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut tmp = File::create(&tmp_path)?;
+        for entry in items.values() {
+            writeln!(tmp, "{}", serde_json::to_string(entry)?)?;
+        }
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+
+        let file = OpenOptions::new().append(true).open(&path)?;
+
+        Ok(Self { path, file, items })
+    }
+
+    /// Read the latest entry for every item at `path` without modifying the file.
+    ///
+    /// Legacy JSON cache files (a map of mint address to error) are read with
+    /// every item marked as failed.
+    pub fn load<P: AsRef<Path>>(path: P) -> AnyResult<IndexMap<String, RunStateEntry>> {
+        let contents = fs::read_to_string(path)?;
+
+        if let Ok(legacy) = serde_json::from_str::<Cache>(&contents) {
+            return Ok(legacy
+                .0
+                .into_iter()
+                .map(|(item, cache_item)| {
+                    let mut entry = RunStateEntry::new(item.clone(), ItemStatus::Failed);
+                    entry.error = cache_item.error;
+                    (item, entry)
+                })
+                .collect());
+        }
+
+        Self::read_entries(BufReader::new(contents.as_bytes()))
+    }
+
+    fn read_entries<R: BufRead>(reader: R) -> AnyResult<IndexMap<String, RunStateEntry>> {
+        let mut items = IndexMap::new();
+        let lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
+        let last = lines.len().saturating_sub(1);
+
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RunStateEntry>(line) {
+                Ok(entry) => {
+                    items.insert(entry.item.clone(), entry);
+                }
+                // A partially written final line means the process died mid-write.
+                Err(_) if i == last => break,
+                Err(e) => return Err(anyhow!("Invalid run state on line {}: {e}", i + 1)),
+            }
+        }
+
+        Ok(items)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a status change and flush it to disk.
+    pub fn record(&mut self, entry: RunStateEntry) -> AnyResult<()> {
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+
+        self.items.insert(entry.item.clone(), entry);
+        Ok(())
+    }
+
+    pub fn get(&self, item: &str) -> Option<&RunStateEntry> {
+        self.items.get(item)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &RunStateEntry> {
+        self.items.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn with_status(&self, status: ItemStatus) -> Vec<&RunStateEntry> {
+        self.entries().filter(|e| e.status == status).collect()
+    }
+
+    /// Items that still need work: everything not yet confirmed.
+    pub fn unfinished(&self) -> Vec<String> {
+        self.entries()
+            .filter(|e| e.status != ItemStatus::Confirmed)
+            .map(|e| e.item.clone())
+            .collect()
+    }
+}
+
+thread_local! {
+    /// The items, and the run state they belong to, whose future is being polled on this thread.
+    static CURRENT_ITEMS: RefCell<Option<(Vec<String>, SharedRunState)>> = const { RefCell::new(None) };
+}
+
+/// Future wrapper that attributes every transaction sent while it is polled to its items.
+///
+/// Actions send transactions with the blocking `RpcClient`, which runs the
+/// request on the polling thread, so a thread-local is enough to let the
+/// `SignatureRecorder` find the items a transaction belongs to.
+pub struct TrackItem<F> {
+    items: Vec<String>,
+    run_state: SharedRunState,
+    fut: Pin<Box<F>>,
+}
+
+impl<F: Future> TrackItem<F> {
+    pub fn new(item: impl Into<String>, run_state: SharedRunState, fut: F) -> Self {
+        Self::batch(vec![item.into()], run_state, fut)
+    }
+
+    /// Track a future that sends one transaction covering several items.
+    pub fn batch(items: Vec<String>, run_state: SharedRunState, fut: F) -> Self {
+        Self {
+            items,
+            run_state,
+            fut: Box::pin(fut),
+        }
+    }
+}
+
+impl<F: Future> Future for TrackItem<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let previous = CURRENT_ITEMS
+            .with(|current| current.replace(Some((this.items.clone(), this.run_state.clone()))));
+        let poll = this.fut.as_mut().poll(cx);
+        CURRENT_ITEMS.with(|current| *current.borrow_mut() = previous);
+        poll
+    }
+}
+
+/// An `RpcSender` that journals each transaction's signature before sending it.
+///
+/// When a `sendTransaction` request is made from inside a `TrackItem` future,
+/// the item is recorded as `Sent` with the transaction's signature and
+/// blockhash before the request goes out. A run killed at any point therefore
+/// knows every signature that may have landed, and `reconcile_sent` can check
+/// them instead of sending the item again.
+pub struct SignatureRecorder {
+    inner: Box<dyn RpcSender + Send + Sync>,
+}
+
+impl SignatureRecorder {
+    pub fn new<T: RpcSender + Send + Sync + 'static>(inner: T) -> Self {
+        Self {
+            inner: Box::new(inner),
+        }
+    }
+
+    fn record_sent(params: &Value) -> ClientResult<Option<(Vec<String>, SharedRunState)>> {
+        let Some((items, run_state)) = CURRENT_ITEMS.with(|current| current.borrow().clone())
+        else {
+            return Ok(None);
+        };
+
+        let encoded = params[0]
+            .as_str()
+            .ok_or_else(|| RpcError::ParseError("encoded transaction".to_string()))?;
+        let encoding = params[1]["encoding"].as_str().unwrap_or("base64");
+        let tx = decode_wire_transaction(encoded, encoding)?;
+        let signature = tx.signatures.first().copied().unwrap_or_default();
+        let blockhash = tx.message.recent_blockhash().to_string();
+
+        // Never send a transaction that could not be journaled.
+        let mut state = run_state.lock().unwrap();
+        for item in &items {
+            let entry = RunStateEntry::new(item, ItemStatus::Sent)
+                .signature(signature.to_string())
+                .data(json!({ "blockhash": blockhash }));
+            state
+                .record(entry)
+                .map_err(|e| RpcError::ForUser(format!("Failed to write run state: {e}")))?;
+        }
+        drop(state);
+
+        Ok(Some((items, run_state)))
+    }
+}
+
+#[async_trait]
+impl RpcSender for SignatureRecorder {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        if request != RpcRequest::SendTransaction {
+            return self.inner.send(request, params).await;
+        }
+
+        let tracked = Self::record_sent(&params)?;
+        let result = self.inner.send(request, params).await;
+
+        // A failed preflight check means the node never forwarded the transaction.
+        if let (Err(err), Some((items, run_state))) = (&result, tracked) {
+            if let ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) = err.kind() {
+                if *code == PREFLIGHT_FAILURE_CODE {
+                    let mut state = run_state.lock().unwrap();
+                    for item in items {
+                        state
+                            .record(
+                                RunStateEntry::new(item, ItemStatus::Failed).error(err.to_string()),
+                            )
+                            .map_err(|e| {
+                                RpcError::ForUser(format!("Failed to write run state: {e}"))
+                            })?;
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Resolve every `Sent` item by checking its signature on-chain.
+///
+/// Landed transactions are marked `Confirmed` or `Failed`. A transaction that
+/// hasn't landed is waited on until its blockhash expires, after which it can
+/// never land and the item is marked `Failed` so it is sent again. Returns the
+/// number of items found confirmed.
+pub fn reconcile_sent(client: &RpcClient, state: &mut RunState) -> AnyResult<usize> {
+    let mut confirmed = 0;
+
+    loop {
+        let sent: Vec<RunStateEntry> = state
+            .with_status(ItemStatus::Sent)
+            .into_iter()
+            .cloned()
+            .collect();
+        let mut in_flight = 0;
+        let mut blockhash_valid: HashMap<Hash, bool> = HashMap::new();
+
+        for chunk in sent.chunks(MAX_SIGNATURE_STATUSES) {
+            let mut tracked = Vec::new();
+            for entry in chunk {
+                match entry
+                    .signature
+                    .as_deref()
+                    .and_then(|s| Signature::from_str(s).ok())
+                {
+                    Some(signature) => tracked.push((entry, signature)),
+                    // Without a signature there is nothing to check, so the item was never sent.
+                    None => state.record(RunStateEntry::new(&entry.item, ItemStatus::Pending))?,
+                }
+            }
+
+            let signatures: Vec<Signature> = tracked.iter().map(|(_, sig)| *sig).collect();
+            let statuses = client
+                .get_signature_statuses_with_history(&signatures)?
+                .value;
+
+            for ((entry, signature), status) in tracked.into_iter().zip(statuses) {
+                let resolved = match &status {
+                    Some(status) if status.err.is_some() => Some(
+                        RunStateEntry::new(&entry.item, ItemStatus::Failed)
+                            .signature(signature.to_string())
+                            .error(status.err.as_ref().unwrap().to_string()),
+                    ),
+                    Some(status) if status.satisfies_commitment(client.commitment()) => {
+                        confirmed += 1;
+                        Some(
+                            RunStateEntry::new(&entry.item, ItemStatus::Confirmed)
+                                .signature(signature.to_string()),
+                        )
+                    }
+                    _ => {
+                        // Not landed yet: it still can for as long as its blockhash is valid.
+                        let blockhash = entry
+                            .data
+                            .as_ref()
+                            .and_then(|data| data["blockhash"].as_str())
+                            .and_then(|hash| Hash::from_str(hash).ok());
+                        let valid = match blockhash {
+                            Some(hash) => match blockhash_valid.get(&hash) {
+                                Some(valid) => *valid,
+                                None => {
+                                    let valid = client
+                                        .is_blockhash_valid(&hash, CommitmentConfig::processed())?;
+                                    blockhash_valid.insert(hash, valid);
+                                    valid
+                                }
+                            },
+                            None => false,
+                        };
+
+                        if valid || status.is_some() {
+                            in_flight += 1;
+                            None
+                        } else {
+                            Some(
+                                RunStateEntry::new(&entry.item, ItemStatus::Failed)
+                                    .signature(signature.to_string())
+                                    .error("transaction expired without landing"),
+                            )
+                        }
+                    }
+                };
+
+                if let Some(resolved) = resolved {
+                    state.record(resolved)?;
+                }
+            }
+        }
+
+        if in_flight == 0 {
+            return Ok(confirmed);
+        }
+
+        say!("Waiting for {in_flight} in-flight transactions to land or expire. . .");
+        thread::sleep(RECONCILE_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheItem;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "mb-run-state-{}-{}-{name}",
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ))
+    }
+
+    #[test]
+    fn record_and_reopen_keeps_latest_status() {
+        let path = temp_path("reopen.jsonl");
+        let mut state = RunState::create(&path).unwrap();
+        state
+            .record(RunStateEntry::new("mintA", ItemStatus::Pending))
+            .unwrap();
+        state
+            .record(RunStateEntry::new("mintB", ItemStatus::Pending))
+            .unwrap();
+        state
+            .record(RunStateEntry::new("mintA", ItemStatus::Confirmed).signature("sigA"))
+            .unwrap();
+        drop(state);
+
+        let state = RunState::open(&path).unwrap();
+
+        assert_eq!(state.len(), 2);
+        assert_eq!(state.get("mintA").unwrap().status, ItemStatus::Confirmed);
+        assert_eq!(
+            state.get("mintA").unwrap().signature.as_deref(),
+            Some("sigA")
+        );
+        assert_eq!(state.unfinished(), vec!["mintB".to_string()]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn open_ignores_truncated_last_line() {
+        let path = temp_path("truncated.jsonl");
+        let mut state = RunState::create(&path).unwrap();
+        state
+            .record(RunStateEntry::new("mintA", ItemStatus::Failed).error("boom"))
+            .unwrap();
+        drop(state);
+        let mut f = OpenOptions::new().append(true).open(&path).unwrap();
+        f.write_all(br#"{"item":"mintB","stat"#).unwrap();
+
+        let state = RunState::open(&path).unwrap();
+
+        assert_eq!(state.len(), 1);
+        assert_eq!(state.get("mintA").unwrap().error.as_deref(), Some("boom"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn open_converts_legacy_cache() {
+        let path = temp_path("legacy.json");
+        let mut cache = Cache::new();
+        cache.insert(
+            "mintA".to_string(),
+            CacheItem {
+                error: Some("old error".to_string()),
+            },
+        );
+        cache.write(File::create(&path).unwrap()).unwrap();
+
+        let mut state = RunState::open(&path).unwrap();
+        state
+            .record(RunStateEntry::new("mintA", ItemStatus::Confirmed))
+            .unwrap();
+        drop(state);
+        let state = RunState::open(&path).unwrap();
+
+        assert_eq!(state.get("mintA").unwrap().status, ItemStatus::Confirmed);
+        assert!(state.unfinished().is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn create_discards_previous_state() {
+        let path = temp_path("create.jsonl");
+        let mut state = RunState::create(&path).unwrap();
+        state
+            .record(RunStateEntry::new("mintA", ItemStatus::Failed))
+            .unwrap();
+        drop(state);
+
+        let state = RunState::create(&path).unwrap();
+
+        assert!(state.is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn with_status_filters_entries() {
+        let path = temp_path("status.jsonl");
+        let mut state = RunState::create(&path).unwrap();
+        state
+            .record(RunStateEntry::new("mintA", ItemStatus::Sent).signature("sigA"))
+            .unwrap();
+        state
+            .record(RunStateEntry::new("mintB", ItemStatus::Failed))
+            .unwrap();
+
+        let sent = state.with_status(ItemStatus::Sent);
+
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].item, "mintA");
+        fs::remove_file(path).unwrap();
+    }
+
+    fn status_response(statuses: Value) -> Value {
+        json!({ "context": { "slot": 1 }, "value": statuses })
+    }
+
+    fn mock_client(mocks: solana_rpc_client::mock_sender::Mocks) -> RpcClient {
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks)
+    }
+
+    #[test]
+    fn reconcile_resolves_landed_and_expired_transactions() {
+        let path = temp_path("reconcile.jsonl");
+        let mut state = RunState::create(&path).unwrap();
+        let blockhash = json!({ "blockhash": Hash::new_unique().to_string() });
+        for item in ["landed", "failed", "expired"] {
+            state
+                .record(
+                    RunStateEntry::new(item, ItemStatus::Sent)
+                        .signature(Signature::new_unique().to_string())
+                        .data(blockhash.clone()),
+                )
+                .unwrap();
+        }
+        state
+            .record(RunStateEntry::new("unsent", ItemStatus::Sent))
+            .unwrap();
+
+        let mut mocks = solana_rpc_client::mock_sender::Mocks::default();
+        mocks.insert(
+            RpcRequest::GetSignatureStatuses,
+            status_response(json!([
+                {
+                    "slot": 1,
+                    "confirmations": null,
+                    "err": null,
+                    "status": { "Ok": null },
+                    "confirmationStatus": "finalized",
+                },
+                {
+                    "slot": 1,
+                    "confirmations": null,
+                    "err": { "InstructionError": [0, { "Custom": 1 }] },
+                    "status": { "Err": { "InstructionError": [0, { "Custom": 1 }] } },
+                    "confirmationStatus": "finalized",
+                },
+                null,
+            ])),
+        );
+        mocks.insert(RpcRequest::IsBlockhashValid, status_response(json!(false)));
+        let client = mock_client(mocks);
+
+        let confirmed = reconcile_sent(&client, &mut state).unwrap();
+
+        assert_eq!(confirmed, 1);
+        assert_eq!(state.get("landed").unwrap().status, ItemStatus::Confirmed);
+        assert_eq!(state.get("failed").unwrap().status, ItemStatus::Failed);
+        assert_eq!(
+            state.get("expired").unwrap().error.as_deref(),
+            Some("transaction expired without landing")
+        );
+        assert_eq!(state.get("unsent").unwrap().status, ItemStatus::Pending);
+        assert!(state.with_status(ItemStatus::Sent).is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn signature_recorder_journals_sends_from_tracked_items() {
+        use solana_rpc_client::mock_sender::MockSender;
+        use solana_sdk::{
+            instruction::{AccountMeta, Instruction},
+            pubkey::Pubkey,
+            signature::Keypair,
+            signer::Signer,
+            transaction::Transaction,
+        };
+
+        let path = temp_path("recorder.jsonl");
+        let state = Arc::new(Mutex::new(RunState::create(&path).unwrap()));
+        let client = RpcClient::new_sender(
+            SignatureRecorder::new(MockSender::new("succeeds")),
+            Default::default(),
+        );
+        let payer = Keypair::new();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new(payer.pubkey(), true)],
+        );
+        let blockhash = Hash::new_unique();
+        let tx =
+            Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+
+        // Sends outside a tracked item are not journaled.
+        client.send_transaction(&tx).unwrap();
+        assert!(state.lock().unwrap().is_empty());
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        runtime.block_on(TrackItem::new("mintA", state.clone(), async {
+            client.send_transaction(&tx).unwrap();
+        }));
+
+        let state = state.lock().unwrap();
+        let entry = state.get("mintA").unwrap();
+        assert_eq!(entry.status, ItemStatus::Sent);
+        assert_eq!(entry.signature, Some(tx.signatures[0].to_string()));
+        assert_eq!(
+            entry.data.as_ref().unwrap()["blockhash"],
+            blockhash.to_string()
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
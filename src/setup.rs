@@ -2,15 +2,36 @@ use anyhow::{anyhow, Result};
 use log::{info, warn};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use solana_client::{nonblocking::rpc_client::RpcClient as AsyncRpcClient, rpc_client::RpcClient};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient as AsyncRpcClient,
+    rpc_client::{RpcClient, RpcClientConfig},
+};
+use solana_rpc_client::http_sender::HttpSender;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
     signature::{read_keypair_file, Keypair},
 };
 
 use std::{path::PathBuf, str::FromStr, time::Duration};
 
-use crate::constants::{PUBLIC_RPC_URLS, RATE_LIMIT_DELAYS, RPC_DELAY_NS, USE_RATE_LIMIT};
+use crate::{
+    config::Profile,
+    constants::{
+        ASSUME_YES, DRY_RUN, MULTISIG, OFFLINE, OUTPUT_FORMAT, PAYER, PRIORITY_FEE_BUDGET,
+        PUBLIC_RPC_URLS, RESUME, RPC_LIMITER, SPEND_GUARD,
+    },
+    cost::SpendLimitSender,
+    dry_run::DryRunSender,
+    failover::{parse_rpc_urls, FailoverSender},
+    job::RpcEndpoints,
+    landing::RebroadcastSender,
+    limiter::{configure_rpc_limiter, LimitedSender},
+    offline::OfflineSettings,
+    output::OutputFormat,
+    proposal::{MultisigSettings, ProposalEncoding},
+    run_state::SignatureRecorder,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
 pub enum ClientType {
@@ -81,9 +102,11 @@ impl CliConfigBuilder {
         };
 
         let client = match self.client_type {
-            ClientType::Standard => {
-                ClientLike::RpcClient(RpcClient::new_with_commitment(rpc_url.clone(), commitment))
-            }
+            ClientType::Standard => ClientLike::RpcClient(RpcClient::new_sender(
+                LimitedSender::new(HttpSender::new(rpc_url.clone()), RPC_LIMITER.clone()),
+                RpcClientConfig::with_commitment(commitment),
+            )),
+            // DAS requests are paced with `limiter::send_das_request`.
             ClientType::DAS => ClientLike::DasClient(Client::new()),
         };
 
@@ -142,44 +165,189 @@ pub struct AppConfig {
     pub client: RpcClient,
     pub async_client: AsyncRpcClient,
     pub rpc_url: String,
+    /// Where DAS API requests go; the primary RPC url unless set.
+    pub das_url: String,
 }
 
 /// Builder for constructing the main application configuration.
 ///
 /// Resolves RPC endpoint, commitment level, and timeout from CLI arguments,
-/// the Solana CLI config file, or built-in defaults. Automatically configures
-/// rate limiting for known public RPC endpoints.
+/// a Metaboss config profile, the Solana CLI config file, or built-in defaults. Configures the shared
+/// adaptive rate limiter for the chosen endpoint.
+#[derive(Clone)]
 pub struct AppConfigBuilder {
     rpc_url: Option<String>,
+    profile_rpc_url: Option<String>,
+    das_url: Option<String>,
+    commitment: Option<String>,
     timeout_secs: u64,
+    dry_run: bool,
+    resume: bool,
+    offline: Option<OfflineSettings>,
+    multisig: Option<MultisigSettings>,
+    payer: Option<String>,
+    max_priority_fee: Option<u64>,
+    max_priority_fee_total: Option<u64>,
+    max_spend: Option<u64>,
+    assume_yes: bool,
+    output: OutputFormat,
 }
 
 impl AppConfigBuilder {
     pub fn new() -> Self {
         Self {
             rpc_url: None,
+            profile_rpc_url: None,
+            das_url: None,
+            commitment: None,
             timeout_secs: DEFAULT_TIMEOUT_SECS,
+            dry_run: false,
+            resume: false,
+            offline: None,
+            multisig: None,
+            payer: None,
+            max_priority_fee: None,
+            max_priority_fee_total: None,
+            max_spend: None,
+            assume_yes: false,
+            output: OutputFormat::Text,
         }
     }
 
     /// Set the RPC endpoint URL, overriding the Solana config file value.
+    /// Several comma-separated URLs spread reads across the endpoints and fail
+    /// over between them; the first is the primary that transactions go to.
     pub fn rpc_url(mut self, rpc_url: String) -> Self {
         self.rpc_url = Some(rpc_url);
         self
     }
 
+    /// Set the RPC endpoint URL unless one was already set, so a URL given on
+    /// the command line takes precedence.
+    pub fn default_rpc_url(mut self, rpc_url: String) -> Self {
+        self.rpc_url.get_or_insert(rpc_url);
+        self
+    }
+
+    /// Use the settings of a config file profile. Call it before the other
+    /// setters so options given on the command line take precedence; its
+    /// RPC urls also give way to those of a job file.
+    pub fn profile(mut self, profile: &Profile) -> Self {
+        self.profile_rpc_url = profile.rpc.as_ref().map(RpcEndpoints::to_rpc_arg);
+        self.das_url = profile.das_url.clone();
+        self.commitment = profile.commitment.clone();
+        if let Some(timeout) = profile.timeout {
+            self.timeout_secs = timeout;
+        }
+        self
+    }
+
+    /// Send DAS API requests to `das_url` instead of the primary RPC url.
+    pub fn das_url(mut self, das_url: String) -> Self {
+        self.das_url = Some(das_url);
+        self
+    }
+
     /// Set the RPC client timeout in seconds. Defaults to 90 seconds.
     pub fn timeout(mut self, timeout_secs: u64) -> Self {
         self.timeout_secs = timeout_secs;
         self
     }
 
+    /// Simulate transactions instead of sending them. Both RPC clients are
+    /// built on a `DryRunSender` so nothing is ever broadcast.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Continue batch runs from their cache file, checking transactions that
+    /// were sent but never confirmed before sending anything again.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Export batch transactions for an offline `authority` to sign instead of
+    /// sending them, using the durable nonce accounts listed in `nonce_accounts`.
+    pub fn offline(mut self, authority: Option<Pubkey>, nonce_accounts: PathBuf) -> Self {
+        self.offline = authority.map(|authority| OfflineSettings {
+            authority,
+            nonce_accounts,
+        });
+        self
+    }
+
+    /// Export the instructions of authority-gated commands as proposals for the
+    /// multisig `vault` instead of signing them with a local keypair.
+    pub fn multisig(mut self, vault: Option<Pubkey>, encoding: ProposalEncoding) -> Self {
+        self.multisig = vault.map(|vault| MultisigSettings { vault, encoding });
+        self
+    }
+
+    /// Pay transaction fees and rent with the keypair at `payer` instead of
+    /// the authority keypair of each command.
+    pub fn payer(mut self, payer: Option<String>) -> Self {
+        self.payer = payer;
+        self
+    }
+
+    /// Cap the priority fees, in lamports, of each transaction and of the
+    /// whole run. Prices are lowered to fit; nothing is capped by default.
+    pub fn priority_fee_caps(mut self, per_tx: Option<u64>, per_run: Option<u64>) -> Self {
+        self.max_priority_fee = per_tx;
+        self.max_priority_fee_total = per_run;
+        self
+    }
+
+    /// Cap the lamports a run spends on transaction fees and rent. Sending
+    /// stops once the next transaction would go over.
+    pub fn max_spend(mut self, max_spend: Option<u64>) -> Self {
+        self.max_spend = max_spend;
+        self
+    }
+
+    /// Start batch runs without prompting to confirm their estimated cost.
+    pub fn assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    /// Write results as JSON objects, one per line, instead of text.
+    pub fn output(mut self, output: OutputFormat) -> Self {
+        self.output = output;
+        self
+    }
+
     /// Build the `AppConfig`, resolving values from the Solana CLI config
-    /// file as needed and configuring rate limiting for public RPC endpoints.
+    /// file as needed. Both clients send through the shared adaptive limiter,
+    /// which starts slower for public RPC endpoints.
     pub fn build(self) -> Result<AppConfig> {
+        // Set first so a configuration error is reported in the chosen format too.
+        *OUTPUT_FORMAT.write().unwrap() = self.output;
+
+        if self.dry_run && self.offline.is_some() {
+            return Err(anyhow!("--dry-run and --offline can't be used together"));
+        }
+        if self.multisig.is_some() && (self.dry_run || self.offline.is_some()) {
+            return Err(anyhow!(
+                "--multisig can't be used with --dry-run or --offline"
+            ));
+        }
+        if self.multisig.is_some() && self.payer.is_some() {
+            return Err(anyhow!(
+                "--payer can't be used with --multisig; the vault pays for its proposals"
+            ));
+        }
+        if self.offline.is_some() && self.payer.is_some() {
+            return Err(anyhow!(
+                "--payer can't be used with --offline; the -k hot wallet pays the fees"
+            ));
+        }
+
         let sol_config = crate::parse::parse_solana_config();
 
-        let (rpc_url, commitment_str) = if let Some(cli_rpc) = self.rpc_url {
+        let (rpc, commitment_str) = if let Some(cli_rpc) = self.rpc_url.or(self.profile_rpc_url) {
             (cli_rpc, String::from("confirmed"))
         } else if let Some(config) = sol_config {
             (config.json_rpc_url, config.commitment)
@@ -191,29 +359,81 @@ impl AppConfigBuilder {
             (String::from(DEFAULT_RPC_URL), String::from("confirmed"))
         };
 
-        // Configure rate limiting for known public RPC endpoints.
+        // The first endpoint is the primary: transactions are sent to it and
+        // it names the cluster for commands that need a single URL.
+        let rpc_urls = parse_rpc_urls(&rpc);
+        let rpc_url = rpc_urls
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow!("No rpc url provided"))?;
+
         if PUBLIC_RPC_URLS.contains(&rpc_url.as_str()) {
             warn!(
                 "Using a public RPC URL is not recommended for heavy tasks as you will be rate-limited and suffer a performance hit"
             );
             warn!("Please use a private RPC endpoint for best performance results.");
-            *USE_RATE_LIMIT.write().unwrap() = true;
-        } else if RATE_LIMIT_DELAYS.contains_key(&rpc_url.as_str()) {
-            *USE_RATE_LIMIT.write().unwrap() = true;
-            *RPC_DELAY_NS.write().unwrap() = RATE_LIMIT_DELAYS[&rpc_url.as_str()];
         }
+        configure_rpc_limiter(&rpc_url);
 
+        let commitment_str = self.commitment.unwrap_or(commitment_str);
         let commitment = CommitmentConfig::from_str(&commitment_str)?;
         let timeout = Duration::from_secs(self.timeout_secs);
 
-        let client =
-            RpcClient::new_with_timeout_and_commitment(rpc_url.clone(), timeout, commitment);
-        let async_client =
-            AsyncRpcClient::new_with_timeout_and_commitment(rpc_url.clone(), timeout, commitment);
+        let (client, async_client) = if self.dry_run {
+            *DRY_RUN.write().unwrap() = true;
+            (
+                RpcClient::new_sender(
+                    DryRunSender::new(LimitedSender::new(
+                        FailoverSender::http(&rpc_urls, timeout),
+                        RPC_LIMITER.clone(),
+                    )),
+                    RpcClientConfig::with_commitment(commitment),
+                ),
+                AsyncRpcClient::new_sender(
+                    DryRunSender::new(LimitedSender::new(
+                        FailoverSender::http(&rpc_urls, timeout),
+                        RPC_LIMITER.clone(),
+                    )),
+                    RpcClientConfig::with_commitment(commitment),
+                ),
+            )
+        } else {
+            // Count the fees of every transaction against --max-spend before
+            // it is journaled, and rebroadcast it until it lands or its
+            // blockhash expires.
+            (
+                RpcClient::new_sender(
+                    SpendLimitSender::new(SignatureRecorder::new(RebroadcastSender::new(
+                        LimitedSender::new(
+                            FailoverSender::http(&rpc_urls, timeout),
+                            RPC_LIMITER.clone(),
+                        ),
+                    ))),
+                    RpcClientConfig::with_commitment(commitment),
+                ),
+                AsyncRpcClient::new_sender(
+                    SpendLimitSender::new(SignatureRecorder::new(RebroadcastSender::new(
+                        LimitedSender::new(
+                            FailoverSender::http(&rpc_urls, timeout),
+                            RPC_LIMITER.clone(),
+                        ),
+                    ))),
+                    RpcClientConfig::with_commitment(commitment),
+                ),
+            )
+        };
+        *RESUME.write().unwrap() = self.resume;
+        *OFFLINE.write().unwrap() = self.offline;
+        *MULTISIG.write().unwrap() = self.multisig;
+        *PAYER.write().unwrap() = self.payer;
+        PRIORITY_FEE_BUDGET.configure(self.max_priority_fee, self.max_priority_fee_total);
+        SPEND_GUARD.configure(self.max_spend);
+        *ASSUME_YES.write().unwrap() = self.assume_yes;
 
         Ok(AppConfig {
             client,
             async_client,
+            das_url: self.das_url.unwrap_or_else(|| rpc_url.clone()),
             rpc_url,
         })
     }
@@ -228,10 +448,6 @@ impl Default for AppConfigBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Mutex;
-
-    /// Guard to serialize tests that read/write the global USE_RATE_LIMIT state.
-    static RATE_LIMIT_TEST_MUTEX: Mutex<()> = Mutex::new(());
 
     // --- CliConfigBuilder tests ---
 
@@ -376,6 +592,8 @@ mod tests {
         let builder = AppConfigBuilder::new();
         assert!(builder.rpc_url.is_none());
         assert_eq!(builder.timeout_secs, DEFAULT_TIMEOUT_SECS);
+        assert!(!builder.dry_run);
+        assert!(!builder.resume);
     }
 
     #[test]
@@ -434,7 +652,6 @@ mod tests {
 
     #[test]
     fn app_config_builder_build_with_custom_timeout() {
-        // Use a private URL to avoid writing to the global USE_RATE_LIMIT state.
         let config = AppConfigBuilder::new()
             .rpc_url("https://my-private-rpc.example.com".to_string())
             .timeout(30)
@@ -444,35 +661,13 @@ mod tests {
     }
 
     #[test]
-    fn app_config_builder_public_rpc_enables_rate_limiting() {
-        let _lock = RATE_LIMIT_TEST_MUTEX
-            .lock()
-            .unwrap_or_else(|e| e.into_inner());
-        *USE_RATE_LIMIT.write().unwrap() = false;
-
-        let _config = AppConfigBuilder::new()
-            .rpc_url("https://api.devnet.solana.com".to_string())
-            .build()
-            .unwrap();
-
-        assert!(*USE_RATE_LIMIT.read().unwrap());
-
-        *USE_RATE_LIMIT.write().unwrap() = false;
-    }
-
-    #[test]
-    fn app_config_builder_private_rpc_does_not_enable_rate_limiting() {
-        let _lock = RATE_LIMIT_TEST_MUTEX
-            .lock()
-            .unwrap_or_else(|e| e.into_inner());
-        *USE_RATE_LIMIT.write().unwrap() = false;
-
-        let _config = AppConfigBuilder::new()
-            .rpc_url("https://my-private-rpc.example.com".to_string())
+    fn app_config_builder_first_of_several_rpc_urls_is_primary() {
+        let config = AppConfigBuilder::new()
+            .rpc_url("https://primary.example.com, https://backup.example.com".to_string())
             .build()
             .unwrap();
-
-        assert!(!*USE_RATE_LIMIT.read().unwrap());
+        assert_eq!(config.rpc_url, "https://primary.example.com");
+        assert_eq!(config.client.url(), "https://primary.example.com");
     }
 
     #[test]
@@ -485,6 +680,31 @@ mod tests {
         assert_eq!(config.rpc_url, "https://second.com");
     }
 
+    #[test]
+    fn app_config_builder_rejects_payer_with_multisig_or_offline() {
+        let err = AppConfigBuilder::new()
+            .rpc_url("https://example.com".to_string())
+            .payer(Some("treasury.json".to_string()))
+            .multisig(Some(Pubkey::new_unique()), ProposalEncoding::Base58)
+            .build()
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .contains("--payer can't be used with --multisig"));
+
+        let err = AppConfigBuilder::new()
+            .rpc_url("https://example.com".to_string())
+            .payer(Some("treasury.json".to_string()))
+            .offline(Some(Pubkey::new_unique()), PathBuf::from("nonces.json"))
+            .build()
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .contains("--payer can't be used with --offline"));
+    }
+
     #[test]
     fn default_timeout_is_90_seconds() {
         assert_eq!(DEFAULT_TIMEOUT_SECS, 90);
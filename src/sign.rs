@@ -5,14 +5,11 @@ use log::{error, info};
 use metaboss_lib::data::Priority;
 use mpl_token_metadata::{accounts::Metadata, instructions::SignMetadata};
 use rayon::prelude::*;
-use retry::{delay::Exponential, retry};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction,
     pubkey::Pubkey,
     signature::Signature,
     signer::{keypair::Keypair, Signer},
-    transaction::Transaction,
 };
 use std::{
     fs::File,
@@ -25,10 +22,13 @@ use std::{
 
 use crate::decode::get_metadata_pda;
 use crate::derive::{derive_cmv2_pda, derive_cmv3_pda};
-use crate::limiter::create_default_rate_limiter;
-use crate::parse::{is_only_one_option, parse_keypair};
+use crate::landing::land;
+use crate::parse::parse_solana_config;
+use crate::parse::{is_only_one_option, parse_keypair, parse_payer};
+use crate::priority::with_priority;
 use crate::snapshot::get_cm_creator_accounts;
-use crate::{constants::*, parse::parse_solana_config};
+use crate::spinner::new_progress_bar;
+use crate::utils::with_payer;
 
 pub fn sign_one(
     client: &RpcClient,
@@ -50,7 +50,7 @@ pub fn sign_one(
 
     let sig = sign(client, &creator, metadata_pubkey, priority)?;
     info!("Tx sig: {}", sig);
-    println!("Tx sig: {sig}");
+    say!("Tx sig: {sig}");
 
     Ok(())
 }
@@ -117,41 +117,18 @@ pub fn sign(
     metadata_pubkey: Pubkey,
     priority: Priority,
 ) -> Result<Signature> {
-    let micro_lamports = match priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
-
     let sign_ix = SignMetadata {
         metadata: metadata_pubkey,
         creator: creator.pubkey(),
     }
     .instruction();
 
-    let ixs = vec![
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-        sign_ix,
-    ];
-
-    let recent_blockhash = client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &ixs,
-        Some(&creator.pubkey()),
-        &[creator],
-        recent_blockhash,
-    );
+    let ixs = with_priority(client, &priority, &[sign_ix])?;
 
-    // Send tx with retries.
-    let res = retry(
-        Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || client.send_and_confirm_transaction(&tx),
-    );
-    let sig = res?;
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(creator);
 
-    Ok(sig)
+    land(client, &with_payer(payer, &[creator]), &ixs, &[])
 }
 
 pub fn sign_mint_accounts(
@@ -160,18 +137,10 @@ pub fn sign_mint_accounts(
     mint_accounts: Vec<String>,
     priority: Priority,
 ) -> Result<()> {
-    let use_rate_limit = *USE_RATE_LIMIT.read().unwrap();
-    let handle = create_default_rate_limiter();
-
     mint_accounts
         .par_iter()
-        .progress()
+        .progress_with(new_progress_bar(mint_accounts.len() as u64))
         .for_each(|mint_account| {
-            let mut handle = handle.clone();
-            if use_rate_limit {
-                handle.wait();
-            }
-
             let account_pubkey = match Pubkey::from_str(mint_account) {
                 Ok(pubkey) => pubkey,
                 Err(err) => {
@@ -206,7 +175,7 @@ pub fn sign_candy_machine_accounts(
 
     accounts
         .par_iter()
-        .progress()
+        .progress_with(new_progress_bar(accounts.len() as u64))
         .for_each(|(metadata_pubkey, account)| {
             let signed_at_least_one_account = signed_at_least_one_account.clone();
             let metadata: Metadata =
@@ -251,7 +220,7 @@ pub fn sign_candy_machine_accounts(
 
     if !signed_at_least_one_account.load(Ordering::Relaxed) {
         info!("No unverified metadata for this creator and candy machine.");
-        println!("No unverified metadata for this creator and candy machine.");
+        say!("No unverified metadata for this creator and candy machine.");
         return Ok(());
     }
 
@@ -0,0 +1,92 @@
+use anyhow::{anyhow, Result};
+use solana_remote_wallet::{
+    locator::Locator, remote_keypair::generate_remote_keypair,
+    remote_wallet::initialize_wallet_manager,
+};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, SignerError},
+};
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+type SignRequest = (Vec<u8>, mpsc::Sender<Result<Signature, SignerError>>);
+
+/// Signs with a key held on a Ledger running the Solana app, through the
+/// Solana CLI's `solana-remote-wallet`. Every signature has to be approved on
+/// the device.
+///
+/// The wallet's device handle can't leave the thread that opened it, so the
+/// Ledger is driven from a thread of its own and the signer can be shared
+/// across tasks.
+pub struct LedgerSigner {
+    pubkey: Pubkey,
+    requests: Mutex<mpsc::Sender<SignRequest>>,
+}
+
+impl LedgerSigner {
+    /// Open the Ledger at `locator` and the key at `derivation_path` on it.
+    /// A locator with a wallet pubkey only matches that device.
+    pub fn open(locator: Locator, derivation_path: DerivationPath) -> Result<Self> {
+        let (requests, receiver) = mpsc::channel::<SignRequest>();
+        let (opened, opened_receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let wallet = initialize_wallet_manager().and_then(|manager| {
+                manager.update_devices()?;
+                let keypair =
+                    generate_remote_keypair(locator, derivation_path, &manager, false, "keypair")?;
+                Ok((manager, keypair))
+            });
+            let (_manager, keypair) = match wallet {
+                Ok(wallet) => wallet,
+                Err(e) => {
+                    let _ = opened.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            let _ = opened.send(Ok(keypair.pubkey()));
+
+            // Until the signer is dropped.
+            for (message, reply) in receiver {
+                let _ = reply.send(keypair.try_sign_message(&message));
+            }
+        });
+
+        let pubkey = opened_receiver
+            .recv()
+            .map_err(|_| anyhow!("The Ledger thread stopped."))?
+            .map_err(|e| anyhow!("Can't open the Ledger: {e}"))?;
+
+        Ok(LedgerSigner {
+            pubkey,
+            requests: Mutex::new(requests),
+        })
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let (reply, response) = mpsc::channel();
+        let lost = || SignerError::Connection("The Ledger thread stopped.".to_string());
+
+        self.requests
+            .lock()
+            .unwrap()
+            .send((message.to_vec(), reply))
+            .map_err(|_| lost())?;
+        response.recv().map_err(|_| lost())?
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
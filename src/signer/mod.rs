@@ -0,0 +1,242 @@
+mod ledger;
+mod remote;
+
+pub use ledger::LedgerSigner;
+pub use remote::{RemoteEndpoint, RemoteSigner, SignerRequest, SignerResponse};
+
+use anyhow::Result;
+use metaboss_lib::data::Priority;
+use solana_client::rpc_client::RpcClient;
+use solana_remote_wallet::locator::Locator;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    derivation_path::DerivationPath,
+    instruction::Instruction,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    signers::Signers,
+};
+
+use std::sync::Arc;
+use uriparse::URIReference;
+
+use crate::{
+    constants::MULTISIG,
+    landing::land,
+    offline::compute_units_unsigned,
+    parse::{read_keypair, SolanaConfig},
+    priority::with_priority,
+};
+
+/// A signer shared across tasks: a keypair file, a hardware wallet or a
+/// remote signing service.
+pub type SharedSigner = Arc<dyn Signer + Send + Sync>;
+
+/// Where a signer comes from, parsed from a keypair argument.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignerSource {
+    File(String),
+    /// A Ledger, addressed like the Solana CLI does: `usb://ledger`,
+    /// `usb://ledger?key=0/0` or `usb://ledger/<wallet pubkey>?key=1`.
+    Ledger {
+        locator: Locator,
+        derivation_path: DerivationPath,
+    },
+    /// A signing service at `http://<host>:<port>[/<path>]` or `unix://<socket path>`.
+    Remote(RemoteEndpoint),
+}
+
+impl SignerSource {
+    pub fn parse(source: &str) -> Result<Self> {
+        if !Self::is_external(source) {
+            return Ok(SignerSource::File(source.to_string()));
+        }
+        if !source.starts_with("usb://") {
+            return Ok(SignerSource::Remote(source.parse()?));
+        }
+
+        let uri = URIReference::try_from(source)?;
+        Ok(SignerSource::Ledger {
+            locator: Locator::new_from_uri(&uri)?,
+            derivation_path: DerivationPath::from_uri_any_query(&uri)?.unwrap_or_default(),
+        })
+    }
+
+    /// Whether the source is a signer other than a keypair file.
+    pub fn is_external(source: &str) -> bool {
+        ["usb://", "http://", "https://", "unix://"]
+            .iter()
+            .any(|scheme| source.starts_with(scheme))
+    }
+}
+
+/// A keypair argument: a keypair file, or an external signer that can't be
+/// handed to code that needs a `Keypair`.
+pub enum KeypairOrSigner {
+    Keypair(Box<Keypair>),
+    Signer(SharedSigner),
+}
+
+/// Parse a keypair argument, falling back to the Solana CLI config and then
+/// the default keypair file.
+pub fn parse_keypair_or_signer(
+    keypair_opt: Option<String>,
+    sol_config_option: Option<SolanaConfig>,
+) -> Result<KeypairOrSigner> {
+    let source = keypair_opt
+        .or_else(|| sol_config_option.map(|config| config.keypair_path))
+        .unwrap_or_else(|| shellexpand::tilde("~/.config/solana/id.json").to_string());
+
+    Ok(match SignerSource::parse(&source)? {
+        SignerSource::File(path) => KeypairOrSigner::Keypair(Box::new(read_keypair(&path)?)),
+        SignerSource::Ledger {
+            locator,
+            derivation_path,
+        } => KeypairOrSigner::Signer(Arc::new(LedgerSigner::open(locator, derivation_path)?)),
+        SignerSource::Remote(endpoint) => {
+            KeypairOrSigner::Signer(Arc::new(RemoteSigner::connect(endpoint)?))
+        }
+    })
+}
+
+/// The signer for a keypair argument, whatever its source.
+pub fn parse_signer(
+    keypair_opt: Option<String>,
+    sol_config_option: Option<SolanaConfig>,
+) -> Result<SharedSigner> {
+    Ok(
+        match parse_keypair_or_signer(keypair_opt, sol_config_option)? {
+            KeypairOrSigner::Keypair(keypair) => Arc::new(*keypair),
+            KeypairOrSigner::Signer(signer) => signer,
+        },
+    )
+}
+
+/// The authority for a batch action: its keypair, or an external signer with a
+/// throwaway keypair standing in for it.
+///
+/// Like [`crate::parse::parse_authority_keypair`], no keypair is read when
+/// exporting multisig proposals.
+pub fn parse_batch_authority(
+    keypair_opt: Option<String>,
+    sol_config_option: Option<SolanaConfig>,
+) -> Result<(Keypair, Option<SharedSigner>)> {
+    if MULTISIG.read().unwrap().is_some() {
+        return Ok((Keypair::new(), None));
+    }
+
+    Ok(
+        match parse_keypair_or_signer(keypair_opt, sol_config_option)? {
+            KeypairOrSigner::Keypair(keypair) => (*keypair, None),
+            KeypairOrSigner::Signer(signer) => (Keypair::new(), Some(signer)),
+        },
+    )
+}
+
+/// Sign and send a transaction. The first signer pays the fees. With lookup
+/// tables the transaction is compiled as a v0 message.
+pub fn send_and_confirm_signed<T: Signers + ?Sized>(
+    client: &RpcClient,
+    signers: &T,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Signature> {
+    land(client, signers, instructions, lookup_tables)
+}
+
+/// The signers of a transaction paid for by `payer`: the payer first, then the
+/// authority when it is a different key.
+pub fn payer_first<'a>(payer: &'a dyn Signer, authority: &'a dyn Signer) -> Vec<&'a dyn Signer> {
+    let mut signers = vec![payer];
+    if authority.pubkey() != payer.pubkey() {
+        signers.push(authority);
+    }
+    signers
+}
+
+/// Sign and send `instructions` as `authority`. `payer` pays the fees when
+/// given, otherwise the authority does.
+pub fn send_with_signer(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    payer: Option<&Keypair>,
+    instructions: &[Instruction],
+    priority: &Priority,
+) -> Result<Signature> {
+    let fee_payer = payer.map_or_else(|| authority.pubkey(), |payer| payer.pubkey());
+
+    // Simulate unsigned so a hardware wallet is only asked to sign once.
+    let mut ixs = Vec::new();
+    if let Some(units) = compute_units_unsigned(client, instructions, &fee_payer, &[])? {
+        ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            units as u32,
+        ));
+    }
+    ixs.extend_from_slice(instructions);
+    let ixs = with_priority(client, priority, &ixs)?;
+
+    let signers = match payer {
+        Some(payer) => payer_first(payer, authority),
+        None => vec![authority],
+    };
+
+    land(client, &signers[..], &ixs, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_remote_wallet::locator::Manufacturer;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn parses_ledger_paths_like_the_solana_cli() {
+        let wallet = Pubkey::new_unique();
+        let ledger = |pubkey| Locator {
+            manufacturer: Manufacturer::Ledger,
+            pubkey,
+        };
+
+        assert_eq!(
+            SignerSource::parse("~/keys/authority.json").unwrap(),
+            SignerSource::File("~/keys/authority.json".to_string())
+        );
+        assert_eq!(
+            SignerSource::parse("usb://ledger").unwrap(),
+            SignerSource::Ledger {
+                locator: ledger(None),
+                derivation_path: DerivationPath::default()
+            }
+        );
+        assert_eq!(
+            SignerSource::parse("usb://ledger?key=1").unwrap(),
+            SignerSource::Ledger {
+                locator: ledger(None),
+                derivation_path: DerivationPath::new_bip44(Some(1), None)
+            }
+        );
+        assert_eq!(
+            SignerSource::parse(&format!("usb://ledger/{wallet}?key=0/2")).unwrap(),
+            SignerSource::Ledger {
+                locator: ledger(Some(wallet)),
+                derivation_path: DerivationPath::new_bip44(Some(0), Some(2))
+            }
+        );
+        assert!(SignerSource::parse("usb://ledger?key=x").is_err());
+        assert!(SignerSource::parse("usb://ledger?account=1").is_err());
+    }
+
+    #[test]
+    fn parses_remote_signers() {
+        assert_eq!(
+            SignerSource::parse("http://127.0.0.1:8900").unwrap(),
+            SignerSource::Remote(RemoteEndpoint::Http("http://127.0.0.1:8900".to_string()))
+        );
+        assert!(matches!(
+            SignerSource::parse("unix:///run/kms.sock").unwrap(),
+            SignerSource::Remote(RemoteEndpoint::Unix(_))
+        ));
+        assert!(SignerSource::parse("usb://trezor").is_err());
+    }
+}
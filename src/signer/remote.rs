@@ -0,0 +1,436 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, SignerError},
+};
+
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+/// How long to wait on the signing service, which may itself be waiting on an
+/// HSM or a KMS.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where an external signing service listens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemoteEndpoint {
+    /// `http://` or `https://` url
+    Http(String),
+    /// `unix://<socket path>`
+    Unix(PathBuf),
+}
+
+impl FromStr for RemoteEndpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            let url =
+                reqwest::Url::parse(s).map_err(|e| anyhow!("Invalid remote signer {s}: {e}"))?;
+            if url.host_str().is_none_or(str::is_empty) {
+                bail!("Missing remote signer host: {s}");
+            }
+            Ok(RemoteEndpoint::Http(s.to_string()))
+        } else if let Some(path) = s.strip_prefix("unix://") {
+            if path.is_empty() {
+                bail!("Missing remote signer socket path: {s}");
+            }
+            Ok(RemoteEndpoint::Unix(PathBuf::from(path)))
+        } else {
+            bail!("Invalid remote signer: {s}")
+        }
+    }
+}
+
+/// A request to the signing service, POSTed as a JSON object.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SignerRequest {
+    GetPubkey,
+    SignMessage {
+        pubkey: String,
+        /// Base64 encoded message bytes.
+        message: String,
+    },
+}
+
+/// The signing service's reply: the field for the request made, or an error.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SignerResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+    /// Base58 encoded signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Forwards messages to an external signing service, e.g. a KMS proxy or HSM
+/// daemon, so the key never has to sit on disk.
+pub struct RemoteSigner {
+    endpoint: RemoteEndpoint,
+    pubkey: Pubkey,
+}
+
+impl RemoteSigner {
+    pub fn connect(endpoint: RemoteEndpoint) -> Result<Self> {
+        let mut signer = RemoteSigner {
+            endpoint,
+            pubkey: Pubkey::default(),
+        };
+
+        let pubkey = signer
+            .call(&SignerRequest::GetPubkey)?
+            .pubkey
+            .ok_or_else(|| anyhow!("The remote signer didn't return a pubkey."))?;
+        signer.pubkey = Pubkey::from_str(&pubkey)?;
+
+        Ok(signer)
+    }
+
+    fn call(&self, request: &SignerRequest) -> Result<SignerResponse> {
+        let body = serde_json::to_vec(request)?;
+
+        let (status, body) = match &self.endpoint {
+            RemoteEndpoint::Http(url) => post_http(url, body)?,
+            #[cfg(unix)]
+            RemoteEndpoint::Unix(socket) => {
+                let stream = std::os::unix::net::UnixStream::connect(socket).map_err(|e| {
+                    anyhow!("Can't reach the remote signer at {}: {e}", socket.display())
+                })?;
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                post(stream, "localhost", "/", &body)?
+            }
+            #[cfg(not(unix))]
+            RemoteEndpoint::Unix(_) => bail!("Unix sockets aren't supported on this platform."),
+        };
+
+        match serde_json::from_slice::<SignerResponse>(&body) {
+            Ok(SignerResponse {
+                error: Some(error), ..
+            }) => bail!("Remote signer error: {error}"),
+            Ok(response) if status == 200 => Ok(response),
+            _ => bail!("Remote signer returned HTTP {status}."),
+        }
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Signature> {
+        let response = self.call(&SignerRequest::SignMessage {
+            pubkey: self.pubkey.to_string(),
+            message: base64::encode(message),
+        })?;
+        let signature = response
+            .signature
+            .ok_or_else(|| anyhow!("The remote signer didn't return a signature."))?;
+        let signature = Signature::from_str(&signature)?;
+
+        // Otherwise a signature for the wrong key or message would only fail on chain.
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            bail!("The remote signer returned a signature that doesn't verify.");
+        }
+
+        Ok(signature)
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.sign(message)
+            .map_err(|e| SignerError::Custom(e.to_string()))
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// POST `body` to `url` with reqwest's blocking client and return the response
+/// status and body. The blocking client refuses to run on an async runtime's
+/// threads, where commands sign, so the request is made from its own thread.
+fn post_http(url: &str, body: Vec<u8>) -> Result<(u16, Vec<u8>)> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                let response = reqwest::blocking::Client::builder()
+                    .timeout(TIMEOUT)
+                    .build()?
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body)
+                    .send()
+                    .map_err(|e| anyhow!("Can't reach the remote signer at {url}: {e}"))?;
+                Ok((response.status().as_u16(), response.bytes()?.to_vec()))
+            })
+            .join()
+            .map_err(|_| anyhow!("The remote signer request panicked."))?
+    })
+}
+
+/// Send one HTTP/1.1 POST over a Unix socket and return the response status
+/// and body.
+fn post<S: Read + Write>(
+    mut stream: S,
+    host: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<(u16, Vec<u8>)> {
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    parse_response(&response)
+}
+
+fn parse_response(response: &[u8]) -> Result<(u16, Vec<u8>)> {
+    let malformed = || anyhow!("Malformed response from the remote signer.");
+
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = std::str::from_utf8(&response[..split])?;
+    let body = &response[split + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(malformed)?;
+    let chunked = lines.any(|line| {
+        let line = line.to_ascii_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+
+    let body = if chunked {
+        dechunk(body).ok_or_else(malformed)?
+    } else {
+        body.to_vec()
+    };
+
+    Ok((status, body))
+}
+
+/// Decode a chunked transfer-encoded body.
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash, instruction::Instruction, signature::Keypair, transaction::Transaction,
+    };
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        thread,
+    };
+
+    /// Stands in for a signing service, signing with `keypair`.
+    #[derive(Clone, Copy)]
+    enum Behavior {
+        Sign,
+        Chunked,
+        WrongKey,
+        Refuse,
+    }
+
+    fn handle<S: Read + Write>(stream: S, keypair: &Keypair, behavior: Behavior) {
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = len.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+
+        let response = match serde_json::from_slice(&body).unwrap() {
+            SignerRequest::GetPubkey => SignerResponse {
+                pubkey: Some(keypair.pubkey().to_string()),
+                ..Default::default()
+            },
+            SignerRequest::SignMessage { .. } if matches!(behavior, Behavior::Refuse) => {
+                SignerResponse {
+                    error: Some("policy denied".to_string()),
+                    ..Default::default()
+                }
+            }
+            SignerRequest::SignMessage { pubkey, message } => {
+                assert_eq!(pubkey, keypair.pubkey().to_string());
+                let message = base64::decode(message).unwrap();
+                let signature = match behavior {
+                    Behavior::WrongKey => Keypair::new().sign_message(&message),
+                    _ => keypair.sign_message(&message),
+                };
+                SignerResponse {
+                    signature: Some(signature.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+        let body = serde_json::to_vec(&response).unwrap();
+
+        let stream = reader.get_mut();
+        if matches!(behavior, Behavior::Chunked) {
+            let (first, second) = body.split_at(body.len() / 2);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n",
+                first.len()
+            )
+            .unwrap();
+            stream.write_all(first).unwrap();
+            write!(stream, "\r\n{:x}\r\n", second.len()).unwrap();
+            stream.write_all(second).unwrap();
+            stream.write_all(b"\r\n0\r\n\r\n").unwrap();
+        } else {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    }
+
+    /// Serve `requests` requests over TCP, returning the endpoint.
+    fn serve_tcp(keypair: Keypair, behavior: Behavior, requests: usize) -> RemoteEndpoint {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                handle(stream.unwrap(), &keypair, behavior);
+            }
+        });
+        format!("http://{host}/sign").parse().unwrap()
+    }
+
+    fn transaction(payer: &Pubkey) -> Transaction {
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![]);
+        Transaction::new_with_payer(&[ix], Some(payer))
+    }
+
+    #[test]
+    fn signs_transactions_over_http() {
+        let keypair = Keypair::new();
+        let expected = keypair.pubkey();
+        let signer = RemoteSigner::connect(serve_tcp(keypair, Behavior::Sign, 2)).unwrap();
+        assert_eq!(signer.pubkey(), expected);
+
+        let mut tx = transaction(&signer.pubkey());
+        let signers: Vec<&dyn Signer> = vec![&signer];
+        tx.try_sign(&signers, Hash::new_unique()).unwrap();
+        tx.verify().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signs_over_a_unix_socket() {
+        use std::os::unix::net::UnixListener;
+
+        let socket = std::env::temp_dir().join(format!("mb-signer-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+        let keypair = Keypair::new();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                handle(stream.unwrap(), &keypair, Behavior::Chunked);
+            }
+        });
+
+        let endpoint = format!("unix://{}", socket.display()).parse().unwrap();
+        let signer = RemoteSigner::connect(endpoint).unwrap();
+        let signature = signer.try_sign_message(b"message").unwrap();
+
+        assert!(signature.verify(signer.pubkey().as_ref(), b"message"));
+        std::fs::remove_file(socket).unwrap();
+    }
+
+    #[test]
+    fn rejects_signatures_that_dont_verify() {
+        let signer =
+            RemoteSigner::connect(serve_tcp(Keypair::new(), Behavior::WrongKey, 2)).unwrap();
+
+        let err = signer.try_sign_message(b"message").unwrap_err();
+        assert!(err.to_string().contains("doesn't verify"));
+    }
+
+    #[test]
+    fn surfaces_errors_from_the_service() {
+        let signer = RemoteSigner::connect(serve_tcp(Keypair::new(), Behavior::Refuse, 2)).unwrap();
+
+        let err = signer.try_sign_message(b"message").unwrap_err();
+        assert!(err.to_string().contains("policy denied"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn signs_from_inside_the_runtime() {
+        let keypair = Keypair::new();
+        let endpoint = serve_tcp(keypair, Behavior::Chunked, 2);
+
+        // Commands connect and sign from async code running on the runtime.
+        let signer = RemoteSigner::connect(endpoint).unwrap();
+        let signature = signer.try_sign_message(b"message").unwrap();
+        assert!(signature.verify(signer.pubkey().as_ref(), b"message"));
+    }
+
+    #[test]
+    fn parses_endpoints() {
+        assert_eq!(
+            "http://127.0.0.1:8900".parse::<RemoteEndpoint>().unwrap(),
+            RemoteEndpoint::Http("http://127.0.0.1:8900".to_string())
+        );
+        assert_eq!(
+            "https://kms.example.com/v1/sign"
+                .parse::<RemoteEndpoint>()
+                .unwrap(),
+            RemoteEndpoint::Http("https://kms.example.com/v1/sign".to_string())
+        );
+        assert_eq!(
+            "unix:///run/signer.sock".parse::<RemoteEndpoint>().unwrap(),
+            RemoteEndpoint::Unix(PathBuf::from("/run/signer.sock"))
+        );
+        assert!("http://".parse::<RemoteEndpoint>().is_err());
+        assert!("ftp://signer".parse::<RemoteEndpoint>().is_err());
+    }
+}
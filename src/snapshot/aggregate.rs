@@ -0,0 +1,375 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    fs::File,
+    path::PathBuf,
+    str::FromStr,
+};
+
+use super::{Holder, Item};
+
+/// Where the traits of an NFT are read from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraitSource {
+    /// The `attributes` of the off-chain JSON, as indexed by DAS.
+    #[default]
+    Offchain,
+    /// The Attributes plugin of an MPL Core asset.
+    Onchain,
+}
+
+impl FromStr for TraitSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "offchain" => Ok(TraitSource::Offchain),
+            "onchain" => Ok(TraitSource::Onchain),
+            _ => Err(format!("Invalid trait source: {s}")),
+        }
+    }
+}
+
+impl Display for TraitSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraitSource::Offchain => write!(f, "offchain"),
+            TraitSource::Onchain => write!(f, "onchain"),
+        }
+    }
+}
+
+/// A `--trait TYPE=VALUE` filter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraitFilter {
+    pub trait_type: String,
+    pub value: String,
+}
+
+impl FromStr for TraitFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (trait_type, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid trait filter: {s}; use TYPE=VALUE"))?;
+        Ok(TraitFilter {
+            trait_type: trait_type.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Trait values by trait type, for one NFT.
+pub type Traits = HashMap<String, String>;
+
+/// Weights by trait type and value, read from a `--weights` file such as
+/// `{"Background": {"Gold": 3, "Silver": 2}}`.
+pub type TraitWeights = HashMap<String, HashMap<String, f64>>;
+
+/// The options of an aggregate holders output.
+#[derive(Debug, Default)]
+pub struct AggregateOptions {
+    pub exclude: HashSet<String>,
+    pub weights: TraitWeights,
+    pub traits: Vec<TraitFilter>,
+    pub trait_source: TraitSource,
+}
+
+impl AggregateOptions {
+    /// The options chosen on the command line, if an aggregate output was asked
+    /// for. Any exclusion, weight or trait filter asks for one.
+    pub fn new(
+        aggregate: bool,
+        exclude: Vec<String>,
+        exclude_file: Option<PathBuf>,
+        weights_file: Option<PathBuf>,
+        traits: Vec<TraitFilter>,
+        trait_source: TraitSource,
+    ) -> Result<Option<Self>> {
+        if !aggregate
+            && exclude.is_empty()
+            && exclude_file.is_none()
+            && weights_file.is_none()
+            && traits.is_empty()
+        {
+            return Ok(None);
+        }
+
+        let mut excluded: HashSet<String> = exclude.into_iter().collect();
+        if let Some(path) = exclude_file {
+            let file =
+                File::open(&path).map_err(|e| anyhow!("Failed to open {}: {e}", path.display()))?;
+            let addresses: Vec<String> = serde_json::from_reader(file)?;
+            excluded.extend(addresses);
+        }
+
+        let weights = match weights_file {
+            Some(path) => {
+                let file = File::open(&path)
+                    .map_err(|e| anyhow!("Failed to open {}: {e}", path.display()))?;
+                serde_json::from_reader(file)
+                    .map_err(|e| anyhow!("Invalid weights file {}: {e}", path.display()))?
+            }
+            None => TraitWeights::new(),
+        };
+
+        Ok(Some(Self {
+            exclude: excluded,
+            weights,
+            traits,
+            trait_source,
+        }))
+    }
+
+    /// Whether an NFT with `traits` passes the trait filters. Filters on the
+    /// same trait type match any of their values; different types must all match.
+    fn matches(&self, traits: &Traits) -> bool {
+        let mut wanted: HashMap<&str, Vec<&str>> = HashMap::new();
+        for filter in &self.traits {
+            wanted
+                .entry(filter.trait_type.as_str())
+                .or_default()
+                .push(filter.value.as_str());
+        }
+
+        wanted.iter().all(|(trait_type, values)| {
+            traits
+                .get(*trait_type)
+                .is_some_and(|value| values.contains(&value.as_str()))
+        })
+    }
+
+    /// The weight of an NFT: the product of the weights of its traits, where
+    /// traits without one weigh 1.
+    fn weight(&self, traits: &Traits) -> f64 {
+        traits
+            .iter()
+            .filter_map(|(trait_type, value)| self.weights.get(trait_type)?.get(value))
+            .product()
+    }
+}
+
+/// The traits of a DAS item.
+pub fn item_traits(item: &Item, source: TraitSource) -> Traits {
+    let (list, key) = match source {
+        TraitSource::Offchain => (&item.content["metadata"]["attributes"], "trait_type"),
+        TraitSource::Onchain => (&item.plugins["attributes"]["data"]["attribute_list"], "key"),
+    };
+
+    list.as_array()
+        .map(|attributes| {
+            attributes
+                .iter()
+                .filter_map(|attribute| {
+                    Some((
+                        value_string(attribute.get(key)?)?,
+                        value_string(attribute.get("value")?)?,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn value_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// One wallet in an aggregate holders output.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AggregateHolder {
+    pub owner: String,
+    pub count: usize,
+    pub weight: f64,
+    pub mints: Vec<String>,
+}
+
+/// Group `holders` by owner, leaving out excluded owners and NFTs that don't
+/// pass the trait filters. Heaviest wallets come first.
+pub fn aggregate_holders(
+    holders: &[Holder],
+    traits: &HashMap<String, Traits>,
+    options: &AggregateOptions,
+) -> Vec<AggregateHolder> {
+    let no_traits = Traits::new();
+    let mut owners: BTreeMap<&str, AggregateHolder> = BTreeMap::new();
+
+    for holder in holders {
+        if options.exclude.contains(&holder.owner) {
+            continue;
+        }
+        let nft_traits = traits.get(&holder.mint).unwrap_or(&no_traits);
+        if !options.matches(nft_traits) {
+            continue;
+        }
+
+        let entry = owners
+            .entry(holder.owner.as_str())
+            .or_insert_with(|| AggregateHolder {
+                owner: holder.owner.clone(),
+                count: 0,
+                weight: 0.0,
+                mints: vec![],
+            });
+        entry.count += 1;
+        entry.weight += options.weight(nft_traits);
+        entry.mints.push(holder.mint.clone());
+    }
+
+    let mut aggregate: Vec<AggregateHolder> = owners.into_values().collect();
+    aggregate.sort_by(|a, b| {
+        b.weight
+            .total_cmp(&a.weight)
+            .then(b.count.cmp(&a.count))
+            .then(a.owner.cmp(&b.owner))
+    });
+    aggregate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holder(owner: &str, mint: &str) -> Holder {
+        Holder {
+            owner: owner.to_string(),
+            mint: mint.to_string(),
+            metadata: String::new(),
+            ata: String::new(),
+        }
+    }
+
+    fn traits(pairs: &[(&str, &str)]) -> Traits {
+        pairs
+            .iter()
+            .map(|(t, v)| (t.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn fixture() -> (Vec<Holder>, HashMap<String, Traits>) {
+        let holders = vec![
+            holder("alice", "m1"),
+            holder("alice", "m2"),
+            holder("bob", "m3"),
+            holder("escrow", "m4"),
+        ];
+        let traits = HashMap::from([
+            ("m1".to_string(), traits(&[("Background", "Gold")])),
+            ("m2".to_string(), traits(&[("Background", "Blue")])),
+            (
+                "m3".to_string(),
+                traits(&[("Background", "Gold"), ("Rank", "Legendary")]),
+            ),
+            ("m4".to_string(), traits(&[("Background", "Gold")])),
+        ]);
+        (holders, traits)
+    }
+
+    #[test]
+    fn holders_are_counted_and_weighted_per_wallet() {
+        let (holders, traits) = fixture();
+        let options = AggregateOptions {
+            exclude: HashSet::from(["escrow".to_string()]),
+            weights: serde_json::from_str(
+                r#"{"Background": {"Gold": 2}, "Rank": {"Legendary": 5}}"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let aggregate = aggregate_holders(&holders, &traits, &options);
+
+        assert_eq!(
+            aggregate,
+            vec![
+                AggregateHolder {
+                    owner: "bob".into(),
+                    count: 1,
+                    weight: 10.0,
+                    mints: vec!["m3".into()],
+                },
+                AggregateHolder {
+                    owner: "alice".into(),
+                    count: 2,
+                    weight: 3.0,
+                    mints: vec!["m1".into(), "m2".into()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn trait_filters_match_any_value_of_a_type_and_every_type() {
+        let (holders, traits) = fixture();
+        let filter = |filters: &[&str]| AggregateOptions {
+            traits: filters.iter().map(|f| f.parse().unwrap()).collect(),
+            ..Default::default()
+        };
+
+        let gold_or_blue = aggregate_holders(
+            &holders,
+            &traits,
+            &filter(&["Background=Gold", "Background=Blue"]),
+        );
+        let gold_legendary = aggregate_holders(
+            &holders,
+            &traits,
+            &filter(&["Background=Gold", "Rank=Legendary"]),
+        );
+
+        assert_eq!(gold_or_blue.iter().map(|h| h.count).sum::<usize>(), 4);
+        assert_eq!(gold_legendary.len(), 1);
+        assert_eq!(gold_legendary[0].owner, "bob");
+        assert!("Background".parse::<TraitFilter>().is_err());
+    }
+
+    #[test]
+    fn traits_are_read_from_either_source() {
+        let item: Item = serde_json::from_value(serde_json::json!({
+            "interface": "MplCoreAsset",
+            "id": "m1",
+            "content": { "metadata": { "attributes": [
+                { "trait_type": "Background", "value": "Gold" },
+                { "trait_type": "Level", "value": 3 }
+            ] } },
+            "authorities": [],
+            "compression": {},
+            "grouping": [],
+            "royalty": {},
+            "creators": [],
+            "ownership": {
+                "delegate": null,
+                "delegated": false,
+                "frozen": false,
+                "owner": "alice",
+                "ownership_model": "single"
+            },
+            "supply": null,
+            "mutable": true,
+            "burnt": false,
+            "plugins": { "attributes": { "data": { "attribute_list": [
+                { "key": "Power", "value": "9000" }
+            ] } } }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            item_traits(&item, TraitSource::Offchain),
+            traits(&[("Background", "Gold"), ("Level", "3")])
+        );
+        assert_eq!(
+            item_traits(&item, TraitSource::Onchain),
+            traits(&[("Power", "9000")])
+        );
+    }
+}
@@ -1,7 +1,8 @@
-use std::{fmt::Display, fs::File, path::PathBuf, str::FromStr};
+use std::{collections::HashMap, fmt::Display, path::PathBuf, str::FromStr};
 
 use anyhow::{bail, Result};
 use metaboss_lib::derive::derive_metadata_pda;
+use rayon::prelude::*;
 use reqwest::{header::HeaderMap, StatusCode};
 use serde_json::{json, Value};
 use solana_program::pubkey::Pubkey;
@@ -9,12 +10,17 @@ use solana_sdk::signer::Signer;
 use spl_associated_token_account::get_associated_token_address;
 
 use crate::{
+    limiter::send_das_request,
     setup::{CliConfig, ClientLike, ClientType},
     snapshot::TokenResponse,
-    spinner::create_spinner,
+    spinner::{create_spinner, new_progress_bar},
 };
 
-use super::{DasResponse, Holder, Item};
+use super::{
+    aggregate_holders, error, item_traits, write_mints, write_rows, AggregateOptions, DasResponse,
+    ExportFormat, HistoricalSnapshot, Holder, Item, ParallelProgressIterator, RpcClient,
+    TokenAccount, Traits,
+};
 
 #[derive(Debug)]
 pub enum HolderGroupKey {
@@ -47,11 +53,15 @@ impl Display for HolderGroupKey {
 }
 
 pub struct HoldersArgs {
+    pub client: RpcClient,
     pub rpc_url: String,
     pub group_key: HolderGroupKey,
     pub group_value: Pubkey,
     pub output: PathBuf,
     pub delay: u64,
+    pub format: ExportFormat,
+    pub history: Option<HistoricalSnapshot>,
+    pub aggregate: Option<AggregateOptions>,
 }
 
 struct Query {
@@ -61,7 +71,11 @@ struct Query {
 }
 
 pub async fn snapshot_holders(args: HoldersArgs) -> Result<()> {
-    let config = CliConfig::new(None, Some(args.rpc_url), ClientType::DAS)?;
+    if args.aggregate.is_some() && matches!(args.group_key, HolderGroupKey::Mint) {
+        bail!("Aggregate output is only available for NFT holders; use the fvca or mcc group key");
+    }
+
+    let config = CliConfig::new(None, Some(args.rpc_url.clone()), ClientType::DAS)?;
 
     let query = match args.group_key {
         HolderGroupKey::Mint => Query {
@@ -108,6 +122,7 @@ pub async fn snapshot_holders(args: HoldersArgs) -> Result<()> {
 
     let mut token_holders = Vec::new();
     let mut holders = Vec::new();
+    let mut traits = HashMap::new();
     let mut page = 1;
 
     let mut body = json!(
@@ -125,12 +140,13 @@ pub async fn snapshot_holders(args: HoldersArgs) -> Result<()> {
 
     let spinner = create_spinner("Getting assets...");
     loop {
-        let response = client
-            .post(config.rpc_url.clone())
-            .headers(headers.clone())
-            .json(&body)
-            .send()
-            .await?;
+        let response = send_das_request(
+            client
+                .post(config.rpc_url.clone())
+                .headers(headers.clone())
+                .json(&body),
+        )
+        .await?;
 
         let status = response.status();
         if response.status() != StatusCode::OK {
@@ -171,6 +187,11 @@ pub async fn snapshot_holders(args: HoldersArgs) -> Result<()> {
                         }
                     })
                     .for_each(|item| {
+                        if let Some(aggregate) = &args.aggregate {
+                            traits
+                                .insert(item.id.clone(), item_traits(item, aggregate.trait_source));
+                        }
+
                         let mint_address = item.id.clone();
                         let metadata_pubkey =
                             derive_metadata_pda(&Pubkey::from_str(mint_address.as_str()).unwrap());
@@ -194,16 +215,23 @@ pub async fn snapshot_holders(args: HoldersArgs) -> Result<()> {
     }
     spinner.finish();
 
+    if let Some(history) = &args.history {
+        return write_holders_at(&args, history, holders, token_holders, &traits);
+    }
+
     if !holders.is_empty() {
         holders.sort();
 
         // Write to file
         std::fs::create_dir_all(&args.output)?;
-        let file = File::create(args.output.join(format!(
-            "{}_{}_holders.json",
-            args.group_value, args.group_key
-        )))?;
-        serde_json::to_writer_pretty(file, &holders)?;
+        let name = format!("{}_{}_holders", args.group_value, args.group_key);
+        write_rows(
+            &args.output.join(args.format.file_name(&name)),
+            args.format,
+            &holders,
+        )?;
+
+        write_aggregate(&args, &name, &holders, &traits)?;
     }
 
     if !token_holders.is_empty() {
@@ -211,16 +239,119 @@ pub async fn snapshot_holders(args: HoldersArgs) -> Result<()> {
 
         // Write to file
         std::fs::create_dir_all(&args.output)?;
-        let file = File::create(
-            args.output
-                .join(format!("{}_token_holders.json", args.group_value)),
+        let name = format!("{}_token_holders", args.group_value);
+        write_rows(
+            &args.output.join(args.format.file_name(&name)),
+            args.format,
+            &token_holders,
         )?;
-        serde_json::to_writer_pretty(file, &token_holders)?;
     }
 
     Ok(())
 }
 
+/// Replace the current holders found through DAS with those at the cutoff,
+/// and write them out.
+fn write_holders_at(
+    args: &HoldersArgs,
+    history: &HistoricalSnapshot,
+    holders: Vec<Holder>,
+    token_holders: Vec<TokenAccount>,
+    traits: &HashMap<String, Traits>,
+) -> Result<()> {
+    say!("Finding holders at {}...", history.cutoff);
+    std::fs::create_dir_all(&args.output)?;
+
+    if let HolderGroupKey::Mint = args.group_key {
+        let start = token_holders
+            .into_iter()
+            .map(|account| account.address)
+            .collect();
+        let mut balances = history.balances(&args.client, &args.group_value, start)?;
+        balances.sort();
+
+        let name = format!("{}_token_holders_at_{}", args.group_value, history.cutoff);
+        write_rows(
+            &args.output.join(args.format.file_name(&name)),
+            args.format,
+            &balances,
+        )?;
+        return Ok(());
+    }
+
+    let results: Vec<(String, Result<Vec<Holder>>)> = holders
+        .par_iter()
+        .progress_with(new_progress_bar(holders.len() as u64))
+        .map(|holder| {
+            let holders_at = Pubkey::from_str(&holder.mint)
+                .map_err(Into::into)
+                .and_then(|mint| history.nft_holders(&args.client, &mint));
+            (holder.mint.clone(), holders_at)
+        })
+        .collect();
+
+    let mut holders_at = Vec::new();
+    let mut failed_mints = Vec::new();
+    for (mint, result) in results {
+        match result {
+            Ok(holders) => holders_at.extend(holders),
+            Err(err) => {
+                error!("Holders of {mint} not found: {err}");
+                failed_mints.push(mint);
+            }
+        }
+    }
+    holders_at.sort();
+
+    let name = format!(
+        "{}_{}_holders_at_{}",
+        args.group_value, args.group_key, history.cutoff
+    );
+    write_rows(
+        &args.output.join(args.format.file_name(&name)),
+        args.format,
+        &holders_at,
+    )?;
+
+    write_aggregate(args, &name, &holders_at, traits)?;
+
+    // A historical snapshot missing mints can't be trusted, so say which.
+    if !failed_mints.is_empty() {
+        bail!(
+            "Holders of {} mints could not be found; rerun to retry them: {}",
+            failed_mints.len(),
+            failed_mints.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Write the holders grouped by wallet next to the `name` holders file, if
+/// that was asked for.
+fn write_aggregate(
+    args: &HoldersArgs,
+    name: &str,
+    holders: &[Holder],
+    traits: &HashMap<String, Traits>,
+) -> Result<()> {
+    let Some(options) = &args.aggregate else {
+        return Ok(());
+    };
+
+    let aggregate = aggregate_holders(holders, traits, options);
+    say!("Found {} wallets", aggregate.len());
+
+    write_rows(
+        &args
+            .output
+            .join(args.format.file_name(&format!("{name}_aggregate"))),
+        args.format,
+        &aggregate,
+    )?;
+    Ok(())
+}
+
 #[derive(Debug)]
 pub enum MintsGroupKey {
     Authority,
@@ -258,6 +389,7 @@ pub struct MintsArgs {
     pub creator_position: usize,
     pub output: PathBuf,
     pub delay: u64,
+    pub format: ExportFormat,
 }
 
 pub async fn snapshot_mints(args: MintsArgs) -> Result<()> {
@@ -327,12 +459,13 @@ pub async fn snapshot_mints(args: MintsArgs) -> Result<()> {
 
     let spinner = create_spinner("Getting assets...");
     loop {
-        let response = client
-            .post(config.rpc_url.clone())
-            .headers(headers.clone())
-            .json(&body)
-            .send()
-            .await?;
+        let response = send_das_request(
+            client
+                .post(config.rpc_url.clone())
+                .headers(headers.clone())
+                .json(&body),
+        )
+        .await?;
 
         let status = response.status();
         if response.status() != StatusCode::OK {
@@ -370,11 +503,12 @@ pub async fn snapshot_mints(args: MintsArgs) -> Result<()> {
 
     // Write to file
     std::fs::create_dir_all(&args.output)?;
-    let file = File::create(args.output.join(format!(
-        "{}_{}_mints.json",
-        args.group_value, args.group_key
-    )))?;
-    serde_json::to_writer_pretty(file, &mints)?;
+    let name = format!("{}_{}_mints", args.group_value, args.group_key);
+    write_mints(
+        &args.output.join(args.format.file_name(&name)),
+        args.format,
+        &mints,
+    )?;
 
     Ok(())
 }
@@ -384,6 +518,7 @@ pub struct FcvaArgs {
     pub creator: Option<Pubkey>,
     pub output: PathBuf,
     pub delay: u64,
+    pub format: ExportFormat,
 }
 
 pub async fn fcva_mints(args: FcvaArgs) -> Result<()> {
@@ -423,12 +558,13 @@ pub async fn fcva_mints(args: FcvaArgs) -> Result<()> {
             },
         });
 
-        let response = client
-            .post(config.rpc_url.clone())
-            .headers(headers.clone())
-            .json(&body)
-            .send()
-            .await?;
+        let response = send_das_request(
+            client
+                .post(config.rpc_url.clone())
+                .headers(headers.clone())
+                .json(&body),
+        )
+        .await?;
 
         let status = response.status();
         if response.status() != StatusCode::OK {
@@ -462,8 +598,12 @@ pub async fn fcva_mints(args: FcvaArgs) -> Result<()> {
 
     // Write to file
     std::fs::create_dir_all(&args.output)?;
-    let file = File::create(args.output.join(format!("{}_fvca_mints.json", creator)))?;
-    serde_json::to_writer_pretty(file, &mints)?;
+    let name = format!("{creator}_fvca_mints");
+    write_mints(
+        &args.output.join(args.format.file_name(&name)),
+        args.format,
+        &mints,
+    )?;
 
     Ok(())
 }
@@ -473,13 +613,39 @@ pub struct MccArgs {
     pub mcc_id: Pubkey,
     pub output: PathBuf,
     pub delay: u64,
+    pub format: ExportFormat,
 }
 
 pub async fn mcc_mints(args: MccArgs) -> Result<()> {
-    let config = CliConfig::new(None, Some(args.rpc_url), ClientType::DAS)?;
-
     let mcc_id = args.mcc_id.to_string();
 
+    let spinner = create_spinner("Getting assets...");
+    let items = get_collection_items(&args.rpc_url, &args.mcc_id, args.delay).await?;
+    spinner.finish_and_clear();
+
+    let mut mints: Vec<String> = items.into_iter().map(|item| item.id).collect();
+    mints.sort();
+
+    // Write to file
+    std::fs::create_dir_all(&args.output)?;
+    let name = format!("{mcc_id}_mcc_mints");
+    write_mints(
+        &args.output.join(args.format.file_name(&name)),
+        args.format,
+        &mints,
+    )?;
+
+    Ok(())
+}
+
+/// Every asset in the verified collection `collection`, from the DAS API at `das_url`.
+pub async fn get_collection_items(
+    das_url: &str,
+    collection: &Pubkey,
+    delay: u64,
+) -> Result<Vec<Item>> {
+    let config = CliConfig::new(None, Some(das_url.to_string()), ClientType::DAS)?;
+
     let mut headers = HeaderMap::new();
     headers.insert("Content-Type", "application/json".parse().unwrap());
 
@@ -488,9 +654,8 @@ pub async fn mcc_mints(args: MccArgs) -> Result<()> {
         _ => panic!("Wrong client type"),
     };
 
-    let mut mints: Vec<String> = Vec::new();
+    let mut items = Vec::new();
     let mut page = 1;
-    let spinner = create_spinner("Getting assets...");
     loop {
         let body = json!(
         {
@@ -499,18 +664,19 @@ pub async fn mcc_mints(args: MccArgs) -> Result<()> {
             "method": "getAssetsByGroup",
             "params": {
                 "groupKey": "collection",
-                "groupValue": mcc_id,
+                "groupValue": collection.to_string(),
                 "page": page,
                 "limit": 1000
             },
         });
 
-        let response = client
-            .post(config.rpc_url.clone())
-            .headers(headers.clone())
-            .json(&body)
-            .send()
-            .await?;
+        let response = send_das_request(
+            client
+                .post(config.rpc_url.clone())
+                .headers(headers.clone())
+                .json(&body),
+        )
+        .await?;
 
         let status = response.status();
         if response.status() != StatusCode::OK {
@@ -524,21 +690,10 @@ pub async fn mcc_mints(args: MccArgs) -> Result<()> {
         }
 
         page += 1;
+        items.extend(res.result.items);
 
-        res.result.items.iter().for_each(|item| {
-            mints.push(item.id.clone());
-        });
-
-        std::thread::sleep(std::time::Duration::from_millis(args.delay));
+        std::thread::sleep(std::time::Duration::from_millis(delay));
     }
-    spinner.finish_and_clear();
 
-    mints.sort();
-
-    // Write to file
-    std::fs::create_dir_all(&args.output)?;
-    let file = File::create(args.output.join(format!("{}_mcc_mints.json", mcc_id)))?;
-    serde_json::to_writer_pretty(file, &mints)?;
-
-    Ok(())
+    Ok(items)
 }
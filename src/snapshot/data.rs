@@ -3,7 +3,7 @@ use super::*;
 pub const PARALLEL_LIMIT: usize = 50;
 pub type HolderResults = Vec<Result<Holder>>;
 
-#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Holder {
     pub owner: String,
     pub mint: String,
@@ -48,6 +48,7 @@ pub struct SnapshotHoldersGpaArgs {
     pub v3: bool,
     pub allow_unverified: bool,
     pub output: String,
+    pub history: Option<HistoricalSnapshot>,
 }
 
 use mpl_token_metadata::types::Creator;
@@ -121,6 +122,8 @@ pub struct Item {
     pub burnt: bool,
     pub inscription: Option<Inscription>,
     pub spl20: Option<Value>,
+    #[serde(default)]
+    pub plugins: Value,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
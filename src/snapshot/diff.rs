@@ -0,0 +1,453 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use super::Holder;
+
+pub struct SnapshotDiffArgs {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    pub output: PathBuf,
+}
+
+/// The contents of a snapshot file, as written by the snapshot commands.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum SnapshotFile {
+    /// `snapshot holders` and `holders-gpa`.
+    Holders(Vec<Holder>),
+    /// `snapshot holders` with the `mint` group key: token accounts and
+    /// their balances.
+    TokenHolders(Vec<TokenHolding>),
+    /// `snapshot mints`, `fvca`, `mcc`, `mints-gpa` and `collections get-items`.
+    Mints(Vec<String>),
+    /// `snapshot prints`: mints by edition number.
+    Editions(BTreeMap<String, String>),
+}
+
+/// A token account in a token holder snapshot. Current and historical
+/// snapshots both have these fields.
+#[derive(Debug, Deserialize)]
+pub struct TokenHolding {
+    pub owner: String,
+    pub mint: String,
+    pub address: String,
+    #[serde(default)]
+    pub amount: Option<u64>,
+}
+
+impl SnapshotFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).map_err(|e| anyhow!("Failed to open {}: {e}", path.display()))?;
+        serde_json::from_reader(file).map_err(|_| {
+            anyhow!(
+                "{} is not a holder, token holder or mint list snapshot",
+                path.display()
+            )
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            SnapshotFile::Holders(holders) => holders.is_empty(),
+            SnapshotFile::TokenHolders(holdings) => holdings.is_empty(),
+            SnapshotFile::Mints(mints) => mints.is_empty(),
+            SnapshotFile::Editions(editions) => editions.is_empty(),
+        }
+    }
+}
+
+/// The difference between two snapshots of the same kind.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum SnapshotDiff {
+    Holders(HolderDiff),
+    TokenHolders(TokenHolderDiff),
+    Mints(MintDiff),
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct HolderDiff {
+    /// Wallets holding something now that held nothing before.
+    pub new_holders: Vec<String>,
+    /// Wallets that held something before and hold nothing now.
+    pub departed_holders: Vec<String>,
+    /// Every wallet whose number of items changed, new and departed ones included.
+    pub count_changes: BTreeMap<String, CountChange>,
+    /// Mints in both snapshots whose owner changed.
+    pub moved_mints: Vec<MovedMint>,
+    pub added_mints: Vec<String>,
+    pub removed_mints: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct CountChange {
+    pub old: usize,
+    pub new: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct MovedMint {
+    pub mint: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TokenHolderDiff {
+    /// Wallets with a balance now that had none before.
+    pub new_holders: Vec<String>,
+    /// Wallets that had a balance before and have none now.
+    pub departed_holders: Vec<String>,
+    /// Every wallet whose balance, summed over its token accounts, changed.
+    pub balance_changes: BTreeMap<String, TokenBalanceChange>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct TokenBalanceChange {
+    pub old: u64,
+    pub new: u64,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct MintDiff {
+    pub added_mints: Vec<String>,
+    pub removed_mints: Vec<String>,
+}
+
+/// Compare two snapshots. An empty snapshot can be compared with either kind.
+pub fn diff_snapshots(old: SnapshotFile, new: SnapshotFile) -> Result<SnapshotDiff> {
+    match (old, new) {
+        (SnapshotFile::Holders(old), SnapshotFile::Holders(new)) => {
+            Ok(SnapshotDiff::Holders(diff_holders(&old, &new)))
+        }
+        (SnapshotFile::TokenHolders(old), SnapshotFile::TokenHolders(new)) => {
+            Ok(SnapshotDiff::TokenHolders(diff_token_holders(&old, &new)))
+        }
+        (old, new) if old.is_empty() && matches!(new, SnapshotFile::TokenHolders(_)) => {
+            diff_snapshots(SnapshotFile::TokenHolders(vec![]), new)
+        }
+        (old, new) if new.is_empty() && matches!(old, SnapshotFile::TokenHolders(_)) => {
+            diff_snapshots(old, SnapshotFile::TokenHolders(vec![]))
+        }
+        (old, new) if old.is_empty() && matches!(new, SnapshotFile::Holders(_)) => {
+            diff_snapshots(SnapshotFile::Holders(vec![]), new)
+        }
+        (old, new) if new.is_empty() && matches!(old, SnapshotFile::Holders(_)) => {
+            diff_snapshots(old, SnapshotFile::Holders(vec![]))
+        }
+        (old, new) if old.is_empty() || new.is_empty() => Ok(SnapshotDiff::Mints(diff_mints(
+            &mint_set(old),
+            &mint_set(new),
+        ))),
+        (SnapshotFile::TokenHolders(_), _) | (_, SnapshotFile::TokenHolders(_)) => {
+            bail!("Can't compare a token holder snapshot with another kind of snapshot")
+        }
+        (SnapshotFile::Holders(_), _) | (_, SnapshotFile::Holders(_)) => {
+            bail!("Can't compare a holder snapshot with a mint list")
+        }
+        (old, new) => Ok(SnapshotDiff::Mints(diff_mints(
+            &mint_set(old),
+            &mint_set(new),
+        ))),
+    }
+}
+
+fn mint_set(snapshot: SnapshotFile) -> BTreeSet<String> {
+    match snapshot {
+        SnapshotFile::Holders(holders) => holders.into_iter().map(|h| h.mint).collect(),
+        SnapshotFile::TokenHolders(holdings) => holdings.into_iter().map(|h| h.mint).collect(),
+        SnapshotFile::Mints(mints) => mints.into_iter().collect(),
+        SnapshotFile::Editions(editions) => editions.into_values().collect(),
+    }
+}
+
+pub fn diff_mints(old: &BTreeSet<String>, new: &BTreeSet<String>) -> MintDiff {
+    MintDiff {
+        added_mints: new.difference(old).cloned().collect(),
+        removed_mints: old.difference(new).cloned().collect(),
+    }
+}
+
+pub fn diff_holders(old: &[Holder], new: &[Holder]) -> HolderDiff {
+    let owners = |holders: &[Holder]| -> BTreeMap<String, String> {
+        holders
+            .iter()
+            .map(|h| (h.mint.clone(), h.owner.clone()))
+            .collect()
+    };
+    let counts = |holders: &[Holder]| -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for holder in holders {
+            *counts.entry(holder.owner.clone()).or_default() += 1;
+        }
+        counts
+    };
+
+    let (old_owners, new_owners) = (owners(old), owners(new));
+    let (old_counts, new_counts) = (counts(old), counts(new));
+
+    let wallets: BTreeSet<&String> = old_counts.keys().chain(new_counts.keys()).collect();
+    let count_changes: BTreeMap<String, CountChange> = wallets
+        .into_iter()
+        .filter_map(|wallet| {
+            let change = CountChange {
+                old: old_counts.get(wallet).copied().unwrap_or(0),
+                new: new_counts.get(wallet).copied().unwrap_or(0),
+            };
+            (change.old != change.new).then(|| (wallet.clone(), change))
+        })
+        .collect();
+
+    let moved_mints = old_owners
+        .iter()
+        .filter_map(|(mint, from)| {
+            let to = new_owners.get(mint)?;
+            (to != from).then(|| MovedMint {
+                mint: mint.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            })
+        })
+        .collect();
+
+    let mints = diff_mints(
+        &old_owners.keys().cloned().collect(),
+        &new_owners.keys().cloned().collect(),
+    );
+
+    HolderDiff {
+        new_holders: count_changes
+            .iter()
+            .filter(|(_, change)| change.old == 0)
+            .map(|(wallet, _)| wallet.clone())
+            .collect(),
+        departed_holders: count_changes
+            .iter()
+            .filter(|(_, change)| change.new == 0)
+            .map(|(wallet, _)| wallet.clone())
+            .collect(),
+        count_changes,
+        moved_mints,
+        added_mints: mints.added_mints,
+        removed_mints: mints.removed_mints,
+    }
+}
+
+pub fn diff_token_holders(old: &[TokenHolding], new: &[TokenHolding]) -> TokenHolderDiff {
+    let balances = |holdings: &[TokenHolding]| -> BTreeMap<String, u64> {
+        let mut balances = BTreeMap::new();
+        for holding in holdings {
+            *balances.entry(holding.owner.clone()).or_default() += holding.amount.unwrap_or(0);
+        }
+        balances.retain(|_, balance| *balance > 0);
+        balances
+    };
+
+    let (old_balances, new_balances) = (balances(old), balances(new));
+
+    let wallets: BTreeSet<&String> = old_balances.keys().chain(new_balances.keys()).collect();
+    let balance_changes: BTreeMap<String, TokenBalanceChange> = wallets
+        .into_iter()
+        .filter_map(|wallet| {
+            let change = TokenBalanceChange {
+                old: old_balances.get(wallet).copied().unwrap_or(0),
+                new: new_balances.get(wallet).copied().unwrap_or(0),
+            };
+            (change.old != change.new).then(|| (wallet.clone(), change))
+        })
+        .collect();
+
+    TokenHolderDiff {
+        new_holders: balance_changes
+            .iter()
+            .filter(|(_, change)| change.old == 0)
+            .map(|(wallet, _)| wallet.clone())
+            .collect(),
+        departed_holders: balance_changes
+            .iter()
+            .filter(|(_, change)| change.new == 0)
+            .map(|(wallet, _)| wallet.clone())
+            .collect(),
+        balance_changes,
+    }
+}
+
+pub fn snapshot_diff(args: SnapshotDiffArgs) -> Result<()> {
+    let old = SnapshotFile::load(&args.old)?;
+    let new = SnapshotFile::load(&args.new)?;
+
+    let diff = diff_snapshots(old, new)?;
+
+    match &diff {
+        SnapshotDiff::Holders(diff) => {
+            say!("New holders: {}", diff.new_holders.len());
+            say!("Departed holders: {}", diff.departed_holders.len());
+            say!("Wallets with changed counts: {}", diff.count_changes.len());
+            say!("Mints moved: {}", diff.moved_mints.len());
+            say!("Mints added: {}", diff.added_mints.len());
+            say!("Mints removed: {}", diff.removed_mints.len());
+        }
+        SnapshotDiff::TokenHolders(diff) => {
+            say!("New holders: {}", diff.new_holders.len());
+            say!("Departed holders: {}", diff.departed_holders.len());
+            say!(
+                "Wallets with changed balances: {}",
+                diff.balance_changes.len()
+            );
+        }
+        SnapshotDiff::Mints(diff) => {
+            say!("Mints added: {}", diff.added_mints.len());
+            say!("Mints removed: {}", diff.removed_mints.len());
+        }
+    }
+
+    let file = File::create(&args.output)?;
+    serde_json::to_writer_pretty(file, &diff)?;
+    say!("Diff written to {}", args.output.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holder(owner: &str, mint: &str) -> Holder {
+        Holder {
+            owner: owner.to_string(),
+            mint: mint.to_string(),
+            metadata: format!("{mint}_metadata"),
+            ata: format!("{owner}_{mint}_ata"),
+        }
+    }
+
+    #[test]
+    fn holder_diff_reports_wallets_counts_and_moves() {
+        let old = vec![
+            holder("alice", "m1"),
+            holder("alice", "m2"),
+            holder("bob", "m3"),
+            holder("carol", "m4"),
+        ];
+        let new = vec![
+            holder("alice", "m1"),
+            holder("dave", "m2"),
+            holder("dave", "m3"),
+            holder("carol", "m5"),
+        ];
+
+        let diff = diff_holders(&old, &new);
+
+        assert_eq!(diff.new_holders, vec!["dave"]);
+        assert_eq!(diff.departed_holders, vec!["bob"]);
+        assert_eq!(diff.count_changes["alice"], CountChange { old: 2, new: 1 });
+        assert_eq!(diff.count_changes["dave"], CountChange { old: 0, new: 2 });
+        assert!(!diff.count_changes.contains_key("carol"));
+        assert_eq!(
+            diff.moved_mints,
+            vec![
+                MovedMint {
+                    mint: "m2".into(),
+                    from: "alice".into(),
+                    to: "dave".into()
+                },
+                MovedMint {
+                    mint: "m3".into(),
+                    from: "bob".into(),
+                    to: "dave".into()
+                },
+            ]
+        );
+        assert_eq!(diff.added_mints, vec!["m5"]);
+        assert_eq!(diff.removed_mints, vec!["m4"]);
+    }
+
+    fn holding(owner: &str, address: &str, amount: u64) -> TokenHolding {
+        TokenHolding {
+            owner: owner.to_string(),
+            mint: "token".to_string(),
+            address: address.to_string(),
+            amount: Some(amount),
+        }
+    }
+
+    #[test]
+    fn token_holder_diff_sums_balances_per_wallet() {
+        let old = vec![
+            holding("alice", "a1", 5),
+            holding("alice", "a2", 5),
+            holding("bob", "b1", 3),
+            holding("carol", "c1", 0),
+        ];
+        let new = vec![
+            holding("alice", "a1", 10),
+            holding("bob", "b1", 0),
+            holding("carol", "c1", 7),
+        ];
+
+        let diff = diff_token_holders(&old, &new);
+
+        assert_eq!(diff.new_holders, vec!["carol"]);
+        assert_eq!(diff.departed_holders, vec!["bob"]);
+        assert!(!diff.balance_changes.contains_key("alice"));
+        assert_eq!(
+            diff.balance_changes["bob"],
+            TokenBalanceChange { old: 3, new: 0 }
+        );
+    }
+
+    #[test]
+    fn snapshot_files_are_told_apart() {
+        let holders: SnapshotFile = serde_json::from_str(
+            r#"[{"owner": "alice", "mint": "m1", "metadata": "md1", "ata": "ata1"}]"#,
+        )
+        .unwrap();
+        let mints: SnapshotFile = serde_json::from_str(r#"["m1", "m2"]"#).unwrap();
+        let editions: SnapshotFile = serde_json::from_str(r#"{"1": "m2", "2": "m3"}"#).unwrap();
+
+        let token_holders: SnapshotFile = serde_json::from_str(
+            r#"[{"owner": "alice", "mint": "t1", "address": "ta1", "amount": 5, "delegated_amount": 0, "frozen": false}]"#,
+        )
+        .unwrap();
+
+        assert!(matches!(holders, SnapshotFile::Holders(_)));
+        assert!(matches!(token_holders, SnapshotFile::TokenHolders(_)));
+        assert_eq!(
+            diff_snapshots(mints, editions).unwrap(),
+            SnapshotDiff::Mints(MintDiff {
+                added_mints: vec!["m3".into()],
+                removed_mints: vec!["m1".into()],
+            })
+        );
+    }
+
+    #[test]
+    fn empty_snapshots_compare_with_either_kind() {
+        let empty: SnapshotFile = serde_json::from_str("[]").unwrap();
+        let holders = SnapshotFile::Holders(vec![holder("alice", "m1")]);
+
+        let SnapshotDiff::Holders(diff) = diff_snapshots(empty, holders).unwrap() else {
+            panic!("expected a holder diff");
+        };
+        assert_eq!(diff.new_holders, vec!["alice"]);
+
+        let empty: SnapshotFile = serde_json::from_str("[]").unwrap();
+        assert!(matches!(
+            diff_snapshots(SnapshotFile::Mints(vec!["m1".into()]), empty).unwrap(),
+            SnapshotDiff::Mints(_)
+        ));
+
+        assert!(diff_snapshots(
+            SnapshotFile::Mints(vec!["m1".into()]),
+            SnapshotFile::Holders(vec![holder("alice", "m1")]),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,484 @@
+use anyhow::{anyhow, Result};
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::types::Type,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
+
+use super::{AggregateHolder, Holder, TokenAccount, TokenBalance};
+
+/// The file format snapshot commands write, set with `--format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A pretty-printed JSON document, as the commands have always written.
+    #[default]
+    Json,
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+    Parquet,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" => Ok(ExportFormat::Jsonl),
+            "parquet" => Ok(ExportFormat::Parquet),
+            _ => Err(format!("Invalid format: {s}")),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Json => write!(f, "json"),
+            ExportFormat::Csv => write!(f, "csv"),
+            ExportFormat::Jsonl => write!(f, "jsonl"),
+            ExportFormat::Parquet => write!(f, "parquet"),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// The name of a file called `name` in this format.
+    pub fn file_name(&self, name: &str) -> String {
+        format!("{name}.{self}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    String,
+    UInt64,
+    Float64,
+    Bool,
+}
+
+/// A column of a tabular export.
+#[derive(Debug)]
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+impl Column {
+    pub const fn new(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            nullable: false,
+        }
+    }
+
+    pub const fn nullable(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            nullable: true,
+        }
+    }
+}
+
+/// A row of a snapshot file. Rows serialize to objects whose fields are the
+/// columns; these are part of the documented schemas, so only add to them.
+pub trait Row: Serialize {
+    const COLUMNS: &'static [Column];
+}
+
+impl Row for Holder {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("owner", ColumnType::String),
+        Column::new("mint", ColumnType::String),
+        Column::new("metadata", ColumnType::String),
+        Column::new("ata", ColumnType::String),
+    ];
+}
+
+impl Row for TokenAccount {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("owner", ColumnType::String),
+        Column::new("mint", ColumnType::String),
+        Column::new("address", ColumnType::String),
+        Column::nullable("amount", ColumnType::UInt64),
+        Column::new("delegated_amount", ColumnType::UInt64),
+        Column::new("frozen", ColumnType::Bool),
+    ];
+}
+
+impl Row for TokenBalance {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("owner", ColumnType::String),
+        Column::new("mint", ColumnType::String),
+        Column::new("address", ColumnType::String),
+        Column::new("amount", ColumnType::UInt64),
+    ];
+}
+
+impl Row for AggregateHolder {
+    // The mints are space separated in CSV and Parquet files.
+    const COLUMNS: &'static [Column] = &[
+        Column::new("owner", ColumnType::String),
+        Column::new("count", ColumnType::UInt64),
+        Column::new("weight", ColumnType::Float64),
+        Column::new("mints", ColumnType::String),
+    ];
+}
+
+/// A row of a mint list.
+#[derive(Debug, Serialize)]
+pub struct MintRow<'a> {
+    pub mint: &'a str,
+}
+
+impl Row for MintRow<'_> {
+    const COLUMNS: &'static [Column] = &[Column::new("mint", ColumnType::String)];
+}
+
+/// A row of a print edition list.
+#[derive(Debug, Serialize)]
+pub struct EditionRow<'a> {
+    pub edition: u64,
+    pub mint: &'a str,
+}
+
+impl Row for EditionRow<'_> {
+    const COLUMNS: &'static [Column] = &[
+        Column::new("edition", ColumnType::UInt64),
+        Column::new("mint", ColumnType::String),
+    ];
+}
+
+/// Write `rows` to `path` in `format`.
+pub fn write_rows<R: Row>(path: &Path, format: ExportFormat, rows: &[R]) -> Result<()> {
+    let file =
+        File::create(path).map_err(|e| anyhow!("Failed to create {}: {e}", path.display()))?;
+
+    match format {
+        ExportFormat::Json => serde_json::to_writer_pretty(file, rows)?,
+        ExportFormat::Jsonl => {
+            let mut writer = BufWriter::new(file);
+            for row in rows {
+                serde_json::to_writer(&mut writer, row)?;
+                writeln!(writer)?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Csv => write_csv(file, rows)?,
+        ExportFormat::Parquet => write_parquet(file, rows)?,
+    }
+    Ok(())
+}
+
+/// Write a mint list to `path`; as JSON, a plain list of addresses.
+pub fn write_mints(path: &Path, format: ExportFormat, mints: &[String]) -> Result<()> {
+    if format == ExportFormat::Json {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, mints)?;
+        return Ok(());
+    }
+
+    let rows: Vec<MintRow> = mints.iter().map(|mint| MintRow { mint }).collect();
+    write_rows(path, format, &rows)
+}
+
+/// Write print edition mints to `path`; as JSON, a map of mints by edition.
+pub fn write_editions(
+    path: &Path,
+    format: ExportFormat,
+    editions: &BTreeMap<u64, String>,
+) -> Result<()> {
+    if format == ExportFormat::Json {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, editions)?;
+        return Ok(());
+    }
+
+    let rows: Vec<EditionRow> = editions
+        .iter()
+        .map(|(edition, mint)| EditionRow {
+            edition: *edition,
+            mint,
+        })
+        .collect();
+    write_rows(path, format, &rows)
+}
+
+/// The cells of `rows`, by column.
+fn columns<R: Row>(rows: &[R]) -> Result<Vec<Vec<Value>>> {
+    let mut columns = vec![Vec::with_capacity(rows.len()); R::COLUMNS.len()];
+    for row in rows {
+        let mut object = match serde_json::to_value(row)? {
+            Value::Object(object) => object,
+            other => return Err(anyhow!("Expected a row object, got {other}")),
+        };
+        for (column, cells) in R::COLUMNS.iter().zip(columns.iter_mut()) {
+            cells.push(object.remove(column.name).unwrap_or(Value::Null));
+        }
+    }
+    Ok(columns)
+}
+
+fn cell_string(cell: &Value) -> Option<String> {
+    match cell {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(values) => Some(
+            values
+                .iter()
+                .filter_map(cell_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+fn write_csv<R: Row>(file: File, rows: &[R]) -> Result<()> {
+    let columns = columns(rows)?;
+    let mut writer = csv::Writer::from_writer(file);
+
+    writer.write_record(R::COLUMNS.iter().map(|column| column.name))?;
+    for i in 0..rows.len() {
+        writer.write_record(
+            columns
+                .iter()
+                .map(|cells| cell_string(&cells[i]).unwrap_or_default()),
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn parquet_type(column: &Column) -> Result<Type> {
+    let (physical_type, logical_type) = match column.column_type {
+        ColumnType::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+        ColumnType::UInt64 => (
+            PhysicalType::INT64,
+            Some(LogicalType::Integer {
+                bit_width: 64,
+                is_signed: false,
+            }),
+        ),
+        ColumnType::Float64 => (PhysicalType::DOUBLE, None),
+        ColumnType::Bool => (PhysicalType::BOOLEAN, None),
+    };
+    let repetition = if column.nullable {
+        Repetition::OPTIONAL
+    } else {
+        Repetition::REQUIRED
+    };
+
+    Ok(Type::primitive_type_builder(column.name, physical_type)
+        .with_repetition(repetition)
+        .with_logical_type(logical_type)
+        .build()?)
+}
+
+fn write_parquet<R: Row>(file: File, rows: &[R]) -> Result<()> {
+    let fields = R::COLUMNS
+        .iter()
+        .map(|column| parquet_type(column).map(Arc::new))
+        .collect::<Result<Vec<_>>>()?;
+    let schema = Arc::new(
+        Type::group_type_builder("snapshot")
+            .with_fields(fields)
+            .build()?,
+    );
+
+    let mut writer =
+        SerializedFileWriter::new(file, schema, Arc::new(WriterProperties::builder().build()))?;
+    let mut row_group = writer.next_row_group()?;
+
+    for (column, cells) in R::COLUMNS.iter().zip(columns(rows)?) {
+        let mut column_writer = row_group
+            .next_column()?
+            .ok_or_else(|| anyhow!("Parquet schema is missing column {}", column.name))?;
+
+        // Optional columns mark each cell present or null; the values leave nulls out.
+        let levels: Option<Vec<i16>> = column.nullable.then(|| {
+            cells
+                .iter()
+                .map(|cell| i16::from(!cell.is_null()))
+                .collect()
+        });
+        let levels = levels.as_deref();
+        let cells = cells.iter().filter(|cell| !cell.is_null());
+        let invalid = |cell: &Value| anyhow!("Invalid {} value: {cell}", column.name);
+
+        match column.column_type {
+            ColumnType::String => {
+                let values: Vec<ByteArray> = cells
+                    .map(|cell| ByteArray::from(cell_string(cell).unwrap_or_default().as_str()))
+                    .collect();
+                column_writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, levels, None)?;
+            }
+            ColumnType::UInt64 => {
+                // Unsigned 64 bit integers are stored in INT64 columns.
+                let values = cells
+                    .map(|cell| cell.as_u64().map(|n| n as i64).ok_or_else(|| invalid(cell)))
+                    .collect::<Result<Vec<_>>>()?;
+                column_writer
+                    .typed::<Int64Type>()
+                    .write_batch(&values, levels, None)?;
+            }
+            ColumnType::Float64 => {
+                let values = cells
+                    .map(|cell| cell.as_f64().ok_or_else(|| invalid(cell)))
+                    .collect::<Result<Vec<_>>>()?;
+                column_writer
+                    .typed::<DoubleType>()
+                    .write_batch(&values, levels, None)?;
+            }
+            ColumnType::Bool => {
+                let values = cells
+                    .map(|cell| cell.as_bool().ok_or_else(|| invalid(cell)))
+                    .collect::<Result<Vec<_>>>()?;
+                column_writer
+                    .typed::<BoolType>()
+                    .write_batch(&values, levels, None)?;
+            }
+        }
+        column_writer.close()?;
+    }
+
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::RowAccessor,
+    };
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mb-export-test-{}-{name}", std::process::id()))
+    }
+
+    fn token_account(owner: &str, amount: Option<u64>) -> TokenAccount {
+        TokenAccount {
+            owner: owner.to_string(),
+            mint: "m1".to_string(),
+            address: format!("{owner}_ata"),
+            amount,
+            delegated_amount: 0,
+            frozen: false,
+        }
+    }
+
+    #[test]
+    fn formats_parse_and_name_files() {
+        assert_eq!(
+            "parquet".parse::<ExportFormat>().unwrap(),
+            ExportFormat::Parquet
+        );
+        assert!("xlsx".parse::<ExportFormat>().is_err());
+        assert_eq!(
+            ExportFormat::Jsonl.file_name("abc_mints"),
+            "abc_mints.jsonl"
+        );
+    }
+
+    #[test]
+    fn csv_and_jsonl_follow_the_column_schema() {
+        let holders = vec![AggregateHolder {
+            owner: "alice".into(),
+            count: 2,
+            weight: 1.5,
+            mints: vec!["m1".into(), "m2".into()],
+        }];
+        let csv_path = temp_path("aggregate.csv");
+        let jsonl_path = temp_path("mints.jsonl");
+
+        write_rows(&csv_path, ExportFormat::Csv, &holders).unwrap();
+        write_mints(
+            &jsonl_path,
+            ExportFormat::Jsonl,
+            &["m1".to_string(), "m2".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&csv_path).unwrap(),
+            "owner,count,weight,mints\nalice,2,1.5,m1 m2\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&jsonl_path).unwrap(),
+            "{\"mint\":\"m1\"}\n{\"mint\":\"m2\"}\n"
+        );
+        std::fs::remove_file(csv_path).unwrap();
+        std::fs::remove_file(jsonl_path).unwrap();
+    }
+
+    #[test]
+    fn json_keeps_the_original_shapes() {
+        let path = temp_path("editions.json");
+        let editions = BTreeMap::from([(1, "m1".to_string()), (2, "m2".to_string())]);
+
+        write_editions(&path, ExportFormat::Json, &editions).unwrap();
+
+        let written: Value = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!({"1": "m1", "2": "m2"}));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn parquet_files_read_back_with_nulls() {
+        let path = temp_path("token_holders.parquet");
+        let accounts = vec![token_account("alice", Some(5)), token_account("bob", None)];
+
+        write_rows(&path, ExportFormat::Parquet, &accounts).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let names: Vec<&str> = schema.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "owner",
+                "mint",
+                "address",
+                "amount",
+                "delegated_amount",
+                "frozen"
+            ]
+        );
+
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get_string(0).unwrap(), "alice");
+        assert_eq!(rows[0].get_ulong(3).unwrap(), 5);
+        assert!(rows[1].get_ulong(3).is_err());
+        assert!(!rows[1].get_bool(5).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+}
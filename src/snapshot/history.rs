@@ -0,0 +1,606 @@
+use anyhow::{anyhow, bail, Result};
+use metaboss_lib::derive::derive_metadata_pda;
+use retry::{delay::Exponential, retry};
+use serde::Serialize;
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status_client_types::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    EncodedTransaction, UiMessage, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::Display,
+    fs::{self, File},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use super::Holder;
+
+/// The point in time a historical snapshot is taken at: the state after the
+/// given slot, or after the last block produced at or before the given time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cutoff {
+    Slot(u64),
+    Time(i64),
+}
+
+impl Cutoff {
+    /// The cutoff chosen with `--at-slot` or `--at-time`, if any.
+    pub fn new(at_slot: Option<u64>, at_time: Option<Timestamp>) -> Result<Option<Self>> {
+        match (at_slot, at_time) {
+            (Some(_), Some(_)) => bail!("Only one of --at-slot and --at-time can be given"),
+            (Some(slot), None) => Ok(Some(Cutoff::Slot(slot))),
+            (None, Some(time)) => Ok(Some(Cutoff::Time(time.0))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Whether a transaction in `slot`, produced at `block_time`, had landed by the cutoff.
+    pub fn includes(&self, slot: u64, block_time: Option<i64>) -> bool {
+        match self {
+            Cutoff::Slot(cutoff) => slot <= *cutoff,
+            // Only the earliest blocks lack a time, and they predate any cutoff.
+            Cutoff::Time(cutoff) => block_time.is_none_or(|time| time <= *cutoff),
+        }
+    }
+}
+
+impl Display for Cutoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cutoff::Slot(slot) => write!(f, "slot_{slot}"),
+            Cutoff::Time(time) => write!(f, "time_{time}"),
+        }
+    }
+}
+
+/// A unix timestamp in seconds, given either as a number or as an RFC 3339
+/// date such as `2024-05-01T00:00:00Z`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timestamp(pub i64);
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(seconds) = s.parse::<i64>() {
+            return Ok(Timestamp(seconds));
+        }
+        chrono::DateTime::parse_from_rfc3339(s)
+            .map(|time| Timestamp(time.timestamp()))
+            .map_err(|_| format!("Invalid time: {s}; use unix seconds or RFC 3339"))
+    }
+}
+
+/// The balance a token account held of a mint.
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Serialize)]
+pub struct TokenBalance {
+    pub owner: String,
+    pub mint: String,
+    pub address: String,
+    pub amount: u64,
+}
+
+/// The balances of one mint's token accounts before and after a transaction.
+#[derive(Debug, Default)]
+pub struct BalanceChange {
+    pub pre: Vec<TokenBalance>,
+    pub post: Vec<TokenBalance>,
+}
+
+/// A transaction in an address's history.
+#[derive(Debug)]
+pub struct SignatureEntry {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+}
+
+/// Where transaction history is read from.
+pub trait TransactionHistory {
+    /// Up to a page of transactions touching `address`, newest first, older
+    /// than `before` when it's given.
+    fn signatures(&self, address: &str, before: Option<&str>) -> Result<Vec<SignatureEntry>>;
+
+    /// How `signature` changed the balances of `mint`'s token accounts.
+    fn balance_change(&self, signature: &str, mint: &str) -> Result<BalanceChange>;
+}
+
+/// The non-zero balances of `mint` at the cutoff.
+///
+/// Each address in `start`, which should include every account holding the
+/// mint now, and the mint itself are walked back from the newest
+/// transaction. Token accounts of the mint seen in a transaction after the
+/// cutoff are walked too, which finds accounts that held the mint then but
+/// have since sent it on, burned it or been closed. An account's balance at
+/// the cutoff is its balance after its last transaction at or before it.
+pub fn balances_at<H: TransactionHistory>(
+    history: &H,
+    mint: &str,
+    start: Vec<String>,
+    cutoff: Cutoff,
+) -> Result<Vec<TokenBalance>> {
+    let mut queue: VecDeque<String> = VecDeque::new();
+    let mut seen = HashSet::new();
+    for address in start.into_iter().chain([mint.to_string()]) {
+        if seen.insert(address.clone()) {
+            queue.push_back(address);
+        }
+    }
+
+    let mut balances = BTreeMap::new();
+    while let Some(address) = queue.pop_front() {
+        let mut before: Option<String> = None;
+        'pages: loop {
+            let page = history.signatures(&address, before.as_deref())?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(last.signature.clone());
+
+            for entry in page.iter().filter(|entry| !entry.failed) {
+                let change = history.balance_change(&entry.signature, mint)?;
+
+                if cutoff.includes(entry.slot, entry.block_time) {
+                    if let Some(balance) = change.post.into_iter().find(|b| b.address == address) {
+                        if balance.amount > 0 {
+                            balances.insert(address.clone(), balance);
+                        }
+                    }
+                    break 'pages;
+                }
+
+                for balance in change.pre.iter().chain(change.post.iter()) {
+                    if seen.insert(balance.address.clone()) {
+                        queue.push_back(balance.address.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(balances.into_values().collect())
+}
+
+/// How a fetched transaction changed the balances of `mint`'s token accounts.
+pub fn token_balances(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    mint: &str,
+) -> Result<BalanceChange> {
+    let Some(meta) = &tx.transaction.meta else {
+        return Ok(BalanceChange::default());
+    };
+
+    let (mut account_keys, lists_loaded) = match &tx.transaction.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(message) => (message.account_keys.clone(), false),
+            // Parsed messages list lookup table accounts along with the rest.
+            UiMessage::Parsed(message) => (
+                message
+                    .account_keys
+                    .iter()
+                    .map(|key| key.pubkey.clone())
+                    .collect(),
+                true,
+            ),
+        },
+        encoded => (
+            encoded
+                .decode()
+                .ok_or_else(|| anyhow!("Transaction could not be decoded"))?
+                .message
+                .static_account_keys()
+                .iter()
+                .map(Pubkey::to_string)
+                .collect(),
+            false,
+        ),
+    };
+    if let (OptionSerializer::Some(loaded), false) = (&meta.loaded_addresses, lists_loaded) {
+        account_keys.extend(loaded.writable.iter().cloned());
+        account_keys.extend(loaded.readonly.iter().cloned());
+    }
+
+    let collect = |balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>| {
+        let OptionSerializer::Some(balances) = balances else {
+            return Ok(vec![]);
+        };
+        balances
+            .iter()
+            .filter(|balance| balance.mint == mint)
+            .map(|balance| {
+                let address = account_keys
+                    .get(balance.account_index as usize)
+                    .ok_or_else(|| anyhow!("Token balance for a missing account"))?;
+                Ok(TokenBalance {
+                    owner: Option::<String>::from(balance.owner.clone()).unwrap_or_default(),
+                    mint: balance.mint.clone(),
+                    address: address.clone(),
+                    amount: balance.ui_token_amount.amount.parse()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+
+    Ok(BalanceChange {
+        pre: collect(&meta.pre_token_balances)?,
+        post: collect(&meta.post_token_balances)?,
+    })
+}
+
+/// Fetched transactions, stored as JSON files named by signature. Finalized
+/// transactions never change, so a snapshot can be re-run without fetching
+/// them again.
+pub struct TransactionCache {
+    dir: PathBuf,
+}
+
+impl TransactionCache {
+    /// A cache in `dir`, or in the user's cache directory when none is given.
+    pub fn new(dir: Option<PathBuf>) -> Result<Self> {
+        let dir = match dir {
+            Some(dir) => dir,
+            None => dirs::cache_dir()
+                .ok_or_else(|| anyhow!("No cache directory found; pass --tx-cache"))?
+                .join("metaboss")
+                .join("transactions"),
+        };
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The transaction `signature`, from the cache or else from the RPC.
+    pub fn get_or_fetch(
+        &self,
+        client: &RpcClient,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+        let path = self.dir.join(format!("{signature}.json"));
+        if let Ok(file) = File::open(&path) {
+            if let Ok(tx) = serde_json::from_reader(file) {
+                return Ok(tx);
+            }
+        }
+
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+        let signature = Signature::from_str(signature)?;
+        let tx = retry(
+            Exponential::from_millis_with_factor(250, 2.0).take(3),
+            || client.get_transaction_with_config(&signature, config),
+        )?;
+
+        // Written under another name first so a parallel reader never sees half a file.
+        let partial = path.with_extension(format!("{}.partial", std::process::id()));
+        serde_json::to_writer(File::create(&partial)?, &tx)?;
+        fs::rename(&partial, &path)?;
+
+        Ok(tx)
+    }
+}
+
+/// Transaction history read from an RPC node through a cache.
+pub struct RpcHistory<'a> {
+    pub client: &'a RpcClient,
+    pub cache: &'a TransactionCache,
+}
+
+impl TransactionHistory for RpcHistory<'_> {
+    fn signatures(&self, address: &str, before: Option<&str>) -> Result<Vec<SignatureEntry>> {
+        let address = Pubkey::from_str(address)?;
+        let before = before.map(Signature::from_str).transpose()?;
+        let page = retry(
+            Exponential::from_millis_with_factor(250, 2.0).take(3),
+            || {
+                self.client.get_signatures_for_address_with_config(
+                    &address,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: None,
+                        commitment: Some(CommitmentConfig::finalized()),
+                    },
+                )
+            },
+        )?;
+
+        Ok(page
+            .into_iter()
+            .map(|status| SignatureEntry {
+                signature: status.signature,
+                slot: status.slot,
+                block_time: status.block_time,
+                failed: status.err.is_some(),
+            })
+            .collect())
+    }
+
+    fn balance_change(&self, signature: &str, mint: &str) -> Result<BalanceChange> {
+        let tx = self.cache.get_or_fetch(self.client, signature)?;
+        token_balances(&tx, mint)
+    }
+}
+
+/// The settings of a snapshot taken at a past slot or time.
+pub struct HistoricalSnapshot {
+    pub cutoff: Cutoff,
+    pub cache: TransactionCache,
+}
+
+impl HistoricalSnapshot {
+    pub fn new(
+        at_slot: Option<u64>,
+        at_time: Option<Timestamp>,
+        tx_cache: Option<PathBuf>,
+    ) -> Result<Option<Self>> {
+        Cutoff::new(at_slot, at_time)?
+            .map(|cutoff| {
+                Ok(Self {
+                    cutoff,
+                    cache: TransactionCache::new(tx_cache)?,
+                })
+            })
+            .transpose()
+    }
+
+    /// The balances of `mint` at the cutoff, walking back from the token
+    /// accounts in `start`.
+    pub fn balances(
+        &self,
+        client: &RpcClient,
+        mint: &Pubkey,
+        start: Vec<String>,
+    ) -> Result<Vec<TokenBalance>> {
+        let history = RpcHistory {
+            client,
+            cache: &self.cache,
+        };
+        balances_at(&history, &mint.to_string(), start, self.cutoff)
+    }
+
+    /// The holders of the NFT `mint` at the cutoff. The walk starts from its
+    /// largest token accounts, which include its current holder's.
+    pub fn nft_holders(&self, client: &RpcClient, mint: &Pubkey) -> Result<Vec<Holder>> {
+        let start = retry(
+            Exponential::from_millis_with_factor(250, 2.0).take(3),
+            || client.get_token_largest_accounts(mint),
+        )?
+        .into_iter()
+        .map(|account| account.address)
+        .collect();
+
+        let metadata = derive_metadata_pda(mint).to_string();
+        Ok(self
+            .balances(client, mint, start)?
+            .into_iter()
+            .map(|balance| Holder {
+                owner: balance.owner,
+                mint: balance.mint,
+                metadata: metadata.clone(),
+                ata: balance.address,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const MINT: &str = "Mint111111111111111111111111111111111111111";
+
+    /// Transactions as (signature, slot, balances before, balances after),
+    /// where a balance is (token account, owner, amount).
+    type Fixture<'a> = [(
+        &'a str,
+        u64,
+        &'a [(&'a str, &'a str, u64)],
+        &'a [(&'a str, &'a str, u64)],
+    )];
+
+    struct MockHistory {
+        signatures: HashMap<String, Vec<(String, u64)>>,
+        changes: HashMap<String, BalanceChange>,
+    }
+
+    impl MockHistory {
+        fn new(transactions: &Fixture) -> Self {
+            let balances = |list: &[(&str, &str, u64)]| {
+                list.iter()
+                    .map(|(address, owner, amount)| TokenBalance {
+                        owner: owner.to_string(),
+                        mint: MINT.to_string(),
+                        address: address.to_string(),
+                        amount: *amount,
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            let mut signatures: HashMap<String, Vec<(String, u64)>> = HashMap::new();
+            let mut changes = HashMap::new();
+            for (signature, slot, pre, post) in transactions {
+                let change = BalanceChange {
+                    pre: balances(pre),
+                    post: balances(post),
+                };
+                let mut touched: Vec<String> = change
+                    .pre
+                    .iter()
+                    .chain(change.post.iter())
+                    .map(|b| b.address.clone())
+                    .collect();
+                touched.sort();
+                touched.dedup();
+                touched.push(MINT.to_string());
+                for address in touched {
+                    signatures
+                        .entry(address)
+                        .or_default()
+                        .push((signature.to_string(), *slot));
+                }
+                changes.insert(signature.to_string(), change);
+            }
+            for history in signatures.values_mut() {
+                history.sort_by_key(|(_, slot)| std::cmp::Reverse(*slot));
+            }
+
+            Self {
+                signatures,
+                changes,
+            }
+        }
+    }
+
+    impl TransactionHistory for MockHistory {
+        fn signatures(&self, address: &str, before: Option<&str>) -> Result<Vec<SignatureEntry>> {
+            let history = self.signatures.get(address).cloned().unwrap_or_default();
+            let start = before.map_or(0, |before| {
+                history.iter().position(|(s, _)| s == before).unwrap() + 1
+            });
+            // Pages of one, to exercise paging.
+            Ok(history
+                .into_iter()
+                .skip(start)
+                .take(1)
+                .map(|(signature, slot)| SignatureEntry {
+                    signature,
+                    slot,
+                    block_time: Some(slot as i64 * 10),
+                    failed: false,
+                })
+                .collect())
+        }
+
+        fn balance_change(&self, signature: &str, _mint: &str) -> Result<BalanceChange> {
+            let change = &self.changes[signature];
+            Ok(BalanceChange {
+                pre: change.pre.clone(),
+                post: change.post.clone(),
+            })
+        }
+    }
+
+    // alice mints at slot 10, sends to bob at 20, bob sends to carol at 30
+    // and closes his account.
+    const TRANSFERS: &Fixture = &[
+        ("mint", 10, &[], &[("alice_ata", "alice", 1)]),
+        (
+            "to_bob",
+            20,
+            &[("alice_ata", "alice", 1)],
+            &[("alice_ata", "alice", 0), ("bob_ata", "bob", 1)],
+        ),
+        (
+            "to_carol",
+            30,
+            &[("bob_ata", "bob", 1)],
+            &[("bob_ata", "bob", 0), ("carol_ata", "carol", 1)],
+        ),
+        ("close_bob", 31, &[("bob_ata", "bob", 0)], &[]),
+    ];
+
+    fn owners(balances: Vec<TokenBalance>) -> Vec<String> {
+        balances.into_iter().map(|b| b.owner).collect()
+    }
+
+    #[test]
+    fn holders_are_found_at_each_point_in_history() {
+        let history = MockHistory::new(TRANSFERS);
+        let start = vec!["carol_ata".to_string()];
+
+        let at = |cutoff| owners(balances_at(&history, MINT, start.clone(), cutoff).unwrap());
+
+        assert_eq!(at(Cutoff::Slot(5)), Vec::<String>::new());
+        assert_eq!(at(Cutoff::Slot(15)), vec!["alice"]);
+        assert_eq!(at(Cutoff::Slot(25)), vec!["bob"]);
+        assert_eq!(at(Cutoff::Slot(30)), vec!["carol"]);
+        assert_eq!(at(Cutoff::Time(250)), vec!["bob"]);
+    }
+
+    #[test]
+    fn burned_tokens_are_found_through_the_mint() {
+        let mut transactions = TRANSFERS.to_vec();
+        transactions.push(("burn", 40, &[("carol_ata", "carol", 1)], &[]));
+        let history = MockHistory::new(&transactions);
+
+        let holders = balances_at(&history, MINT, vec![], Cutoff::Slot(35)).unwrap();
+
+        assert_eq!(owners(holders), vec!["carol"]);
+    }
+
+    #[test]
+    fn cutoff_parses_from_either_option() {
+        assert_eq!(Cutoff::new(None, None).unwrap(), None);
+        assert_eq!(Cutoff::new(Some(5), None).unwrap(), Some(Cutoff::Slot(5)));
+        assert!(Cutoff::new(Some(5), Some(Timestamp(1))).is_err());
+
+        assert_eq!("1714521600".parse::<Timestamp>().unwrap().0, 1714521600);
+        assert_eq!(
+            "2024-05-01T00:00:00Z".parse::<Timestamp>().unwrap().0,
+            1714521600
+        );
+        assert!("yesterday".parse::<Timestamp>().is_err());
+    }
+
+    #[test]
+    fn token_balances_map_indexes_to_addresses() {
+        let tx: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(serde_json::json!({
+            "slot": 20,
+            "blockTime": 200,
+            "transaction": {
+                "signatures": ["sig"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": ["alice", "alice_ata", "program"],
+                    "recentBlockhash": "hash",
+                    "instructions": []
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "preTokenBalances": [
+                    { "accountIndex": 1, "mint": MINT, "owner": "alice",
+                      "uiTokenAmount": { "uiAmount": 1.0, "decimals": 0, "amount": "1", "uiAmountString": "1" } }
+                ],
+                "postTokenBalances": [
+                    { "accountIndex": 1, "mint": MINT, "owner": "alice",
+                      "uiTokenAmount": { "uiAmount": 0.0, "decimals": 0, "amount": "0", "uiAmountString": "0" } },
+                    { "accountIndex": 3, "mint": MINT, "owner": "bob",
+                      "uiTokenAmount": { "uiAmount": 1.0, "decimals": 0, "amount": "1", "uiAmountString": "1" } },
+                    { "accountIndex": 1, "mint": "OtherMint", "owner": "alice",
+                      "uiTokenAmount": { "uiAmount": 5.0, "decimals": 0, "amount": "5", "uiAmountString": "5" } }
+                ],
+                "loadedAddresses": { "writable": ["bob_ata"], "readonly": [] }
+            }
+        }))
+        .unwrap();
+
+        let change = token_balances(&tx, MINT).unwrap();
+
+        assert_eq!(change.pre.len(), 1);
+        assert_eq!(change.post[0].address, "alice_ata");
+        assert_eq!(change.post[0].amount, 0);
+        assert_eq!(change.post[1].address, "bob_ata");
+        assert_eq!(change.post[1].owner, "bob");
+        assert_eq!(change.post.len(), 2);
+    }
+}
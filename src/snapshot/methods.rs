@@ -6,8 +6,7 @@ use super::*;
 
 use crate::data::Indexers;
 use crate::derive::{derive_cmv2_pda, derive_cmv3_pda};
-use crate::limiter::create_default_rate_limiter;
-use crate::limiter::create_rate_limiter;
+use crate::limiter::Pacer;
 use crate::parse::{creator_is_verified, is_only_one_option};
 use crate::spinner::*;
 use crate::theindexio;
@@ -114,7 +113,7 @@ pub fn get_mint_accounts(
     spinner.finish();
 
     info!("Getting metadata and writing to file...");
-    println!("Getting metadata and writing to file...");
+    say!("Getting metadata and writing to file...");
     let mut mint_accounts: Vec<String> = Vec::new();
 
     for (pubkey, account) in accounts {
@@ -135,9 +134,6 @@ pub fn get_mint_accounts(
 }
 
 pub fn snapshot_holders_gpa(client: RpcClient, args: SnapshotHoldersGpaArgs) -> Result<()> {
-    let use_rate_limit = *USE_RATE_LIMIT.read().unwrap();
-    let handle = create_default_rate_limiter();
-
     let spinner = create_spinner("Getting accounts...");
     let accounts = if let Some(ref update_authority) = args.update_authority {
         get_mints_by_update_authority(&client, update_authority)?
@@ -165,19 +161,23 @@ pub fn snapshot_holders_gpa(client: RpcClient, args: SnapshotHoldersGpaArgs) ->
     };
     spinner.finish_with_message("Getting accounts...Done!");
 
-    info!("Finding current holders...");
-    println!("Finding current holders...");
+    match &args.history {
+        Some(history) => {
+            info!("Finding holders at {}...", history.cutoff);
+            say!("Finding holders at {}...", history.cutoff);
+        }
+        None => {
+            info!("Finding current holders...");
+            say!("Finding current holders...");
+        }
+    }
     let nft_holders: Arc<Mutex<Vec<Holder>>> = Arc::new(Mutex::new(Vec::new()));
+    let failed_mints: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
     accounts
         .par_iter()
-        .progress()
+        .progress_with(new_progress_bar(accounts.len() as u64))
         .for_each(|(metadata_pubkey, account)| {
-            let mut handle = handle.clone();
-            if use_rate_limit {
-                handle.wait();
-            }
-
             let nft_holders = nft_holders.clone();
 
             let metadata: Metadata = match Metadata::deserialize(&mut account.data.as_slice()) {
@@ -193,6 +193,25 @@ pub fn snapshot_holders_gpa(client: RpcClient, args: SnapshotHoldersGpaArgs) ->
                 return;
             }
 
+            if let Some(history) = &args.history {
+                match history.nft_holders(&client, &metadata.mint) {
+                    Ok(holders) => {
+                        nft_holders
+                            .lock()
+                            .unwrap()
+                            .extend(holders.into_iter().map(|holder| Holder {
+                                metadata: metadata_pubkey.to_string(),
+                                ..holder
+                            }))
+                    }
+                    Err(err) => {
+                        error!("Holders of {} not found: {}", metadata.mint, err);
+                        failed_mints.lock().unwrap().push(metadata.mint.to_string());
+                    }
+                }
+                return;
+            }
+
             let token_accounts = match retry(
                 Exponential::from_millis_with_factor(250, 2.0).take(3),
                 || get_holder_token_accounts(&client, metadata.mint.to_string()),
@@ -264,11 +283,26 @@ pub fn snapshot_holders_gpa(client: RpcClient, args: SnapshotHoldersGpaArgs) ->
         ));
     };
 
+    let file_name = match &args.history {
+        Some(history) => format!("{}_holders_at_{}.json", prefix, history.cutoff),
+        None => format!("{}_holders.json", prefix),
+    };
+
     nft_holders.lock().unwrap().sort_unstable();
-    let mut file = File::create(format!("{}/{}_holders.json", args.output, prefix))?;
+    let mut file = File::create(format!("{}/{}", args.output, file_name))?;
     let holders = nft_holders.lock().unwrap();
     serde_json::to_writer_pretty(&mut file, &*holders)?;
 
+    // A historical snapshot missing mints can't be trusted, so say which.
+    let failed_mints = failed_mints.into_inner().unwrap();
+    if !failed_mints.is_empty() {
+        return Err(anyhow!(
+            "Holders of {} mints could not be found; rerun to retry them: {}",
+            failed_mints.len(),
+            failed_mints.join(", ")
+        ));
+    }
+
     Ok(())
 }
 
@@ -287,17 +321,15 @@ pub async fn snapshot_indexed_holders(args: NftsByCreatorArgs) -> Result<()> {
         ..
     } = args;
 
-    let delay = 1_000_000;
-
-    let mut handle = create_rate_limiter(delay);
+    let pacer = Pacer::new(1_000);
 
-    println!("Found {} mints", md_results.len());
+    say!("Found {} mints", md_results.len());
 
     // Create a vector of futures to execute.
     let spinner = create_alt_spinner("Sending network requests....");
     let mut tasks = Vec::new();
     for md in md_results {
-        handle.wait();
+        pacer.wait();
         tasks.push(tokio::spawn(get_holder_from_gpa_result(
             api_key.clone(),
             md,
@@ -305,7 +337,7 @@ pub async fn snapshot_indexed_holders(args: NftsByCreatorArgs) -> Result<()> {
     }
     spinner.finish();
 
-    println!("Tasks created: {}", tasks.len());
+    say!("Tasks created: {}", tasks.len());
 
     // Wait for all the tasks to resolve and push the results to our results vector
     let spinner = create_alt_spinner("Awaiting results....");
@@ -319,11 +351,11 @@ pub async fn snapshot_indexed_holders(args: NftsByCreatorArgs) -> Result<()> {
     // Partition decode results.
     let (successful_results, failed_results): (HolderResults, HolderResults) =
         task_results.into_iter().partition(Result::is_ok);
-    println!("Found {} successful results", successful_results.len());
-    println!("Found {} failed results", failed_results.len());
+    say!("Found {} successful results", successful_results.len());
+    say!("Found {} failed results", failed_results.len());
 
     if !failed_results.is_empty() {
-        println!("Failed results: {:?}", failed_results[0]);
+        say!("Failed results: {:?}", failed_results[0]);
         let errors = failed_results
             .into_iter()
             .map(Result::unwrap_err)
@@ -335,7 +367,7 @@ pub async fn snapshot_indexed_holders(args: NftsByCreatorArgs) -> Result<()> {
 
     // Unwrap sucessful
     let nft_holders: Vec<Holder> = successful_results.into_iter().map(Result::unwrap).collect();
-    println!("Found {} holders", nft_holders.len());
+    say!("Found {} holders", nft_holders.len());
 
     let mut file = File::create(format!("{output}/{creator}_holders.json"))?;
     serde_json::to_writer_pretty(&mut file, &nft_holders)?;
@@ -422,18 +454,10 @@ fn get_mint_account_infos(
     client: &RpcClient,
     mint_accounts: Vec<String>,
 ) -> Result<Vec<(Pubkey, Account)>> {
-    let use_rate_limit = *USE_RATE_LIMIT.read().unwrap();
-    let handle = create_default_rate_limiter();
-
     let address_account_pairs: Arc<Mutex<Vec<(Pubkey, Account)>>> =
         Arc::new(Mutex::new(Vec::new()));
 
     mint_accounts.par_iter().for_each(|mint_account| {
-        let mut handle = handle.clone();
-        if use_rate_limit {
-            handle.wait();
-        }
-
         let mint_pubkey = match Pubkey::from_str(mint_account) {
             Ok(pubkey) => pubkey,
             Err(_) => {
@@ -29,15 +29,23 @@ pub use std::{
     sync::{Arc, Mutex},
 };
 
+mod aggregate;
 mod das_api;
 mod data;
+mod diff;
+mod export;
+mod history;
 mod indexer_methods;
 mod methods;
 mod print_editions;
 mod process;
 
+pub use aggregate::*;
 pub use das_api::*;
 pub use data::*;
+pub use diff::*;
+pub use export::*;
+pub use history::*;
 pub use indexer_methods::*;
 pub use methods::*;
 pub use print_editions::*;
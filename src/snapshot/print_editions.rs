@@ -6,6 +6,7 @@ use metaboss_lib::{
 };
 use mpl_token_metadata::accounts::Edition;
 use rayon::prelude::*;
+use std::path::Path;
 
 use crate::spinner::create_spinner;
 
@@ -26,6 +27,7 @@ pub struct SnapshotPrintEditionsArgs {
     pub master_mint: String,
     pub creator: Option<String>,
     pub output: String,
+    pub format: ExportFormat,
 }
 
 pub async fn snapshot_print_editions(args: SnapshotPrintEditionsArgs) -> Result<()> {
@@ -89,14 +91,15 @@ pub async fn snapshot_print_editions(args: SnapshotPrintEditionsArgs) -> Result<
         .map(|(k, v)| (k, v.to_string()))
         .collect();
 
-    println!("Found {} editions", edition_mints.len());
+    say!("Found {} editions", edition_mints.len());
 
-    println!("Writing to file...");
-    let mut file = File::create(format!(
-        "{}/{}_mint_accounts.json",
-        args.output, &args.master_mint
-    ))?;
-    serde_json::to_writer_pretty(&mut file, &edition_mints)?;
+    say!("Writing to file...");
+    let name = format!("{}_mint_accounts", args.master_mint);
+    write_editions(
+        &Path::new(&args.output).join(args.format.file_name(&name)),
+        args.format,
+        &edition_mints,
+    )?;
 
     Ok(())
 }
@@ -17,13 +17,34 @@ pub async fn process_snapshot(
             group_value,
             output,
             delay,
+            at_slot,
+            at_time,
+            tx_cache,
+            aggregate,
+            exclude,
+            exclude_file,
+            weights,
+            traits,
+            trait_source,
+            format,
         } => {
             snapshot_holders(HoldersArgs {
+                client,
                 rpc_url,
                 group_key,
                 group_value,
                 output,
                 delay,
+                format,
+                history: HistoricalSnapshot::new(at_slot, at_time, tx_cache)?,
+                aggregate: AggregateOptions::new(
+                    aggregate,
+                    exclude,
+                    exclude_file,
+                    weights,
+                    traits,
+                    trait_source,
+                )?,
             })
             .await
         }
@@ -33,6 +54,7 @@ pub async fn process_snapshot(
             creator_position,
             output,
             delay,
+            format,
         } => {
             snapshot_mints(MintsArgs {
                 rpc_url,
@@ -41,6 +63,7 @@ pub async fn process_snapshot(
                 creator_position,
                 output,
                 delay,
+                format,
             })
             .await
         }
@@ -48,12 +71,14 @@ pub async fn process_snapshot(
             creator,
             output,
             delay,
+            format,
         } => {
             fcva_mints(FcvaArgs {
                 rpc_url,
                 creator,
                 output,
                 delay,
+                format,
             })
             .await
         }
@@ -61,12 +86,14 @@ pub async fn process_snapshot(
             mcc_id,
             output,
             delay,
+            format,
         } => {
             mcc_mints(MccArgs {
                 rpc_url,
                 mcc_id,
                 output,
                 delay,
+                format,
             })
             .await
         }
@@ -99,6 +126,9 @@ pub async fn process_snapshot(
             v3,
             allow_unverified,
             output,
+            at_slot,
+            at_time,
+            tx_cache,
         } => snapshot_holders_gpa(
             client,
             SnapshotHoldersGpaArgs {
@@ -110,18 +140,24 @@ pub async fn process_snapshot(
                 v3,
                 allow_unverified,
                 output,
+                history: HistoricalSnapshot::new(at_slot, at_time, tx_cache)?,
             },
         ),
+        SnapshotSubcommands::Diff { old, new, output } => {
+            snapshot_diff(SnapshotDiffArgs { old, new, output })
+        }
         SnapshotSubcommands::Prints {
             master_mint,
             creator,
             output,
+            format,
         } => {
             snapshot_print_editions(SnapshotPrintEditionsArgs {
                 client,
                 master_mint,
                 creator,
                 output,
+                format,
             })
             .await
         }
@@ -1,7 +1,27 @@
 use indicatif::{ProgressBar, ProgressStyle};
 
+use crate::output::json_output;
+
+/// A spinner that draws nothing when the output is JSON.
+pub fn new_spinner() -> ProgressBar {
+    if json_output() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new_spinner()
+    }
+}
+
+/// A progress bar of `len` steps that draws nothing when the output is JSON.
+pub fn new_progress_bar(len: u64) -> ProgressBar {
+    if json_output() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(len)
+    }
+}
+
 pub fn create_spinner(msg: &'static str) -> ProgressBar {
-    let spinner = ProgressBar::new_spinner();
+    let spinner = new_spinner();
     spinner.enable_steady_tick(10);
     spinner.set_style(
         ProgressStyle::default_spinner()
@@ -13,7 +33,7 @@ pub fn create_spinner(msg: &'static str) -> ProgressBar {
 }
 
 pub fn create_alt_spinner(msg: &'static str) -> ProgressBar {
-    let spinner = ProgressBar::new_spinner();
+    let spinner = new_spinner();
     spinner.enable_steady_tick(80);
     spinner.set_style(
         ProgressStyle::default_spinner()
@@ -28,7 +48,7 @@ pub fn create_alt_spinner(msg: &'static str) -> ProgressBar {
 }
 
 pub fn create_progress_bar(msg: &'static str, len: u64) -> ProgressBar {
-    let bar = ProgressBar::new(len);
+    let bar = new_progress_bar(len);
     bar.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.blue} {msg} {wide_bar:.cyan/blue} {pos:>7}/{len:7} {eta_precise}"),
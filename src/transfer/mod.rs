@@ -8,18 +8,21 @@ use mpl_token_metadata::{
 };
 use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::{compute_budget::ComputeBudgetInstruction, signer::Signer};
+use solana_sdk::signer::Signer;
 use spl_associated_token_account::get_associated_token_address;
 
 use crate::{
-    parse::{parse_keypair, parse_solana_config},
-    utils::send_and_confirm_transaction,
+    lookup_table::{parse_lookup_table, send_and_confirm_v0_tx},
+    parse::{parse_keypair, parse_payer, parse_solana_config},
+    priority::with_priority,
+    utils::{send_and_confirm_transaction, with_payer},
 };
 
 /// The program ID for the mpl-token-auth-rules program.
 const MPL_TOKEN_AUTH_RULES_ID: Pubkey =
     solana_program::pubkey!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
 
+#[allow(clippy::too_many_arguments)]
 pub fn process_transfer_asset(
     client: &RpcClient,
     keypair_path: Option<String>,
@@ -28,10 +31,12 @@ pub fn process_transfer_asset(
     mint: String,
     amount: u64,
     priority: Priority,
+    lookup_table: Option<Pubkey>,
 ) -> Result<()> {
     let solana_opts = parse_solana_config();
-    // Authority is the payer as well.
+    // Authority is the payer as well, unless a payer is given.
     let authority = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
     let receiver = Pubkey::from_str(&receiver)?;
     let mint = Pubkey::from_str(&mint)?;
 
@@ -46,7 +51,7 @@ pub fn process_transfer_asset(
 
     let mut transfer_builder = TransferV1Builder::new();
     transfer_builder
-        .payer(authority.pubkey())
+        .payer(payer.as_ref().unwrap_or(&authority).pubkey())
         .authority(authority.pubkey())
         .token(source_ata)
         .token_owner(authority.pubkey())
@@ -94,20 +99,23 @@ pub fn process_transfer_asset(
 
     let transfer_ix = transfer_builder.instruction();
 
-    let micro_lamports = match priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
+    let instructions = with_priority(client, &priority, &[transfer_ix])?;
+    let sig = match parse_lookup_table(client, lookup_table.as_ref())? {
+        Some(lookup_table) => {
+            let fee_payer = payer.as_ref().unwrap_or(&authority);
+            send_and_confirm_v0_tx(
+                client,
+                &with_payer(fee_payer, &[&authority]),
+                &instructions,
+                &[lookup_table],
+            )?
+            .to_string()
+        }
+        None => send_and_confirm_transaction(client, authority, &instructions)?,
     };
 
-    let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_price(micro_lamports);
-
-    let sig = send_and_confirm_transaction(client, authority, &[compute_budget_ix, transfer_ix])?;
-
-    println!("Transferred asset: {mint:?}");
-    println!("Transaction signature: {sig}");
+    say!("Transferred asset: {mint:?}");
+    say!("Transaction signature: {sig}");
 
     Ok(())
 }
@@ -9,8 +9,8 @@ use solana_sdk::signature::{Keypair, Signature};
 use crate::{
     cache::NewValue,
     update::{
-        parse_keypair, parse_mint_list, parse_solana_config, Action, ActionError, BatchActionArgs,
-        RunActionArgs,
+        parse_batch_authority, parse_mint_list, parse_solana_config, Action, ActionError,
+        BatchActionArgs, RunActionArgs,
     },
 };
 
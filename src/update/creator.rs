@@ -5,10 +5,12 @@ pub type UpdateResults = Vec<Result<(), ActionError>>;
 pub struct UpdateCreatorArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub new_creators: String,
     pub should_append: bool,
     pub priority: Priority,
+    pub journal: Option<Arc<DiffJournal>>,
 }
 
 pub async fn update_creator(args: UpdateCreatorArgs) -> Result<Signature, ActionError> {
@@ -20,7 +22,7 @@ pub async fn update_creator(args: UpdateCreatorArgs) -> Result<Signature, Action
         Err(e) => return Err(ActionError::ActionFailed(args.mint_account, e.to_string())),
     };
 
-    let new_creators = if let Some(mut old_creators) = current_md.creators {
+    let new_creators = if let Some(mut old_creators) = current_md.creators.clone() {
         if !args.should_append {
             parsed_creators
         } else {
@@ -48,19 +50,19 @@ pub async fn update_creator(args: UpdateCreatorArgs) -> Result<Signature, Action
     // Token Metadata UpdateArgs enum.
     let mut update_args = V1UpdateArgs::default();
 
-    let data = Data {
-        name: current_md.name,
-        symbol: current_md.symbol,
-        uri: current_md.uri,
+    let new_data = Data {
+        name: current_md.name.clone(),
+        symbol: current_md.symbol.clone(),
+        uri: current_md.uri.clone(),
         seller_fee_basis_points: current_md.seller_fee_basis_points,
         creators: Some(new_creators),
     };
 
-    update_args.data = Some(data);
+    update_args.data = Some(new_data.clone());
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>, // The lib will find this if it's a pNFT.
@@ -69,8 +71,14 @@ pub async fn update_creator(args: UpdateCreatorArgs) -> Result<Signature, Action
         priority: args.priority,
     };
 
-    update_asset(&args.client, update_args)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+    let sig = update_asset(&args.client, update_args)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    if let Some(journal) = &args.journal {
+        journal.record(&args.mint_account, &current_md, &new_data);
+    }
+
+    Ok(sig)
 }
 
 pub struct UpdateCreatorAllArgs {
@@ -89,8 +97,7 @@ pub async fn update_creator_all(args: UpdateCreatorAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair_path, solana_opts);
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
@@ -98,6 +105,7 @@ pub async fn update_creator_all(args: UpdateCreatorAllArgs) -> AnyResult<()> {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::Single(args.new_creators),
@@ -105,6 +113,7 @@ pub async fn update_creator_all(args: UpdateCreatorAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     UpdateCreatorAll::run(args).await
 }
@@ -121,10 +130,12 @@ impl Action for UpdateCreatorAll {
         update_creator(UpdateCreatorArgs {
             client: args.client,
             keypair: args.keypair,
+            payer: args.payer,
             mint_account: args.mint_account,
             new_creators: args.new_value,
             should_append: args.should_append,
             priority: args.priority,
+            journal: Some(args.journal),
         })
         .await
         .map(|_| ())
@@ -6,6 +6,7 @@ use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use std::{collections::HashMap, path::Path, sync::Mutex};
 
 use crate::cache::NewValue;
+use crate::spinner::new_progress_bar;
 
 use super::*;
 
@@ -22,23 +23,36 @@ pub struct UpdateDataAllArgs {
 pub struct UpdateDataArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub new_data: Data,
     pub priority: Priority,
+    pub journal: Option<Arc<DiffJournal>>,
 }
 
 pub async fn update_data(args: UpdateDataArgs) -> Result<Signature, ActionError> {
     // Add metadata delegate record here later.
 
+    // The current values are only needed to record a diff.
+    let current_md = if args.journal.is_some() {
+        Some(
+            decode_metadata_from_mint(&args.client, args.mint_account.clone()).map_err(|e| {
+                ActionError::ActionFailed(args.mint_account.to_string(), e.to_string())
+            })?,
+        )
+    } else {
+        None
+    };
+
     // Token Metadata UpdateArgs enum.
     let update_args = V1UpdateArgs {
-        data: Some(args.new_data),
+        data: Some(args.new_data.clone()),
         ..Default::default()
     };
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
@@ -47,8 +61,14 @@ pub async fn update_data(args: UpdateDataArgs) -> Result<Signature, ActionError>
         priority: args.priority,
     };
 
-    update_asset(&args.client, update_args)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+    let sig = update_asset(&args.client, update_args)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    if let (Some(journal), Some(current_md)) = (&args.journal, &current_md) {
+        journal.record(&args.mint_account, current_md, &args.new_data);
+    }
+
+    Ok(sig)
 }
 
 pub struct UpdateDataAll {}
@@ -70,9 +90,11 @@ impl Action for UpdateDataAll {
         update_data(UpdateDataArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account,
             new_data,
             priority: args.priority,
+            journal: Some(args.journal),
         })
         .await
         .map(|_| ())
@@ -93,7 +115,7 @@ pub async fn update_data_all(args: UpdateDataAllArgs) -> AnyResult<()> {
     if !errors.is_empty() {
         for error in errors {
             error!("Failed to read path: {:?}", error);
-            println!("Failed to read path: {:?}", error);
+            say!("Failed to read path: {:?}", error);
         }
         return Err(anyhow!("Failed to read paths"));
     }
@@ -105,37 +127,41 @@ pub async fn update_data_all(args: UpdateDataAllArgs) -> AnyResult<()> {
     // If user hasn't passed in a cache file, then we construct the mint list from the URI file.
 
     info!("Updating...");
-    println!("Updating...");
-    paths.par_iter().progress().for_each(|path| {
-        let f = match File::open(path) {
-            Ok(f) => f,
-            Err(e) => {
-                error!("Failed to open file: {:?} error: {}", path, e);
-                println!("Failed to open file: {:?} error: {}", path, e);
-                return;
-            }
-        };
-
-        let update_nft_data: UpdateNftData = match serde_json::from_reader(f) {
-            Ok(data) => data,
-            Err(e) => {
-                error!(
-                    "Failed to parse JSON data from file: {:?} error: {}",
-                    path, e
-                );
-                println!(
-                    "Failed to parse JSON data from file: {:?} error: {}",
-                    path, e
-                );
-                return;
-            }
-        };
-
-        mint_values.lock().unwrap().insert(
-            update_nft_data.mint,
-            serde_json::to_string(&update_nft_data.data).unwrap(),
-        );
-    });
+    say!("Updating...");
+    paths
+        .par_iter()
+        .progress_with(new_progress_bar(paths.len() as u64))
+        .for_each(|path| {
+            let f = match File::open(path) {
+                Ok(f) => f,
+                Err(e) => {
+                    error!("Failed to open file: {:?} error: {}", path, e);
+                    say!("Failed to open file: {:?} error: {}", path, e);
+                    return;
+                }
+            };
+
+            let update_nft_data: UpdateNftData = match serde_json::from_reader(f) {
+                Ok(data) => data,
+                Err(e) => {
+                    error!(
+                        "Failed to parse JSON data from file: {:?} error: {}",
+                        path, e
+                    );
+                    say!(
+                        "Failed to parse JSON data from file: {:?} error: {}",
+                        path,
+                        e
+                    );
+                    return;
+                }
+            };
+
+            mint_values.lock().unwrap().insert(
+                update_nft_data.mint,
+                serde_json::to_string(&update_nft_data.data).unwrap(),
+            );
+        });
 
     let mint_list = if args.cache_file.is_none() {
         Some(
@@ -152,13 +178,13 @@ pub async fn update_data_all(args: UpdateDataAllArgs) -> AnyResult<()> {
 
     let mint_values = mint_values.lock().unwrap().clone();
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::List(mint_values),
@@ -166,6 +192,7 @@ pub async fn update_data_all(args: UpdateDataAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     UpdateDataAll::run(args).await
 }
@@ -0,0 +1,386 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use chrono::Utc;
+use indexmap::{IndexMap, IndexSet};
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::*;
+
+/// Old and new value of a single metadata field.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Before/after record of a single mint's metadata for one update.
+///
+/// `old` holds the full `Data` struct as it was on-chain before the update so
+/// the change can be reverted, while `changes` lists only the fields that
+/// actually differ. `update_authority` is the authority before the update; if
+/// the update changed it, the new one is in `changes`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataDiff {
+    pub update_authority: String,
+    pub old: Data,
+    pub new: Data,
+    pub changes: IndexMap<String, FieldChange>,
+}
+
+impl MetadataDiff {
+    pub fn new(old_md: &Metadata, new_data: &Data, new_update_authority: &Pubkey) -> Self {
+        let old = Data {
+            name: trim_padding(&old_md.name),
+            symbol: trim_padding(&old_md.symbol),
+            uri: trim_padding(&old_md.uri),
+            seller_fee_basis_points: old_md.seller_fee_basis_points,
+            creators: old_md.creators.clone(),
+        };
+        let new = Data {
+            name: trim_padding(&new_data.name),
+            symbol: trim_padding(&new_data.symbol),
+            uri: trim_padding(&new_data.uri),
+            seller_fee_basis_points: new_data.seller_fee_basis_points,
+            creators: new_data.creators.clone(),
+        };
+
+        let mut changes = IndexMap::new();
+        let mut compare = |field: &str, old: Value, new: Value| {
+            if old != new {
+                changes.insert(field.to_string(), FieldChange { old, new });
+            }
+        };
+        compare("name", old.name.clone().into(), new.name.clone().into());
+        compare(
+            "symbol",
+            old.symbol.clone().into(),
+            new.symbol.clone().into(),
+        );
+        compare("uri", old.uri.clone().into(), new.uri.clone().into());
+        compare(
+            "seller_fee_basis_points",
+            old.seller_fee_basis_points.into(),
+            new.seller_fee_basis_points.into(),
+        );
+        compare(
+            "creators",
+            serde_json::to_value(&old.creators).unwrap_or_default(),
+            serde_json::to_value(&new.creators).unwrap_or_default(),
+        );
+        compare(
+            "update_authority",
+            old_md.update_authority.to_string().into(),
+            new_update_authority.to_string().into(),
+        );
+
+        Self {
+            update_authority: old_md.update_authority.to_string(),
+            old,
+            new,
+            changes,
+        }
+    }
+
+    /// A diff that changes only the update authority.
+    pub fn update_authority(old_md: &Metadata, new_update_authority: &Pubkey) -> Self {
+        Self::new(old_md, &data_from_metadata(old_md), new_update_authority)
+    }
+
+    /// True if any field of the `Data` struct changed.
+    pub fn data_changed(&self) -> bool {
+        self.changes.keys().any(|field| field != "update_authority")
+    }
+
+    /// The update authority after the update.
+    pub fn new_update_authority(&self) -> AnyResult<Pubkey> {
+        let authority = match self.changes.get("update_authority") {
+            Some(change) => change
+                .new
+                .as_str()
+                .ok_or_else(|| anyhow!("Invalid update authority in diff"))?,
+            None => &self.update_authority,
+        };
+        Ok(Pubkey::from_str(authority)?)
+    }
+}
+
+/// One line of a diff file: a mint and what changed on it.
+#[derive(Debug, Deserialize, Serialize)]
+struct DiffEntry {
+    mint: String,
+    #[serde(flatten)]
+    diff: MetadataDiff,
+}
+
+/// Per-mint metadata diffs recorded over a batch run.
+///
+/// Actions record into the journal concurrently as their items land. Each
+/// diff is appended to the journal file and flushed as its own line straight
+/// away, so a run that dies partway still leaves the trail of every mint it
+/// changed. A journal without a file only counts the diffs.
+#[derive(Debug, Default)]
+pub struct DiffJournal {
+    path: Option<PathBuf>,
+    state: Mutex<JournalState>,
+}
+
+#[derive(Debug, Default)]
+struct JournalState {
+    file: Option<File>,
+    mints: IndexSet<String>,
+}
+
+impl DiffJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A journal appending to `path`, which is created on the first diff.
+    pub fn to_file<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: Some(path.as_ref().to_path_buf()),
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn record(&self, mint_address: &str, old_md: &Metadata, new_data: &Data) {
+        let diff = MetadataDiff::new(old_md, new_data, &old_md.update_authority);
+        self.insert(mint_address, diff);
+    }
+
+    pub fn record_update_authority(
+        &self,
+        mint_address: &str,
+        old_md: &Metadata,
+        new_update_authority: &Pubkey,
+    ) {
+        self.insert(
+            mint_address,
+            MetadataDiff::update_authority(old_md, new_update_authority),
+        );
+    }
+
+    pub fn insert(&self, mint_address: &str, diff: MetadataDiff) {
+        // Nothing changed so there is nothing to audit or revert.
+        if diff.changes.is_empty() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.mints.insert(mint_address.to_string());
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        let entry = DiffEntry {
+            mint: mint_address.to_string(),
+            diff,
+        };
+        // The update has already landed, so failing the item would only retry it.
+        if let Err(e) = append_entry(&mut state.file, path, &entry) {
+            error!(
+                "Failed to write the diff for {mint_address} to {}: {e}",
+                path.display()
+            );
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().unwrap().mints.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().mints.len()
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Read a diff file back, by mint. The latest line for a mint wins.
+    pub fn load<P: AsRef<Path>>(path: P) -> AnyResult<IndexMap<String, MetadataDiff>> {
+        let contents = std::fs::read_to_string(path)?;
+        let lines: Vec<&str> = contents.lines().collect();
+        let last = lines.len().saturating_sub(1);
+
+        let mut diffs = IndexMap::new();
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<DiffEntry>(line) {
+                Ok(entry) => {
+                    diffs.insert(entry.mint, entry.diff);
+                }
+                // A partially written final line means the process died mid-write.
+                Err(_) if i == last => break,
+                Err(e) => return Err(anyhow!("Invalid diff file on line {}: {e}", i + 1)),
+            }
+        }
+        Ok(diffs)
+    }
+
+    /// Timestamped file name so diffs from a retry never overwrite the original run.
+    pub fn file_name(action_name: &str) -> String {
+        format!(
+            "mb-diff-{}-{}.jsonl",
+            action_name,
+            Utc::now().format("%Y%m%dT%H%M%S")
+        )
+    }
+}
+
+fn append_entry(file: &mut Option<File>, path: &Path, entry: &DiffEntry) -> AnyResult<()> {
+    let file = match file {
+        Some(file) => file,
+        None => file.insert(OpenOptions::new().create(true).append(true).open(path)?),
+    };
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
+
+fn data_from_metadata(md: &Metadata) -> Data {
+    Data {
+        name: md.name.clone(),
+        symbol: md.symbol.clone(),
+        uri: md.uri.clone(),
+        seller_fee_basis_points: md.seller_fee_basis_points,
+        creators: md.creators.clone(),
+    }
+}
+
+fn trim_padding(value: &str) -> String {
+    value.trim_matches(char::from(0)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpl_token_metadata::types::{Creator, Key};
+
+    fn metadata(uri: &str) -> Metadata {
+        Metadata {
+            key: Key::MetadataV1,
+            update_authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            name: "Test\0\0\0".to_string(),
+            symbol: "TST".to_string(),
+            uri: format!("{uri}\0\0"),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![Creator {
+                address: Pubkey::new_unique(),
+                verified: true,
+                share: 100,
+            }]),
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        }
+    }
+
+    #[test]
+    fn diff_lists_only_changed_fields() {
+        let md = metadata("https://old.uri");
+        let mut new_data = data_from_metadata(&md);
+        new_data.uri = "https://new.uri".to_string();
+
+        let diff = MetadataDiff::new(&md, &new_data, &md.update_authority);
+
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(
+            diff.changes["uri"],
+            FieldChange {
+                old: "https://old.uri".into(),
+                new: "https://new.uri".into(),
+            }
+        );
+        assert_eq!(diff.old.name, "Test");
+        assert_eq!(diff.update_authority, md.update_authority.to_string());
+        assert!(diff.data_changed());
+        assert_eq!(diff.new_update_authority().unwrap(), md.update_authority);
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mb-diff-test-{}-{name}.jsonl", std::process::id()))
+    }
+
+    #[test]
+    fn journal_records_update_authority_changes() {
+        let path = temp_path("authority");
+        let journal = DiffJournal::to_file(&path);
+        let md = metadata("https://uri");
+        let new_authority = Pubkey::new_unique();
+
+        journal.record_update_authority("mintA", &md, &new_authority);
+
+        let parsed = DiffJournal::load(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let diff = &parsed["mintA"];
+        assert_eq!(diff.changes.len(), 1);
+        assert!(!diff.data_changed());
+        assert_eq!(diff.update_authority, md.update_authority.to_string());
+        assert_eq!(diff.new_update_authority().unwrap(), new_authority);
+    }
+
+    #[test]
+    fn diff_records_creator_changes() {
+        let md = metadata("https://uri");
+        let mut new_data = data_from_metadata(&md);
+        new_data.creators = None;
+
+        let diff = MetadataDiff::new(&md, &new_data, &md.update_authority);
+
+        assert!(diff.changes.contains_key("creators"));
+        assert_eq!(diff.changes["creators"].new, Value::Null);
+    }
+
+    #[test]
+    fn journal_skips_unchanged_mints() {
+        let journal = DiffJournal::new();
+        let md = metadata("https://uri");
+
+        journal.record("mintA", &md, &data_from_metadata(&md));
+
+        assert!(journal.is_empty());
+    }
+
+    #[test]
+    fn journal_appends_each_diff_as_it_is_recorded() {
+        let path = temp_path("append");
+        let journal = DiffJournal::to_file(&path);
+        let md = metadata("https://old.uri");
+        let mut new_data = data_from_metadata(&md);
+        new_data.seller_fee_basis_points = 250;
+
+        journal.record("mintB", &md, &new_data);
+        // On disk before the run finishes.
+        assert_eq!(DiffJournal::load(&path).unwrap().len(), 1);
+        journal.record("mintA", &md, &new_data);
+
+        // A line cut short by a crash is ignored.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"mint\":\"mintC\",\"upd").unwrap();
+        let parsed = DiffJournal::load(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(journal.len(), 2);
+        assert_eq!(parsed.keys().collect::<Vec<_>>(), vec!["mintB", "mintA"]);
+        assert_eq!(parsed["mintA"].old.seller_fee_basis_points, 500);
+        assert_eq!(parsed["mintA"].new.seller_fee_basis_points, 250);
+    }
+}
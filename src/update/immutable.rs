@@ -7,6 +7,7 @@ use super::*;
 pub struct SetImmutableArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub priority: Priority,
 }
@@ -32,7 +33,7 @@ pub async fn set_immutable(args: SetImmutableArgs) -> Result<Signature, ActionEr
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
@@ -57,6 +58,7 @@ impl Action for SetImmutableAll {
         set_immutable(SetImmutableArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account.clone(),
             priority: args.priority,
         })
@@ -71,13 +73,13 @@ pub async fn set_immutable_all(args: SetImmutableAllArgs) -> AnyResult<()> {
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::None,
@@ -85,6 +87,7 @@ pub async fn set_immutable_all(args: SetImmutableAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     SetImmutableAll::run(args).await
 }
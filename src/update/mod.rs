@@ -1,8 +1,10 @@
 pub mod creator;
 pub mod data;
+pub mod diff;
 pub mod immutable;
 pub mod name;
 pub mod primary_sale_happened;
+pub mod rollback;
 pub mod rule_set;
 pub mod seller_fee_basis_points;
 pub mod symbol;
@@ -13,9 +15,11 @@ pub mod uses;
 
 pub use creator::*;
 pub use data::*;
+pub use diff::*;
 pub use immutable::*;
 pub use name::*;
 pub use primary_sale_happened::*;
+pub use rollback::*;
 pub use rule_set::*;
 pub use seller_fee_basis_points::*;
 pub use symbol::*;
@@ -32,7 +36,7 @@ pub use metaboss_lib::{
     decode::{decode_metadata_from_mint, ToPubkey},
     derive::{derive_edition_pda, derive_metadata_pda},
     nft::get_nft_token_account,
-    update::{update_asset, UpdateAssetArgs, V1UpdateArgs},
+    update::{update_asset_ix, UpdateAssetArgs, V1UpdateArgs},
 };
 pub use mpl_token_metadata::{
     accounts::Metadata,
@@ -42,7 +46,13 @@ pub use mpl_token_metadata::{
     ID as TOKEN_METADATA_PROGRAM_ID,
 };
 
+use metaboss_lib::{
+    constants::{AUTH_RULES_PROGRAM_ID, SYSTEM_PROGRAM_ID},
+    data::Asset,
+};
+use mpl_token_metadata::instructions::UpdateV1;
 pub use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
 pub use solana_sdk::{
     pubkey::Pubkey,
     signature::{Keypair, Signature},
@@ -57,7 +67,9 @@ pub use crate::cache::{Action, BatchActionArgs, Cache, NewValue, RunActionArgs};
 pub use crate::decode::{decode, get_metadata_pda};
 pub use crate::errors::ActionError;
 pub use crate::parse::parse_solana_config;
-pub use crate::parse::{parse_cli_creators, parse_keypair};
+pub use crate::parse::{parse_authority_keypair, parse_cli_creators, parse_keypair, parse_payer};
+pub use crate::signer::parse_batch_authority;
+use crate::signer::send_with_signer;
 
 pub fn parse_mint_list(
     mint_list_file: Option<String>,
@@ -73,3 +85,81 @@ pub fn parse_mint_list(
         Ok(None)
     }
 }
+
+/// Update an asset with Token Metadata's `UpdateV1`. The transaction is priced
+/// by `compute_unit_price`, so `--priority auto` and the fee caps apply.
+pub fn update_asset<P1: ToPubkey, P2: ToPubkey, P3: ToPubkey>(
+    client: &RpcClient,
+    args: UpdateAssetArgs<P1, P2, P3>,
+) -> AnyResult<Signature> {
+    let UpdateAssetArgs::V1 {
+        payer,
+        authority,
+        ref priority,
+        ..
+    } = args;
+    let priority = priority.clone();
+    let ix = update_asset_ix(client, args)?;
+
+    send_with_signer(client, authority, payer, &[ix], &priority)
+}
+
+/// Build a Token Metadata `UpdateV1` instruction for `authority` without its
+/// keypair, so the transaction can be signed elsewhere. `payer` pays for any
+/// accounts the update creates.
+pub fn update_asset_ix_for_authority(
+    client: &RpcClient,
+    payer: Pubkey,
+    authority: Pubkey,
+    mint: &str,
+    update_args: V1UpdateArgs,
+) -> AnyResult<Instruction> {
+    let mint = mint.to_pubkey()?;
+    let mut asset = Asset::new(mint);
+
+    let md = asset.get_metadata(client)?;
+
+    // The token account is needed for pNFT updates.
+    let token = if md.token_standard == Some(TokenStandard::ProgrammableNonFungible) {
+        Some(get_nft_token_account(client, &mint.to_string())?)
+    } else {
+        None
+    };
+
+    // Fungibles without a token standard fail when an edition is passed in, but
+    // these are much more likely to be NonFungible, so assume that.
+    if matches!(
+        md.token_standard,
+        Some(
+            TokenStandard::NonFungible
+                | TokenStandard::NonFungibleEdition
+                | TokenStandard::ProgrammableNonFungible
+        ) | None
+    ) {
+        asset.add_edition();
+    }
+
+    let (authorization_rules, authorization_rules_program) = if let Some(ProgrammableConfig::V1 {
+        rule_set: Some(rule_set),
+    }) = md.programmable_config
+    {
+        (Some(rule_set), Some(AUTH_RULES_PROGRAM_ID))
+    } else {
+        (None, None)
+    };
+
+    Ok(UpdateV1 {
+        payer,
+        authority,
+        mint: asset.mint,
+        metadata: asset.metadata,
+        delegate_record: None,
+        token,
+        edition: asset.edition,
+        system_program: SYSTEM_PROGRAM_ID,
+        sysvar_instructions: solana_program::sysvar::instructions::ID,
+        authorization_rules,
+        authorization_rules_program,
+    }
+    .instruction(update_args.into()))
+}
@@ -6,6 +6,7 @@ use super::*;
 pub struct UpdateNameArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub new_name: String,
     pub priority: Priority,
@@ -30,7 +31,7 @@ pub async fn update_name(args: UpdateNameArgs) -> Result<Signature, ActionError>
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
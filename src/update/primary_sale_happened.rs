@@ -1,6 +1,6 @@
-use metaboss_lib::update::V1UpdateArgs;
+use solana_sdk::instruction::Instruction;
 
-use crate::cache::NewValue;
+use crate::cache::{NewValue, PackedAction};
 
 use super::*;
 
@@ -12,39 +12,62 @@ pub struct SetPrimarySaleHappenedAllArgs {
     pub rate_limit: usize,
     pub retries: u8,
     pub priority: Priority,
+    pub lookup_table: Option<Pubkey>,
 }
 
 pub struct SetPrimarySaleHappenedArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub priority: Priority,
 }
 
-pub async fn set_primary_sale_happened(
-    args: SetPrimarySaleHappenedArgs,
-) -> Result<Signature, ActionError> {
-    // Token Metadata UpdateArgs enum.
-    let update_args = V1UpdateArgs {
+// Token Metadata UpdateArgs enum.
+fn v1_update_args() -> V1UpdateArgs {
+    V1UpdateArgs {
         primary_sale_happened: Some(true),
         ..Default::default()
-    };
+    }
+}
 
+fn update_args(args: &SetPrimarySaleHappenedArgs) -> UpdateAssetArgs<'_, String, String, String> {
     // Metaboss UpdateAssetArgs enum.
-    let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+    UpdateAssetArgs::V1 {
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
         delegate_record: None::<String>, // Not supported yet in update.
-        update_args,
-        priority: args.priority,
-    };
+        update_args: v1_update_args(),
+        priority: args.priority.clone(),
+    }
+}
 
-    update_asset(&args.client, update_args)
+pub async fn set_primary_sale_happened(
+    args: SetPrimarySaleHappenedArgs,
+) -> Result<Signature, ActionError> {
+    update_asset(&args.client, update_args(&args))
         .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
 }
 
+/// The update instruction alone, to be packed with other mints' instructions.
+/// `payer` pays for any accounts created and `authority` signs the update.
+pub async fn set_primary_sale_happened_ix(
+    args: SetPrimarySaleHappenedArgs,
+    payer: Pubkey,
+    authority: Pubkey,
+) -> Result<Instruction, ActionError> {
+    update_asset_ix_for_authority(
+        &args.client,
+        payer,
+        authority,
+        &args.mint_account,
+        v1_update_args(),
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+}
+
 pub struct SetPrimarySaleHappenedAll {}
 
 #[async_trait]
@@ -57,27 +80,52 @@ impl Action for SetPrimarySaleHappenedAll {
         set_primary_sale_happened(SetPrimarySaleHappenedArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account,
             priority: args.priority,
         })
         .await
         .map(|_| ())
     }
+
+    fn packable() -> bool {
+        true
+    }
+
+    async fn instructions(args: RunActionArgs) -> Result<PackedAction, ActionError> {
+        let ix = set_primary_sale_happened_ix(
+            SetPrimarySaleHappenedArgs {
+                client: args.client.clone(),
+                keypair: args.keypair.clone(),
+                payer: args.payer.clone(),
+                mint_account: args.mint_account,
+                priority: args.priority,
+            },
+            args.fee_payer,
+            args.authority,
+        )
+        .await?;
+
+        Ok(PackedAction {
+            instructions: vec![ix],
+            diff: None,
+        })
+    }
 }
 
 pub async fn set_primary_sale_happened_all(args: SetPrimarySaleHappenedAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
+    let (keypair, signer) = parse_batch_authority(args.keypair, solana_opts)?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::None,
@@ -85,6 +133,7 @@ pub async fn set_primary_sale_happened_all(args: SetPrimarySaleHappenedAllArgs)
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: args.lookup_table,
     };
     SetPrimarySaleHappenedAll::run(args).await
 }
@@ -0,0 +1,149 @@
+use metaboss_lib::update::V1UpdateArgs;
+
+use crate::cache::{MintValues, NewValue};
+
+use super::*;
+
+pub struct RollbackArgs {
+    pub client: RpcClient,
+    pub keypair: Option<String>,
+    pub journal_file: String,
+    pub cache_file: Option<String>,
+    pub rate_limit: usize,
+    pub retries: u8,
+    pub priority: Priority,
+}
+
+pub struct RollbackMintArgs {
+    pub client: Arc<RpcClient>,
+    pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
+    pub mint_account: String,
+    pub diff: MetadataDiff,
+    pub priority: Priority,
+    pub journal: Option<Arc<DiffJournal>>,
+}
+
+pub async fn rollback_mint(args: RollbackMintArgs) -> Result<Signature, ActionError> {
+    let current_md = decode_metadata_from_mint(&args.client, args.mint_account.clone())
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    let old_update_authority = Pubkey::from_str(&args.diff.update_authority)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+    let new_update_authority = args
+        .diff
+        .new_update_authority()
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    // Refuse to overwrite changes made after the journal was written.
+    let drift = MetadataDiff::new(&current_md, &args.diff.new, &new_update_authority);
+    if !drift.changes.is_empty() {
+        return Err(ActionError::ActionFailed(
+            args.mint_account,
+            format!(
+                "metadata changed since the journal was written: {}",
+                drift.changes.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+        ));
+    }
+
+    // Token Metadata UpdateArgs enum.
+    let mut update_args = V1UpdateArgs::default();
+
+    if args.diff.data_changed() {
+        update_args.data = Some(args.diff.old.clone());
+    }
+    if new_update_authority != old_update_authority {
+        update_args.new_update_authority = Some(old_update_authority);
+    }
+
+    // Metaboss UpdateAssetArgs enum.
+    let update_args = UpdateAssetArgs::V1 {
+        payer: args.payer.as_ref().as_ref(),
+        authority: &args.keypair,
+        mint: args.mint_account.clone(),
+        token: None::<String>,
+        delegate_record: None::<String>, // Not supported yet in update.
+        update_args,
+        priority: args.priority,
+    };
+
+    let sig = update_asset(&args.client, update_args)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    // Journal the rollback too, so it can itself be reverted.
+    if let Some(journal) = &args.journal {
+        journal.insert(
+            &args.mint_account,
+            MetadataDiff::new(&current_md, &args.diff.old, &old_update_authority),
+        );
+    }
+
+    Ok(sig)
+}
+
+pub struct RollbackAll {}
+
+#[async_trait]
+impl Action for RollbackAll {
+    fn name() -> &'static str {
+        "rollback"
+    }
+
+    async fn action(args: RunActionArgs) -> Result<(), ActionError> {
+        let diff: MetadataDiff = serde_json::from_str(&args.new_value).map_err(|e| {
+            ActionError::ActionFailed(
+                args.mint_account.to_string(),
+                format!("Failed to parse journal entry: {e}"),
+            )
+        })?;
+
+        rollback_mint(RollbackMintArgs {
+            client: args.client.clone(),
+            keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
+            mint_account: args.mint_account,
+            diff,
+            priority: args.priority,
+            journal: Some(args.journal),
+        })
+        .await
+        .map(|_| ())
+    }
+}
+
+pub async fn rollback(args: RollbackArgs) -> AnyResult<()> {
+    let solana_opts = parse_solana_config();
+    let keypair = parse_keypair(args.keypair, solana_opts);
+    let payer = parse_payer()?;
+
+    let diffs = DiffJournal::load(&args.journal_file)?;
+
+    // If user hasn't passed in a cache file, then we roll back every mint in the journal.
+    let mint_list = if args.cache_file.is_none() {
+        Some(diffs.keys().cloned().collect::<Vec<_>>())
+    } else {
+        None
+    };
+
+    let mint_values = diffs
+        .iter()
+        .map(|(mint, diff)| Ok((mint.clone(), serde_json::to_string(diff)?)))
+        .collect::<AnyResult<MintValues>>()?;
+
+    let args = BatchActionArgs {
+        client: args.client,
+        keypair,
+        payer,
+        signer: None,
+        mint_list,
+        cache_file: args.cache_file,
+        new_value: NewValue::List(mint_values),
+        should_append: false,
+        rate_limit: args.rate_limit,
+        retries: args.retries,
+        priority: args.priority,
+        lookup_table: None,
+    };
+    RollbackAll::run(args).await
+}
@@ -18,6 +18,7 @@ pub struct UpdateRuleSetAllArgs {
 pub struct UpdateRuleSetArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub new_rule_set: String,
     pub priority: Priority,
@@ -36,6 +37,7 @@ pub struct ClearRuleSetAllArgs {
 pub struct ClearRuleSetArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub priority: Priority,
 }
@@ -54,7 +56,7 @@ pub async fn update_rule_set(args: UpdateRuleSetArgs) -> Result<Signature, Actio
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
@@ -81,7 +83,7 @@ pub async fn clear_rule_set(args: ClearRuleSetArgs) -> Result<Signature, ActionE
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint,
         token: None::<String>,
@@ -106,6 +108,7 @@ impl Action for UpdateRuleSetAll {
         update_rule_set(UpdateRuleSetArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account,
             new_rule_set: args.new_value,
             priority: args.priority,
@@ -121,13 +124,13 @@ pub async fn update_rule_set_all(args: UpdateRuleSetAllArgs) -> AnyResult<()> {
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::Single(args.new_rule_set),
@@ -135,6 +138,7 @@ pub async fn update_rule_set_all(args: UpdateRuleSetAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     UpdateRuleSetAll::run(args).await
 }
@@ -151,6 +155,7 @@ impl Action for ClearRuleSetAll {
         clear_rule_set(ClearRuleSetArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account,
             priority: args.priority,
         })
@@ -165,13 +170,13 @@ pub async fn clear_rule_set_all(args: ClearRuleSetAllArgs) -> AnyResult<()> {
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::None,
@@ -179,6 +184,7 @@ pub async fn clear_rule_set_all(args: ClearRuleSetAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     ClearRuleSetAll::run(args).await
 }
@@ -8,9 +8,11 @@ use super::*;
 pub struct UpdateSellerFeeBasisPointsArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub new_sfbp: u16,
     pub priority: Priority,
+    pub journal: Option<Arc<DiffJournal>>,
 }
 pub struct UpdateSellerFeeBasisPointsAllArgs {
     pub client: RpcClient,
@@ -28,23 +30,23 @@ pub async fn update_sfbp(args: UpdateSellerFeeBasisPointsArgs) -> Result<Signatu
     let current_md = decode_metadata_from_mint(&args.client, args.mint_account.clone())
         .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
 
-    let data = Some(Data {
-        name: current_md.name,
-        symbol: current_md.symbol,
-        uri: current_md.uri,
+    let new_data = Data {
+        name: current_md.name.clone(),
+        symbol: current_md.symbol.clone(),
+        uri: current_md.uri.clone(),
         seller_fee_basis_points: args.new_sfbp,
-        creators: current_md.creators,
-    });
+        creators: current_md.creators.clone(),
+    };
 
     // Token Metadata UpdateArgs enum.
     let update_args = V1UpdateArgs {
-        data,
+        data: Some(new_data.clone()),
         ..Default::default()
     };
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
@@ -53,8 +55,14 @@ pub async fn update_sfbp(args: UpdateSellerFeeBasisPointsArgs) -> Result<Signatu
         priority: args.priority,
     };
 
-    update_asset(&args.client, update_args)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+    let sig = update_asset(&args.client, update_args)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    if let Some(journal) = &args.journal {
+        journal.record(&args.mint_account, &current_md, &new_data);
+    }
+
+    Ok(sig)
 }
 
 pub struct UpdateSellerFeeBasisPointsAll {}
@@ -78,9 +86,11 @@ impl Action for UpdateSellerFeeBasisPointsAll {
         update_sfbp(UpdateSellerFeeBasisPointsArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account,
             new_sfbp: sfbp,
             priority: args.priority,
+            journal: Some(args.journal),
         })
         .await
         .map(|_| ())
@@ -93,13 +103,13 @@ pub async fn update_sfbp_all(args: UpdateSellerFeeBasisPointsAllArgs) -> AnyResu
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::Single(args.new_sfbp.to_string()),
@@ -107,6 +117,7 @@ pub async fn update_sfbp_all(args: UpdateSellerFeeBasisPointsAllArgs) -> AnyResu
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     UpdateSellerFeeBasisPointsAll::run(args).await
 }
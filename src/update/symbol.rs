@@ -1,7 +1,7 @@
 use metaboss_lib::{data::Priority, update::V1UpdateArgs};
 use mpl_token_metadata::types::Data;
 
-use crate::cache::NewValue;
+use crate::cache::{NewValue, PackedAction};
 
 use super::*;
 
@@ -14,46 +14,99 @@ pub struct UpdateSymbolAllArgs {
     pub rate_limit: usize,
     pub retries: u8,
     pub priority: Priority,
+    pub lookup_table: Option<Pubkey>,
 }
 
 pub struct UpdateSymbolArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub new_symbol: String,
     pub priority: Priority,
+    pub journal: Option<Arc<DiffJournal>>,
 }
 
-pub async fn update_symbol(args: UpdateSymbolArgs) -> Result<Signature, ActionError> {
+/// Current metadata and the data with the new symbol.
+fn new_data(args: &UpdateSymbolArgs) -> Result<(Metadata, Data), ActionError> {
     let current_md = decode_metadata_from_mint(&args.client, args.mint_account.clone())
         .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
 
-    // Token Metadata UpdateArgs enum.
-    let mut update_args = V1UpdateArgs::default();
-
-    let data = Data {
-        name: current_md.name,
-        symbol: args.new_symbol,
-        uri: current_md.uri,
+    let new_data = Data {
+        name: current_md.name.clone(),
+        symbol: args.new_symbol.clone(),
+        uri: current_md.uri.clone(),
         seller_fee_basis_points: current_md.seller_fee_basis_points,
-        creators: current_md.creators,
+        creators: current_md.creators.clone(),
     };
 
-    update_args.data = Some(data);
+    Ok((current_md, new_data))
+}
+
+// Token Metadata UpdateArgs enum.
+fn v1_update_args(new_data: &Data) -> V1UpdateArgs {
+    V1UpdateArgs {
+        data: Some(new_data.clone()),
+        ..Default::default()
+    }
+}
 
+fn update_args<'a>(
+    args: &'a UpdateSymbolArgs,
+    new_data: &Data,
+) -> UpdateAssetArgs<'a, String, String, String> {
     // Metaboss UpdateAssetArgs enum.
-    let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+    UpdateAssetArgs::V1 {
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
         delegate_record: None::<String>, // Not supported yet in update.
-        update_args,
-        priority: args.priority,
-    };
+        update_args: v1_update_args(new_data),
+        priority: args.priority.clone(),
+    }
+}
+
+pub async fn update_symbol(args: UpdateSymbolArgs) -> Result<Signature, ActionError> {
+    let (current_md, new_data) = new_data(&args)?;
 
-    update_asset(&args.client, update_args)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+    let sig = update_asset(&args.client, update_args(&args, &new_data))
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    if let Some(journal) = &args.journal {
+        journal.record(&args.mint_account, &current_md, &new_data);
+    }
+
+    Ok(sig)
+}
+
+/// The update instruction alone, to be packed with other mints' instructions,
+/// and the diff to record once it lands.
+/// `payer` pays for any accounts created and `authority` signs the update.
+pub async fn update_symbol_ix(
+    args: UpdateSymbolArgs,
+    payer: Pubkey,
+    authority: Pubkey,
+) -> Result<PackedAction, ActionError> {
+    let (current_md, new_data) = new_data(&args)?;
+
+    let ix = update_asset_ix_for_authority(
+        &args.client,
+        payer,
+        authority,
+        &args.mint_account,
+        v1_update_args(&new_data),
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    Ok(PackedAction {
+        instructions: vec![ix],
+        diff: Some(MetadataDiff::new(
+            &current_md,
+            &new_data,
+            &current_md.update_authority,
+        )),
+    })
 }
 
 pub struct UpdateSymbolAll {}
@@ -68,28 +121,51 @@ impl Action for UpdateSymbolAll {
         update_symbol(UpdateSymbolArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account,
             new_symbol: args.new_value,
             priority: args.priority,
+            journal: Some(args.journal),
         })
         .await
         .map(|_| ())
     }
+
+    fn packable() -> bool {
+        true
+    }
+
+    async fn instructions(args: RunActionArgs) -> Result<PackedAction, ActionError> {
+        update_symbol_ix(
+            UpdateSymbolArgs {
+                client: args.client.clone(),
+                keypair: args.keypair.clone(),
+                payer: args.payer.clone(),
+                mint_account: args.mint_account,
+                new_symbol: args.new_value,
+                priority: args.priority,
+                journal: None,
+            },
+            args.fee_payer,
+            args.authority,
+        )
+        .await
+    }
 }
 
 pub async fn update_symbol_all(args: UpdateSymbolAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
+    let (keypair, signer) = parse_batch_authority(args.keypair, solana_opts)?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::Single(args.new_symbol),
@@ -97,6 +173,7 @@ pub async fn update_symbol_all(args: UpdateSymbolAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: args.lookup_table,
     };
     UpdateSymbolAll::run(args).await?;
 
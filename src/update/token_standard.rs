@@ -2,12 +2,15 @@ use mpl_token_metadata::instructions::SetTokenStandard;
 use solana_sdk::commitment_config::CommitmentConfig;
 
 use crate::cache::NewValue;
+use crate::landing::land;
+use crate::utils::with_payer;
 
 use super::*;
 
 pub struct SetTokenStandardArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub priority: Priority,
 }
@@ -45,21 +48,14 @@ pub async fn set_token_standard_one(args: SetTokenStandardArgs) -> Result<Signat
     }
     .instruction();
 
-    let recent_blockhash = args
-        .client
-        .get_latest_blockhash()
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
-
-    let tx = Transaction::new_signed_with_payer(
+    let payer = args.payer.as_ref().as_ref().unwrap_or(&args.keypair);
+    land(
+        &args.client,
+        &with_payer(payer, &[&args.keypair]),
         &[ix],
-        Some(&update_authority),
-        &[&*args.keypair],
-        recent_blockhash,
-    );
-
-    args.client
-        .send_and_confirm_transaction(&tx)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+        &[],
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
 }
 
 pub struct SetTokenStandardAll {}
@@ -74,6 +70,7 @@ impl Action for SetTokenStandardAll {
         set_token_standard_one(SetTokenStandardArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account.clone(),
             priority: args.priority,
         })
@@ -88,13 +85,13 @@ pub async fn set_token_standard_all(args: SetTokenStandardAllArgs) -> AnyResult<
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer: None,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::None,
@@ -102,6 +99,7 @@ pub async fn set_token_standard_all(args: SetTokenStandardAllArgs) -> AnyResult<
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     SetTokenStandardAll::run(args).await
 }
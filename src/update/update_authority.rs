@@ -1,13 +1,13 @@
 use metaboss_lib::update::V1UpdateArgs;
 
-use crate::cache::NewValue;
+use crate::cache::{NewValue, PackedAction};
+use crate::signer::send_with_signer;
 
 use super::*;
 
 pub struct SetUpdateAuthorityAllArgs {
     pub client: RpcClient,
     pub keypair: Option<String>,
-    pub payer: Option<String>,
     pub mint_list: Option<String>,
     pub cache_file: Option<String>,
     pub new_authority: String,
@@ -23,17 +23,27 @@ pub struct SetUpdateAuthorityArgs {
     pub mint_account: String,
     pub new_authority: String,
     pub priority: Priority,
+    pub journal: Option<Arc<DiffJournal>>,
 }
 
 pub async fn set_update_authority(args: SetUpdateAuthorityArgs) -> Result<Signature, ActionError> {
-    let new_update_authority =
-        Some(Pubkey::from_str(&args.new_authority).map_err(|e| {
-            ActionError::ActionFailed(args.mint_account.to_string(), e.to_string())
-        })?);
+    let new_update_authority = Pubkey::from_str(&args.new_authority)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    // The current values are only needed to record a diff.
+    let current_md = if args.journal.is_some() {
+        Some(
+            decode_metadata_from_mint(&args.client, args.mint_account.clone()).map_err(|e| {
+                ActionError::ActionFailed(args.mint_account.to_string(), e.to_string())
+            })?,
+        )
+    } else {
+        None
+    };
 
     // Token Metadata UpdateArgs enum.
     let update_args = V1UpdateArgs {
-        new_update_authority,
+        new_update_authority: Some(new_update_authority),
         ..Default::default()
     };
 
@@ -48,8 +58,76 @@ pub async fn set_update_authority(args: SetUpdateAuthorityArgs) -> Result<Signat
         priority: args.priority,
     };
 
-    update_asset(&args.client, update_args)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+    let sig = update_asset(&args.client, update_args)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    if let (Some(journal), Some(current_md)) = (&args.journal, &current_md) {
+        journal.record_update_authority(&args.mint_account, current_md, &new_update_authority);
+    }
+
+    Ok(sig)
+}
+
+/// Set the update authority of a single mint, signed by `authority`, which may
+/// be a hardware wallet.
+pub fn set_update_authority_with_signer(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    payer: Option<&Keypair>,
+    mint_account: &str,
+    new_authority: &str,
+    priority: &Priority,
+) -> AnyResult<Signature> {
+    let update_args = V1UpdateArgs {
+        new_update_authority: Some(Pubkey::from_str(new_authority)?),
+        ..Default::default()
+    };
+
+    let fee_payer = payer.map_or_else(|| authority.pubkey(), |payer| payer.pubkey());
+    let ix = update_asset_ix_for_authority(
+        client,
+        fee_payer,
+        authority.pubkey(),
+        mint_account,
+        update_args,
+    )?;
+
+    send_with_signer(client, authority, payer, &[ix], priority)
+}
+
+/// The update instruction alone, for export as a multisig proposal.
+/// `payer` pays for any accounts created and `authority` signs the update.
+pub async fn set_update_authority_ix(
+    args: &SetUpdateAuthorityArgs,
+    payer: Pubkey,
+    authority: Pubkey,
+) -> Result<PackedAction, ActionError> {
+    let new_update_authority = Pubkey::from_str(&args.new_authority)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    let current_md = decode_metadata_from_mint(&args.client, args.mint_account.clone())
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    let update_args = V1UpdateArgs {
+        new_update_authority: Some(new_update_authority),
+        ..Default::default()
+    };
+    let ix = update_asset_ix_for_authority(
+        &args.client,
+        payer,
+        authority,
+        &args.mint_account,
+        update_args,
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    Ok(PackedAction {
+        instructions: vec![ix],
+        diff: Some(MetadataDiff::update_authority(
+            &current_md,
+            &new_update_authority,
+        )),
+    })
 }
 
 pub struct SetUpdateAuthorityAll {}
@@ -69,27 +147,46 @@ impl Action for SetUpdateAuthorityAll {
             mint_account: args.mint_account,
             new_authority: args.new_value,
             priority: args.priority,
+            journal: Some(args.journal),
         })
         .await
         .map(|_| ())
     }
+
+    fn builds_instructions() -> bool {
+        true
+    }
+
+    async fn instructions(args: RunActionArgs) -> Result<PackedAction, ActionError> {
+        set_update_authority_ix(
+            &SetUpdateAuthorityArgs {
+                client: args.client.clone(),
+                keypair: args.keypair.clone(),
+                payer: args.payer.clone(),
+                mint_account: args.mint_account,
+                new_authority: args.new_value,
+                priority: args.priority,
+                journal: None,
+            },
+            args.fee_payer,
+            args.authority,
+        )
+        .await
+    }
 }
 
 pub async fn set_update_authority_all(args: SetUpdateAuthorityAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
+    let (keypair, signer) = parse_batch_authority(args.keypair, solana_opts)?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
-
-    let solana_opts = parse_solana_config();
-    let payer = args
-        .payer
-        .map(|path| parse_keypair(Some(path), solana_opts));
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::Single(args.new_authority),
@@ -97,6 +194,7 @@ pub async fn set_update_authority_all(args: SetUpdateAuthorityAllArgs) -> AnyRes
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     SetUpdateAuthorityAll::run(args).await
 }
@@ -3,7 +3,7 @@ use std::{collections::HashMap, fs::File};
 use metaboss_lib::{data::UpdateUriData, update::V1UpdateArgs};
 use mpl_token_metadata::types::Data;
 
-use crate::cache::{MintValues, NewValue};
+use crate::cache::{MintValues, NewValue, PackedAction};
 
 use super::*;
 
@@ -20,12 +20,16 @@ pub struct UpdateUriAllArgs {
 pub struct UpdateUriArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint_account: String,
     pub new_uri: String,
     pub priority: Priority,
+    pub journal: Option<Arc<DiffJournal>>,
 }
 
-pub async fn update_uri(args: UpdateUriArgs) -> Result<Signature, ActionError> {
+/// Current metadata and the data with the new uri, or `None` if the uri is
+/// already up to date.
+fn new_data(args: &UpdateUriArgs) -> Result<Option<(Metadata, Data)>, ActionError> {
     let current_md = decode_metadata_from_mint(&args.client, args.mint_account.clone())
         .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
 
@@ -33,25 +37,34 @@ pub async fn update_uri(args: UpdateUriArgs) -> Result<Signature, ActionError> {
 
     // Save a transaction by not updating if the uri is the same.
     if current_md.uri.trim_matches(char::from(0)) == args.new_uri.trim_matches(char::from(0)) {
-        return Ok(Signature::default());
+        return Ok(None);
     }
 
-    // Token Metadata UpdateArgs enum.
-    let mut update_args = V1UpdateArgs::default();
-
-    let data = Data {
-        name: current_md.name,
-        symbol: current_md.symbol,
-        uri: args.new_uri,
+    let new_data = Data {
+        name: current_md.name.clone(),
+        symbol: current_md.symbol.clone(),
+        uri: args.new_uri.clone(),
         seller_fee_basis_points: current_md.seller_fee_basis_points,
-        creators: current_md.creators,
+        creators: current_md.creators.clone(),
     };
 
-    update_args.data = Some(data);
+    Ok(Some((current_md, new_data)))
+}
+
+pub async fn update_uri(args: UpdateUriArgs) -> Result<Signature, ActionError> {
+    let Some((current_md, new_data)) = new_data(&args)? else {
+        return Ok(Signature::default());
+    };
+
+    // Token Metadata UpdateArgs enum.
+    let update_args = V1UpdateArgs {
+        data: Some(new_data.clone()),
+        ..Default::default()
+    };
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: args.payer.as_ref().as_ref(),
         authority: &args.keypair,
         mint: args.mint_account.clone(),
         token: None::<String>,
@@ -60,8 +73,51 @@ pub async fn update_uri(args: UpdateUriArgs) -> Result<Signature, ActionError> {
         priority: args.priority,
     };
 
-    update_asset(&args.client, update_args)
-        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))
+    let sig = update_asset(&args.client, update_args)
+        .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    if let Some(journal) = &args.journal {
+        journal.record(&args.mint_account, &current_md, &new_data);
+    }
+
+    Ok(sig)
+}
+
+/// The update instruction alone, with no instructions if the uri is already
+/// up to date. `payer` pays for any accounts created and `authority` signs the update.
+pub async fn update_uri_ix(
+    args: UpdateUriArgs,
+    payer: Pubkey,
+    authority: Pubkey,
+) -> Result<PackedAction, ActionError> {
+    let Some((current_md, new_data)) = new_data(&args)? else {
+        return Ok(PackedAction {
+            instructions: Vec::new(),
+            diff: None,
+        });
+    };
+
+    let update_args = V1UpdateArgs {
+        data: Some(new_data.clone()),
+        ..Default::default()
+    };
+    let ix = update_asset_ix_for_authority(
+        &args.client,
+        payer,
+        authority,
+        &args.mint_account,
+        update_args,
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint_account.to_string(), e.to_string()))?;
+
+    Ok(PackedAction {
+        instructions: vec![ix],
+        diff: Some(MetadataDiff::new(
+            &current_md,
+            &new_data,
+            &current_md.update_authority,
+        )),
+    })
 }
 
 pub struct UpdateUriAll {}
@@ -76,18 +132,41 @@ impl Action for UpdateUriAll {
         update_uri(UpdateUriArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint_account: args.mint_account,
             new_uri: args.new_value,
             priority: args.priority,
+            journal: Some(args.journal),
         })
         .await
         .map(|_| ())
     }
+
+    fn builds_instructions() -> bool {
+        true
+    }
+
+    async fn instructions(args: RunActionArgs) -> Result<PackedAction, ActionError> {
+        update_uri_ix(
+            UpdateUriArgs {
+                client: args.client.clone(),
+                keypair: args.keypair.clone(),
+                payer: args.payer.clone(),
+                mint_account: args.mint_account,
+                new_uri: args.new_value,
+                priority: args.priority,
+                journal: None,
+            },
+            args.fee_payer,
+            args.authority,
+        )
+        .await
+    }
 }
 
 pub async fn update_uri_all(args: UpdateUriAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
+    let (keypair, signer) = parse_batch_authority(args.keypair, solana_opts)?;
 
     let f = File::open(args.new_uris_file)?;
     let update_uris: Vec<UpdateUriData> = serde_json::from_reader(f)?;
@@ -110,13 +189,13 @@ pub async fn update_uri_all(args: UpdateUriAllArgs) -> AnyResult<()> {
         .map(|data| (data.mint_account.clone(), data.new_uri.clone()))
         .collect::<HashMap<_, _>>();
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::List(mint_values),
@@ -124,6 +203,7 @@ pub async fn update_uri_all(args: UpdateUriAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: None,
     };
     UpdateUriAll::run(args).await?;
     Ok(())
@@ -19,6 +19,8 @@ pub struct UsesArgs {
 pub fn update_uses_one(args: UsesArgs) -> Result<Signature, ActionError> {
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(args.keypair, solana_opts);
+    let payer = parse_payer()
+        .map_err(|e| ActionError::ActionFailed(args.account.to_string(), e.to_string()))?;
 
     let current_md = decode_metadata_from_mint(&args.client, args.account.clone())
         .map_err(|e| ActionError::ActionFailed(args.account.to_string(), e.to_string()))?;
@@ -57,7 +59,7 @@ pub fn update_uses_one(args: UsesArgs) -> Result<Signature, ActionError> {
 
     // Metaboss UpdateAssetArgs enum.
     let update_args = UpdateAssetArgs::V1 {
-        payer: None,
+        payer: payer.as_ref(),
         authority: &keypair,
         mint: args.account.clone(),
         token: None::<String>,
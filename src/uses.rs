@@ -9,7 +9,7 @@ use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
 use crate::{
     derive::{derive_metadata_pda, derive_use_authority_record},
-    parse::{parse_keypair, parse_solana_config},
+    parse::{parse_keypair, parse_payer, parse_solana_config},
     utils::send_and_confirm_transaction,
 };
 
@@ -25,6 +25,7 @@ pub fn approve_use_delegate(
     let nft_pubkey = Pubkey::from_str(&nft_mint)?;
     let solana_opts = parse_solana_config();
     let keypair = parse_keypair(keypair_path, solana_opts);
+    let payer = parse_payer()?;
 
     let delegate_pubkey = Pubkey::from_str(&use_auth_delegate)?;
     let owner_nft_token_pubkey = Pubkey::from_str(&owner_nft_token_account)?;
@@ -38,7 +39,7 @@ pub fn approve_use_delegate(
         .use_authority_record(use_authority_record)
         .metadata(nft_metadata)
         .owner(keypair.pubkey())
-        .payer(keypair.pubkey())
+        .payer(payer.as_ref().unwrap_or(&keypair).pubkey())
         .mint(nft_pubkey)
         .burner(burner_program_pubkey)
         .owner_token_account(owner_nft_token_pubkey)
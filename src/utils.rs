@@ -3,14 +3,14 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use retry::{delay::Exponential, retry};
 use serde::Deserialize;
 use serde_json::json;
-use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_request::{RpcError, RpcRequest};
 use solana_client::{nonblocking::rpc_client::RpcClient as AsyncRpcClient, rpc_client::RpcClient};
 use solana_program::instruction::AccountMeta;
 use solana_program::program_pack::Pack;
 use solana_program::{pubkey, pubkey::Pubkey};
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::{
-    instruction::Instruction, signature::Keypair, signer::Signer, transaction::Transaction,
+    instruction::Instruction, signature::Keypair, signer::Signer, transaction::VersionedTransaction,
 };
 use solana_sdk_ids::system_program;
 use spl_token::state::Account;
@@ -18,6 +18,8 @@ use std::str::FromStr;
 use std::{ops::Add, sync::Arc};
 
 use crate::data::FoundError;
+use crate::landing::{land, land_async, LandingConfig};
+use crate::parse::parse_payer;
 use crate::wtf_errors::{
     ANCHOR_ERROR, AUCTIONEER_ERROR, AUCTION_HOUSE_ERROR, CANDY_CORE_ERROR, CANDY_ERROR,
     CANDY_GUARD_ERROR, METADATA_ERROR,
@@ -28,23 +30,29 @@ pub fn send_and_confirm_transaction(
     keypair: Keypair,
     instructions: &[Instruction],
 ) -> Result<String> {
-    let recent_blockhash = client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        instructions,
-        Some(&keypair.pubkey()),
-        &[&keypair],
-        recent_blockhash,
-    );
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
+
+    let sig = land(client, &with_payer(payer, &[&keypair]), instructions, &[])?;
+
+    say!("Tx sig: {sig}");
+    Ok(sig.to_string())
+}
 
-    // Send tx with retries.
+/// Send an already signed transaction, with the same retries as
+/// `send_and_confirm_transaction`.
+pub fn send_and_confirm_versioned_transaction(
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+) -> Result<String> {
     let res = retry(
         Exponential::from_millis_with_factor(250, 2.0).take(3),
-        || client.send_and_confirm_transaction(&tx),
+        || client.send_and_confirm_transaction(tx),
     );
 
     let sig = res?;
 
-    println!("Tx sig: {sig}");
+    say!("Tx sig: {sig}");
     Ok(sig.to_string())
 }
 
@@ -53,21 +61,37 @@ pub async fn async_send_and_confirm_transaction(
     keypair: Arc<Keypair>,
     instructions: &[Instruction],
 ) -> Result<String> {
-    let recent_blockhash = async_client.get_latest_blockhash().await?;
-    let tx = Transaction::new_signed_with_payer(
-        instructions,
-        Some(&keypair.pubkey()),
-        &[&*keypair],
-        recent_blockhash,
-    );
+    let payer = parse_payer()?;
+    let payer = payer.as_ref().unwrap_or(&keypair);
 
-    let sig = async_client.send_and_confirm_transaction(&tx).await?;
+    let sig = land_async(
+        &async_client,
+        &with_payer(payer, &[&*keypair]),
+        instructions,
+        &[],
+        &LandingConfig::default(),
+    )
+    .await?;
 
     Ok(sig.to_string())
 }
 
 pub async fn retry_with_cache() {}
 
+/// Decode a transaction as sent in the params of a `sendTransaction` request.
+pub fn decode_wire_transaction(
+    encoded: &str,
+    encoding: &str,
+) -> std::result::Result<VersionedTransaction, RpcError> {
+    let tx_bytes = match encoding {
+        "base58" => bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| RpcError::ParseError(e.to_string()))?,
+        _ => base64::decode(encoded).map_err(|e| RpcError::ParseError(e.to_string()))?,
+    };
+    bincode::deserialize(&tx_bytes).map_err(|e| RpcError::ParseError(e.to_string()))
+}
+
 pub fn generate_phf_map_var(var_name: &str) -> String {
     format!("pub static {var_name}: phf::Map<&'static str, &'static str> = phf_map! {{\n")
 }
@@ -245,6 +269,18 @@ pub fn find_tm_error(hex_code: &str) -> Option<String> {
     METADATA_ERROR.get(&hex_code).map(|e| e.to_string())
 }
 
+/// The signers of a transaction paid for by `payer`, with the payer first and
+/// listed once even when it is also one of `signers`.
+pub fn with_payer<'a>(payer: &'a Keypair, signers: &[&'a Keypair]) -> Vec<&'a Keypair> {
+    let mut all = vec![payer];
+    all.extend(
+        signers
+            .iter()
+            .filter(|signer| signer.pubkey() != payer.pubkey()),
+    );
+    all
+}
+
 pub fn clone_keypair(keypair: &Keypair) -> Keypair {
     Keypair::try_from(keypair.to_bytes().as_slice()).unwrap()
 }
@@ -663,6 +699,26 @@ pub enum CandyError {
         );
     }
 
+    // ── with_payer ───────────────────────────────────────────────────
+
+    #[test]
+    fn with_payer_puts_the_payer_first() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let signers = with_payer(&payer, &[&authority]);
+        let pubkeys: Vec<Pubkey> = signers.iter().map(|s| s.pubkey()).collect();
+        assert_eq!(pubkeys, vec![payer.pubkey(), authority.pubkey()]);
+    }
+
+    #[test]
+    fn with_payer_lists_an_authority_that_pays_once() {
+        let authority = Keypair::new();
+        let mint = Keypair::new();
+        let signers = with_payer(&authority, &[&authority, &mint]);
+        let pubkeys: Vec<Pubkey> = signers.iter().map(|s| s.pubkey()).collect();
+        assert_eq!(pubkeys, vec![authority.pubkey(), mint.pubkey()]);
+    }
+
     // ── clone_keypair ────────────────────────────────────────────────
 
     #[test]
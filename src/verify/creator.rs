@@ -1,12 +1,19 @@
-use metaboss_lib::{data::Asset, decode::ToPubkey, transaction::send_and_confirm_tx};
+use metaboss_lib::{data::Asset, decode::ToPubkey};
 use mpl_token_metadata::{instructions::VerifyCreatorV1Builder, types::TokenStandard};
-use solana_sdk::{compute_budget::ComputeBudgetInstruction, signer::Signer};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signer::Signer};
+
+use crate::cache::PackedAction;
+use crate::landing::land;
+use crate::parse::parse_payer;
+use crate::priority::with_priority;
+use crate::utils::with_payer;
 
 use super::*;
 
 pub struct VerifyCreatorArgs {
     pub client: Arc<RpcClient>,
     pub keypair: Arc<Keypair>,
+    pub payer: Arc<Option<Keypair>>,
     pub mint: String,
     pub priority: Priority,
 }
@@ -19,9 +26,15 @@ pub struct VerifyCreatorAllArgs {
     pub rate_limit: usize,
     pub retries: u8,
     pub priority: Priority,
+    pub lookup_table: Option<Pubkey>,
 }
 
-pub async fn verify_creator(args: VerifyCreatorArgs) -> Result<Signature, ActionError> {
+/// The verify instruction alone, to be packed with other mints' instructions.
+/// `authority` is the creator signing it.
+pub async fn verify_creator_ix(
+    args: &VerifyCreatorArgs,
+    authority: Pubkey,
+) -> Result<Instruction, ActionError> {
     let mint = args
         .mint
         .clone()
@@ -44,27 +57,25 @@ pub async fn verify_creator(args: VerifyCreatorArgs) -> Result<Signature, Action
     }
 
     let mut verify_builder = VerifyCreatorV1Builder::new();
-    verify_builder
-        .authority(args.keypair.pubkey())
-        .metadata(asset.metadata);
-
-    let verify_ix = verify_builder.instruction();
-
-    let micro_lamports = match args.priority {
-        Priority::None => 20,
-        Priority::Low => 20_000,
-        Priority::Medium => 200_000,
-        Priority::High => 1_000_000,
-        Priority::Max => 2_000_000,
-    };
+    verify_builder.authority(authority).metadata(asset.metadata);
 
-    let instructions = vec![
-        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
-        verify_ix,
-    ];
+    Ok(verify_builder.instruction())
+}
 
-    send_and_confirm_tx(&args.client, &[&args.keypair], &instructions)
-        .map_err(|e| ActionError::ActionFailed(args.mint.to_string(), e.to_string()))
+pub async fn verify_creator(args: VerifyCreatorArgs) -> Result<Signature, ActionError> {
+    let verify_ix = verify_creator_ix(&args, args.keypair.pubkey()).await?;
+
+    let instructions = with_priority(&args.client, &args.priority, &[verify_ix])
+        .map_err(|e| ActionError::ActionFailed(args.mint.to_string(), e.to_string()))?;
+
+    let payer = args.payer.as_ref().as_ref().unwrap_or(&args.keypair);
+    land(
+        &args.client,
+        &with_payer(payer, &[&args.keypair]),
+        &instructions,
+        &[],
+    )
+    .map_err(|e| ActionError::ActionFailed(args.mint.to_string(), e.to_string()))
 }
 
 pub struct VerifyCreatorAll {}
@@ -79,27 +90,51 @@ impl Action for VerifyCreatorAll {
         verify_creator(VerifyCreatorArgs {
             client: args.client.clone(),
             keypair: args.keypair.clone(),
+            payer: args.payer.clone(),
             mint: args.mint_account.clone(),
             priority: args.priority.clone(),
         })
         .await
         .map(|_| ())
     }
+
+    fn packable() -> bool {
+        true
+    }
+
+    async fn instructions(args: RunActionArgs) -> Result<PackedAction, ActionError> {
+        let ix = verify_creator_ix(
+            &VerifyCreatorArgs {
+                client: args.client.clone(),
+                keypair: args.keypair.clone(),
+                payer: args.payer.clone(),
+                mint: args.mint_account.clone(),
+                priority: args.priority.clone(),
+            },
+            args.authority,
+        )
+        .await?;
+
+        Ok(PackedAction {
+            instructions: vec![ix],
+            diff: None,
+        })
+    }
 }
 
 pub async fn verify_creator_all(args: VerifyCreatorAllArgs) -> AnyResult<()> {
     let solana_opts = parse_solana_config();
-    let keypair = parse_keypair(args.keypair, solana_opts);
+    let (keypair, signer) = parse_batch_authority(args.keypair, solana_opts)?;
 
     let mint_list = parse_mint_list(args.mint_list, &args.cache_file)?;
 
-    // We don't support an optional payer for this action currently.
-    let payer = None;
+    let payer = parse_payer()?;
 
     let args = BatchActionArgs {
         client: args.client,
         keypair,
         payer,
+        signer,
         mint_list,
         cache_file: args.cache_file,
         new_value: NewValue::None,
@@ -107,6 +142,7 @@ pub async fn verify_creator_all(args: VerifyCreatorAllArgs) -> AnyResult<()> {
         rate_limit: args.rate_limit,
         retries: args.retries,
         priority: args.priority,
+        lookup_table: args.lookup_table,
     };
     VerifyCreatorAll::run(args).await
 }
@@ -0,0 +1,992 @@
+use anyhow::{anyhow, bail, Result};
+use futures::{
+    future::try_join_all,
+    stream::{self, BoxStream, SelectAll},
+    StreamExt,
+};
+use log::warn;
+use metaboss_lib::derive::derive_metadata_pda;
+use mpl_token_metadata::accounts::Metadata;
+use reqwest::Url;
+use serde::Serialize;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_response::Response as RpcResponse,
+};
+use solana_program::program_pack::Pack;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account as TokenAccount;
+
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap},
+    io::{self, Write},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::snapshot::get_collection_items;
+
+/// Accounts fetched per `getMultipleAccounts` call.
+const ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Subscriptions opened on each websocket connection. Providers limit how
+/// many one connection may hold, so large collections are spread over several.
+const SUBSCRIPTIONS_PER_CONNECTION: usize = 500;
+
+/// Offset of the update authority in a metadata account, after its key.
+const UPDATE_AUTHORITY_OFFSET: usize = 1;
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// A change to an NFT in the watched collection, written as one line of JSON.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WatchEvent {
+    OwnerChanged {
+        slot: u64,
+        mint: String,
+        from: String,
+        to: String,
+        token_account: String,
+    },
+    Burned {
+        slot: u64,
+        mint: String,
+        owner: String,
+    },
+    MetadataUpdated {
+        slot: u64,
+        mint: String,
+        name: String,
+        uri: String,
+    },
+}
+
+/// Where an NFT is held, as far as the RPC node knows.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Holding {
+    /// In this token account, owned by this wallet.
+    Held(Pubkey, Pubkey),
+    Burned,
+    /// In no account, but the mint still has supply; the node may be behind.
+    Unknown,
+}
+
+/// Finds where an NFT is held when its token account empties.
+pub trait HolderLookup {
+    fn holding(&self, mint: &Pubkey) -> Result<Holding>;
+
+    /// The data of `addresses`, `None` for closed accounts, and the slot it
+    /// was read at.
+    fn accounts(&self, addresses: &[Pubkey]) -> Result<(u64, Vec<Option<Vec<u8>>>)>;
+}
+
+impl HolderLookup for RpcClient {
+    fn accounts(&self, addresses: &[Pubkey]) -> Result<(u64, Vec<Option<Vec<u8>>>)> {
+        let mut slot = 0;
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(ACCOUNTS_PER_REQUEST) {
+            let response =
+                self.get_multiple_accounts_with_commitment(chunk, CommitmentConfig::confirmed())?;
+            slot = slot.max(response.context.slot);
+            accounts.extend(
+                response
+                    .value
+                    .into_iter()
+                    .map(|account| account.map(|account| account.data)),
+            );
+        }
+        Ok((slot, accounts))
+    }
+
+    fn holding(&self, mint: &Pubkey) -> Result<Holding> {
+        let largest = self.get_token_largest_accounts(mint)?;
+        if let Some(balance) = largest.iter().find(|b| b.amount.amount == "1") {
+            let address = Pubkey::from_str(&balance.address)?;
+            let account = TokenAccount::unpack(&self.get_account_data(&address)?)?;
+            return Ok(Holding::Held(address, account.owner));
+        }
+
+        if self.get_token_supply(mint)?.amount == "0" {
+            Ok(Holding::Burned)
+        } else {
+            Ok(Holding::Unknown)
+        }
+    }
+}
+
+/// An NFT being watched.
+#[derive(Debug, Clone)]
+pub struct WatchedNft {
+    pub metadata: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    /// The metadata account data last seen, to tell real updates from lamport changes.
+    pub metadata_data: Vec<u8>,
+}
+
+/// What the watcher knows about the collection.
+#[derive(Debug, Default)]
+pub struct HolderState {
+    nfts: HashMap<Pubkey, WatchedNft>,
+    /// Mints by the token and metadata accounts subscribed to.
+    accounts: HashMap<Pubkey, Pubkey>,
+}
+
+/// The outcome of one account notification.
+#[derive(Debug, Default)]
+pub struct Handled {
+    pub events: Vec<WatchEvent>,
+    /// Token accounts that now hold an NFT and need subscribing to.
+    pub subscribe: Vec<Pubkey>,
+}
+
+impl HolderState {
+    pub fn insert(&mut self, mint: Pubkey, nft: WatchedNft) {
+        self.accounts.insert(nft.metadata, mint);
+        self.accounts.insert(nft.token_account, mint);
+        self.nfts.insert(mint, nft);
+    }
+
+    pub fn len(&self) -> usize {
+        self.nfts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nfts.is_empty()
+    }
+
+    /// The token accounts currently holding the NFTs.
+    pub fn token_accounts(&self) -> Vec<Pubkey> {
+        self.nfts.values().map(|nft| nft.token_account).collect()
+    }
+
+    /// The update authorities of the NFTs. Metadata accounts are watched with
+    /// one program subscription per update authority.
+    pub fn update_authorities(&self) -> Vec<Pubkey> {
+        let authorities: BTreeSet<Pubkey> = self
+            .nfts
+            .values()
+            .filter_map(|nft| {
+                let bytes = nft
+                    .metadata_data
+                    .get(UPDATE_AUTHORITY_OFFSET..UPDATE_AUTHORITY_OFFSET + 32)?;
+                Pubkey::try_from(bytes).ok()
+            })
+            .collect();
+        authorities.into_iter().collect()
+    }
+
+    /// Apply a notification that `address` changed in `slot`. `data` is `None`
+    /// when the account was closed.
+    pub fn handle<L: HolderLookup>(
+        &mut self,
+        address: &Pubkey,
+        slot: u64,
+        data: Option<&[u8]>,
+        lookup: &L,
+    ) -> Result<Handled> {
+        let Some(&mint) = self.accounts.get(address) else {
+            return Ok(Handled::default());
+        };
+        let Some(nft) = self.nfts.get(&mint) else {
+            // Burned already.
+            return Ok(Handled::default());
+        };
+
+        if nft.metadata == *address {
+            return Ok(self.handle_metadata(mint, slot, data));
+        }
+
+        let account = data.and_then(|data| TokenAccount::unpack(data).ok());
+        match account {
+            Some(account) if account.amount == 1 && account.mint == mint => {
+                Ok(self.move_to(mint, slot, *address, account.owner))
+            }
+            _ if nft.token_account == *address => match lookup.holding(&mint)? {
+                // The node hasn't caught up with the notification yet.
+                Holding::Held(token_account, _) if token_account == *address => {
+                    Ok(Handled::default())
+                }
+                Holding::Held(token_account, owner) => {
+                    Ok(self.move_to(mint, slot, token_account, owner))
+                }
+                Holding::Burned => Ok(self.burn(mint, slot)),
+                Holding::Unknown => {
+                    warn!("No holder found for {mint} after {address} emptied");
+                    Ok(Handled::default())
+                }
+            },
+            // An account that held the NFT before, still subscribed to.
+            _ => Ok(Handled::default()),
+        }
+    }
+
+    fn handle_metadata(&mut self, mint: Pubkey, slot: u64, data: Option<&[u8]>) -> Handled {
+        let Some(data) = data.filter(|data| !data.is_empty()) else {
+            return self.burn(mint, slot);
+        };
+
+        let nft = self.nfts.get_mut(&mint).expect("watched mint");
+        if nft.metadata_data == data {
+            return Handled::default();
+        }
+        nft.metadata_data = data.to_vec();
+
+        let (name, uri) = match Metadata::safe_deserialize(data) {
+            Ok(metadata) => (
+                metadata.name.trim_matches(char::from(0)).to_string(),
+                metadata.uri.trim_matches(char::from(0)).to_string(),
+            ),
+            Err(_) => (String::new(), String::new()),
+        };
+
+        Handled {
+            events: vec![WatchEvent::MetadataUpdated {
+                slot,
+                mint: mint.to_string(),
+                name,
+                uri,
+            }],
+            subscribe: vec![],
+        }
+    }
+
+    fn move_to(
+        &mut self,
+        mint: Pubkey,
+        slot: u64,
+        token_account: Pubkey,
+        owner: Pubkey,
+    ) -> Handled {
+        let mut handled = Handled::default();
+        if let Entry::Vacant(entry) = self.accounts.entry(token_account) {
+            entry.insert(mint);
+            handled.subscribe.push(token_account);
+        }
+
+        let nft = self.nfts.get_mut(&mint).expect("watched mint");
+        let from = nft.owner;
+        nft.token_account = token_account;
+        nft.owner = owner;
+
+        if from != owner {
+            handled.events.push(WatchEvent::OwnerChanged {
+                slot,
+                mint: mint.to_string(),
+                from: from.to_string(),
+                to: owner.to_string(),
+                token_account: token_account.to_string(),
+            });
+        }
+        handled
+    }
+
+    fn burn(&mut self, mint: Pubkey, slot: u64) -> Handled {
+        let nft = self.nfts.remove(&mint).expect("watched mint");
+        Handled {
+            events: vec![WatchEvent::Burned {
+                slot,
+                mint: mint.to_string(),
+                owner: nft.owner.to_string(),
+            }],
+            subscribe: vec![],
+        }
+    }
+}
+
+/// The websocket url of an RPC url: `ws` or `wss` for `http` or `https`, and
+/// the next port up when one is given, as a local validator serves it.
+pub fn websocket_url(rpc_url: &str) -> Result<String> {
+    let mut url = Url::parse(rpc_url)?;
+    let scheme = match url.scheme() {
+        "https" => "wss",
+        "http" => "ws",
+        _ => return Ok(rpc_url.to_string()),
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow!("Can't make a websocket url from {rpc_url}"))?;
+    if let Some(port) = url.port() {
+        url.set_port(Some(port + 1))
+            .map_err(|_| anyhow!("Can't make a websocket url from {rpc_url}"))?;
+    }
+    Ok(url.to_string())
+}
+
+/// Find the current holder of every NFT in `collection`.
+pub async fn load_collection(
+    client: &RpcClient,
+    das_url: &str,
+    collection: &Pubkey,
+    delay: u64,
+) -> Result<HolderState> {
+    let items = get_collection_items(das_url, collection, delay).await?;
+
+    let mut nfts = Vec::new();
+    let mut skipped = 0;
+    for item in items {
+        let compressed = item.compression["compressed"].as_bool().unwrap_or(false);
+        if item.burnt || compressed || item.interface.starts_with("MplCore") {
+            skipped += 1;
+            continue;
+        }
+        let mint = Pubkey::from_str(&item.id)?;
+        let owner = Pubkey::from_str(&item.ownership.owner)?;
+        nfts.push((mint, owner));
+    }
+    if skipped > 0 {
+        eprintln!("Skipping {skipped} burned, compressed or Core assets");
+    }
+
+    let mut state = HolderState::default();
+    for chunk in nfts.chunks(ACCOUNTS_PER_REQUEST / 2) {
+        let mut addresses = Vec::new();
+        for (mint, owner) in chunk {
+            addresses.push(get_associated_token_address(owner, mint));
+            addresses.push(derive_metadata_pda(mint));
+        }
+        let accounts = client.get_multiple_accounts(&addresses)?;
+
+        for (i, (mint, _)) in chunk.iter().enumerate() {
+            let (ata, metadata) = (addresses[2 * i], addresses[2 * i + 1]);
+            let Some(metadata_account) = &accounts[2 * i + 1] else {
+                warn!("{mint} has no metadata account");
+                continue;
+            };
+
+            let held = accounts[2 * i]
+                .as_ref()
+                .and_then(|account| TokenAccount::unpack(&account.data).ok())
+                .filter(|account| account.amount == 1);
+            let (token_account, owner) = match held {
+                Some(account) => (ata, account.owner),
+                None => match client.holding(mint)? {
+                    Holding::Held(token_account, owner) => (token_account, owner),
+                    _ => {
+                        warn!("No holder found for {mint}");
+                        continue;
+                    }
+                },
+            };
+
+            state.insert(
+                *mint,
+                WatchedNft {
+                    metadata,
+                    token_account,
+                    owner,
+                    metadata_data: metadata_account.data.clone(),
+                },
+            );
+        }
+    }
+
+    Ok(state)
+}
+
+/// Account changes, ending with `None` when the subscription closes.
+type Notifications<'a> = BoxStream<'a, Option<(Pubkey, RpcResponse<UiAccount>)>>;
+
+/// Writing an event failed. The watcher stops rather than reconnecting.
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to write an event: {0}")]
+struct OutputError(#[from] io::Error);
+
+fn account_config() -> RpcAccountInfoConfig {
+    RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    }
+}
+
+async fn subscribe(pubsub: &PubsubClient, address: Pubkey) -> Result<Notifications<'_>> {
+    let (notifications, _unsubscribe) = pubsub
+        .account_subscribe(&address, Some(account_config()))
+        .await?;
+    Ok(notifications
+        .map(move |notification| Some((address, notification)))
+        .chain(stream::once(async { None }))
+        .boxed())
+}
+
+/// Subscribe to every metadata account with `update_authority`.
+async fn subscribe_metadata(
+    pubsub: &PubsubClient,
+    update_authority: Pubkey,
+) -> Result<Notifications<'_>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            UPDATE_AUTHORITY_OFFSET,
+            update_authority.to_bytes().to_vec(),
+        ))]),
+        account_config: account_config(),
+        with_context: None,
+        sort_results: None,
+    };
+    let (notifications, _unsubscribe) = pubsub
+        .program_subscribe(&mpl_token_metadata::ID, Some(config))
+        .await?;
+    Ok(notifications
+        .filter_map(|notification| async move {
+            let address = Pubkey::from_str(&notification.value.pubkey).ok()?;
+            Some(Some((
+                address,
+                RpcResponse {
+                    context: notification.context,
+                    value: notification.value.account,
+                },
+            )))
+        })
+        .chain(stream::once(async { None }))
+        .boxed())
+}
+
+fn write_events<W: Write>(out: &mut W, events: Vec<WatchEvent>) -> Result<(), OutputError> {
+    for event in events {
+        writeln!(
+            out,
+            "{}",
+            serde_json::to_string(&event).expect("events serialize")
+        )?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Catch up on changes missed while disconnected from the current state of
+/// every NFT's accounts. Returns the token accounts that need subscribing to.
+fn resync<L: HolderLookup, W: Write>(
+    state: &mut HolderState,
+    lookup: &L,
+    out: &mut W,
+) -> Result<Vec<Pubkey>> {
+    let addresses: Vec<Pubkey> = state
+        .nfts
+        .values()
+        .flat_map(|nft| [nft.token_account, nft.metadata])
+        .collect();
+    let (slot, accounts) = lookup.accounts(&addresses)?;
+
+    let mut subscribe = Vec::new();
+    for (address, data) in addresses.iter().zip(accounts) {
+        let handled = state.handle(address, slot, data.as_deref(), lookup)?;
+        write_events(out, handled.events)?;
+        subscribe.extend(handled.subscribe);
+    }
+    Ok(subscribe)
+}
+
+/// Subscribe to the accounts in `state` over the websocket at `ws_url` and
+/// write an event to `out` for each change, until a connection closes. With
+/// `catch_up`, changes made before subscribing are written first.
+async fn watch_session<L: HolderLookup, W: Write>(
+    ws_url: &str,
+    state: &mut HolderState,
+    lookup: &L,
+    out: &mut W,
+    catch_up: bool,
+) -> Result<()> {
+    let update_authorities = state.update_authorities();
+    let token_accounts = state.token_accounts();
+    let connections =
+        (update_authorities.len() + token_accounts.len()) / SUBSCRIPTIONS_PER_CONNECTION + 1;
+    let pubsubs = try_join_all((0..connections).map(|_| PubsubClient::new(ws_url))).await?;
+    // Subscriptions are dealt out to the connections in turn.
+    let pubsub = |n: usize| &pubsubs[n % connections];
+
+    let mut notifications: SelectAll<Notifications> = SelectAll::new();
+    let metadata = update_authorities
+        .into_iter()
+        .enumerate()
+        .map(|(n, authority)| subscribe_metadata(pubsub(n), authority));
+    notifications.extend(try_join_all(metadata).await?);
+    let mut next = notifications.len();
+    let tokens = token_accounts
+        .into_iter()
+        .enumerate()
+        .map(|(n, address)| subscribe(pubsub(next + n), address));
+    notifications.extend(try_join_all(tokens).await?);
+    next = notifications.len();
+
+    if catch_up {
+        for address in resync(state, lookup, out)? {
+            notifications.push(subscribe(pubsub(next), address).await?);
+            next += 1;
+        }
+    }
+
+    while let Some(Some((address, notification))) = notifications.next().await {
+        let account = notification.value;
+        let data = if account.lamports == 0 {
+            None
+        } else {
+            account.data.decode()
+        };
+
+        let handled = state.handle(&address, notification.context.slot, data.as_deref(), lookup)?;
+        write_events(out, handled.events)?;
+        for address in handled.subscribe {
+            notifications.push(subscribe(pubsub(next), address).await?);
+            next += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Watch `state` over the websocket at `ws_url`, writing an event to `out` for
+/// each change. When a connection closes or a lookup fails, reconnect with a
+/// growing delay and catch up on what changed in between. Only fails when an
+/// event can't be written.
+pub async fn watch<L: HolderLookup, W: Write>(
+    ws_url: &str,
+    state: &mut HolderState,
+    lookup: &L,
+    out: &mut W,
+) -> Result<()> {
+    let mut delay = MIN_RECONNECT_DELAY;
+    let mut catch_up = false;
+    loop {
+        let started = Instant::now();
+        let reason = match watch_session(ws_url, state, lookup, out, catch_up).await {
+            Ok(()) => anyhow!("The websocket connection closed"),
+            Err(err) if err.is::<OutputError>() => return Err(err),
+            Err(err) => err,
+        };
+        if state.is_empty() {
+            bail!("Every watched NFT has been burned");
+        }
+
+        if started.elapsed() >= MAX_RECONNECT_DELAY {
+            delay = MIN_RECONNECT_DELAY;
+        }
+        eprintln!("{reason:#}; reconnecting in {}s", delay.as_secs());
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        catch_up = true;
+    }
+}
+
+pub struct WatchHoldersArgs {
+    pub client: RpcClient,
+    pub rpc_url: String,
+    pub das_url: String,
+    pub collection: Pubkey,
+    pub ws_url: Option<String>,
+    pub delay: u64,
+}
+
+pub async fn watch_holders(args: WatchHoldersArgs) -> Result<()> {
+    // Events alone go to stdout, so progress goes to stderr.
+    eprintln!("Finding the holders of {}...", args.collection);
+    let mut state =
+        load_collection(&args.client, &args.das_url, &args.collection, args.delay).await?;
+    if state.is_empty() {
+        bail!("No NFTs found in collection {}", args.collection);
+    }
+
+    let ws_url = match args.ws_url {
+        Some(ws_url) => ws_url,
+        None => websocket_url(&args.rpc_url)?,
+    };
+    eprintln!("Watching {} NFTs through {ws_url}", state.len());
+
+    watch(&ws_url, &mut state, &args.client, &mut io::stdout()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+    use futures::SinkExt;
+    use mpl_token_metadata::types::Key;
+    use serde_json::{json, Value};
+    use solana_program::program_option::COption;
+    use spl_token::state::AccountState;
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    #[derive(Default)]
+    struct MockLookup {
+        holdings: Mutex<HashMap<Pubkey, Holding>>,
+        accounts: HashMap<Pubkey, Vec<u8>>,
+    }
+
+    impl MockLookup {
+        fn new(holdings: impl IntoIterator<Item = (Pubkey, Holding)>) -> Self {
+            Self {
+                holdings: Mutex::new(holdings.into_iter().collect()),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl HolderLookup for MockLookup {
+        fn holding(&self, mint: &Pubkey) -> Result<Holding> {
+            Ok(self
+                .holdings
+                .lock()
+                .unwrap()
+                .remove(mint)
+                .unwrap_or(Holding::Unknown))
+        }
+
+        fn accounts(&self, addresses: &[Pubkey]) -> Result<(u64, Vec<Option<Vec<u8>>>)> {
+            let accounts = addresses
+                .iter()
+                .map(|address| self.accounts.get(address).cloned())
+                .collect();
+            Ok((20, accounts))
+        }
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+        let account = TokenAccount {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(account, &mut data).unwrap();
+        data
+    }
+
+    fn metadata(mint: Pubkey, uri: &str) -> Vec<u8> {
+        Metadata {
+            key: Key::MetadataV1,
+            update_authority: Pubkey::default(),
+            mint,
+            name: "Watched #1".to_string(),
+            symbol: String::new(),
+            uri: uri.to_string(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    struct Nft {
+        mint: Pubkey,
+        metadata: Pubkey,
+        token_account: Pubkey,
+        owner: Pubkey,
+    }
+
+    fn watched(state: &mut HolderState) -> Nft {
+        let nft = Nft {
+            mint: Pubkey::new_unique(),
+            metadata: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+        };
+        state.insert(
+            nft.mint,
+            WatchedNft {
+                metadata: nft.metadata,
+                token_account: nft.token_account,
+                owner: nft.owner,
+                metadata_data: metadata(nft.mint, "https://example.com/1.json"),
+            },
+        );
+        nft
+    }
+
+    fn ui_account(data: Option<Vec<u8>>) -> Value {
+        let (lamports, data) = match data {
+            Some(data) => (1_000_000, base64::encode(data)),
+            None => (0, String::new()),
+        };
+        json!({
+            "lamports": lamports,
+            "data": [data, "base64"],
+            "owner": Pubkey::default().to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+            "space": 0
+        })
+    }
+
+    fn notification(subscription: u64, slot: u64, data: Option<Vec<u8>>) -> Message {
+        Message::Text(
+            json!({
+                "jsonrpc": "2.0",
+                "method": "accountNotification",
+                "params": {
+                    "subscription": subscription,
+                    "result": {
+                        "context": { "slot": slot },
+                        "value": ui_account(data)
+                    }
+                }
+            })
+            .to_string(),
+        )
+    }
+
+    fn program_notification(
+        subscription: u64,
+        slot: u64,
+        address: Pubkey,
+        data: Vec<u8>,
+    ) -> Message {
+        Message::Text(
+            json!({
+                "jsonrpc": "2.0",
+                "method": "programNotification",
+                "params": {
+                    "subscription": subscription,
+                    "result": {
+                        "context": { "slot": slot },
+                        "value": {
+                            "pubkey": address.to_string(),
+                            "account": ui_account(Some(data))
+                        }
+                    }
+                }
+            })
+            .to_string(),
+        )
+    }
+
+    #[test]
+    fn lamport_only_changes_to_metadata_are_ignored() {
+        let mut state = HolderState::default();
+        let nft = watched(&mut state);
+        let lookup = MockLookup::default();
+
+        let same = metadata(nft.mint, "https://example.com/1.json");
+        let handled = state
+            .handle(&nft.metadata, 5, Some(&same), &lookup)
+            .unwrap();
+
+        assert!(handled.events.is_empty());
+    }
+
+    #[test]
+    fn old_token_accounts_emptying_is_ignored() {
+        let mut state = HolderState::default();
+        let nft = watched(&mut state);
+        let new_account = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+        let lookup = MockLookup::default();
+
+        // The new account is seen first, then the old one emptying.
+        state.accounts.insert(new_account, nft.mint);
+        let moved = state
+            .handle(
+                &new_account,
+                5,
+                Some(&token_account(nft.mint, new_owner, 1)),
+                &lookup,
+            )
+            .unwrap();
+        let emptied = state
+            .handle(
+                &nft.token_account,
+                5,
+                Some(&token_account(nft.mint, nft.owner, 0)),
+                &lookup,
+            )
+            .unwrap();
+
+        assert_eq!(moved.events.len(), 1);
+        assert!(emptied.events.is_empty());
+    }
+
+    #[test]
+    fn resync_catches_up_on_missed_changes() {
+        let mut state = HolderState::default();
+        let sold = watched(&mut state);
+        let updated = watched(&mut state);
+        let buyer_account = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let mut lookup = MockLookup::new([(sold.mint, Holding::Held(buyer_account, buyer))]);
+        lookup.accounts = HashMap::from([
+            (
+                sold.metadata,
+                metadata(sold.mint, "https://example.com/1.json"),
+            ),
+            (
+                updated.token_account,
+                token_account(updated.mint, updated.owner, 1),
+            ),
+            (
+                updated.metadata,
+                metadata(updated.mint, "https://example.com/2.json"),
+            ),
+        ]);
+
+        let mut out = Vec::new();
+        let subscribe = resync(&mut state, &lookup, &mut out).unwrap();
+        let events: BTreeSet<String> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["event"].clone())
+            .filter_map(|event| event.as_str().map(str::to_string))
+            .collect();
+
+        assert_eq!(subscribe, vec![buyer_account]);
+        assert_eq!(
+            events,
+            BTreeSet::from(["metadata_updated".to_string(), "owner_changed".to_string()])
+        );
+    }
+
+    #[test]
+    fn websocket_urls_follow_the_rpc_url() {
+        assert_eq!(
+            websocket_url("https://api.mainnet-beta.solana.com").unwrap(),
+            "wss://api.mainnet-beta.solana.com/"
+        );
+        assert_eq!(
+            websocket_url("http://127.0.0.1:8899").unwrap(),
+            "ws://127.0.0.1:8900/"
+        );
+    }
+
+    /// Serve account and program subscriptions, replying to each with a
+    /// subscription id and the notifications `script` gives for its account or
+    /// program.
+    async fn mock_server(
+        listener: TcpListener,
+        script: impl Fn(&Pubkey, u64) -> Vec<Message>,
+        subscriptions: usize,
+    ) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        let mut seen = 0;
+        while seen < subscriptions {
+            let Some(Ok(Message::Text(text))) = ws.next().await else {
+                continue;
+            };
+            let request: Value = serde_json::from_str(&text).unwrap();
+            let id = request["id"].as_u64().unwrap();
+            let address = Pubkey::from_str(request["params"][0].as_str().unwrap()).unwrap();
+            ws.send(Message::Text(
+                json!({ "jsonrpc": "2.0", "result": id, "id": id }).to_string(),
+            ))
+            .await
+            .unwrap();
+            seen += 1;
+
+            for message in script(&address, id) {
+                ws.send(message).await.unwrap();
+            }
+        }
+
+        // Let the client read everything before the connection closes.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        ws.close(None).await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ownership_changes_burns_and_updates_stream_as_ndjson() {
+        let mut state = HolderState::default();
+        let sold = watched(&mut state);
+        let burned = watched(&mut state);
+        let buyer_account = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let lookup = MockLookup::new([
+            (sold.mint, Holding::Held(buyer_account, buyer)),
+            (burned.mint, Holding::Burned),
+        ]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let (sold_mint, sold_account, sold_metadata) =
+            (sold.mint, sold.token_account, sold.metadata);
+        let burned_account = burned.token_account;
+        let script = move |address: &Pubkey, id: u64| -> Vec<Message> {
+            if *address == sold_account {
+                vec![notification(
+                    id,
+                    10,
+                    Some(token_account(sold_mint, sold.owner, 0)),
+                )]
+            } else if *address == buyer_account {
+                // Subscribed after the sale: now the rest can happen.
+                vec![notification(
+                    id,
+                    11,
+                    Some(token_account(sold_mint, buyer, 1)),
+                )]
+            } else if *address == burned_account {
+                vec![notification(id, 12, None)]
+            } else if *address == mpl_token_metadata::ID {
+                vec![program_notification(
+                    id,
+                    13,
+                    sold_metadata,
+                    metadata(sold_mint, "https://example.com/2.json"),
+                )]
+            } else {
+                vec![]
+            }
+        };
+        // The metadata program, two token accounts and the buyer's.
+        let server = tokio::spawn(mock_server(listener, script, 4));
+
+        let mut out = Vec::new();
+        let result = watch_session(&ws_url, &mut state, &lookup, &mut out, false).await;
+        server.await.unwrap();
+
+        assert!(result.is_ok());
+        let mut events: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        events.sort_by_key(|event| event["slot"].as_u64());
+
+        assert_eq!(
+            events,
+            vec![
+                json!({
+                    "event": "owner_changed",
+                    "slot": 10,
+                    "mint": sold.mint.to_string(),
+                    "from": sold.owner.to_string(),
+                    "to": buyer.to_string(),
+                    "token_account": buyer_account.to_string(),
+                }),
+                json!({
+                    "event": "burned",
+                    "slot": 12,
+                    "mint": burned.mint.to_string(),
+                    "owner": burned.owner.to_string(),
+                }),
+                json!({
+                    "event": "metadata_updated",
+                    "slot": 13,
+                    "mint": sold.mint.to_string(),
+                    "name": "Watched #1",
+                    "uri": "https://example.com/2.json",
+                }),
+            ]
+        );
+    }
+}